@@ -373,6 +373,8 @@ pub struct IndexConfig {
     /// Currently this is always an array of size 1. In the future there may
     /// be more columns to represent composite indices.
     pub columns: Vec<String>,
+    /// The version of the table when the index was last updated
+    pub version: Option<i64>,
 }
 
 impl From<lancedb::index::IndexConfig> for IndexConfig {
@@ -382,6 +384,7 @@ impl From<lancedb::index::IndexConfig> for IndexConfig {
             index_type,
             columns: value.columns,
             name: value.name,
+            version: value.version.map(|version| version as i64),
         }
     }
 }
@@ -501,6 +504,8 @@ pub struct IndexStatistics {
     /// The KMeans loss value of the index,
     /// it is only present for vector indices.
     pub loss: Option<f64>,
+    /// The total size of the files of the index, in bytes
+    pub size_bytes: Option<f64>,
 }
 impl From<lancedb::index::IndexStatistics> for IndexStatistics {
     fn from(value: lancedb::index::IndexStatistics) -> Self {
@@ -511,6 +516,7 @@ impl From<lancedb::index::IndexStatistics> for IndexStatistics {
             distance_type: value.distance_type.map(|d| d.to_string()),
             num_indices: value.num_indices,
             loss: value.loss,
+            size_bytes: value.size_bytes.map(|size| size as f64),
         }
     }
 }
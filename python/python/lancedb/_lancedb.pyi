@@ -65,6 +65,8 @@ class Table:
 class IndexConfig:
     index_type: str
     columns: List[str]
+    name: str
+    version: Optional[int]
 
 async def connect(
     uri: str,
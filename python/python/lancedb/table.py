@@ -3779,6 +3779,8 @@ class IndexStatistics:
         The number of parts the index is split into.
     loss: Optional[float]
         The KMeans loss for the index, for only vector indices.
+    size_bytes: Optional[int]
+        The total size of the files of the index, in bytes.
     """
 
     num_indexed_rows: int
@@ -3789,6 +3791,7 @@ class IndexStatistics:
     distance_type: Optional[Literal["l2", "cosine", "dot"]] = None
     num_indices: Optional[int] = None
     loss: Optional[float] = None
+    size_bytes: Optional[int] = None
 
     # This exists for backwards compatibility with an older API, which returned
     # a dictionary instead of a class.
@@ -30,6 +30,7 @@ impl<T> PythonErrorExt<T> for std::result::Result<T, LanceError> {
                 | LanceError::InvalidTableName { .. }
                 | LanceError::TableNotFound { .. }
                 | LanceError::Schema { .. }
+                | LanceError::InvalidFilter { .. }
                 | LanceError::TableAlreadyExists { .. } => self.value_error(),
                 LanceError::CreateDir { .. } => self.os_error(),
                 LanceError::ObjectStore { .. } => Err(PyIOError::new_err(err.to_string())),
@@ -239,6 +239,10 @@ impl Table {
                         dict.set_item("loss", loss)?;
                     }
 
+                    if let Some(size_bytes) = stats.size_bytes {
+                        dict.set_item("size_bytes", size_bytes)?;
+                    }
+
                     Ok(Some(dict.unbind()))
                 })
             } else {
@@ -327,4 +327,34 @@ mod tests {
             ]
         );
     }
+
+    #[tokio::test]
+    async fn table_to_polars() {
+        use crate::query::{ExecutableQuery, QueryBase};
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let db = crate::connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let table = db
+            .create_table("test", get_record_batch_reader_from_polars())
+            .execute()
+            .await
+            .unwrap();
+
+        let df = table.to_polars().await.unwrap();
+        assert_eq!(df.height(), 2);
+        assert_eq!(df.width(), 3);
+
+        let df = table
+            .query()
+            .only_if("int > 1")
+            .select(crate::query::Select::columns(&["string"]))
+            .execute_polars()
+            .await
+            .unwrap();
+        assert_eq!(df.shape(), (1, 1));
+        assert_eq!(df.get_column_names(), vec!["string"]);
+    }
 }
@@ -3,39 +3,68 @@
 
 //! Functions to establish a connection to a LanceDB database
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
-use arrow_array::RecordBatchReader;
-use arrow_schema::{Field, SchemaRef};
+use arrow_array::{RecordBatchIterator, RecordBatchReader};
+use arrow_schema::{Field, Schema, SchemaRef};
+use datafusion::prelude::{SQLOptions, SessionContext};
+use datafusion_common::DataFusionError;
+use futures::{StreamExt, TryStreamExt};
+use lance::dataset::scanner::DatasetRecordBatchStream;
 use lance::dataset::ReadParams;
+use lance::io::WrappingObjectStore;
 use object_store::aws::AwsCredential;
 
 use crate::arrow::{IntoArrow, IntoArrowStream, SendableRecordBatchStream};
 use crate::catalog::listing::ListingCatalog;
-use crate::catalog::CatalogOptions;
+use crate::catalog::{
+    Catalog, CatalogOptions, CreateDatabaseMode, CreateDatabaseRequest, DatabaseNamesRequest,
+    OpenDatabaseRequest,
+};
+use crate::data::file::FileSource;
 use crate::database::listing::{
     ListingDatabase, OPT_NEW_TABLE_STORAGE_VERSION, OPT_NEW_TABLE_V2_MANIFEST_PATHS,
 };
+use crate::database::observer::{DatabaseObserver, ObservedDatabase};
+use crate::database::read_only::ReadOnlyDatabase;
 use crate::database::{
     CreateTableData, CreateTableMode, CreateTableRequest, Database, DatabaseOptions,
-    OpenTableRequest, TableNamesRequest,
+    ListTablesRequest, OpenTableRequest, TableInfo, TableNamesRequest, TableVersionRef,
 };
 use crate::embeddings::{
     EmbeddingDefinition, EmbeddingFunction, EmbeddingRegistry, MemoryRegistry, WithEmbeddings,
 };
 use crate::error::{Error, Result};
+use crate::index::vector::{
+    IvfFlatIndexBuilder, IvfHnswPqIndexBuilder, IvfHnswSqIndexBuilder, IvfPqIndexBuilder,
+};
+use crate::index::{Index, IndexType};
+use crate::io::policy::{RetryConfig, TimeoutConfig};
+use crate::query::ExecutableQuery;
 #[cfg(feature = "remote")]
 use crate::remote::{
+    catalog::RemoteCatalog,
     client::ClientConfig,
-    db::{OPT_REMOTE_API_KEY, OPT_REMOTE_HOST_OVERRIDE, OPT_REMOTE_REGION},
+    db::{OPT_REMOTE_API_KEY, OPT_REMOTE_HOST_OVERRIDE, OPT_REMOTE_PREFIX, OPT_REMOTE_REGION},
 };
+use crate::table::auto_id::{with_auto_id_columns, AutoId, WithAutoIds};
+use crate::table::constraints::{
+    constraints_metadata, CheckedReader, ColumnConstraint, Violations,
+};
+use crate::table::datafusion::BaseTableAdapter;
+use crate::table::defaults::{validate_defaults, with_default, WithDefaults};
+use crate::table::partition::{partition_metadata, validate_partition_columns};
 use crate::table::{TableDefinition, WriteOptions};
-use crate::Table;
+use crate::transaction::Transaction;
+use crate::{DistanceType, Table};
 pub use lance_encoding::version::LanceFileVersion;
 #[cfg(feature = "remote")]
 use lance_io::object_store::StorageOptions;
 
+/// The number of requests issued at a time by the bulk operations on [`Connection`]
+const BULK_CONCURRENCY: usize = 8;
+
 /// A builder for configuring a [`Connection::table_names`] operation
 pub struct TableNamesBuilder {
     parent: Arc<dyn Database>,
@@ -72,6 +101,132 @@ impl TableNamesBuilder {
     }
 }
 
+/// A builder for configuring a [`Connection::list_tables`] operation
+pub struct ListTablesBuilder {
+    parent: Arc<dyn Database>,
+    request: ListTablesRequest,
+}
+
+impl ListTablesBuilder {
+    fn new(parent: Arc<dyn Database>) -> Self {
+        Self {
+            parent,
+            request: ListTablesRequest::default(),
+        }
+    }
+
+    /// If present, only return tables whose names come lexicographically after
+    /// the supplied value.
+    pub fn start_after(mut self, start_after: impl Into<String>) -> Self {
+        self.request.start_after = Some(start_after.into());
+        self
+    }
+
+    /// The maximum number of tables to return
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.request.limit = Some(limit);
+        self
+    }
+
+    /// If true, return the statistics of each table in [`TableInfo::stats`]
+    ///
+    /// A local database opens every table to read its statistics, so this is
+    /// off by default.
+    pub fn include_stats(mut self, include_stats: bool) -> Self {
+        self.request.include_stats = include_stats;
+        self
+    }
+
+    /// Execute the list tables operation
+    pub async fn execute(self) -> Result<Vec<TableInfo>> {
+        self.parent.clone().list_tables(self.request).await
+    }
+}
+
+/// A builder for configuring a [`Connection::drop_all_tables_builder`] operation
+pub struct DropAllTablesBuilder {
+    parent: Arc<dyn Database>,
+    dry_run: bool,
+}
+
+impl DropAllTablesBuilder {
+    fn new(parent: Arc<dyn Database>) -> Self {
+        Self {
+            parent,
+            dry_run: false,
+        }
+    }
+
+    /// If true, list the tables that would be dropped without dropping them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Execute the drop all tables operation
+    ///
+    /// Returns the names of the tables that were dropped, or that would be
+    /// dropped in a dry run.
+    pub async fn execute(self) -> Result<Vec<String>> {
+        // Remote databases return the names a page at a time
+        let mut names = Vec::new();
+        loop {
+            let page = self
+                .parent
+                .table_names(TableNamesRequest {
+                    start_after: names.last().cloned(),
+                    limit: None,
+                })
+                .await?;
+            if page.is_empty() {
+                break;
+            }
+            names.extend(page);
+        }
+        if !self.dry_run {
+            self.parent.drop_all_tables().await?;
+        }
+        Ok(names)
+    }
+}
+
+/// A builder for configuring a [`Connection::database_names`] operation
+pub struct DatabaseNamesBuilder {
+    parent: Option<Arc<dyn Catalog>>,
+    request: DatabaseNamesRequest,
+}
+
+impl DatabaseNamesBuilder {
+    fn new(parent: Option<Arc<dyn Catalog>>) -> Self {
+        Self {
+            parent,
+            request: DatabaseNamesRequest::default(),
+        }
+    }
+
+    /// If present, only return names that come lexicographically after the supplied
+    /// value.
+    ///
+    /// This can be combined with limit to implement pagination by setting this to
+    /// the last database name from the previous page.
+    pub fn start_after(mut self, start_after: impl Into<String>) -> Self {
+        self.request.start_after = Some(start_after.into());
+        self
+    }
+
+    /// The maximum number of database names to return
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.request.limit = Some(limit);
+        self
+    }
+
+    /// Execute the database names operation
+    pub async fn execute(self) -> Result<Vec<String>> {
+        let parent = self.parent.ok_or_else(Connection::no_catalog_error)?;
+        parent.database_names(self.request).await
+    }
+}
+
 pub struct NoData {}
 
 impl IntoArrow for NoData {
@@ -93,6 +248,12 @@ pub struct CreateTableBuilder<const HAS_DATA: bool> {
     parent: Arc<dyn Database>,
     embeddings: Vec<(EmbeddingDefinition, Arc<dyn EmbeddingFunction>)>,
     embedding_registry: Arc<dyn EmbeddingRegistry>,
+    // Applied if no embeddings are added explicitly, see [`ConnectBuilder::default_embedding`]
+    default_embedding: Option<EmbeddingDefinition>,
+    auto_ids: Vec<(String, AutoId)>,
+    constraints: BTreeMap<String, ColumnConstraint>,
+    defaults: BTreeMap<String, String>,
+    partition_columns: Vec<String>,
     request: CreateTableRequest,
     // This is a bit clumsy but we defer errors until `execute` is called
     // to maintain backwards compatibility
@@ -116,6 +277,11 @@ impl CreateTableBuilder<true> {
             ),
             embeddings: Vec::new(),
             embedding_registry,
+            default_embedding: None,
+            auto_ids: Vec::new(),
+            constraints: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            partition_columns: Vec::new(),
             data: CreateTableBuilderInitialData::Iterator(data.into_arrow()),
         }
     }
@@ -135,6 +301,11 @@ impl CreateTableBuilder<true> {
             ),
             embeddings: Vec::new(),
             embedding_registry,
+            default_embedding: None,
+            auto_ids: Vec::new(),
+            constraints: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            partition_columns: Vec::new(),
             data: CreateTableBuilderInitialData::Stream(data.into_arrow()),
         }
     }
@@ -149,44 +320,110 @@ impl CreateTableBuilder<true> {
     pub async fn execute(self) -> Result<Table> {
         let embedding_registry = self.embedding_registry.clone();
         let parent = self.parent.clone();
-        let request = self.into_request()?;
+        let (request, violations) = self.apply_default_embedding()?.into_request()?;
+        let table = parent
+            .create_table(request)
+            .await
+            .map_err(|err| violations.or_error(err))?;
         Ok(Table::new_with_embedding_registry(
-            parent.create_table(request).await?,
+            table,
             embedding_registry,
         ))
     }
 
-    fn into_request(self) -> Result<CreateTableRequest> {
-        if self.embeddings.is_empty() {
+    fn with_default_embedding(mut self, definition: Option<EmbeddingDefinition>) -> Self {
+        self.default_embedding = definition;
+        self
+    }
+
+    /// Adds the default embedding if no embeddings were configured and the data
+    /// has the source column but not the embedding column
+    fn apply_default_embedding(mut self) -> Result<Self> {
+        let Some(definition) = self.default_embedding.take() else {
+            return Ok(self);
+        };
+        let CreateTableBuilderInitialData::Iterator(Ok(data)) = &self.data else {
+            return Ok(self);
+        };
+        if !self.embeddings.is_empty() {
+            return Ok(self);
+        }
+        let schema = data.schema();
+        let dest_column = definition
+            .dest_column
+            .clone()
+            .unwrap_or_else(|| format!("{}_embedding", definition.source_column));
+        if schema.column_with_name(&definition.source_column).is_some()
+            && schema.column_with_name(&dest_column).is_none()
+        {
+            self.add_embedding(definition)
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn into_request(self) -> Result<(CreateTableRequest, Violations)> {
+        if self.embeddings.is_empty()
+            && self.auto_ids.is_empty()
+            && self.constraints.is_empty()
+            && self.defaults.is_empty()
+            && self.partition_columns.is_empty()
+        {
             match self.data {
                 CreateTableBuilderInitialData::Iterator(maybe_iter) => {
                     let data = maybe_iter?;
-                    Ok(CreateTableRequest {
+                    let request = CreateTableRequest {
                         data: CreateTableData::Data(data),
                         ..self.request
-                    })
+                    };
+                    Ok((request, Violations::default()))
                 }
                 CreateTableBuilderInitialData::None => {
                     unreachable!("No data provided for CreateTableBuilder<true>")
                 }
                 CreateTableBuilderInitialData::Stream(maybe_stream) => {
                     let data = maybe_stream?;
-                    Ok(CreateTableRequest {
+                    let request = CreateTableRequest {
                         data: CreateTableData::StreamingData(data),
                         ..self.request
-                    })
+                    };
+                    Ok((request, Violations::default()))
                 }
             }
         } else {
             let CreateTableBuilderInitialData::Iterator(maybe_iter) = self.data else {
-                return Err(Error::NotSupported { message: "Creating a table with embeddings is currently not support when the input is streaming".to_string() });
+                return Err(Error::NotSupported { message: "Creating a table with embeddings, auto id columns, constraints, defaults or partition columns is currently not support when the input is streaming".to_string() });
             };
-            let data = maybe_iter?;
-            let data = Box::new(WithEmbeddings::new(data, self.embeddings));
-            Ok(CreateTableRequest {
+            let mut data = maybe_iter?;
+            if !self.embeddings.is_empty() {
+                data = Box::new(WithEmbeddings::new(data, self.embeddings));
+            }
+            if !self.defaults.is_empty() {
+                // The data has every column, this only stores the defaults
+                let schema = schema_with_defaults(&data.schema(), &self.defaults)?;
+                data = Box::new(WithDefaults::try_new(data, &schema)?);
+            }
+            if !self.auto_ids.is_empty() {
+                data = Box::new(WithAutoIds::try_new(data, self.auto_ids, None)?);
+            }
+            if !self.partition_columns.is_empty() {
+                let mut schema = data.schema().as_ref().clone();
+                validate_partition_columns(&schema, &self.partition_columns)?;
+                let (key, value) = partition_metadata(&self.partition_columns);
+                schema.metadata.insert(key, value);
+                data = Box::new(RecordBatchIterator::new(data, Arc::new(schema)));
+            }
+            let mut violations = Violations::default();
+            if !self.constraints.is_empty() {
+                let (checked, checked_violations) = CheckedReader::new(data, self.constraints);
+                data = Box::new(checked);
+                violations = checked_violations;
+            }
+            let request = CreateTableRequest {
                 data: CreateTableData::Data(data),
                 ..self.request
-            })
+            };
+            Ok((request, violations))
         }
     }
 }
@@ -206,17 +443,80 @@ impl CreateTableBuilder<false> {
             data: CreateTableBuilderInitialData::None,
             embeddings: Vec::default(),
             embedding_registry,
+            default_embedding: None,
+            auto_ids: Vec::new(),
+            constraints: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            partition_columns: Vec::new(),
         }
     }
 
     /// Execute the create table operation
-    pub async fn execute(self) -> Result<Table> {
+    pub async fn execute(mut self) -> Result<Table> {
+        if let CreateTableData::Empty(definition) = &self.request.data {
+            if !self.auto_ids.is_empty() {
+                let definition = with_auto_id_columns(definition.clone(), &self.auto_ids)?;
+                let schema = definition.clone().into_rich_schema();
+                self.request.data = CreateTableData::Empty(TableDefinition::new(
+                    schema,
+                    definition.column_definitions,
+                ));
+            }
+        }
+        if let CreateTableData::Empty(definition) = &mut self.request.data {
+            if !self.defaults.is_empty() {
+                definition.schema =
+                    Arc::new(schema_with_defaults(&definition.schema, &self.defaults)?);
+            }
+            if !self.constraints.is_empty() {
+                let mut schema = definition.schema.as_ref().clone();
+                let (key, value) = constraints_metadata(&self.constraints);
+                schema.metadata.insert(key, value);
+                definition.schema = Arc::new(schema);
+            }
+            if !self.partition_columns.is_empty() {
+                let mut schema = definition.schema.as_ref().clone();
+                validate_partition_columns(&schema, &self.partition_columns)?;
+                let (key, value) = partition_metadata(&self.partition_columns);
+                schema.metadata.insert(key, value);
+                definition.schema = Arc::new(schema);
+            }
+        }
         Ok(Table::new(
             self.parent.clone().create_table(self.request).await?,
         ))
     }
 }
 
+/// The schema of a new table with the given column defaults stored in it
+fn schema_with_defaults(schema: &Schema, defaults: &BTreeMap<String, String>) -> Result<Schema> {
+    if let Some(column) = defaults
+        .keys()
+        .find(|column| schema.column_with_name(column).is_none())
+    {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "cannot set a default for {}, there is no such column",
+                column
+            ),
+        });
+    }
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match defaults.get(field.name()) {
+            Some(default) => with_default(field, default),
+            None => field.as_ref().clone(),
+        })
+        .collect::<Vec<_>>();
+    validate_defaults(
+        fields
+            .iter()
+            .filter_map(|field| Some((field, defaults.get(field.name())?.as_str()))),
+    )?;
+    Ok(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
 impl<const HAS_DATA: bool> CreateTableBuilder<HAS_DATA> {
     /// Set the mode for creating the table
     ///
@@ -291,6 +591,54 @@ impl<const HAS_DATA: bool> CreateTableBuilder<HAS_DATA> {
         Ok(self)
     }
 
+    /// Add a column of unique ids that the table fills in
+    ///
+    /// The ids are generated for the initial data and for every later
+    /// [`Table::add`], so the data must not contain the column.  Ids are not
+    /// generated for rows inserted by [`Table::merge_insert`].  See [`AutoId`]
+    /// for the kinds of ids.  This is not supported for remote tables.
+    pub fn auto_id(mut self, column: impl Into<String>, kind: AutoId) -> Self {
+        self.auto_ids.push((column.into(), kind));
+        self
+    }
+
+    /// Set the default expression of a column
+    ///
+    /// The default is a SQL expression, such as `0` or `'unknown'`, which
+    /// can't refer to other columns.  It is stored with the table and filled
+    /// in when data is added without the column.  See
+    /// [`Table::set_column_default`].
+    pub fn column_default(mut self, column: impl Into<String>, default: impl Into<String>) -> Self {
+        self.defaults.insert(column.into(), default.into());
+        self
+    }
+
+    /// Partition the table by the given columns
+    ///
+    /// Each write is split so that every fragment holds rows of only one
+    /// combination of partition values.  Plain scans with a filter on the
+    /// partition columns, such as `day = '2024-01-01'`, then skip the
+    /// fragments that can't match.  Vector and full text searches are not
+    /// pruned.  Partitioned writes are held in memory while they are split,
+    /// so large loads should be added in several calls.  This is not supported
+    /// for remote tables.
+    pub fn partition_columns(
+        mut self,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.partition_columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a constraint that the values of a column must pass
+    ///
+    /// The constraint is checked for the initial data and is stored with the
+    /// table, where it is checked by later writes.  See [`ColumnConstraint`].
+    pub fn constraint(mut self, column: impl Into<String>, constraint: ColumnConstraint) -> Self {
+        self.constraints.insert(column.into(), constraint);
+        self
+    }
+
     /// Set whether to use V2 manifest paths for the table. (default: false)
     ///
     /// These paths provide more efficient opening of tables with many
@@ -368,6 +716,9 @@ impl OpenTableBuilder {
                 name,
                 index_cache_size: None,
                 lance_read_params: None,
+                version: None,
+                skip_existence_check: false,
+                query_cache_size: None,
             },
             embedding_registry,
         }
@@ -389,6 +740,31 @@ impl OpenTableBuilder {
         self
     }
 
+    /// Keep up to `query_cache_size` bytes of query results in memory
+    ///
+    /// When the same query is run again on the same version of the table, its
+    /// results are returned from memory instead of running the query.  This
+    /// helps applications that repeat identical searches, such as a chatbot
+    /// retrieving the same context again and again.
+    ///
+    /// Entries are keyed on the query and the table version, so a new commit
+    /// makes the cached results of older versions unreachable, and they are
+    /// evicted, least recently used first, as new queries are cached.  The
+    /// results of a cached query are read fully before they are returned, so
+    /// the first batch can arrive later than without the cache.
+    ///
+    /// Only queries with a limit are cached, and random samples are only
+    /// cached when they have a seed.  Results larger than a quarter of the
+    /// cache are not cached.
+    ///
+    /// The default is 0, i.e. query results are not cached.  Tables opened
+    /// with a query cache have their own handle, which is not shared with
+    /// other calls to `open_table`.  This is not supported on LanceDB Cloud.
+    pub fn query_cache_size(mut self, query_cache_size: usize) -> Self {
+        self.request.query_cache_size = Some(query_cache_size);
+        self
+    }
+
     /// Advanced parameters that can be used to customize table reads
     ///
     /// If set, these will take precedence over any overlapping `OpenTableOptions` options
@@ -441,6 +817,38 @@ impl OpenTableBuilder {
         self
     }
 
+    /// Open the table at a specific version
+    ///
+    /// The returned table is read-only, as if [`Table::checkout`] had been called.
+    pub fn version(mut self, version: u64) -> Self {
+        self.request.version = Some(TableVersionRef::Version(version));
+        self
+    }
+
+    /// Open the table at the version the given tag points to
+    ///
+    /// The returned table is read-only, as if [`Table::checkout`] had been called.
+    /// Tags are created with [`Table::tags`] and are only supported in LanceDB OSS.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.request.version = Some(TableVersionRef::Tag(tag.into()));
+        self
+    }
+
+    /// Don't check that the table exists when opening it
+    ///
+    /// By default, opening a LanceDB Cloud table makes a request to the server
+    /// to confirm the table exists. If this is set, the handle is returned
+    /// straight away and a missing table is only reported, as
+    /// [`Error::TableNotFound`], by the first operation on the table. This
+    /// saves a round trip when opening many tables that are known to exist.
+    ///
+    /// This has no effect in LanceDB OSS, where the table's manifest must be
+    /// read to open it.
+    pub fn skip_existence_check(mut self, skip_existence_check: bool) -> Self {
+        self.request.skip_existence_check = skip_existence_check;
+        self
+    }
+
     /// Open the table
     pub async fn execute(self) -> Result<Table> {
         Ok(Table::new_with_embedding_registry(
@@ -450,12 +858,133 @@ impl OpenTableBuilder {
     }
 }
 
+/// A builder for configuring a [`Connection::copy_table`] operation
+pub struct CopyTableBuilder {
+    source: Connection,
+    source_name: String,
+    target: Connection,
+    target_name: String,
+    copy_indices: bool,
+}
+
+impl CopyTableBuilder {
+    fn new(
+        source: Connection,
+        source_name: String,
+        target: Connection,
+        target_name: String,
+    ) -> Self {
+        Self {
+            source,
+            source_name,
+            target,
+            target_name,
+            copy_indices: false,
+        }
+    }
+
+    /// Whether to also create the source table's indices on the new table
+    ///
+    /// Indices are rebuilt from the copied data, with the same type and
+    /// distance type as the source index.  The other parameters of the source
+    /// index, such as the number of partitions and sub-vectors of a vector
+    /// index or the tokenizer of an FTS index, can't be read back, so the
+    /// defaults are used.  The indices this applies to are listed in
+    /// [`CopyTableResult::indices_with_default_params`], recreate them with
+    /// [`Table::create_index`] if they need other parameters.  The default is
+    /// `false`.
+    pub fn copy_indices(mut self, copy_indices: bool) -> Self {
+        self.copy_indices = copy_indices;
+        self
+    }
+
+    /// Execute the copy table operation, returning the new table
+    pub async fn execute(self) -> Result<CopyTableResult> {
+        let source = self.source.open_table(&self.source_name).execute().await?;
+        let data = source.query().execute().await?;
+        let target = self
+            .target
+            .create_table_streaming(&self.target_name, data)
+            .execute()
+            .await?;
+
+        let mut indices_with_default_params = Vec::new();
+        if self.copy_indices {
+            for index in source.list_indices().await? {
+                let stats = source.index_stats(&index.name).await?;
+                let distance_type = stats.and_then(|stats| stats.distance_type);
+                target
+                    .create_index(
+                        &index.columns,
+                        index_for_type(&index.index_type, distance_type),
+                    )
+                    .execute()
+                    .await?;
+                if has_params(&index.index_type) {
+                    indices_with_default_params.push(index.name);
+                }
+            }
+        }
+        Ok(CopyTableResult {
+            table: target,
+            indices_with_default_params,
+        })
+    }
+}
+
+/// The outcome of a [`Connection::copy_table`] operation
+#[derive(Clone)]
+pub struct CopyTableResult {
+    /// The new table
+    pub table: Table,
+    /// The names of the source indices that were rebuilt with default
+    /// parameters, see [`CopyTableBuilder::copy_indices`]
+    pub indices_with_default_params: Vec<String>,
+}
+
+/// Whether an index of the given type has parameters other than the distance
+/// type, which are lost when it is rebuilt with [`index_for_type`]
+fn has_params(index_type: &IndexType) -> bool {
+    !matches!(
+        index_type,
+        IndexType::BTree | IndexType::Bitmap | IndexType::LabelList
+    )
+}
+
+/// An index of the given type with default parameters
+fn index_for_type(index_type: &IndexType, distance_type: Option<DistanceType>) -> Index {
+    let distance_type = distance_type.unwrap_or(DistanceType::L2);
+    match index_type {
+        IndexType::BTree => Index::BTree(Default::default()),
+        IndexType::Bitmap => Index::Bitmap(Default::default()),
+        IndexType::LabelList => Index::LabelList(Default::default()),
+        IndexType::FTS => Index::FTS(Default::default()),
+        IndexType::IvfFlat => {
+            Index::IvfFlat(IvfFlatIndexBuilder::default().distance_type(distance_type))
+        }
+        IndexType::IvfPq => Index::IvfPq(IvfPqIndexBuilder::default().distance_type(distance_type)),
+        IndexType::IvfHnswPq => {
+            Index::IvfHnswPq(IvfHnswPqIndexBuilder::default().distance_type(distance_type))
+        }
+        IndexType::IvfHnswSq => {
+            Index::IvfHnswSq(IvfHnswSqIndexBuilder::default().distance_type(distance_type))
+        }
+    }
+}
+
+/// The prefix of the vector search functions of [`Connection::sql`]
+#[cfg(feature = "vector-search-udtf")]
+const SQL_VECTOR_SEARCH_PREFIX: &str = "search_";
+
 /// A connection to LanceDB
 #[derive(Clone)]
 pub struct Connection {
     uri: String,
     internal: Arc<dyn Database>,
+    // The catalog the database belongs to, if the deployment has more than one database
+    catalog: Option<Arc<dyn Catalog>>,
     embedding_registry: Arc<dyn EmbeddingRegistry>,
+    default_embedding: Option<EmbeddingDefinition>,
 }
 
 impl std::fmt::Display for Connection {
@@ -484,6 +1013,16 @@ impl Connection {
         TableNamesBuilder::new(self.internal.clone())
     }
 
+    /// List the tables in the database, optionally with statistics about each
+    ///
+    /// With [`ListTablesBuilder::include_stats`], this returns the row count,
+    /// size, latest version and number of indices of every table in one call.
+    /// Tables are returned in the same order as [`Self::table_names`] and can
+    /// be paginated in the same way.
+    pub fn list_tables(&self) -> ListTablesBuilder {
+        ListTablesBuilder::new(self.internal.clone())
+    }
+
     /// Create a new table from an iterator of data
     ///
     /// # Parameters
@@ -501,10 +1040,38 @@ impl Connection {
             initial_data,
             self.embedding_registry.clone(),
         )
+        .with_default_embedding(self.default_embedding.clone())
+    }
+
+    /// Create a new table from data files
+    ///
+    /// The files are read from local disk or object storage and may be CSV,
+    /// newline delimited JSON, Arrow IPC or Parquet files.  Unless a schema is
+    /// set on the source, the schema of CSV and JSON files is inferred from the
+    /// first rows of the first file.  See [`FileSource`] for more details.
+    ///
+    /// The files are streamed into the table as they are downloaded, like
+    /// [`Self::create_table_streaming`], so the connection's
+    /// [`ConnectBuilder::default_embedding`] is not applied.
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - The name of the table
+    /// * `source` - The files to read, such as `"s3://bucket/data/*.csv"`
+    pub async fn create_table_from_uri(
+        &self,
+        name: impl Into<String>,
+        source: impl Into<FileSource>,
+    ) -> Result<CreateTableBuilder<true>> {
+        let data = source.into().open().await?;
+        Ok(self.create_table_streaming(name, data))
     }
 
     /// Create a new table from a stream of data
     ///
+    /// Embeddings can't be computed for a stream, so the connection's
+    /// [`ConnectBuilder::default_embedding`] is not applied.
+    ///
     /// # Parameters
     ///
     /// * `name` - The name of the table
@@ -556,9 +1123,21 @@ impl Connection {
         )
     }
 
+    /// Check whether a table exists in the database.
+    ///
+    /// This is cheaper than opening the table and does not require handling
+    /// [`Error::TableNotFound`].
+    pub async fn table_exists(&self, name: impl AsRef<str>) -> Result<bool> {
+        self.internal.table_exists(name.as_ref()).await
+    }
+
     /// Rename a table in the database.
     ///
-    /// This is only supported in LanceDB Cloud.
+    /// On local storage the table's directory is renamed, which is atomic.  On
+    /// object stores every file of the table is copied, without downloading
+    /// it, and the old table is removed afterwards, so readers may see both
+    /// tables for a moment.  Open handles to the table are not updated and
+    /// should be opened again with the new name.
     pub async fn rename_table(
         &self,
         old_name: impl AsRef<str>,
@@ -569,6 +1148,116 @@ impl Connection {
             .await
     }
 
+    /// Run an SQL query against the tables of the database
+    ///
+    /// The query is planned and run by DataFusion.  Every table the query
+    /// refers to is opened and registered under its name, filters are pushed
+    /// down into the table scans.  Only a single read-only statement is
+    /// allowed, statements that create or change data are rejected.
+    ///
+    /// With the `vector-search-udtf` feature, `search_<table>` runs a vector
+    /// search on a table, see `VectorSearchFunction` in
+    /// [`crate::table::datafusion`] for its arguments.  The results have the columns of the table and a
+    /// `_distance` column:
+    ///
+    /// ```sql
+    /// SELECT id, _distance FROM search_items('[0.1, 0.2]', 5) WHERE label = 'cat'
+    /// ```
+    ///
+    /// ```no_run
+    /// # use futures::TryStreamExt;
+    /// # async fn example(db: &lancedb::Connection) -> lancedb::Result<()> {
+    /// let results = db
+    ///     .sql("SELECT label, count(*) AS n FROM items WHERE price > 10 GROUP BY label")
+    ///     .await?
+    ///     .try_collect::<Vec<_>>()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sql(&self, sql: &str) -> Result<SendableRecordBatchStream> {
+        let sql_error = |err: DataFusionError| Error::InvalidInput {
+            message: format!("invalid SQL query: {}", err),
+        };
+        let ctx = SessionContext::new();
+        let state = ctx.state();
+        let statement = state.sql_to_statement(sql, "generic").map_err(sql_error)?;
+        for reference in state
+            .resolve_table_references(&statement)
+            .map_err(sql_error)?
+        {
+            if reference.schema().is_some() {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "tables must be referred to by their name, not {}",
+                        reference
+                    ),
+                });
+            }
+            let table = match self.open_table(reference.table()).execute().await {
+                Ok(table) => table,
+                #[cfg(feature = "vector-search-udtf")]
+                Err(Error::TableNotFound { .. })
+                    if reference.table().starts_with(SQL_VECTOR_SEARCH_PREFIX) =>
+                {
+                    let name = &reference.table()[SQL_VECTOR_SEARCH_PREFIX.len()..];
+                    let table = self.open_table(name).execute().await?;
+                    ctx.register_udtf(reference.table(), table.vector_search_function().await?);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let provider = BaseTableAdapter::try_new(table.base_table().clone()).await?;
+            ctx.register_table(reference, Arc::new(provider))
+                .map_err(sql_error)?;
+        }
+        let options = SQLOptions::new()
+            .with_allow_ddl(false)
+            .with_allow_dml(false)
+            .with_allow_statements(false);
+        let stream = ctx
+            .sql_with_options(sql, options)
+            .await
+            .map_err(sql_error)?
+            .execute_stream()
+            .await
+            .map_err(sql_error)?;
+        Ok(DatasetRecordBatchStream::new(stream).into())
+    }
+
+    /// Start a transaction that commits writes to several tables together
+    ///
+    /// See [`crate::transaction`] for details. This is only supported for local
+    /// tables.
+    pub fn transaction(&self) -> Transaction {
+        Transaction::new(self.internal.clone())
+    }
+
+    /// Copy a table to another database.
+    ///
+    /// The data is streamed from this connection to `target`, which can be of a
+    /// different kind. For example, this can be used to move a local table to
+    /// LanceDB Cloud, or to make a local copy of a cloud table. Only the latest
+    /// version of the table is copied.
+    ///
+    /// # Arguments
+    /// * `source_name` - The name of the table to copy
+    /// * `target` - The connection to create the new table in, can be this connection
+    /// * `target_name` - The name of the new table
+    pub fn copy_table(
+        &self,
+        source_name: impl Into<String>,
+        target: &Self,
+        target_name: impl Into<String>,
+    ) -> CopyTableBuilder {
+        CopyTableBuilder::new(
+            self.clone(),
+            source_name.into(),
+            target.clone(),
+            target_name.into(),
+        )
+    }
+
     /// Drop a table in the database.
     ///
     /// # Arguments
@@ -577,6 +1266,62 @@ impl Connection {
         self.internal.drop_table(name.as_ref()).await
     }
 
+    /// Create several tables at once.
+    ///
+    /// The tables are created concurrently, a few at a time, which is much faster
+    /// than creating them one after another against a remote database.
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use arrow_schema::{DataType, Field, Schema};
+    /// # async fn example(db: &lancedb::Connection) -> lancedb::Result<()> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+    /// let tables = db
+    ///     .create_tables([
+    ///         db.create_empty_table("users", schema.clone()),
+    ///         db.create_empty_table("events", schema.clone()),
+    ///     ])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Returns
+    /// The created tables, in the same order as the builders. If any table fails to
+    /// be created the first error is returned. Tables created before the failure are
+    /// not rolled back.
+    pub async fn create_tables(
+        &self,
+        tables: impl IntoIterator<Item = CreateTableBuilder<false>>,
+    ) -> Result<Vec<Table>> {
+        futures::stream::iter(tables)
+            .map(|builder| builder.execute())
+            .buffered(BULK_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    /// Drop several tables at once.
+    ///
+    /// The tables are dropped concurrently, a few at a time. If any table fails to
+    /// be dropped the first error is returned. Tables dropped before the failure
+    /// stay dropped.
+    pub async fn drop_tables(
+        &self,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<()> {
+        let names = names
+            .into_iter()
+            .map(|name| name.as_ref().to_string())
+            .collect::<Vec<_>>();
+        futures::stream::iter(names)
+            .map(|name| async move { self.internal.drop_table(&name).await })
+            .buffer_unordered(BULK_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
     /// Drop the database
     ///
     /// This is the same as dropping all of the tables
@@ -590,28 +1335,151 @@ impl Connection {
         self.internal.drop_all_tables().await
     }
 
-    /// Get the in-memory embedding registry.
-    /// It's important to note that the embedding registry is not persisted across connections.
-    /// So if a table contains embeddings, you will need to make sure that you are using a connection that has the same embedding functions registered
-    pub fn embedding_registry(&self) -> &dyn EmbeddingRegistry {
-        self.embedding_registry.as_ref()
+    /// Drops all tables in the database, returning their names
+    ///
+    /// Use [`DropAllTablesBuilder::dry_run`] to see which tables would be
+    /// dropped without dropping them.
+    pub fn drop_all_tables_builder(&self) -> DropAllTablesBuilder {
+        DropAllTablesBuilder::new(self.internal.clone())
     }
-}
 
-/// A request to connect to a database
-#[derive(Clone, Debug)]
-pub struct ConnectRequest {
-    /// Database URI
+    /// Back up every table in the database to another location.
     ///
-    /// ### Accpeted URI formats
+    /// The latest version of every table is read before any table is copied,
+    /// and each table is copied at that version, along with only the data and
+    /// index files it references, so the backup is not affected by writes that
+    /// happen while it runs. The versions are read one table after another, so
+    /// a write to several tables that is committed while they are read may be
+    /// in the backup for some of the tables only. Tables that already exist at
+    /// the target are replaced.
     ///
-    /// - `/path/to/database` - local database on file system.
-    /// - `s3://bucket/path/to/database` or `gs://bucket/path/to/database` - database on cloud object store
-    /// - `db://dbname` - LanceDB Cloud
-    pub uri: String,
-
-    #[cfg(feature = "remote")]
-    pub client_config: ClientConfig,
+    /// The target uses the same storage options as this connection. This is only
+    /// supported in LanceDB OSS.
+    ///
+    /// # Arguments
+    /// * `target_uri` - The URI of the directory to write the backup to
+    pub async fn backup(&self, target_uri: impl AsRef<str>) -> Result<()> {
+        self.internal.backup(target_uri.as_ref()).await
+    }
+
+    /// Restore every table from a backup made with [`Self::backup`].
+    ///
+    /// Tables in the backup replace tables with the same name in this database.
+    /// Each replacement is copied in full before the existing table is swapped
+    /// out, so a restore that fails part way leaves the existing table as it
+    /// was. Other tables are left untouched. This is only supported in LanceDB
+    /// OSS.
+    ///
+    /// # Arguments
+    /// * `source_uri` - The URI of the backup to restore from
+    pub async fn restore(&self, source_uri: impl AsRef<str>) -> Result<()> {
+        self.internal.restore(source_uri.as_ref()).await
+    }
+
+    /// Get the names of the other databases available to this connection
+    ///
+    /// The names will be returned in lexicographical order (ascending)
+    ///
+    /// This is only supported in LanceDB Cloud.
+    pub fn database_names(&self) -> DatabaseNamesBuilder {
+        DatabaseNamesBuilder::new(self.catalog.clone())
+    }
+
+    /// Create a new database alongside the current one.
+    ///
+    /// This is only supported in LanceDB Cloud.
+    pub async fn create_database(&self, name: impl Into<String>) -> Result<()> {
+        let catalog = self.catalog.as_ref().ok_or_else(Self::no_catalog_error)?;
+        catalog
+            .create_database(CreateDatabaseRequest {
+                name: name.into(),
+                mode: CreateDatabaseMode::Create,
+                options: HashMap::new(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Drop a database (and all of its tables).
+    ///
+    /// This is only supported in LanceDB Cloud.
+    pub async fn drop_database(&self, name: impl AsRef<str>) -> Result<()> {
+        let catalog = self.catalog.as_ref().ok_or_else(Self::no_catalog_error)?;
+        catalog.drop_database(name.as_ref()).await
+    }
+
+    /// Switch to a different database
+    ///
+    /// Returns a new connection to the database with the given name.  The new
+    /// connection shares the credentials, client configuration and embedding
+    /// registry of this connection.  This connection is not modified.
+    ///
+    /// This is only supported in LanceDB Cloud.
+    ///
+    /// # Returns
+    /// A new [`Connection`], or [`Error::DatabaseNotFound`] if the database does not exist.
+    pub async fn use_database(&self, name: impl Into<String>) -> Result<Self> {
+        let catalog = self.catalog.as_ref().ok_or_else(Self::no_catalog_error)?;
+        let name = name.into();
+        let internal = catalog
+            .open_database(OpenDatabaseRequest {
+                name: name.clone(),
+                database_options: HashMap::new(),
+            })
+            .await?;
+        Ok(Self {
+            uri: format!("db://{}", name),
+            internal,
+            catalog: self.catalog.clone(),
+            embedding_registry: self.embedding_registry.clone(),
+            default_embedding: self.default_embedding.clone(),
+        })
+    }
+
+    fn no_catalog_error() -> Error {
+        Error::NotSupported {
+            message: "managing databases is only supported in LanceDB Cloud".to_string(),
+        }
+    }
+
+    /// Get the in-memory embedding registry.
+    /// It's important to note that the embedding registry is not persisted across connections.
+    /// So if a table contains embeddings, you will need to make sure that you are using a connection that has the same embedding functions registered
+    pub fn embedding_registry(&self) -> &dyn EmbeddingRegistry {
+        self.embedding_registry.as_ref()
+    }
+}
+
+/// A request to connect to a database
+#[derive(Clone, Debug)]
+pub struct ConnectRequest {
+    /// Database URI
+    ///
+    /// ### Accpeted URI formats
+    ///
+    /// - `/path/to/database` - local database on file system.
+    /// - `s3://bucket/path/to/database` or `gs://bucket/path/to/database` - database on cloud object store
+    /// - `s3+ddb://bucket/path/to/database?ddbTableName=table` - database on S3 that
+    ///   coordinates commits through a DynamoDB table, so that several processes
+    ///   can write to it safely. See
+    ///   [`crate::database::listing::ListingDatabaseOptions::dynamodb_commit_table`]
+    /// - `memory://` - database held in memory, which is lost when the connection
+    ///   is dropped. Each connection gets its own, separate, store.
+    /// - `db://dbname` - LanceDB Cloud
+    ///
+    /// LanceDB Cloud URIs may carry connection options as query parameters, so a
+    /// connection can be configured from a single string, for example
+    /// `db://dbname?api_key=...&region=us-east-1&timeout=30s`. The supported
+    /// parameters are `api_key`, `region`, `host_override`, `timeout` (which sets
+    /// both the connect and read timeouts), `connect_timeout`, `read_timeout`,
+    /// `pool_idle_timeout` and `retries`. Timeouts are given as a number followed
+    /// by `ms`, `s`, `m` or `h`, or as a plain number of seconds. Any other
+    /// parameter is passed on as a storage option. Options set on the
+    /// [`ConnectBuilder`] take precedence over the URI.
+    pub uri: String,
+
+    #[cfg(feature = "remote")]
+    pub client_config: ClientConfig,
 
     /// Database/Catalog specific options
     pub options: HashMap<String, String>,
@@ -627,12 +1495,19 @@ pub struct ConnectRequest {
     /// consistency only applies to read operations. Write operations are
     /// always consistent.
     pub read_consistency_interval: Option<std::time::Duration>,
+
+    /// A wrapper applied to the object store of every table opened or created
+    /// through the connection. Only used by LanceDB OSS.
+    pub object_store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
 }
 
 #[derive(Debug)]
 pub struct ConnectBuilder {
     request: ConnectRequest,
     embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
+    read_only: bool,
+    observer: Option<Arc<dyn DatabaseObserver>>,
+    default_embedding: Option<EmbeddingDefinition>,
 }
 
 impl ConnectBuilder {
@@ -645,8 +1520,12 @@ impl ConnectBuilder {
                 client_config: Default::default(),
                 read_consistency_interval: None,
                 options: HashMap::new(),
+                object_store_wrapper: None,
             },
             embedding_registry: None,
+            read_only: false,
+            observer: None,
+            default_embedding: None,
         }
     }
 
@@ -735,6 +1614,40 @@ impl ConnectBuilder {
         self
     }
 
+    /// Set how requests are retried, for both storage and LanceDB Cloud.
+    ///
+    /// For LanceDB Cloud this is the same as setting
+    /// [`ClientConfig::retry_config`]. For LanceDB OSS the number of retries is
+    /// passed to the object store, unless `client_max_retries` has been set as
+    /// a storage option. See [`RetryConfig`] for details.
+    pub fn retry_config(mut self, config: RetryConfig) -> Self {
+        for (key, value) in config.storage_options() {
+            self.request.options.entry(key).or_insert(value);
+        }
+        #[cfg(feature = "remote")]
+        {
+            self.request.client_config.retry_config = config;
+        }
+        self
+    }
+
+    /// Set the timeouts for requests, for both storage and LanceDB Cloud.
+    ///
+    /// For LanceDB Cloud this is the same as setting
+    /// [`ClientConfig::timeout_config`]. For LanceDB OSS the timeouts are passed
+    /// to the object store, unless the matching storage options have been set.
+    /// See [`TimeoutConfig`] for details.
+    pub fn timeout_config(mut self, config: TimeoutConfig) -> Self {
+        for (key, value) in config.storage_options() {
+            self.request.options.entry(key).or_insert(value);
+        }
+        #[cfg(feature = "remote")]
+        {
+            self.request.client_config.timeout_config = config;
+        }
+        self
+    }
+
     /// Provide a custom [`EmbeddingRegistry`] to use for this connection.
     pub fn embedding_registry(mut self, registry: Arc<dyn EmbeddingRegistry>) -> Self {
         self.embedding_registry = Some(registry);
@@ -779,6 +1692,17 @@ impl ConnectBuilder {
         self
     }
 
+    /// Wrap the object store used to read and write tables.
+    ///
+    /// This can be used to add a layer, such as caching or metrics, between
+    /// LanceDB and the underlying storage. The wrapper is applied whenever a
+    /// table is opened or created through this connection. This only affects
+    /// LanceDB OSS.
+    pub fn object_store_wrapper(mut self, wrapper: Arc<dyn WrappingObjectStore>) -> Self {
+        self.request.object_store_wrapper = Some(wrapper);
+        self
+    }
+
     /// The interval at which to check for updates from other processes. This
     /// only affects LanceDB OSS.
     ///
@@ -789,6 +1713,10 @@ impl ConnectBuilder {
     /// If more than that duration has passed since the last read, the read will
     /// check for updates from other processes.
     ///
+    /// Setting this to [`std::time::Duration::MAX`] is the same as leaving it
+    /// unset: tables only see updates from other processes when they are
+    /// refreshed manually with [`crate::Table::checkout_latest`].
+    ///
     /// This only affects read operations. Write operations are always
     /// consistent.
     ///
@@ -798,15 +1726,85 @@ impl ConnectBuilder {
         mut self,
         read_consistency_interval: std::time::Duration,
     ) -> Self {
-        self.request.read_consistency_interval = Some(read_consistency_interval);
+        self.request.read_consistency_interval =
+            if read_consistency_interval == std::time::Duration::MAX {
+                None
+            } else {
+                Some(read_consistency_interval)
+            };
         self
     }
 
+    /// Move the options in the query string of a LanceDB Cloud URI into the
+    /// builder, without overriding options that were set explicitly
+    #[cfg(feature = "remote")]
+    fn with_uri_options(mut self) -> Result<Self> {
+        use crate::remote::util::parse_duration;
+
+        let mut url = url::Url::parse(&self.request.uri).map_err(|err| Error::InvalidInput {
+            message: format!("invalid database URI '{}': {}", self.request.uri, err),
+        })?;
+        if url.query().is_none() {
+            return Ok(self);
+        }
+        let client_config = &mut self.request.client_config;
+        for (key, value) in url.query_pairs() {
+            let (key, value) = (key.into_owned(), value.into_owned());
+            match key.as_str() {
+                "api_key" | "region" | "host_override" => {
+                    let key = format!("{}{}", OPT_REMOTE_PREFIX, key);
+                    self.request.options.entry(key).or_insert(value);
+                }
+                "timeout" => {
+                    let timeout = parse_duration(&value)?;
+                    let timeouts = &mut client_config.timeout_config;
+                    timeouts.connect_timeout.get_or_insert(timeout);
+                    timeouts.read_timeout.get_or_insert(timeout);
+                }
+                "connect_timeout" => {
+                    let timeout = parse_duration(&value)?;
+                    client_config
+                        .timeout_config
+                        .connect_timeout
+                        .get_or_insert(timeout);
+                }
+                "read_timeout" => {
+                    let timeout = parse_duration(&value)?;
+                    client_config
+                        .timeout_config
+                        .read_timeout
+                        .get_or_insert(timeout);
+                }
+                "pool_idle_timeout" => {
+                    let timeout = parse_duration(&value)?;
+                    client_config
+                        .timeout_config
+                        .pool_idle_timeout
+                        .get_or_insert(timeout);
+                }
+                "retries" => {
+                    let retries = value.parse::<u8>().map_err(|_| Error::InvalidInput {
+                        message: format!("invalid number of retries '{}'", value),
+                    })?;
+                    client_config.retry_config.retries.get_or_insert(retries);
+                }
+                _ => {
+                    self.request.options.entry(key).or_insert(value);
+                }
+            }
+        }
+        // The query string may hold the API key, so it is not kept in the URI
+        url.set_query(None);
+        self.request.uri = url.to_string();
+        Ok(self)
+    }
+
     #[cfg(feature = "remote")]
     fn execute_remote(self) -> Result<Connection> {
         use crate::remote::db::RemoteDatabaseOptions;
 
-        let options = RemoteDatabaseOptions::parse_from_map(&self.request.options)?;
+        let builder = self.with_uri_options()?;
+        let options = RemoteDatabaseOptions::parse_from_map(&builder.request.options)?;
 
         let region = options.region.ok_or_else(|| Error::InvalidInput {
             message: "A region is required when connecting to LanceDb Cloud".to_string(),
@@ -817,19 +1815,29 @@ impl ConnectBuilder {
 
         let storage_options = StorageOptions(options.storage_options.clone());
         let internal = Arc::new(crate::remote::db::RemoteDatabase::try_new(
-            &self.request.uri,
+            &builder.request.uri,
+            &api_key,
+            &region,
+            options.host_override.clone(),
+            builder.request.client_config.clone(),
+            storage_options.clone().into(),
+        )?);
+        let catalog = Arc::new(RemoteCatalog::try_new(
+            &builder.request.uri,
             &api_key,
             &region,
             options.host_override,
-            self.request.client_config,
+            builder.request.client_config,
             storage_options.into(),
         )?);
         Ok(Connection {
             internal,
-            uri: self.request.uri,
-            embedding_registry: self
+            catalog: Some(catalog),
+            uri: builder.request.uri,
+            embedding_registry: builder
                 .embedding_registry
                 .unwrap_or_else(|| Arc::new(MemoryRegistry::new())),
+            default_embedding: None,
         })
     }
 
@@ -841,20 +1849,74 @@ impl ConnectBuilder {
         })
     }
 
+    /// Open the connection in read-only mode
+    ///
+    /// Any operation that would modify the database or one of its tables, such as
+    /// creating or dropping a table, adding data, or creating an index, will fail
+    /// with [`Error::ReadOnly`].
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Embed a column by default in every table created through this connection
+    ///
+    /// When a table is created with [`Connection::create_table`] without any
+    /// embeddings configured, and the data has the definition's source column
+    /// but not its embedding column, the definition is added to the table as if
+    /// [`CreateTableBuilder::add_embedding`] had been called. As with any other
+    /// embedding, it is then also applied to data added to the table later.
+    ///
+    /// Embeddings are only computed for data given as a [`RecordBatchReader`],
+    /// so the default is not applied to tables created from a stream with
+    /// [`Connection::create_table_streaming`] or from files with
+    /// [`Connection::create_table_from_uri`], nor to empty tables.  Streams
+    /// and files can't be added to a table once it has an embedding either,
+    /// see [`crate::Table::add_stream`].
+    ///
+    /// The embedding function must be registered in the connection's
+    /// [`EmbeddingRegistry`] by the time the table is created.
+    pub fn default_embedding(mut self, definition: EmbeddingDefinition) -> Self {
+        self.default_embedding = Some(definition);
+        self
+    }
+
+    /// Register an observer that is told when tables are created, dropped, or
+    /// written to through this connection
+    ///
+    /// See [`DatabaseObserver`] for details.
+    pub fn observer(mut self, observer: Arc<dyn DatabaseObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
     /// Establishes a connection to the database
     pub async fn execute(self) -> Result<Connection> {
-        if self.request.uri.starts_with("db") {
-            self.execute_remote()
+        let read_only = self.read_only;
+        let observer = self.observer.clone();
+        let default_embedding = self.default_embedding.clone();
+        let mut connection = if self.request.uri.starts_with("db") {
+            self.execute_remote()?
         } else {
             let internal = Arc::new(ListingDatabase::connect_with_options(&self.request).await?);
-            Ok(Connection {
+            Connection {
                 internal,
+                catalog: None,
                 uri: self.request.uri,
                 embedding_registry: self
                     .embedding_registry
                     .unwrap_or_else(|| Arc::new(MemoryRegistry::new())),
-            })
+                default_embedding: None,
+            }
+        };
+        connection.default_embedding = default_embedding;
+        if let Some(observer) = observer {
+            connection.internal = Arc::new(ObservedDatabase::new(connection.internal, observer));
         }
+        if read_only {
+            connection.internal = Arc::new(ReadOnlyDatabase::new(connection.internal));
+        }
+        Ok(connection)
     }
 }
 
@@ -884,6 +1946,7 @@ impl CatalogConnectBuilder {
                 client_config: Default::default(),
                 read_consistency_interval: None,
                 options: HashMap::new(),
+                object_store_wrapper: None,
             },
         }
     }
@@ -921,11 +1984,14 @@ mod test_utils {
         where
             T: Into<reqwest::Body>,
         {
-            let internal = Arc::new(crate::remote::db::RemoteDatabase::new_mock(handler));
+            let internal = Arc::new(crate::remote::db::RemoteDatabase::new_mock(handler.clone()));
+            let catalog = Arc::new(RemoteCatalog::new_mock(handler));
             Self {
                 internal,
+                catalog: Some(catalog),
                 uri: "db://test".to_string(),
                 embedding_registry: Arc::new(MemoryRegistry::new()),
+                default_embedding: None,
             }
         }
     }
@@ -940,7 +2006,7 @@ mod tests {
     use crate::query::QueryBase;
     use crate::query::{ExecutableQuery, QueryExecutionOptions};
     use arrow::compute::concat_batches;
-    use arrow_array::RecordBatchReader;
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator, RecordBatchReader};
     use arrow_schema::{DataType, Field, Schema};
     use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
     use futures::{stream, TryStreamExt};
@@ -961,6 +2027,75 @@ mod tests {
         assert_eq!(db.uri, uri);
     }
 
+    #[cfg(feature = "remote")]
+    #[tokio::test]
+    async fn test_connect_uri_options() {
+        let uri = concat!(
+            "db://mydb/prefix?api_key=key&region=us-east-1",
+            "&timeout=30s&read_timeout=2m&retries=5&foo=bar"
+        );
+        let builder = connect(uri).region("us-west-2").with_uri_options().unwrap();
+        assert_eq!(builder.request.uri, "db://mydb/prefix");
+        let options = &builder.request.options;
+        assert_eq!(options[OPT_REMOTE_API_KEY], "key");
+        // Options set on the builder take precedence
+        assert_eq!(options[OPT_REMOTE_REGION], "us-west-2");
+        assert_eq!(options["foo"], "bar");
+        let timeouts = &builder.request.client_config.timeout_config;
+        assert_eq!(
+            timeouts.connect_timeout,
+            Some(std::time::Duration::from_secs(30))
+        );
+        assert_eq!(
+            timeouts.read_timeout,
+            Some(std::time::Duration::from_secs(120))
+        );
+        assert_eq!(timeouts.pool_idle_timeout, None);
+        assert_eq!(builder.request.client_config.retry_config.retries, Some(5));
+
+        let db = connect("db://mydb?api_key=key&region=us-east-1")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(db.uri(), "db://mydb");
+
+        let err = connect("db://mydb?api_key=key&region=us-east-1&timeout=soon")
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_connect_retry_and_timeout_config() {
+        let builder = connect("s3://bucket/path")
+            .storage_option("timeout", "10s")
+            .retry_config(RetryConfig {
+                retries: Some(5),
+                ..Default::default()
+            })
+            .timeout_config(TimeoutConfig {
+                connect_timeout: Some(std::time::Duration::from_secs(3)),
+                read_timeout: Some(std::time::Duration::from_secs(30)),
+                ..Default::default()
+            });
+        let options = &builder.request.options;
+        assert_eq!(options["client_max_retries"], "5");
+        assert_eq!(options["connect_timeout"], "3000ms");
+        // Storage options that were set explicitly are kept
+        assert_eq!(options["timeout"], "10s");
+        assert!(!options.contains_key("pool_idle_timeout"));
+        #[cfg(feature = "remote")]
+        {
+            let client_config = &builder.request.client_config;
+            assert_eq!(client_config.retry_config.retries, Some(5));
+            assert_eq!(
+                client_config.timeout_config.read_timeout,
+                Some(std::time::Duration::from_secs(30))
+            );
+        }
+    }
+
     #[cfg(not(windows))]
     #[tokio::test]
     async fn test_connect_relative() {
@@ -1242,6 +2377,954 @@ mod tests {
         assert_eq!(other_schema, overwritten.schema().await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_create_and_drop_tables() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        let names = (0..20)
+            .map(|i| format!("table_{:02}", i))
+            .collect::<Vec<_>>();
+        let tables = db
+            .create_tables(
+                names
+                    .iter()
+                    .map(|name| db.create_empty_table(name, schema.clone())),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            tables.iter().map(|t| t.name()).collect::<Vec<_>>(),
+            names.iter().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert_eq!(db.table_names().execute().await.unwrap(), names);
+
+        db.drop_tables(&names[..10]).await.unwrap();
+        assert_eq!(db.table_names().execute().await.unwrap(), names[10..]);
+
+        // Creating a table that already exists fails the whole batch
+        let result = db
+            .create_tables([
+                db.create_empty_table("new_table", schema.clone()),
+                db.create_empty_table(&names[15], schema.clone()),
+            ])
+            .await;
+        assert!(matches!(result, Err(Error::TableAlreadyExists { .. })));
+    }
+
+    #[derive(Default, Debug)]
+    struct CountingWrapper {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl WrappingObjectStore for CountingWrapper {
+        fn wrap(
+            &self,
+            original: Arc<dyn object_store::ObjectStore>,
+        ) -> Arc<dyn object_store::ObjectStore> {
+            self.calls
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            original
+        }
+    }
+
+    #[tokio::test]
+    async fn test_object_store_wrapper() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let wrapper = Arc::new(CountingWrapper::default());
+        let db = connect(uri)
+            .object_store_wrapper(wrapper.clone())
+            .execute()
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        db.create_empty_table("test", schema)
+            .execute()
+            .await
+            .unwrap();
+        let after_create = wrapper.calls.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(after_create > 0);
+
+        db.open_table("test").execute().await.unwrap();
+        assert!(wrapper.calls.load(std::sync::atomic::Ordering::Relaxed) > after_create);
+    }
+
+    #[tokio::test]
+    async fn test_open_table_at_version() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let table = db
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        let num_rows = table.count_rows(None).await.unwrap();
+        table.add(make_data()).execute().await.unwrap();
+
+        table.tags().create("initial", 1).await.unwrap();
+
+        let old = db.open_table("test").version(1).execute().await.unwrap();
+        assert_eq!(old.version().await.unwrap(), 1);
+        assert_eq!(old.count_rows(None).await.unwrap(), num_rows);
+        assert!(old.add(make_data()).execute().await.is_err());
+
+        let tagged = db
+            .open_table("test")
+            .tag("initial")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tagged.version().await.unwrap(), 1);
+
+        let latest = db.open_table("test").execute().await.unwrap();
+        assert_eq!(latest.count_rows(None).await.unwrap(), num_rows * 2);
+    }
+
+    #[tokio::test]
+    async fn test_memory_database() {
+        let db = connect("memory://").execute().await.unwrap();
+        assert!(db.table_names().execute().await.unwrap().is_empty());
+
+        let table = db
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        let num_rows = table.count_rows(None).await.unwrap();
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+
+        let table = db.open_table("test").execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), num_rows);
+
+        // Each connection has its own store
+        let other = connect("memory://").execute().await.unwrap();
+        assert!(other.table_names().execute().await.unwrap().is_empty());
+
+        db.drop_table("test").await.unwrap();
+        assert!(db.table_names().execute().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_connection() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        db.create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        let db = connect(uri).read_only(true).execute().await.unwrap();
+        assert_eq!(db.table_names().execute().await.unwrap(), vec!["test"]);
+        let table = db.open_table("test").execute().await.unwrap();
+        let num_rows = table.count_rows(None).await.unwrap();
+
+        assert!(matches!(
+            table.add(make_data()).execute().await,
+            Err(Error::ReadOnly { .. })
+        ));
+        assert!(matches!(
+            table.delete("true").await,
+            Err(Error::ReadOnly { .. })
+        ));
+        assert!(matches!(
+            db.create_table("other", make_data()).execute().await,
+            Err(Error::ReadOnly { .. })
+        ));
+        assert!(matches!(
+            db.drop_table("test").await,
+            Err(Error::ReadOnly { .. })
+        ));
+        // Operations only local tables support can read but not write
+        assert!(matches!(
+            table.set_column_default("id", Some("0")).await,
+            Err(Error::ReadOnly { .. })
+        ));
+        assert_eq!(table.sample(5).execute().await.unwrap().num_rows(), 5);
+        assert_eq!(table.count_rows(None).await.unwrap(), num_rows);
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().join("db");
+        let uri = uri.to_str().unwrap();
+        let backup_uri = tmp_dir.path().join("backup");
+        let backup_uri = backup_uri.to_str().unwrap();
+
+        let db = connect(uri).execute().await.unwrap();
+        let table = db
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        table.delete("id < 100").await.unwrap();
+        let num_rows = table.count_rows(None).await.unwrap();
+        db.create_table("other", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        db.backup(backup_uri).await.unwrap();
+
+        // Changes after the backup are not included
+        table.add(make_data()).execute().await.unwrap();
+
+        let backup = connect(backup_uri).execute().await.unwrap();
+        assert_eq!(
+            backup.table_names().execute().await.unwrap(),
+            vec!["other", "test"]
+        );
+        let backup_table = backup.open_table("test").execute().await.unwrap();
+        assert_eq!(backup_table.count_rows(None).await.unwrap(), num_rows);
+        assert_eq!(backup_table.list_versions().await.unwrap().len(), 1);
+
+        db.restore(backup_uri).await.unwrap();
+        let table = db.open_table("test").execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), num_rows);
+        // The tables that were swapped in and out are not left behind
+        assert_eq!(
+            db.table_names().execute().await.unwrap(),
+            vec!["other", "test"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_table() {
+        let tmp_dir = tempdir().unwrap();
+        // A local directory is renamed, the files of a memory table are copied
+        for uri in [tmp_dir.path().to_str().unwrap(), "memory://rename"] {
+            let db = connect(uri).execute().await.unwrap();
+            let table = db.create_table("old", make_data()).execute().await.unwrap();
+            table.delete("id < 100").await.unwrap();
+            let num_rows = table.count_rows(None).await.unwrap();
+            db.create_table("taken", make_data())
+                .execute()
+                .await
+                .unwrap();
+
+            let err = db.rename_table("old", "taken").await.unwrap_err();
+            assert!(matches!(err, Error::TableAlreadyExists { .. }), "{:?}", err);
+            let err = db.rename_table("missing", "other").await.unwrap_err();
+            assert!(matches!(err, Error::TableNotFound { .. }), "{:?}", err);
+            let err = db.rename_table("old", "../escaped").await.unwrap_err();
+            assert!(matches!(err, Error::InvalidTableName { .. }), "{:?}", err);
+            let err = db.rename_table("../escaped", "new").await.unwrap_err();
+            assert!(matches!(err, Error::InvalidTableName { .. }), "{:?}", err);
+
+            db.rename_table("old", "new").await.unwrap();
+            assert_eq!(
+                db.table_names().execute().await.unwrap(),
+                vec!["new", "taken"]
+            );
+            let renamed = db.open_table("new").execute().await.unwrap();
+            assert_eq!(renamed.count_rows(None).await.unwrap(), num_rows);
+            // The history of the table is kept
+            assert_eq!(renamed.list_versions().await.unwrap().len(), 2);
+            let err = db.open_table("old").execute().await.unwrap_err();
+            assert!(matches!(err, Error::TableNotFound { .. }), "{:?}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sql() {
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("price", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(Int32Array::from_iter_values((0..10).map(|i| i * 10))),
+            ],
+        )
+        .unwrap();
+        db.create_table("items", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let results = db
+            .sql("SELECT id, price FROM items WHERE price >= 50 ORDER BY id")
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(
+            results["id"].as_ref(),
+            &Int32Array::from_iter_values(5..10) as &dyn arrow_array::Array
+        );
+
+        let err = db.sql("SELECT * FROM missing").await.err().unwrap();
+        assert!(matches!(err, Error::TableNotFound { .. }), "{:?}", err);
+        let err = db.sql("DROP TABLE items").await.err().unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        assert!(db.table_exists("items").await.unwrap());
+    }
+
+    #[cfg(feature = "vector-search-udtf")]
+    #[tokio::test]
+    async fn test_sql_vector_search() {
+        use arrow_array::{types::Float32Type, ArrayRef, FixedSizeListArray, Float32Array};
+
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let vectors = (0..10).map(|i| Some(vec![Some(i as f32), Some(i as f32)]));
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(0..10)) as ArrayRef,
+            ),
+            (
+                "vector",
+                Arc::new(FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vectors, 2))
+                    as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        db.create_table("items", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let results = db
+            .sql(
+                "SELECT id, _distance FROM search_items('[0, 0]', 3) \
+                 WHERE id % 2 = 0 ORDER BY _distance",
+            )
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(
+            results["id"].as_ref(),
+            &Int32Array::from(vec![0, 2, 4]) as &dyn arrow_array::Array
+        );
+        assert_eq!(
+            results["_distance"].as_ref(),
+            &Float32Array::from(vec![0.0, 8.0, 32.0]) as &dyn arrow_array::Array
+        );
+
+        let err = db
+            .sql("SELECT * FROM search_missing('[0, 0]')")
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::TableNotFound { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_copy_table() {
+        let source_dir = tempdir().unwrap();
+        let target_dir = tempdir().unwrap();
+        let source = connect(source_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let target = connect(target_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+
+        let table = source
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let result = source
+            .copy_table("test", &target, "copy")
+            .copy_indices(true)
+            .execute()
+            .await
+            .unwrap();
+        // A BTree index has no parameters to lose
+        assert!(result.indices_with_default_params.is_empty());
+        let copy = result.table;
+        assert_eq!(target.table_names().execute().await.unwrap(), vec!["copy"]);
+        assert_eq!(
+            copy.count_rows(None).await.unwrap(),
+            table.count_rows(None).await.unwrap()
+        );
+        assert_eq!(copy.schema().await.unwrap(), table.schema().await.unwrap());
+        let indices = copy.list_indices().await.unwrap();
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices[0].index_type, IndexType::BTree);
+        assert_eq!(indices[0].columns, vec!["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_transaction() {
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let documents = db
+            .create_table("documents", make_data())
+            .execute()
+            .await
+            .unwrap();
+        let chunks = db
+            .create_table("chunks", make_data())
+            .execute()
+            .await
+            .unwrap();
+
+        let mut transaction = db.transaction();
+        transaction.add(documents.add(make_data())).await.unwrap();
+        transaction.add(chunks.add(make_data())).await.unwrap();
+        // Nothing is visible until the transaction commits
+        assert_eq!(documents.count_rows(None).await.unwrap(), 20000);
+        assert_eq!(chunks.count_rows(None).await.unwrap(), 20000);
+        transaction.commit().await.unwrap();
+        assert_eq!(documents.count_rows(None).await.unwrap(), 40000);
+        assert_eq!(chunks.count_rows(None).await.unwrap(), 40000);
+
+        // An append conflicts with a concurrent overwrite, which is found
+        // before either table is committed
+        let mut transaction = db.transaction();
+        transaction.add(documents.add(make_data())).await.unwrap();
+        transaction.add(chunks.add(make_data())).await.unwrap();
+        chunks
+            .add(make_data())
+            .mode(crate::table::AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        let documents_version = documents.version().await.unwrap();
+        let chunks_version = chunks.version().await.unwrap();
+        let err = transaction.commit().await.unwrap_err();
+        assert!(matches!(err, Error::CommitConflict { .. }), "{:?}", err);
+        assert_eq!(documents.version().await.unwrap(), documents_version);
+        assert_eq!(chunks.version().await.unwrap(), chunks_version);
+        assert_eq!(documents.count_rows(None).await.unwrap(), 40000);
+        assert_eq!(chunks.count_rows(None).await.unwrap(), 20000);
+
+        // Rolling back removes the staged data files
+        let data_dir = tmp_dir.path().join("documents.lance").join("data");
+        let data_files = std::fs::read_dir(&data_dir).unwrap().count();
+        let mut transaction = db.transaction();
+        transaction.add(documents.add(make_data())).await.unwrap();
+        assert!(std::fs::read_dir(&data_dir).unwrap().count() > data_files);
+        transaction.rollback().await.unwrap();
+        assert_eq!(std::fs::read_dir(&data_dir).unwrap().count(), data_files);
+        assert_eq!(documents.count_rows(None).await.unwrap(), 40000);
+
+        // Tables from other connections are rejected
+        let other_dir = tempdir().unwrap();
+        let other_db = connect(other_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let mut transaction = other_db.transaction();
+        let err = transaction
+            .add(documents.add(make_data()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        // Undoing a committed append keeps the rows appended after it
+        let mut transaction = db.transaction();
+        transaction.add(documents.add(make_data())).await.unwrap();
+        transaction.commit().await.unwrap();
+        let version = documents.version().await.unwrap();
+        documents.add(make_data()).execute().await.unwrap();
+        documents.base_table().revert_staged(version).await.unwrap();
+        assert_eq!(documents.count_rows(None).await.unwrap(), 60000);
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl DatabaseObserver for RecordingObserver {
+        fn on_table_created(&self, name: &str) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("created {}", name));
+        }
+
+        fn on_table_dropped(&self, name: &str) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("dropped {}", name));
+        }
+
+        fn on_write_committed(
+            &self,
+            table_name: &str,
+            operation: crate::database::observer::WriteOperation,
+        ) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{:?} {}", operation, table_name));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let observer = Arc::new(RecordingObserver::default());
+        let db = connect(uri)
+            .observer(observer.clone())
+            .execute()
+            .await
+            .unwrap();
+
+        let table = db
+            .create_table("test", make_data())
+            .execute()
+            .await
+            .unwrap();
+        table.add(make_data()).execute().await.unwrap();
+        // Failed writes are not reported
+        assert!(table.delete("not_a_column > 0").await.is_err());
+        let table = db.open_table("test").execute().await.unwrap();
+        table.delete("id > 10").await.unwrap();
+        // Operations only local tables support go through the observed table
+        table.set_column_default("id", Some("0")).await.unwrap();
+        assert!(table.changes(1).await.is_ok());
+        let mut transaction = db.transaction();
+        transaction.add(table.add(make_data())).await.unwrap();
+        transaction.commit().await.unwrap();
+        db.drop_table("test").await.unwrap();
+
+        assert_eq!(
+            *observer.events.lock().unwrap(),
+            vec![
+                "created test",
+                "Add test",
+                "Delete test",
+                "UpdateMetadata test",
+                "Add test",
+                "dropped test"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_table_exists() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        assert!(!db.table_exists("test").await.unwrap());
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        db.create_empty_table("test", schema.clone())
+            .execute()
+            .await
+            .unwrap();
+        assert!(db.table_exists("test").await.unwrap());
+        assert!(!db.table_exists("other").await.unwrap());
+
+        db.drop_table("test").await.unwrap();
+        assert!(!db.table_exists("test").await.unwrap());
+
+        // A table opened by this connection and dropped by another is gone
+        db.create_empty_table("test", schema)
+            .execute()
+            .await
+            .unwrap();
+        db.open_table("test").execute().await.unwrap();
+        let other = connect(uri).execute().await.unwrap();
+        other.drop_table("test").await.unwrap();
+        assert!(!db.table_exists("test").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_tables() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        assert!(db.list_tables().execute().await.unwrap().is_empty());
+
+        let table = db
+            .create_table("data", make_data())
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        db.create_empty_table("empty", schema)
+            .execute()
+            .await
+            .unwrap();
+
+        let tables = db.list_tables().execute().await.unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].name, "data");
+        assert!(tables.iter().all(|table| table.stats.is_none()));
+
+        let tables = db
+            .list_tables()
+            .include_stats(true)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].name, "data");
+        let data = tables[0].stats.as_ref().unwrap();
+        assert_eq!(data.num_rows, 20000);
+        assert_eq!(data.version, table.version().await.unwrap());
+        assert_eq!(data.num_indices, 1);
+        assert!(data.size_bytes.unwrap() > 0);
+        assert_eq!(tables[1].name, "empty");
+        let empty = tables[1].stats.as_ref().unwrap();
+        assert_eq!(empty.num_rows, 0);
+        assert_eq!(empty.num_indices, 0);
+        assert!(data.last_modified <= empty.last_modified);
+
+        let tables = db
+            .list_tables()
+            .start_after("data")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "empty");
+    }
+
+    #[tokio::test]
+    async fn test_dynamodb_commit_table() {
+        let options = ListingDatabaseOptions::builder()
+            .dynamodb_commit_table("commits")
+            .storage_options([
+                ("aws_region", "us-east-1"),
+                ("aws_access_key_id", "key"),
+                ("aws_secret_access_key", "secret"),
+            ])
+            .build();
+        let db = connect("s3://bucket/db")
+            .database_options(&options)
+            .execute()
+            .await
+            .unwrap();
+        assert!(db.internal.to_string().contains("uri=s3+ddb://bucket/db"));
+
+        let tmp_dir = tempdir().unwrap();
+        let err = connect(tmp_dir.path().to_str().unwrap())
+            .database_options(&options)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_drop_all_tables() {
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        for name in ["a", "b", "c"] {
+            db.create_empty_table(name, schema.clone())
+                .execute()
+                .await
+                .unwrap();
+        }
+
+        let names = db
+            .drop_all_tables_builder()
+            .dry_run(true)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(db.table_names().execute().await.unwrap().len(), 3);
+
+        let names = db.drop_all_tables_builder().execute().await.unwrap();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert!(db.table_names().execute().await.unwrap().is_empty());
+
+        db.create_empty_table("d", schema).execute().await.unwrap();
+        db.drop_all_tables().await.unwrap();
+        assert!(db.table_names().execute().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tenant() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let connect_tenant = |tenant: &str| {
+            connect(uri).database_options(&ListingDatabaseOptions::builder().tenant(tenant).build())
+        };
+        let tenant_a = connect_tenant("tenants/a").execute().await.unwrap();
+        let tenant_b = connect_tenant("tenants/b").execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        tenant_a
+            .create_empty_table("table1", schema.clone())
+            .execute()
+            .await
+            .unwrap();
+        tenant_b
+            .create_empty_table("table2", schema)
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tenant_a.table_names().execute().await.unwrap(),
+            vec!["table1"]
+        );
+        assert_eq!(
+            tenant_b.table_names().execute().await.unwrap(),
+            vec!["table2"]
+        );
+        assert!(tenant_a.open_table("table2").execute().await.is_err());
+        assert!(tmp_dir.path().join("tenants/a/table1.lance").exists());
+
+        let root = connect(uri).execute().await.unwrap();
+        assert!(root.table_names().execute().await.unwrap().is_empty());
+
+        assert!(connect_tenant("a/../b").execute().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_table_cache() {
+        use crate::table::BaseTable;
+
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri)
+            .database_options(
+                &ListingDatabaseOptions::builder()
+                    .table_cache_size(2)
+                    .build(),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let listing = db
+            .database()
+            .as_any()
+            .downcast_ref::<ListingDatabase>()
+            .unwrap();
+        let open = |name: &str| {
+            listing.open_table(OpenTableRequest {
+                name: name.to_string(),
+                index_cache_size: None,
+                lance_read_params: None,
+                version: None,
+                skip_existence_check: false,
+                query_cache_size: None,
+            })
+        };
+
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        db.create_empty_table("test", schema.clone())
+            .execute()
+            .await
+            .unwrap();
+
+        let first = open("test").await.unwrap();
+        let second = open("test").await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // A handle checked out at an old version is not handed out again
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema.clone());
+        Table::new(second.clone())
+            .add(reader)
+            .execute()
+            .await
+            .unwrap();
+        first.checkout(1).await.unwrap();
+        let third = open("test").await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &third));
+        assert_eq!(third.version().await.unwrap(), 2);
+        assert_eq!(third.count_rows(None).await.unwrap(), 1);
+
+        listing.invalidate_table("test").await;
+        let fourth = open("test").await.unwrap();
+        assert!(!Arc::ptr_eq(&third, &fourth));
+
+        // Renaming the table removes it from the cache
+        db.rename_table("test", "renamed").await.unwrap();
+        assert!(matches!(
+            open("test").await,
+            Err(Error::TableNotFound { .. })
+        ));
+        db.rename_table("renamed", "test").await.unwrap();
+
+        // Dropping the table removes it from the cache
+        db.drop_table("test").await.unwrap();
+        assert!(matches!(
+            open("test").await,
+            Err(Error::TableNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_conflict_retries() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let connect_with_retries = |conflict_retries: u32| {
+            connect(uri).database_options(
+                &ListingDatabaseOptions::builder()
+                    .conflict_retries(conflict_retries)
+                    .build(),
+            )
+        };
+        let writer = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..10))],
+        )
+        .unwrap();
+        let table = writer
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Without a read consistency interval the handles below only see the
+        // version they were opened at, so their deletes conflict with the
+        // concurrent delete of the same fragment
+        let no_retries = connect_with_retries(0).execute().await.unwrap();
+        let stale = no_retries.open_table("test").execute().await.unwrap();
+        let retries = connect(uri).execute().await.unwrap();
+        let stale_with_retries = retries.open_table("test").execute().await.unwrap();
+
+        table.delete("x < 5").await.unwrap();
+
+        let err = stale.delete("x < 3").await.unwrap_err();
+        assert!(matches!(err, Error::CommitConflict { .. }), "{:?}", err);
+        assert!(err.is_retryable());
+
+        // With the default retries the delete is rerun on the latest version
+        stale_with_retries.delete("x >= 8").await.unwrap();
+        assert_eq!(stale_with_retries.count_rows(None).await.unwrap(), 3);
+        table.checkout_latest().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_auto_index() {
+        use crate::index::{vector::IvfPqIndexBuilder, Index};
+        use crate::table::AutoIndex;
+        use arrow_array::{types::Float32Type, FixedSizeListArray};
+        use rand::Rng;
+
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri)
+            .database_options(
+                &ListingDatabaseOptions::builder()
+                    .auto_index(AutoIndex::Inline)
+                    .build(),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "vector",
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 8),
+            true,
+        )]));
+        let batches = || {
+            let mut rng = rand::thread_rng();
+            let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                (0..512).map(|_| Some((0..8).map(|_| Some(rng.gen::<f32>())).collect::<Vec<_>>())),
+                8,
+            );
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(vectors)]).unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
+        let table = db.create_table("test", batches()).execute().await.unwrap();
+
+        // Without indices there is nothing to update
+        table.add(batches()).execute().await.unwrap();
+
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let name = table.list_indices().await.unwrap()[0].name.clone();
+        table.add(batches()).execute().await.unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 1536);
+        assert_eq!(stats.num_unindexed_rows, 0);
+
+        // Tables opened by the connection index new rows too
+        let table = db.open_table("test").execute().await.unwrap();
+        table.add(batches()).execute().await.unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_unindexed_rows, 0);
+
+        // In the background, writes made while a run is pending share it
+        let db = connect(uri)
+            .database_options(
+                &ListingDatabaseOptions::builder()
+                    .auto_index(AutoIndex::Background)
+                    .build(),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let table = db.open_table("test").execute().await.unwrap();
+        for _ in 0..3 {
+            table.add(batches()).execute().await.unwrap();
+        }
+        table
+            .wait_for_index(&[name.as_str()], std::time::Duration::from_secs(30))
+            .await
+            .unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 3584);
+
+        let options = HashMap::from([("auto_index".to_string(), "background".to_string())]);
+        let options = ListingDatabaseOptions::parse_from_map(&options).unwrap();
+        assert_eq!(options.auto_index, Some(AutoIndex::Background));
+        assert!(options.storage_options.is_empty());
+    }
+
     #[tokio::test]
     async fn test_connect_catalog() {
         let tmp_dir = tempdir().unwrap();
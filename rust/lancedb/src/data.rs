@@ -3,5 +3,8 @@
 
 //! Data types, schema coercion, and data cleaning and etc.
 
+pub mod export;
+pub mod file;
 pub mod inspect;
+pub mod rows;
 pub mod sanitize;
@@ -0,0 +1,532 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Writing table and query results to Parquet files
+//!
+//! The results are streamed into files under a directory on local disk or
+//! object storage, optionally split into Hive style partitions such as
+//! `year=2024/part-00000.parquet`, which Spark, DuckDB and most other engines
+//! can read directly.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::compute::take_record_batch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use arrow_array::{Array, RecordBatch, UInt32Array};
+use arrow_schema::{Schema, SchemaRef};
+use futures::TryStreamExt;
+use lance::io::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use lance_io::object_writer::ObjectWriter;
+use object_store::path::Path;
+use parquet::arrow::AsyncArrowWriter;
+pub use parquet::file::properties::WriterProperties;
+
+use crate::arrow::SendableRecordBatchStream;
+use crate::error::{Error, Result};
+
+/// The directory name used for rows where a partition column is null
+///
+/// This is the name Hive and Spark use, so they read these rows back as null.
+/// A partition column can't hold this string as a value.
+pub const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Options to control how results are written to Parquet
+#[derive(Debug, Clone)]
+pub struct ExportParquetOptions {
+    /// Columns to partition the files by
+    ///
+    /// Each distinct combination of values is written to its own directory,
+    /// named `column=value`.  The partition columns are not stored in the
+    /// files themselves.  By default the files are not partitioned.
+    pub partition_by: Vec<String>,
+    /// The maximum number of rows in each file
+    ///
+    /// Once a file reaches this size a new file is started.
+    pub max_rows_per_file: usize,
+    /// Options for the storage the files are written to
+    ///
+    /// See available options at <https://lancedb.github.io/lancedb/guides/storage/>
+    pub storage_options: HashMap<String, String>,
+    /// Parquet writer settings, such as compression and row group size
+    pub writer_properties: Option<WriterProperties>,
+}
+
+impl Default for ExportParquetOptions {
+    fn default() -> Self {
+        Self {
+            partition_by: Vec::new(),
+            max_rows_per_file: 1024 * 1024,
+            storage_options: HashMap::new(),
+            writer_properties: None,
+        }
+    }
+}
+
+/// Statistics about an export to Parquet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// The number of rows written
+    pub num_rows: u64,
+    /// The files written, relative to the export directory, in the order they
+    /// were started
+    pub files: Vec<String>,
+}
+
+/// An open file in one partition
+struct PartitionWriter {
+    dir: Path,
+    /// The path of `dir` relative to the export directory
+    relative_dir: String,
+    writer: Option<AsyncArrowWriter<ObjectWriter>>,
+    rows_in_file: usize,
+    num_files: usize,
+}
+
+impl PartitionWriter {
+    fn new(base: &Path, partition_by: &[String], key: &[String]) -> Result<Self> {
+        let mut relative_dir = String::new();
+        for (column, value) in partition_by.iter().zip(key) {
+            let part = format!(
+                "{}={}",
+                escape_partition_part(column)?,
+                escape_partition_part(value)?
+            );
+            relative_dir.push_str(&part);
+            relative_dir.push('/');
+        }
+        // The parts are already escaped, so they are parsed rather than added
+        // with `child`, which would escape the `%` again
+        let dir = if relative_dir.is_empty() {
+            base.clone()
+        } else {
+            Path::parse(format!("{}/{}", base, relative_dir))?
+        };
+        Ok(Self {
+            dir,
+            relative_dir,
+            writer: None,
+            rows_in_file: 0,
+            num_files: 0,
+        })
+    }
+}
+
+/// Escape a partition column or value for use in a directory name
+///
+/// The characters Hive escapes, which include `/`, `=`, `%` and control
+/// characters, are written as `%XX`, so Hive and Spark read back the original
+/// value and no value can name a directory outside of the export directory.
+/// `.` and `..` can't be escaped this way and are rejected.
+fn escape_partition_part(part: &str) -> Result<String> {
+    if part == "." || part == ".." {
+        return Err(Error::InvalidInput {
+            message: format!("cannot write a partition named \"{}\"", part),
+        });
+    }
+    let mut escaped = String::with_capacity(part.len());
+    for c in part.chars() {
+        if c.is_ascii_control()
+            || matches!(
+                c,
+                '"' | '#' | '%' | '\'' | '*' | '/' | ':' | '=' | '?' | '\\' | '{' | '[' | ']' | '^'
+            )
+        {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    Ok(escaped)
+}
+
+struct Exporter {
+    store: ObjectStore,
+    base: Path,
+    schema: SchemaRef,
+    /// The indices of the partition columns in the input
+    partition_indices: Vec<usize>,
+    /// The indices of the columns stored in the files
+    data_indices: Vec<usize>,
+    options: ExportParquetOptions,
+    partitions: HashMap<Vec<String>, PartitionWriter>,
+    stats: ExportStats,
+}
+
+impl Exporter {
+    /// Write a batch, which only has the data columns, to one partition
+    async fn write(&mut self, key: Vec<String>, mut batch: RecordBatch) -> Result<()> {
+        let max_rows = self.options.max_rows_per_file.max(1);
+        let schema = self.schema.clone();
+        let properties = self.options.writer_properties.clone();
+        self.stats.num_rows += batch.num_rows() as u64;
+        let store = &self.store;
+        let files = &mut self.stats.files;
+        let partition = match self.partitions.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let partition =
+                    PartitionWriter::new(&self.base, &self.options.partition_by, entry.key())?;
+                entry.insert(partition)
+            }
+        };
+        loop {
+            if partition.rows_in_file == max_rows {
+                if let Some(writer) = partition.writer.take() {
+                    writer.close().await?;
+                }
+                partition.rows_in_file = 0;
+            }
+            if partition.writer.is_none() {
+                let name = format!("part-{:05}.parquet", partition.num_files);
+                let path = partition.dir.child(name.as_str());
+                let object_writer = store.create(&path).await?;
+                partition.writer = Some(AsyncArrowWriter::try_new(
+                    object_writer,
+                    schema.clone(),
+                    properties.clone(),
+                )?);
+                partition.num_files += 1;
+                files.push(format!("{}{}", partition.relative_dir, name));
+            }
+            let num_rows = (max_rows - partition.rows_in_file).min(batch.num_rows());
+            let writer = partition.writer.as_mut().unwrap();
+            writer.write(&batch.slice(0, num_rows)).await?;
+            partition.rows_in_file += num_rows;
+            if num_rows == batch.num_rows() {
+                return Ok(());
+            }
+            batch = batch.slice(num_rows, batch.num_rows() - num_rows);
+        }
+    }
+
+    /// Split a batch by the values of the partition columns and write each part
+    async fn write_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let data = batch.project(&self.data_indices)?;
+        if self.partition_indices.is_empty() {
+            return self.write(Vec::new(), data).await;
+        }
+        // Group the rows by partition, keeping the partitions in the order
+        // they first appear.  The formatters are not `Send`, so they are
+        // dropped before writing.
+        let groups = {
+            let format_options = FormatOptions::default().with_null(NULL_PARTITION);
+            let columns = self
+                .partition_indices
+                .iter()
+                .map(|index| batch.column(*index))
+                .collect::<Vec<_>>();
+            let formatters = columns
+                .iter()
+                .map(|column| ArrayFormatter::try_new(column.as_ref(), &format_options))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let mut groups: Vec<(Vec<String>, Vec<u32>)> = Vec::new();
+            let mut group_of_key = HashMap::new();
+            for row in 0..batch.num_rows() {
+                let key = formatters
+                    .iter()
+                    .zip(&columns)
+                    .map(|(formatter, column)| {
+                        let value = formatter.value(row).to_string();
+                        // The value would be read back as null
+                        if value == NULL_PARTITION && !column.is_null(row) {
+                            return Err(Error::InvalidInput {
+                                message: format!(
+                                    "cannot write the partition value {}, which is reserved for nulls",
+                                    NULL_PARTITION
+                                ),
+                            });
+                        }
+                        Ok(value)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let group = *group_of_key.entry(key.clone()).or_insert_with(|| {
+                    groups.push((key, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[group].1.push(row as u32);
+            }
+            groups
+        };
+        for (key, rows) in groups {
+            let rows = take_record_batch(&data, &UInt32Array::from(rows))?;
+            self.write(key, rows).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<ExportStats> {
+        for partition in self.partitions.values_mut() {
+            if let Some(writer) = partition.writer.take() {
+                writer.close().await?;
+            }
+        }
+        Ok(self.stats)
+    }
+}
+
+/// Write a stream of results to Parquet files under the directory `uri`
+///
+/// Files that already exist with the same names are overwritten, other files
+/// in the directory are left alone.  When the results are partitioned, one
+/// file is kept open for every partition seen so far, so exporting to a very
+/// large number of partitions uses a lot of memory.
+pub async fn export_parquet(
+    mut stream: SendableRecordBatchStream,
+    uri: &str,
+    options: ExportParquetOptions,
+) -> Result<ExportStats> {
+    let input_schema = stream.schema();
+    let partition_indices = options
+        .partition_by
+        .iter()
+        .map(|column| {
+            input_schema
+                .index_of(column)
+                .map_err(|_| Error::InvalidInput {
+                    message: format!("cannot partition by {}, no such column", column),
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let data_indices = (0..input_schema.fields().len())
+        .filter(|index| !partition_indices.contains(index))
+        .collect::<Vec<_>>();
+    if data_indices.is_empty() {
+        return Err(Error::InvalidInput {
+            message: "cannot partition by every column, no columns would be left to write"
+                .to_string(),
+        });
+    }
+    let schema = Arc::new(Schema::new_with_metadata(
+        data_indices
+            .iter()
+            .map(|index| input_schema.field(*index).clone())
+            .collect::<Vec<_>>(),
+        input_schema.metadata().clone(),
+    ));
+
+    let params = ObjectStoreParams {
+        storage_options: Some(options.storage_options.clone()),
+        ..Default::default()
+    };
+    let registry = Arc::new(ObjectStoreRegistry::default());
+    let (store, base) = ObjectStore::from_uri_and_params(registry, uri, &params).await?;
+
+    let mut exporter = Exporter {
+        store,
+        base,
+        schema,
+        partition_indices,
+        data_indices,
+        options,
+        partitions: HashMap::new(),
+        stats: ExportStats::default(),
+    };
+    if exporter.partition_indices.is_empty() {
+        // Always write a file, even with no results, so readers see the schema
+        let partition = PartitionWriter::new(&exporter.base, &[], &[])?;
+        exporter.partitions.insert(Vec::new(), partition);
+    }
+    while let Some(batch) = stream.try_next().await? {
+        exporter.write_batch(batch).await?;
+    }
+    exporter.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int32Array, RecordBatchIterator, StringArray};
+    use arrow_schema::{DataType, Field};
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::arrow::SimpleRecordBatchStream;
+    use crate::connect;
+    use crate::query::{ExecutableQuery, QueryBase};
+
+    fn read_parquet(path: &std::path::Path) -> Vec<RecordBatch> {
+        let data = Bytes::from(std::fs::read(path).unwrap());
+        ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_export_parquet() {
+        let tmp_dir = tempdir().unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..10)),
+                Arc::new(StringArray::from_iter((0..10).map(|i| match i % 3 {
+                    0 => Some("eu"),
+                    1 => Some("us"),
+                    _ => None,
+                }))),
+            ],
+        )
+        .unwrap();
+        let db = connect(tmp_dir.path().join("db").to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let table = db
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Without partitions
+        let out = tmp_dir.path().join("plain");
+        let stats = table
+            .export_parquet(
+                out.to_str().unwrap(),
+                ExportParquetOptions {
+                    max_rows_per_file: 4,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(stats.num_rows, 10);
+        assert_eq!(
+            stats.files,
+            vec![
+                "part-00000.parquet",
+                "part-00001.parquet",
+                "part-00002.parquet"
+            ]
+        );
+        let batches = read_parquet(&out.join("part-00002.parquet"));
+        assert_eq!(batches[0].schema(), schema);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+
+        // Partitioned by region
+        let out = tmp_dir.path().join("partitioned");
+        let stats = table
+            .export_parquet(
+                out.to_str().unwrap(),
+                ExportParquetOptions {
+                    partition_by: vec!["region".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(stats.num_rows, 10);
+        assert_eq!(stats.files.len(), 3);
+        let batches = read_parquet(&out.join("region=eu/part-00000.parquet"));
+        assert_eq!(batches[0].schema().fields().len(), 1);
+        assert_eq!(
+            batches[0].column(0).as_ref(),
+            &Int32Array::from(vec![0, 3, 6, 9]) as &dyn arrow_array::Array
+        );
+        let batches = read_parquet(
+            &out.join(format!("region={}", NULL_PARTITION))
+                .join("part-00000.parquet"),
+        );
+        assert_eq!(batches[0].num_rows(), 3);
+
+        // Query results, including an empty result
+        let out = tmp_dir.path().join("query");
+        let stats = table
+            .query()
+            .only_if("id > 100")
+            .export_parquet(out.to_str().unwrap(), ExportParquetOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(stats.num_rows, 0);
+        assert_eq!(stats.files, vec!["part-00000.parquet"]);
+        let data = Bytes::from(std::fs::read(out.join("part-00000.parquet")).unwrap());
+        let builder = ParquetRecordBatchReaderBuilder::try_new(data).unwrap();
+        assert_eq!(builder.schema().fields().len(), 2);
+
+        let err = table
+            .export_parquet(
+                out.to_str().unwrap(),
+                ExportParquetOptions {
+                    partition_by: vec!["missing".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_export_parquet_escapes_partition_values() {
+        let tmp_dir = tempdir().unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("region", DataType::Utf8, false),
+        ]));
+        let make_stream = |regions: Vec<&str>| {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(0..regions.len() as i32)),
+                    Arc::new(StringArray::from(regions)),
+                ],
+            )
+            .unwrap();
+            Box::pin(SimpleRecordBatchStream::new(
+                futures::stream::iter(vec![Ok(batch)]),
+                schema.clone(),
+            )) as SendableRecordBatchStream
+        };
+        let options = ExportParquetOptions {
+            partition_by: vec!["region".to_string()],
+            ..Default::default()
+        };
+
+        let out = tmp_dir.path().join("out");
+        let stats = export_parquet(
+            make_stream(vec!["../../escape", "a=b%c"]),
+            out.to_str().unwrap(),
+            options.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            stats.files,
+            vec![
+                "region=..%2F..%2Fescape/part-00000.parquet",
+                "region=a%3Db%25c/part-00000.parquet"
+            ]
+        );
+        for file in &stats.files {
+            assert_eq!(read_parquet(&out.join(file))[0].num_rows(), 1);
+        }
+        // Nothing is written outside of the export directory
+        let entries = std::fs::read_dir(tmp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec!["out"]);
+
+        for region in ["..", NULL_PARTITION] {
+            let err = export_parquet(
+                make_stream(vec![region]),
+                out.to_str().unwrap(),
+                options.clone(),
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        }
+    }
+}
@@ -0,0 +1,655 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Reading data files (CSV, JSON, Arrow IPC, Parquet) from local disk or
+//! object storage
+//!
+//! A [`FileSource`] points at one file, or at several files with a wildcard in
+//! the file name, such as `s3://bucket/data/*.csv`.  Opening it returns a
+//! stream of batches that can be passed to
+//! [`crate::connection::Connection::create_table_streaming`] or
+//! [`crate::Table::add_streaming`].  [`crate::Table::add_from_uri`] and
+//! [`crate::connection::Connection::create_table_from_uri`] do both steps.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::buffer::Buffer;
+use arrow::csv::reader::Format;
+use arrow::json::reader::infer_json_schema_from_seekable;
+use arrow_array::RecordBatch;
+use arrow_ipc::convert::fb_to_schema;
+use arrow_ipc::reader::{read_footer_length, FileDecoder};
+use arrow_ipc::{root_as_footer, Block, MetadataVersion};
+use arrow_schema::{ArrowError, Schema, SchemaRef};
+use bytes::{Buf, Bytes};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use lance::io::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry};
+use object_store::path::Path;
+use parquet::arrow::async_reader::ParquetObjectReader;
+use parquet::arrow::ParquetRecordBatchStreamBuilder;
+
+use crate::arrow::{SendableRecordBatchStream, SimpleRecordBatchStream};
+use crate::error::{Error, Result};
+
+/// The number of bytes read from the start of a CSV or JSON file to infer
+/// its schema
+const INFER_SCHEMA_BYTES: usize = 1024 * 1024;
+/// The number of rows read to infer the schema of CSV and JSON files
+const INFER_SCHEMA_ROWS: usize = 1000;
+
+type DynObjectStore = Arc<dyn object_store::ObjectStore>;
+
+/// The format of a data file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Comma separated values with a header row
+    Csv,
+    /// Newline delimited JSON, one object per row
+    Json,
+    /// The Arrow IPC file format, also known as Feather
+    ArrowIpc,
+    /// Apache Parquet
+    Parquet,
+}
+
+impl FileFormat {
+    /// Guess the format from a file name's extension
+    fn from_file_name(name: &str) -> Result<Self> {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" | "jsonl" | "ndjson" => Ok(Self::Json),
+            "arrow" | "ipc" | "feather" => Ok(Self::ArrowIpc),
+            "parquet" | "parq" => Ok(Self::Parquet),
+            _ => Err(Error::InvalidInput {
+                message: format!(
+                    "cannot tell the format of {} from its extension, set it with FileSource::format",
+                    name
+                ),
+            }),
+        }
+    }
+}
+
+/// One or more data files to read
+///
+/// The file name may contain `*` and `?` wildcards to read all matching files
+/// in a directory.  All of the files must have the same format and schema.
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    uri: String,
+    format: Option<FileFormat>,
+    schema: Option<SchemaRef>,
+    storage_options: HashMap<String, String>,
+}
+
+impl FileSource {
+    /// Read the file, or the files matching the pattern, at `uri`
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            format: None,
+            schema: None,
+            storage_options: HashMap::new(),
+        }
+    }
+
+    /// Set the format of the files
+    ///
+    /// By default the format is guessed from the file extension.
+    pub fn format(mut self, format: FileFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the schema to read CSV and JSON files with
+    ///
+    /// By default the schema is inferred from the first rows of the first
+    /// file.  Arrow IPC and Parquet files always use the schema stored in the
+    /// file, which must match this one if it is set.
+    pub fn schema(mut self, schema: SchemaRef) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Set an option for the storage the files are read from
+    ///
+    /// See available options at <https://lancedb.github.io/lancedb/guides/storage/>
+    pub fn storage_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.storage_options.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set several options for the storage the files are read from
+    pub fn storage_options(
+        mut self,
+        pairs: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        for (key, value) in pairs {
+            self.storage_options.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    pub(crate) fn has_schema(&self) -> bool {
+        self.schema.is_some()
+    }
+
+    /// Find the files and read their schema
+    ///
+    /// The files are read one after another as the returned stream is
+    /// polled, and each file is decoded as it is downloaded, so only a few
+    /// batches are held in memory at a time.
+    pub async fn open(self) -> Result<SendableRecordBatchStream> {
+        let (dir_uri, pattern) = match self.uri.rsplit_once('/') {
+            Some((dir, name)) => (dir.to_string(), name.to_string()),
+            None => (".".to_string(), self.uri.clone()),
+        };
+        if pattern.is_empty() {
+            return Err(Error::InvalidInput {
+                message: format!("{} is a directory, add a file name or pattern", self.uri),
+            });
+        }
+        let params = ObjectStoreParams {
+            storage_options: Some(self.storage_options),
+            ..Default::default()
+        };
+        let registry = Arc::new(ObjectStoreRegistry::default());
+        let (store, dir) = ObjectStore::from_uri_and_params(registry, &dir_uri, &params).await?;
+
+        let mut names = if pattern.contains(['*', '?']) {
+            store
+                .read_dir(dir.clone())
+                .await?
+                .into_iter()
+                .filter(|name| matches_pattern(&pattern, name))
+                .collect::<Vec<_>>()
+        } else {
+            vec![pattern]
+        };
+        if names.is_empty() {
+            return Err(Error::InvalidInput {
+                message: format!("no files match {}", self.uri),
+            });
+        }
+        names.sort();
+
+        let format = match self.format {
+            Some(format) => format,
+            None => FileFormat::from_file_name(&names[0])?,
+        };
+        let paths = names
+            .iter()
+            .map(|name| dir.child(name.as_str()))
+            .collect::<Vec<_>>();
+        let store = store.inner.clone();
+        let schema = match self.schema {
+            Some(schema) => schema,
+            None => read_schema(format, &store, &paths[0]).await?,
+        };
+        let file_schema = schema.clone();
+        let batches = futures::stream::iter(paths)
+            .then(move |path| read_file(format, store.clone(), path, file_schema.clone()))
+            .try_flatten();
+        Ok(Box::pin(SimpleRecordBatchStream::new(batches, schema)))
+    }
+}
+
+impl From<&str> for FileSource {
+    fn from(uri: &str) -> Self {
+        Self::new(uri)
+    }
+}
+
+impl From<String> for FileSource {
+    fn from(uri: String) -> Self {
+        Self::new(uri)
+    }
+}
+
+/// Read the schema of the first file, inferring it for CSV and JSON files
+async fn read_schema(format: FileFormat, store: &DynObjectStore, path: &Path) -> Result<SchemaRef> {
+    let schema = match format {
+        FileFormat::Csv | FileFormat::Json => {
+            let size = store.head(path).await?.size;
+            let mut data = store
+                .get_range(path, 0..size.min(INFER_SCHEMA_BYTES))
+                .await?;
+            // Don't infer the type of a column from a cut off value
+            if size > INFER_SCHEMA_BYTES {
+                let end = data.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+                data.truncate(end);
+            }
+            if format == FileFormat::Csv {
+                Format::default()
+                    .with_header(true)
+                    .infer_schema(Cursor::new(data), Some(INFER_SCHEMA_ROWS))?
+                    .0
+            } else {
+                infer_json_schema_from_seekable(Cursor::new(data), Some(INFER_SCHEMA_ROWS))?.0
+            }
+        }
+        FileFormat::ArrowIpc => return Ok(IpcFooter::read(store, path).await?.schema),
+        FileFormat::Parquet => {
+            let meta = store.head(path).await?;
+            return Ok(
+                ParquetRecordBatchStreamBuilder::new(ParquetObjectReader::new(store.clone(), meta))
+                    .await?
+                    .schema()
+                    .clone(),
+            );
+        }
+    };
+    Ok(Arc::new(schema))
+}
+
+/// Open one file as a stream of its batches
+async fn read_file(
+    format: FileFormat,
+    store: DynObjectStore,
+    path: Path,
+    schema: SchemaRef,
+) -> Result<BoxStream<'static, Result<RecordBatch>>> {
+    let check_schema = |file_schema: &Schema| {
+        if same_fields(file_schema, &schema) {
+            Ok(())
+        } else {
+            Err(Error::Schema {
+                message: format!(
+                    "the schema of {} does not match the other files, expected {:?} but found {:?}",
+                    path, schema, file_schema
+                ),
+            })
+        }
+    };
+    match format {
+        FileFormat::Csv => {
+            let decoder = arrow::csv::ReaderBuilder::new(schema.clone())
+                .with_header(true)
+                .build_decoder();
+            let chunks = store.get(&path).await?.into_stream();
+            Ok(decode_text(chunks, decoder))
+        }
+        FileFormat::Json => {
+            let decoder = arrow::json::ReaderBuilder::new(schema.clone()).build_decoder()?;
+            let chunks = store.get(&path).await?.into_stream();
+            Ok(decode_text(chunks, decoder))
+        }
+        FileFormat::ArrowIpc => {
+            let footer = IpcFooter::read(&store, &path).await?;
+            check_schema(&footer.schema)?;
+            Ok(footer.into_stream(store, path))
+        }
+        FileFormat::Parquet => {
+            let meta = store.head(&path).await?;
+            let builder =
+                ParquetRecordBatchStreamBuilder::new(ParquetObjectReader::new(store, meta)).await?;
+            check_schema(builder.schema())?;
+            Ok(builder.build()?.map_err(Error::from).boxed())
+        }
+    }
+}
+
+/// The push based decoders of the text formats
+trait TextDecoder: Send + 'static {
+    /// Decode rows from `buf`, returning how many bytes were used
+    ///
+    /// This returns 0 once a batch is full, or at the end of the input when
+    /// `buf` is empty.
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError>;
+
+    /// Return the decoded rows as a batch, if there are any
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError>;
+}
+
+impl TextDecoder for arrow::csv::reader::Decoder {
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError> {
+        Self::decode(self, buf)
+    }
+
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError> {
+        Self::flush(self)
+    }
+}
+
+impl TextDecoder for arrow::json::reader::Decoder {
+    fn decode(&mut self, buf: &[u8]) -> std::result::Result<usize, ArrowError> {
+        Self::decode(self, buf)
+    }
+
+    fn flush(&mut self) -> std::result::Result<Option<RecordBatch>, ArrowError> {
+        Self::flush(self)
+    }
+}
+
+/// Decode the chunks of a CSV or JSON file as they are downloaded
+fn decode_text(
+    chunks: BoxStream<'static, object_store::Result<Bytes>>,
+    decoder: impl TextDecoder,
+) -> BoxStream<'static, Result<RecordBatch>> {
+    futures::stream::try_unfold(
+        (chunks, decoder, Bytes::new(), false),
+        |(mut chunks, mut decoder, mut buffer, mut eof)| async move {
+            loop {
+                if buffer.is_empty() && !eof {
+                    match chunks.try_next().await? {
+                        Some(chunk) => buffer = chunk,
+                        None => eof = true,
+                    }
+                    continue;
+                }
+                let decoded = decoder.decode(&buffer)?;
+                if decoded == 0 {
+                    break;
+                }
+                buffer.advance(decoded);
+            }
+            Ok(decoder
+                .flush()?
+                .map(|batch| (batch, (chunks, decoder, buffer, eof))))
+        },
+    )
+    .boxed()
+}
+
+/// The footer of an Arrow IPC file, which says where its batches are
+struct IpcFooter {
+    schema: SchemaRef,
+    version: MetadataVersion,
+    dictionaries: Vec<Block>,
+    batches: Vec<Block>,
+}
+
+impl IpcFooter {
+    async fn read(store: &DynObjectStore, path: &Path) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidInput {
+            message: format!("{} is not an Arrow IPC file: {}", path, reason),
+        };
+        // The footer is followed by its length and the magic bytes
+        let size = store.head(path).await?.size;
+        if size < 10 {
+            return Err(invalid("it is too short".to_string()));
+        }
+        let trailer = store.get_range(path, size - 10..size).await?;
+        let footer_len = read_footer_length(trailer[..].try_into().unwrap())
+            .map_err(|err| invalid(err.to_string()))?;
+        if footer_len > size - 10 {
+            return Err(invalid(format!("invalid footer length {}", footer_len)));
+        }
+        let data = store
+            .get_range(path, size - 10 - footer_len..size - 10)
+            .await?;
+        let footer = root_as_footer(&data).map_err(|err| invalid(err.to_string()))?;
+        let schema = footer
+            .schema()
+            .ok_or_else(|| invalid("the footer has no schema".to_string()))?;
+        Ok(Self {
+            schema: Arc::new(fb_to_schema(schema)),
+            version: footer.version(),
+            dictionaries: footer
+                .dictionaries()
+                .map(|blocks| blocks.iter().cloned().collect())
+                .unwrap_or_default(),
+            batches: footer
+                .recordBatches()
+                .map(|blocks| blocks.iter().cloned().collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Read the batches one at a time, after reading the dictionaries
+    fn into_stream(
+        self,
+        store: DynObjectStore,
+        path: Path,
+    ) -> BoxStream<'static, Result<RecordBatch>> {
+        let read_block = move |block: Block| {
+            let store = store.clone();
+            let path = path.clone();
+            async move {
+                let start = block.offset() as usize;
+                let end = start + block.metaDataLength() as usize + block.bodyLength() as usize;
+                let data = store.get_range(&path, start..end).await?;
+                Result::Ok((block, Buffer::from(data)))
+            }
+        };
+        let dictionaries = futures::stream::iter(self.dictionaries).then(read_block.clone());
+        let decoder = FileDecoder::new(self.schema, self.version);
+        let batches = futures::stream::iter(self.batches).then(read_block);
+        futures::stream::once(async move {
+            let decoder = dictionaries
+                .try_fold(decoder, |mut decoder, (block, data)| async move {
+                    decoder.read_dictionary(&block, &data)?;
+                    Result::Ok(decoder)
+                })
+                .await?;
+            let decoder = Arc::new(decoder);
+            Result::Ok(batches.try_filter_map(move |(block, data)| {
+                let batch = decoder.read_record_batch(&block, &data);
+                async move { Ok(batch?) }
+            }))
+        })
+        .try_flatten()
+        .boxed()
+    }
+}
+
+/// Whether a file name matches a pattern with `*` and `?` wildcards
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // The position to go back to after a mismatch: just after the last `*`,
+    // and the name position it was matched at
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            // Let the last `*` match one more character
+            backtrack = Some((star_p, star_n + 1));
+            p = star_p;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn same_fields(left: &Schema, right: &Schema) -> bool {
+    left.fields().len() == right.fields().len()
+        && left
+            .fields()
+            .iter()
+            .zip(right.fields().iter())
+            .all(|(l, r)| {
+                l.name() == r.name()
+                    && l.data_type() == r.data_type()
+                    && l.is_nullable() == r.is_nullable()
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int64Type;
+    use arrow_array::{Int64Array, StringArray};
+    use arrow_schema::{DataType, Field};
+    use parquet::arrow::ArrowWriter;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::ipc::batches_to_ipc_file;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.csv", "a.csv"));
+        assert!(matches_pattern("*.csv", ".csv"));
+        assert!(!matches_pattern("*.csv", "a.csv.bak"));
+        assert!(matches_pattern("part-?.json", "part-1.json"));
+        assert!(!matches_pattern("part-?.json", "part-10.json"));
+        assert!(matches_pattern("part-*-*.arrow", "part-1-2.arrow"));
+        assert!(matches_pattern("data", "data"));
+        assert!(!matches_pattern("data", "data2"));
+    }
+
+    #[tokio::test]
+    async fn test_read_files() {
+        let tmp_dir = tempdir().unwrap();
+        let dir = tmp_dir.path();
+        std::fs::write(dir.join("part-1.csv"), "id,name\n1,a\n2,b\n").unwrap();
+        std::fs::write(dir.join("part-2.csv"), "id,name\n3,c\n").unwrap();
+        std::fs::write(dir.join("other.json"), "{\"id\": 4, \"name\": \"d\"}\n").unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let stream = FileSource::new(format!("{}/*.csv", dir))
+            .open()
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.schema().field(0),
+            &Field::new("id", DataType::Int64, true)
+        );
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        let stream = FileSource::new(format!("{}/other.json", dir))
+            .open()
+            .await
+            .unwrap();
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+
+        assert!(matches!(
+            FileSource::new(format!("{}/*.txt", dir)).open().await,
+            Err(Error::InvalidInput { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_large_csv() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("large.csv");
+        // Larger than the prefix used to infer the schema and than one batch
+        let mut data = String::from("id,name\n");
+        for i in 0..100_000 {
+            data.push_str(&format!("{},name-{}\n", i, i));
+        }
+        std::fs::write(&path, data).unwrap();
+
+        let stream = FileSource::new(path.to_str().unwrap())
+            .open()
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.schema().field(1),
+            &Field::new("name", DataType::Utf8, true)
+        );
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        assert!(batches.len() > 1);
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_read_parquet() {
+        let tmp_dir = tempdir().unwrap();
+        let dir = tmp_dir.path();
+        for (name, ids) in [("a.parquet", vec![1, 2]), ("b.parquet", vec![3])] {
+            let batch =
+                RecordBatch::try_from_iter(vec![("id", Arc::new(Int64Array::from(ids)) as _)])
+                    .unwrap();
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+            writer.write(&batch).unwrap();
+            writer.close().unwrap();
+        }
+
+        let stream = FileSource::new(format!("{}/*.parquet", dir.to_str().unwrap()))
+            .open()
+            .await
+            .unwrap();
+        assert_eq!(
+            stream.schema().field(0),
+            &Field::new("id", DataType::Int64, true)
+        );
+        let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+        let ids = batches
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_ipc_schema_mismatch() {
+        let tmp_dir = tempdir().unwrap();
+        let dir = tmp_dir.path();
+        let batch =
+            RecordBatch::try_from_iter(vec![("id", Arc::new(Int64Array::from(vec![1, 2])) as _)])
+                .unwrap();
+        std::fs::write(dir.join("a.arrow"), batches_to_ipc_file(&[batch]).unwrap()).unwrap();
+        let batch =
+            RecordBatch::try_from_iter(vec![("name", Arc::new(StringArray::from(vec!["x"])) as _)])
+                .unwrap();
+        std::fs::write(dir.join("b.arrow"), batches_to_ipc_file(&[batch]).unwrap()).unwrap();
+
+        let stream = FileSource::new(format!("{}/*.arrow", dir.to_str().unwrap()))
+            .open()
+            .await
+            .unwrap();
+        let results = stream.collect::<Vec<_>>().await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::Schema { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_create_from_uri() {
+        let tmp_dir = tempdir().unwrap();
+        let data_dir = tmp_dir.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        std::fs::write(data_dir.join("1.csv"), "id,score\n1,0.5\n2,1.5\n").unwrap();
+        std::fs::write(data_dir.join("2.csv"), "id,score\n3,2\n").unwrap();
+        let files = format!("{}/*.csv", data_dir.to_str().unwrap());
+
+        let db = connect(tmp_dir.path().join("db").to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let table = db
+            .create_table_from_uri("scores", files.as_str())
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 3);
+
+        // CSV values are parsed with the table's schema when adding
+        std::fs::write(data_dir.join("more.csv"), "id,score\n4,3\n").unwrap();
+        table
+            .add_from_uri(format!("{}/more.csv", data_dir.to_str().unwrap()))
+            .await
+            .unwrap()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 4);
+        assert_eq!(
+            table.schema().await.unwrap().field(1).data_type(),
+            &DataType::Float64
+        );
+    }
+}
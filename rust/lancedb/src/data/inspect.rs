@@ -40,8 +40,9 @@ where
 /// Parameters
 /// ----------
 /// - reader: RecordBatchReader
-/// - strict: if set true, only `fixed_size_list<float>` is considered as vector column. If set to false,
-///           a `list<float>` column with same length is also considered as vector column.
+/// - strict: if set true, only `fixed_size_list<float>` and `fixed_size_list<uint8>` (binary vectors)
+///           are considered as vector columns. If set to false, a `list<float>` column with same length
+///           is also considered as vector column.
 pub fn infer_vector_columns(
     reader: impl RecordBatchReader + Send,
     strict: bool,
@@ -51,7 +52,10 @@ pub fn infer_vector_columns(
     let mut columns_to_infer: HashMap<String, Option<i64>> = HashMap::new();
     for field in reader.schema().fields() {
         match field.data_type() {
-            DataType::FixedSizeList(sub_field, _) if sub_field.data_type().is_floating() => {
+            DataType::FixedSizeList(sub_field, _)
+                if sub_field.data_type().is_floating()
+                    || sub_field.data_type() == &DataType::UInt8 =>
+            {
                 columns.push(field.name().to_string());
             }
             DataType::List(sub_field) if sub_field.data_type().is_floating() && !strict => {
@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Converting Arrow data into application types with serde
+//!
+//! Each row of a batch is deserialized as a map from column name to value,
+//! so a struct with `#[derive(Deserialize)]` whose fields are named after the
+//! columns can be used directly.  Columns without a matching field are
+//! ignored, unless the struct uses `#[serde(deny_unknown_fields)]`.
+//!
+//! The Arrow types are mapped to serde as follows:
+//!
+//! | Arrow                                   | serde / Rust                      |
+//! |-----------------------------------------|-----------------------------------|
+//! | Boolean                                 | `bool`                            |
+//! | Int*, UInt*                             | any integer type it fits in       |
+//! | Float16, Float32, Float64               | `f32` or `f64`                    |
+//! | Utf8, LargeUtf8                         | `String`                          |
+//! | List, LargeList, FixedSizeList          | `Vec<T>`, e.g. `Vec<f32>` vectors |
+//! | Struct                                  | a nested struct or map            |
+//! | Date, Time, Timestamp                   | `String` in ISO 8601 format       |
+//! | Binary, LargeBinary, FixedSizeBinary    | `String` of hex digits            |
+//!
+//! Null values are deserialized as `None`, so nullable columns should be read
+//! into `Option` fields.  Floating point values, including NaN and the
+//! infinities, are passed through unchanged, and a string column can be read
+//! into an enum with unit variants named after its values.
+
+use std::fmt::Display;
+
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Fields};
+use serde::de::value::StringDeserializer;
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+
+use crate::error::{Error, Result};
+
+/// Deserialize each row of `batch` into a `T`
+///
+/// See the [module documentation](self) for how Arrow types are mapped.
+pub fn batch_into_rows<T: DeserializeOwned>(batch: &RecordBatch) -> Result<Vec<T>> {
+    let fields = batch.schema_ref().fields();
+    (0..batch.num_rows())
+        .map(|row| {
+            T::deserialize(RowDeserializer {
+                fields,
+                columns: batch.columns(),
+                row,
+            })
+            .map_err(|err| Error::InvalidInput {
+                message: format!("could not deserialize row {}: {}", row, err),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct DeserializeError(String);
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl serde::de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+type DeserializeResult<T> = std::result::Result<T, DeserializeError>;
+
+/// One row of a batch, or of a struct array, as a map from column to value
+struct RowDeserializer<'a> {
+    fields: &'a Fields,
+    columns: &'a [ArrayRef],
+    row: usize,
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer<'_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> DeserializeResult<V::Value> {
+        visitor.visit_map(ColumnsAccess {
+            row: self,
+            position: 0,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct ColumnsAccess<'a> {
+    row: RowDeserializer<'a>,
+    position: usize,
+}
+
+impl<'de> MapAccess<'de> for ColumnsAccess<'_> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> DeserializeResult<Option<K::Value>> {
+        let Some(field) = self.row.fields.get(self.position) else {
+            return Ok(None);
+        };
+        let name: StringDeserializer<DeserializeError> = field.name().clone().into_deserializer();
+        seed.deserialize(name).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> DeserializeResult<V::Value> {
+        let array = self.row.columns[self.position].as_ref();
+        self.position += 1;
+        seed.deserialize(ValueDeserializer {
+            array,
+            index: self.row.row,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.row.fields.len() - self.position)
+    }
+}
+
+/// The items of a list value
+struct ItemsAccess {
+    items: ArrayRef,
+    position: usize,
+}
+
+impl<'de> SeqAccess<'de> for ItemsAccess {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> DeserializeResult<Option<T::Value>> {
+        if self.position >= self.items.len() {
+            return Ok(None);
+        }
+        let index = self.position;
+        self.position += 1;
+        seed.deserialize(ValueDeserializer {
+            array: self.items.as_ref(),
+            index,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len() - self.position)
+    }
+}
+
+/// One value of an array
+struct ValueDeserializer<'a> {
+    array: &'a dyn Array,
+    index: usize,
+}
+
+impl ValueDeserializer<'_> {
+    /// The value as text, for the types without a direct serde equivalent
+    fn formatted(&self) -> DeserializeResult<String> {
+        let formatter = ArrayFormatter::try_new(self.array, &FormatOptions::default())
+            .map_err(serde::de::Error::custom)?;
+        Ok(formatter.value(self.index).to_string())
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> DeserializeResult<V::Value> {
+        let (array, i) = (self.array, self.index);
+        if array.is_null(i) {
+            return visitor.visit_unit();
+        }
+        match array.data_type() {
+            DataType::Null => visitor.visit_unit(),
+            DataType::Boolean => visitor.visit_bool(array.as_boolean().value(i)),
+            DataType::Int8 => visitor.visit_i8(array.as_primitive::<Int8Type>().value(i)),
+            DataType::Int16 => visitor.visit_i16(array.as_primitive::<Int16Type>().value(i)),
+            DataType::Int32 => visitor.visit_i32(array.as_primitive::<Int32Type>().value(i)),
+            DataType::Int64 => visitor.visit_i64(array.as_primitive::<Int64Type>().value(i)),
+            DataType::UInt8 => visitor.visit_u8(array.as_primitive::<UInt8Type>().value(i)),
+            DataType::UInt16 => visitor.visit_u16(array.as_primitive::<UInt16Type>().value(i)),
+            DataType::UInt32 => visitor.visit_u32(array.as_primitive::<UInt32Type>().value(i)),
+            DataType::UInt64 => visitor.visit_u64(array.as_primitive::<UInt64Type>().value(i)),
+            DataType::Float16 => {
+                visitor.visit_f32(array.as_primitive::<Float16Type>().value(i).to_f32())
+            }
+            DataType::Float32 => visitor.visit_f32(array.as_primitive::<Float32Type>().value(i)),
+            DataType::Float64 => visitor.visit_f64(array.as_primitive::<Float64Type>().value(i)),
+            DataType::Utf8 => visitor.visit_str(array.as_string::<i32>().value(i)),
+            DataType::LargeUtf8 => visitor.visit_str(array.as_string::<i64>().value(i)),
+            DataType::List(_) => visitor.visit_seq(ItemsAccess {
+                items: array.as_list::<i32>().value(i),
+                position: 0,
+            }),
+            DataType::LargeList(_) => visitor.visit_seq(ItemsAccess {
+                items: array.as_list::<i64>().value(i),
+                position: 0,
+            }),
+            DataType::FixedSizeList(_, _) => visitor.visit_seq(ItemsAccess {
+                items: array.as_fixed_size_list().value(i),
+                position: 0,
+            }),
+            DataType::Struct(fields) => RowDeserializer {
+                fields,
+                columns: array.as_struct().columns(),
+                row: i,
+            }
+            .deserialize_any(visitor),
+            // Dates, times and binary values are formatted the same way as
+            // when Arrow writes them as JSON
+            _ => visitor.visit_string(self.formatted()?),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> DeserializeResult<V::Value> {
+        if self.array.is_null(self.index) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> DeserializeResult<V::Value> {
+        // Unit variants are stored as their names
+        let variant = String::deserialize(self)?;
+        let variant: StringDeserializer<DeserializeError> = variant.into_deserializer();
+        variant.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> DeserializeResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::buffer::NullBuffer;
+    use arrow::datatypes::Float32Type;
+    use arrow_array::{
+        ArrayRef, FixedSizeListArray, Float64Array, Int64Array, StringArray, StructArray,
+    };
+    use arrow_schema::Field;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Row {
+        id: u64,
+        name: Option<String>,
+        vector: Vec<f32>,
+    }
+
+    #[test]
+    fn test_batch_into_rows() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("a"), None])) as ArrayRef,
+            ),
+            (
+                "vector",
+                Arc::new(
+                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                        vec![
+                            Some(vec![Some(0.5), Some(1.0)]),
+                            Some(vec![Some(2.0), Some(-1.5)]),
+                        ],
+                        2,
+                    ),
+                ) as ArrayRef,
+            ),
+            ("extra", Arc::new(Int64Array::from(vec![7, 8])) as ArrayRef),
+        ])
+        .unwrap();
+
+        let rows = batch_into_rows::<Row>(&batch).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    id: 1,
+                    name: Some("a".to_string()),
+                    vector: vec![0.5, 1.0],
+                },
+                Row {
+                    id: 2,
+                    name: None,
+                    vector: vec![2.0, -1.5],
+                },
+            ]
+        );
+
+        // A column of the wrong type is reported
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct BadRow {
+            name: u32,
+        }
+        let err = batch_into_rows::<BadRow>(&batch).unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_batch_into_rows_values() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Color {
+            Red,
+            Green,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Point {
+            x: f64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Row {
+            score: f64,
+            color: Color,
+            point: Option<Point>,
+        }
+
+        let points = StructArray::new(
+            Fields::from(vec![Field::new("x", DataType::Float64, false)]),
+            vec![Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])) as ArrayRef],
+            Some(NullBuffer::from(vec![true, false, true])),
+        );
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![
+                    f64::NAN,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                ])) as ArrayRef,
+            ),
+            (
+                "color",
+                Arc::new(StringArray::from(vec!["red", "green", "red"])) as ArrayRef,
+            ),
+            ("point", Arc::new(points) as ArrayRef),
+        ])
+        .unwrap();
+
+        let rows = batch_into_rows::<Row>(&batch).unwrap();
+        assert!(rows[0].score.is_nan());
+        assert_eq!(rows[1].score, f64::INFINITY);
+        assert_eq!(rows[2].score, f64::NEG_INFINITY);
+        assert_eq!(
+            rows.iter().map(|row| &row.color).collect::<Vec<_>>(),
+            vec![&Color::Red, &Color::Green, &Color::Red]
+        );
+        assert_eq!(rows[0].point, Some(Point { x: 1.0 }));
+        assert_eq!(rows[1].point, None);
+    }
+}
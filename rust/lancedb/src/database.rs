@@ -19,16 +19,19 @@ use std::sync::Arc;
 
 use arrow_array::RecordBatchReader;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
 use futures::stream;
 use lance::dataset::ReadParams;
 use lance_datafusion::utils::StreamingWriteSource;
 
 use crate::arrow::{SendableRecordBatchStream, SendableRecordBatchStreamExt};
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::table::{BaseTable, TableDefinition, WriteOptions};
 
 pub mod listing;
+pub mod observer;
+pub mod read_only;
 
 pub trait DatabaseOptions {
     fn serialize_into_map(&self, map: &mut HashMap<String, String>);
@@ -47,12 +50,68 @@ pub struct TableNamesRequest {
     pub limit: Option<u32>,
 }
 
+/// A request to list the tables in the database
+#[derive(Clone, Debug, Default)]
+pub struct ListTablesRequest {
+    /// If present, only return tables whose names come lexicographically after
+    /// the supplied value.
+    pub start_after: Option<String>,
+    /// The maximum number of tables to return
+    pub limit: Option<u32>,
+    /// Whether to return the statistics of each table
+    ///
+    /// Some databases have to open every table to find its statistics.
+    pub include_stats: bool,
+}
+
+/// A table, as returned by [`Database::list_tables`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableInfo {
+    pub name: String,
+    /// The statistics of the table, if they were requested with
+    /// [`ListTablesRequest::include_stats`]
+    pub stats: Option<TableStats>,
+}
+
+/// Summary statistics about a table, see [`TableInfo::stats`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableStats {
+    /// The number of rows in the latest version of the table
+    pub num_rows: usize,
+    /// The total size of the table in storage, including old versions and indices
+    ///
+    /// This is `None` if the database cannot determine the size cheaply.
+    pub size_bytes: Option<u64>,
+    /// The latest version of the table
+    pub version: u64,
+    /// When the latest version of the table was committed
+    pub last_modified: DateTime<Utc>,
+    /// The number of indices on the table
+    pub num_indices: usize,
+}
+
+/// A specific version of a table, identified by number or by tag
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableVersionRef {
+    Version(u64),
+    Tag(String),
+}
+
 /// A request to open a table
 #[derive(Clone, Debug)]
 pub struct OpenTableRequest {
     pub name: String,
     pub index_cache_size: Option<u32>,
     pub lance_read_params: Option<ReadParams>,
+    /// If set, the table is opened in a read-only state at the given version
+    pub version: Option<TableVersionRef>,
+    /// If true, databases that can do so return a handle without checking that
+    /// the table exists. Errors for missing tables are then deferred to the first
+    /// operation on the table.
+    pub skip_existence_check: bool,
+    /// If set, the results of up to this many queries are kept in memory, see
+    /// [`crate::connection::OpenTableBuilder::query_cache_size`]
+    pub query_cache_size: Option<usize>,
 }
 
 pub type TableBuilderCallback = Box<dyn FnOnce(OpenTableRequest) -> OpenTableRequest + Send>;
@@ -153,15 +212,96 @@ pub trait Database:
 {
     /// List the names of tables in the database
     async fn table_names(&self, request: TableNamesRequest) -> Result<Vec<String>>;
+    /// List tables in the database, optionally with summary statistics about each
+    ///
+    /// The default implementation opens every table to find its statistics,
+    /// implementations should override this if they can answer more cheaply.
+    async fn list_tables(&self, request: ListTablesRequest) -> Result<Vec<TableInfo>> {
+        let names = self
+            .table_names(TableNamesRequest {
+                start_after: request.start_after,
+                limit: request.limit,
+            })
+            .await?;
+        if !request.include_stats {
+            return Ok(names
+                .into_iter()
+                .map(|name| TableInfo { name, stats: None })
+                .collect());
+        }
+        let mut tables = Vec::new();
+        for name in names {
+            let table = self
+                .open_table(OpenTableRequest {
+                    name: name.clone(),
+                    index_cache_size: None,
+                    lance_read_params: None,
+                    version: None,
+                    skip_existence_check: false,
+                    query_cache_size: None,
+                })
+                .await?;
+            let version = table.version().await?;
+            let last_modified = table
+                .list_versions()
+                .await?
+                .into_iter()
+                .find(|v| v.version == version)
+                .map(|v| v.timestamp)
+                .unwrap_or_default();
+            tables.push(TableInfo {
+                name,
+                stats: Some(TableStats {
+                    num_rows: table.count_rows(None).await?,
+                    size_bytes: None,
+                    version,
+                    last_modified,
+                    num_indices: table.list_indices().await?.len(),
+                }),
+            });
+        }
+        Ok(tables)
+    }
     /// Create a table in the database
     async fn create_table(&self, request: CreateTableRequest) -> Result<Arc<dyn BaseTable>>;
     /// Open a table in the database
     async fn open_table(&self, request: OpenTableRequest) -> Result<Arc<dyn BaseTable>>;
+    /// Check whether a table exists in the database
+    ///
+    /// The default implementation opens the table, implementations should override
+    /// this if they can answer more cheaply.
+    async fn table_exists(&self, name: &str) -> Result<bool> {
+        let request = OpenTableRequest {
+            name: name.to_string(),
+            index_cache_size: None,
+            lance_read_params: None,
+            version: None,
+            skip_existence_check: false,
+            query_cache_size: None,
+        };
+        match self.open_table(request).await {
+            Ok(_) => Ok(true),
+            Err(Error::TableNotFound { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
     /// Rename a table in the database
     async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()>;
     /// Drop a table in the database
     async fn drop_table(&self, name: &str) -> Result<()>;
     /// Drop all tables in the database
     async fn drop_all_tables(&self) -> Result<()>;
+    /// Copy the current version of every table to another location
+    async fn backup(&self, _target_uri: &str) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "backup is not supported by this database".to_string(),
+        })
+    }
+    /// Copy every table from a backup into this database
+    async fn restore(&self, _source_uri: &str) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "restore is not supported by this database".to_string(),
+        })
+    }
     fn as_any(&self) -> &dyn std::any::Any;
 }
@@ -7,23 +7,30 @@ use std::fs::create_dir_all;
 use std::path::Path;
 use std::{collections::HashMap, sync::Arc};
 
-use lance::dataset::{ReadParams, WriteMode};
+use futures::{StreamExt, TryStreamExt};
+use lance::dataset::{Dataset, ReadParams, WriteMode};
 use lance::io::{ObjectStore, ObjectStoreParams, ObjectStoreRegistry, WrappingObjectStore};
 use lance_datafusion::utils::StreamingWriteSource;
 use lance_encoding::version::LanceFileVersion;
-use lance_table::io::commit::commit_handler_from_url;
+use lance_index::DatasetIndexExt;
+use lance_table::io::commit::{commit_handler_from_url, ManifestNamingScheme};
+use lance_table::io::deletion::deletion_file_path;
+use moka::future::Cache;
+use moka::policy::EvictionPolicy;
 use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::WriteMultipart;
 use snafu::{OptionExt, ResultExt};
 
 use crate::connection::ConnectRequest;
 use crate::error::{CreateDirSnafu, Error, InvalidTableNameSnafu, Result};
 use crate::io::object_store::MirroringObjectStoreWrapper;
-use crate::table::NativeTable;
+use crate::table::{AutoIndex, NativeTable, NativeTableExt, DEFAULT_CONFLICT_RETRIES};
 use crate::utils::validate_table_name;
 
 use super::{
-    BaseTable, CreateTableMode, CreateTableRequest, Database, DatabaseOptions, OpenTableRequest,
-    TableNamesRequest,
+    BaseTable, CreateTableMode, CreateTableRequest, Database, DatabaseOptions, ListTablesRequest,
+    OpenTableRequest, TableInfo, TableNamesRequest, TableStats, TableVersionRef,
 };
 
 /// File extension to indicate a lance table
@@ -31,6 +38,11 @@ pub const LANCE_FILE_EXTENSION: &str = "lance";
 
 pub const OPT_NEW_TABLE_STORAGE_VERSION: &str = "new_table_data_storage_version";
 pub const OPT_NEW_TABLE_V2_MANIFEST_PATHS: &str = "new_table_enable_v2_manifest_paths";
+pub const OPT_TABLE_CACHE_SIZE: &str = "table_cache_size";
+pub const OPT_TENANT: &str = "tenant";
+pub const OPT_DYNAMODB_COMMIT_TABLE: &str = "dynamodb_commit_table";
+pub const OPT_CONFLICT_RETRIES: &str = "conflict_retries";
+pub const OPT_AUTO_INDEX: &str = "auto_index";
 
 /// Controls how new tables should be created
 #[derive(Clone, Debug, Default)]
@@ -58,6 +70,56 @@ pub struct ListingDatabaseOptions {
     ///
     /// See available options at <https://lancedb.github.io/lancedb/guides/storage/>
     pub storage_options: HashMap<String, String>,
+    /// The maximum number of opened tables to keep in memory
+    ///
+    /// If set, `open_table` returns the handle of a recently opened table
+    /// instead of reading the table's manifest again.  The handle is shared, so
+    /// it sees writes from other processes according to the read consistency
+    /// interval.  Tables opened at a version, or with custom parameters, are
+    /// not cached, and a cached handle that was checked out at an old version
+    /// is replaced.  Tables are removed from the cache when they are dropped or
+    /// renamed through this database.  The least recently used tables are
+    /// evicted first.  If unset, or zero, tables are not cached.
+    pub table_cache_size: Option<usize>,
+    /// A path prefix, relative to the database URI, that all tables are stored under
+    ///
+    /// This gives each tenant its own set of tables within one bucket or
+    /// directory. Tables outside of the prefix are not visible to the database.
+    pub tenant: Option<String>,
+    /// The name of a DynamoDB table used to coordinate commits
+    ///
+    /// S3 does not let two writers safely commit to the same table at once. If
+    /// this is set, commits are coordinated through the given DynamoDB table
+    /// instead, so several processes can write to the database concurrently.
+    /// This is the same as connecting to `s3+ddb://bucket/path?ddbTableName=...`
+    /// and is only supported for `s3://` URIs.
+    ///
+    /// The DynamoDB table must have a string partition key `base_uri` and a
+    /// number sort key `version`. The `dynamodb_endpoint` storage option can be
+    /// used to point at a non-AWS endpoint.
+    pub dynamodb_commit_table: Option<String>,
+    /// How many times to retry a write that conflicts with a concurrent write
+    ///
+    /// Compatible concurrent writes, such as two appends, are always resolved
+    /// by rebasing one on top of the other. Deletes, updates and compaction
+    /// can also conflict with each other, in which case the table is reloaded
+    /// and the operation is run again, up to this many times, before
+    /// [`crate::Error::CommitConflict`] is returned.
+    ///
+    /// If unset, [`crate::table::DEFAULT_CONFLICT_RETRIES`] is used.
+    pub conflict_retries: Option<u32>,
+    /// Whether rows written with `add` and `merge_insert` are added to the
+    /// existing indices of the table
+    ///
+    /// New rows are otherwise only indexed by
+    /// [`crate::table::OptimizeAction::Index`], and searches scan them in
+    /// full until then.  This runs the same optimization, with the default
+    /// options, after each write to a table with indices, either before the
+    /// write returns or in a background task.  Writes through other
+    /// connections are indexed by their next write through this one.
+    ///
+    /// If unset, new rows are not indexed automatically.
+    pub auto_index: Option<AutoIndex>,
 }
 
 impl ListingDatabaseOptions {
@@ -84,18 +146,54 @@ impl ListingDatabaseOptions {
                 })
                 .transpose()?,
         };
+        let table_cache_size = map
+            .get(OPT_TABLE_CACHE_SIZE)
+            .map(|s| {
+                s.parse::<usize>().map_err(|_| Error::InvalidInput {
+                    message: format!(
+                        "table_cache_size must be a non-negative integer, received {}",
+                        s
+                    ),
+                })
+            })
+            .transpose()?;
+        let conflict_retries = map
+            .get(OPT_CONFLICT_RETRIES)
+            .map(|s| {
+                s.parse::<u32>().map_err(|_| Error::InvalidInput {
+                    message: format!(
+                        "conflict_retries must be a non-negative integer, received {}",
+                        s
+                    ),
+                })
+            })
+            .transpose()?;
+        let auto_index = map
+            .get(OPT_AUTO_INDEX)
+            .map(|s| s.parse::<AutoIndex>())
+            .transpose()?;
         // We just assume that any options that are not new table config options are storage options
         let storage_options = map
             .iter()
             .filter(|(key, _)| {
                 key.as_str() != OPT_NEW_TABLE_STORAGE_VERSION
                     && key.as_str() != OPT_NEW_TABLE_V2_MANIFEST_PATHS
+                    && key.as_str() != OPT_TABLE_CACHE_SIZE
+                    && key.as_str() != OPT_TENANT
+                    && key.as_str() != OPT_DYNAMODB_COMMIT_TABLE
+                    && key.as_str() != OPT_CONFLICT_RETRIES
+                    && key.as_str() != OPT_AUTO_INDEX
             })
             .map(|(key, value)| (key.clone(), value.clone()))
             .collect();
         Ok(Self {
             new_table_config,
             storage_options,
+            table_cache_size,
+            tenant: map.get(OPT_TENANT).cloned(),
+            dynamodb_commit_table: map.get(OPT_DYNAMODB_COMMIT_TABLE).cloned(),
+            conflict_retries,
+            auto_index,
         })
     }
 }
@@ -114,6 +212,27 @@ impl DatabaseOptions for ListingDatabaseOptions {
                 enable_v2_manifest_paths.to_string(),
             );
         }
+        if let Some(table_cache_size) = self.table_cache_size {
+            map.insert(
+                OPT_TABLE_CACHE_SIZE.to_string(),
+                table_cache_size.to_string(),
+            );
+        }
+        if let Some(tenant) = &self.tenant {
+            map.insert(OPT_TENANT.to_string(), tenant.clone());
+        }
+        if let Some(table_name) = &self.dynamodb_commit_table {
+            map.insert(OPT_DYNAMODB_COMMIT_TABLE.to_string(), table_name.clone());
+        }
+        if let Some(conflict_retries) = self.conflict_retries {
+            map.insert(
+                OPT_CONFLICT_RETRIES.to_string(),
+                conflict_retries.to_string(),
+            );
+        }
+        if let Some(auto_index) = self.auto_index {
+            map.insert(OPT_AUTO_INDEX.to_string(), auto_index.to_string());
+        }
     }
 }
 
@@ -151,6 +270,46 @@ impl ListingDatabaseOptionsBuilder {
         self
     }
 
+    /// Cache up to `table_cache_size` opened tables
+    ///
+    /// See [`ListingDatabaseOptions::table_cache_size`]
+    pub fn table_cache_size(mut self, table_cache_size: usize) -> Self {
+        self.options.table_cache_size = Some(table_cache_size);
+        self
+    }
+
+    /// Store all tables under the given path prefix
+    ///
+    /// See [`ListingDatabaseOptions::tenant`]
+    pub fn tenant(mut self, prefix: impl Into<String>) -> Self {
+        self.options.tenant = Some(prefix.into());
+        self
+    }
+
+    /// Coordinate commits through a DynamoDB table
+    ///
+    /// See [`ListingDatabaseOptions::dynamodb_commit_table`]
+    pub fn dynamodb_commit_table(mut self, table_name: impl Into<String>) -> Self {
+        self.options.dynamodb_commit_table = Some(table_name.into());
+        self
+    }
+
+    /// Retry writes that conflict with a concurrent write up to `conflict_retries` times
+    ///
+    /// See [`ListingDatabaseOptions::conflict_retries`]
+    pub fn conflict_retries(mut self, conflict_retries: u32) -> Self {
+        self.options.conflict_retries = Some(conflict_retries);
+        self
+    }
+
+    /// Add written rows to the indices of tables automatically
+    ///
+    /// See [`ListingDatabaseOptions::auto_index`]
+    pub fn auto_index(mut self, auto_index: AutoIndex) -> Self {
+        self.options.auto_index = Some(auto_index);
+        self
+    }
+
     /// Set an option for the storage layer.
     ///
     /// See available options at <https://lancedb.github.io/lancedb/guides/storage/>
@@ -207,7 +366,7 @@ pub struct ListingDatabase {
     pub(crate) uri: String,
     pub(crate) base_path: object_store::path::Path,
 
-    // the object store wrapper to use on write path
+    // the object store wrapper to use when opening or creating tables
     pub(crate) store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
 
     read_consistency_interval: Option<std::time::Duration>,
@@ -217,6 +376,18 @@ pub struct ListingDatabase {
 
     // Options for tables created by this connection
     new_table_config: NewTableConfig,
+
+    // How many times to retry writes that conflict with concurrent writes
+    conflict_retries: u32,
+
+    // Whether tables add written rows to their indices
+    auto_index: Option<AutoIndex>,
+
+    // Recently opened tables, if caching is enabled
+    table_cache: Option<Cache<String, Arc<NativeTable>>>,
+
+    // The store holding all tables, for `memory://` databases
+    memory_store: Option<Arc<dyn object_store::ObjectStore>>,
 }
 
 impl std::fmt::Display for ListingDatabase {
@@ -237,9 +408,37 @@ impl std::fmt::Display for ListingDatabase {
     }
 }
 
+/// Applies one object store wrapper on top of another
+#[derive(Debug)]
+struct ChainedStoreWrapper {
+    inner: Arc<dyn WrappingObjectStore>,
+    outer: Arc<dyn WrappingObjectStore>,
+}
+
+impl WrappingObjectStore for ChainedStoreWrapper {
+    fn wrap(
+        &self,
+        original: Arc<dyn object_store::ObjectStore>,
+    ) -> Arc<dyn object_store::ObjectStore> {
+        self.outer.wrap(self.inner.wrap(original))
+    }
+}
+
 const LANCE_EXTENSION: &str = "lance";
 const ENGINE: &str = "engine";
 const MIRRORED_STORE: &str = "mirroredStore";
+const MEMORY_SCHEME: &str = "memory";
+/// The query parameter lance reads the DynamoDB table name of `s3+ddb://` URIs from
+const DDB_TABLE_NAME_QUERY: &str = "ddbTableName";
+/// The number of files copied at a time by backup and restore
+const COPY_CONCURRENCY: usize = 8;
+/// The number of parts of a file uploaded at a time when it is copied to
+/// another object store
+const UPLOAD_CONCURRENCY: usize = 4;
+/// The number of tables dropped at a time by drop_all_tables
+const DROP_CONCURRENCY: usize = 8;
+/// The number of tables read at a time by list_tables
+const LIST_CONCURRENCY: usize = 8;
 
 /// A connection to LanceDB
 impl ListingDatabase {
@@ -250,10 +449,16 @@ impl ListingDatabase {
     /// See [`ListingDatabaseOptions`] for options that can be set on the connection (via
     /// `storage_options`).
     pub async fn connect_with_options(request: &ConnectRequest) -> Result<Self> {
-        let uri = &request.uri;
-        let parse_res = url::Url::parse(uri);
-
         let options = ListingDatabaseOptions::parse_from_map(&request.options)?;
+        let mut uri = match &options.tenant {
+            Some(tenant) => Self::tenant_uri(&request.uri, tenant)?,
+            None => request.uri.clone(),
+        };
+        if let Some(table_name) = &options.dynamodb_commit_table {
+            uri = Self::dynamodb_uri(&uri, table_name)?;
+        }
+        let uri = &uri;
+        let parse_res = url::Url::parse(uri);
 
         // TODO: pass params regardless of OS
         match parse_res {
@@ -261,7 +466,8 @@ impl ListingDatabase {
                 Self::open_path(
                     uri,
                     request.read_consistency_interval,
-                    options.new_table_config,
+                    request.object_store_wrapper.clone(),
+                    options,
                 )
                 .await
             }
@@ -299,6 +505,25 @@ impl ListingDatabase {
                 // will add a trailing '?' to the url
                 url.set_query(None);
 
+                // Lance creates a new, empty store each time it sees a memory URI, so
+                // we create one store up front and hand it to every table instead.
+                let memory_store = if url.scheme() == MEMORY_SCHEME {
+                    // Lance only looks at the path of memory URIs, so fold any host
+                    // (e.g. `memory://my_db`) into the path.
+                    let path = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+                    url = url::Url::parse(&format!(
+                        "{}:///{}",
+                        MEMORY_SCHEME,
+                        path.trim_start_matches('/')
+                    ))
+                    .map_err(|err| Error::InvalidInput {
+                        message: format!("invalid memory URI {}: {}", uri, err),
+                    })?;
+                    Some(Arc::new(InMemory::new()) as Arc<dyn object_store::ObjectStore>)
+                } else {
+                    None
+                };
+
                 let table_base_uri = if let Some(store) = engine {
                     static WARN_ONCE: std::sync::Once = std::sync::Once::new();
                     WARN_ONCE.call_once(|| {
@@ -316,6 +541,7 @@ impl ListingDatabase {
                 let registry = Arc::new(ObjectStoreRegistry::default());
                 let os_params = ObjectStoreParams {
                     storage_options: Some(options.storage_options.clone()),
+                    object_store: memory_store.clone().map(|store| (store, url.clone())),
                     ..Default::default()
                 };
                 let (object_store, base_path) =
@@ -324,7 +550,7 @@ impl ListingDatabase {
                     Self::try_create_dir(&plain_uri).context(CreateDirSnafu { path: plain_uri })?;
                 }
 
-                let write_store_wrapper = match mirrored_store {
+                let mirror_wrapper = match mirrored_store {
                     Some(path) => {
                         let mirrored_store = Arc::new(LocalFileSystem::new_with_prefix(path)?);
                         let wrapper = MirroringObjectStoreWrapper::new(mirrored_store);
@@ -332,6 +558,15 @@ impl ListingDatabase {
                     }
                     None => None,
                 };
+                let write_store_wrapper =
+                    match (request.object_store_wrapper.clone(), mirror_wrapper) {
+                        (Some(user), Some(mirror)) => Some(Arc::new(ChainedStoreWrapper {
+                            inner: mirror,
+                            outer: user,
+                        })
+                            as Arc<dyn WrappingObjectStore>),
+                        (user, mirror) => user.or(mirror),
+                    };
 
                 Ok(Self {
                     uri: table_base_uri,
@@ -342,23 +577,80 @@ impl ListingDatabase {
                     read_consistency_interval: request.read_consistency_interval,
                     storage_options: options.storage_options,
                     new_table_config: options.new_table_config,
+                    conflict_retries: options.conflict_retries.unwrap_or(DEFAULT_CONFLICT_RETRIES),
+                    auto_index: options.auto_index,
+                    table_cache: Self::build_table_cache(options.table_cache_size),
+                    memory_store,
                 })
             }
             Err(_) => {
                 Self::open_path(
                     uri,
                     request.read_consistency_interval,
-                    options.new_table_config,
+                    request.object_store_wrapper.clone(),
+                    options,
                 )
                 .await
             }
         }
     }
 
+    /// Append a tenant prefix to the database URI
+    fn tenant_uri(uri: &str, tenant: &str) -> Result<String> {
+        let segments = tenant.trim_matches('/').split('/').collect::<Vec<_>>();
+        if segments
+            .iter()
+            .any(|segment| segment.is_empty() || *segment == "." || *segment == "..")
+        {
+            return Err(Error::InvalidInput {
+                message: format!("invalid tenant prefix '{}'", tenant),
+            });
+        }
+        match url::Url::parse(uri) {
+            Ok(mut url) if url.scheme().len() > 1 => {
+                url.path_segments_mut()
+                    .map_err(|_| Error::InvalidInput {
+                        message: format!("cannot add a tenant prefix to the URI {}", uri),
+                    })?
+                    .pop_if_empty()
+                    .extend(segments);
+                Ok(url.to_string())
+            }
+            _ => {
+                let path = segments
+                    .iter()
+                    .fold(Path::new(uri).to_path_buf(), |path, segment| {
+                        path.join(segment)
+                    });
+                Ok(path.to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    /// Switch an `s3://` URI to the `s3+ddb://` scheme, which commits through
+    /// the given DynamoDB table
+    fn dynamodb_uri(uri: &str, table_name: &str) -> Result<String> {
+        let invalid = || Error::InvalidInput {
+            message: format!(
+                "a DynamoDB commit table can only be used with s3:// URIs, not {}",
+                uri
+            ),
+        };
+        let mut url = url::Url::parse(uri).map_err(|_| invalid())?;
+        if url.scheme() != "s3" {
+            return Err(invalid());
+        }
+        url.set_scheme("s3+ddb").map_err(|_| invalid())?;
+        url.query_pairs_mut()
+            .append_pair(DDB_TABLE_NAME_QUERY, table_name);
+        Ok(url.to_string())
+    }
+
     async fn open_path(
         path: &str,
         read_consistency_interval: Option<std::time::Duration>,
-        new_table_config: NewTableConfig,
+        store_wrapper: Option<Arc<dyn WrappingObjectStore>>,
+        options: ListingDatabaseOptions,
     ) -> Result<Self> {
         let (object_store, base_path) = ObjectStore::from_uri(path).await?;
         if object_store.is_local() {
@@ -370,13 +662,50 @@ impl ListingDatabase {
             query_string: None,
             base_path,
             object_store,
-            store_wrapper: None,
+            store_wrapper,
             read_consistency_interval,
             storage_options: HashMap::new(),
-            new_table_config,
+            new_table_config: options.new_table_config,
+            conflict_retries: options.conflict_retries.unwrap_or(DEFAULT_CONFLICT_RETRIES),
+            auto_index: options.auto_index,
+            table_cache: Self::build_table_cache(options.table_cache_size),
+            memory_store: None,
+        })
+    }
+
+    /// Point the object store params for a table at the database's memory store,
+    /// if this is a `memory://` database
+    fn use_memory_store(&self, params: &mut ObjectStoreParams, table_uri: &str) -> Result<()> {
+        if let Some(store) = &self.memory_store {
+            let url = url::Url::parse(table_uri).map_err(|err| Error::InvalidInput {
+                message: format!("invalid table URI {}: {}", table_uri, err),
+            })?;
+            params.object_store = Some((store.clone(), url));
+        }
+        Ok(())
+    }
+
+    fn build_table_cache(size: Option<usize>) -> Option<Cache<String, Arc<NativeTable>>> {
+        size.filter(|size| *size > 0).map(|size| {
+            Cache::builder()
+                .max_capacity(size as u64)
+                .eviction_policy(EvictionPolicy::lru())
+                .build()
         })
     }
 
+    /// Remove a table from the open table cache
+    ///
+    /// The next call to `open_table` will read the table from storage. This is
+    /// done automatically when a table is dropped or overwritten through this
+    /// database, but should be called if the table is modified in a way this
+    /// database can't see, such as being deleted by another process.
+    pub async fn invalidate_table(&self, name: &str) {
+        if let Some(cache) = &self.table_cache {
+            cache.invalidate(name).await;
+        }
+    }
+
     /// Try to create a local directory to store the lancedb dataset
     fn try_create_dir(path: &str) -> core::result::Result<(), std::io::Error> {
         let path = Path::new(path);
@@ -411,6 +740,245 @@ impl ListingDatabase {
         Ok(uri)
     }
 
+    /// Connect to another listing database, using the same storage options
+    async fn connect_sibling(&self, uri: &str) -> Result<Self> {
+        let request = ConnectRequest {
+            uri: uri.to_string(),
+            #[cfg(feature = "remote")]
+            client_config: Default::default(),
+            read_consistency_interval: None,
+            options: self.storage_options.clone(),
+            object_store_wrapper: None,
+        };
+        Self::connect_with_options(&request).await
+    }
+
+    /// Copy the current version of every table in `source` to `target`
+    ///
+    /// Tables in `target` with the same name as a table in `source` are replaced.
+    /// The replacement is copied under a temporary name first and swapped in
+    /// once it is complete, so a copy that fails leaves the existing table as
+    /// it was.
+    async fn copy_tables(source: &Self, target: &Self) -> Result<()> {
+        // The version of every table is read before anything is copied, so
+        // writes made while the tables are copied are left out
+        let mut tables = Vec::new();
+        for name in source.table_names(TableNamesRequest::default()).await? {
+            let dataset = source.latest_dataset(&name).await?;
+            tables.push((name, dataset));
+        }
+        for (name, dataset) in tables {
+            if !target.table_exists(&name).await? {
+                source.copy_table(&name, &dataset, target, &name).await?;
+                continue;
+            }
+            let suffix = uuid::Uuid::new_v4().simple().to_string();
+            let staged = format!("{}.restoring-{}", name, suffix);
+            if let Err(err) = source.copy_table(&name, &dataset, target, &staged).await {
+                let staged_dir = target
+                    .base_path
+                    .child(format!("{}.{}", staged, LANCE_EXTENSION));
+                if let Err(cleanup_err) = target.object_store.remove_dir_all(staged_dir).await {
+                    log::warn!(
+                        "LanceDB: failed to remove the partial copy of table {}: {}",
+                        name,
+                        cleanup_err
+                    );
+                }
+                return Err(err);
+            }
+            let replaced = format!("{}.replaced-{}", name, suffix);
+            target.rename_table(&name, &replaced).await?;
+            target.rename_table(&staged, &name).await?;
+            target.drop_tables(vec![replaced]).await?;
+        }
+        Ok(())
+    }
+
+    /// The latest version of a table, read from storage
+    async fn latest_dataset(&self, name: &str) -> Result<Dataset> {
+        let table = self
+            .open_table(OpenTableRequest {
+                name: name.to_string(),
+                index_cache_size: None,
+                lance_read_params: None,
+                version: None,
+                skip_existence_check: false,
+                query_cache_size: None,
+            })
+            .await?;
+        let table = table.as_native().ok_or_else(|| Error::Runtime {
+            message: format!("table {} is not a native table", name),
+        })?;
+        let mut dataset = table.dataset.get().await?.clone();
+        // A cached table may not have seen the latest version yet
+        dataset.checkout_latest().await?;
+        Ok(dataset)
+    }
+
+    /// Copy a version of a table, and only the files it references, to
+    /// `target_name` in another database
+    async fn copy_table(
+        &self,
+        name: &str,
+        dataset: &Dataset,
+        target: &Self,
+        target_name: &str,
+    ) -> Result<()> {
+        let version = dataset.version().version;
+
+        let source_dir = self
+            .base_path
+            .child(format!("{}.{}", name, LANCE_EXTENSION));
+        let target_dir = target
+            .base_path
+            .child(format!("{}.{}", target_name, LANCE_EXTENSION));
+
+        let mut files = Vec::new();
+        for fragment in dataset.get_fragments() {
+            let metadata = fragment.metadata();
+            for file in &metadata.files {
+                files.push(source_dir.child("data").child(file.path.as_str()));
+            }
+            if let Some(deletion_file) = &metadata.deletion_file {
+                files.push(deletion_file_path(
+                    &source_dir,
+                    fragment.id() as u64,
+                    deletion_file,
+                ));
+            }
+        }
+        for index in dataset.load_indices().await?.iter() {
+            let index_dir = source_dir.child("_indices").child(index.uuid.to_string());
+            let mut index_files = self.object_store.read_dir_all(&index_dir, None).await?;
+            while let Some(file) = index_files.try_next().await? {
+                files.push(file.location);
+            }
+        }
+
+        let manifest = self
+            .object_store
+            .read_dir(source_dir.child("_versions"))
+            .await?
+            .into_iter()
+            .find(|filename| {
+                ManifestNamingScheme::detect_scheme(filename)
+                    .and_then(|scheme| scheme.parse_version(filename))
+                    == Some(version)
+            })
+            .ok_or_else(|| Error::Runtime {
+                message: format!(
+                    "manifest for version {} of table {} not found",
+                    version, name
+                ),
+            })?;
+
+        futures::stream::iter(files)
+            .map(|file| self.copy_file(file, &source_dir, target, &target_dir))
+            .buffer_unordered(COPY_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        // The manifest is copied last, so the table is not visible in the target
+        // until all of the files it references are in place.
+        self.copy_file(
+            source_dir.child("_versions").child(manifest.as_str()),
+            &source_dir,
+            target,
+            &target_dir,
+        )
+        .await
+    }
+
+    async fn copy_file(
+        &self,
+        path: object_store::path::Path,
+        source_dir: &object_store::path::Path,
+        target: &Self,
+        target_dir: &object_store::path::Path,
+    ) -> Result<()> {
+        let target_path = path
+            .prefix_match(source_dir)
+            .ok_or_else(|| Error::Runtime {
+                message: format!("file {} is outside of the table directory", path),
+            })?
+            .fold(target_dir.clone(), |dir, part| dir.child(part));
+        // The file is streamed in parts, so it is never held in memory whole
+        let mut data = self.object_store.inner.get(&path).await?.into_stream();
+        let upload = target
+            .object_store
+            .inner
+            .put_multipart(&target_path)
+            .await?;
+        let mut writer = WriteMultipart::new(upload);
+        let written = async {
+            while let Some(chunk) = data.try_next().await? {
+                writer.wait_for_capacity(UPLOAD_CONCURRENCY).await?;
+                writer.put(chunk);
+            }
+            Ok::<_, object_store::Error>(())
+        }
+        .await;
+        match written {
+            Ok(()) => {
+                writer.finish().await?;
+                Ok(())
+            }
+            Err(err) => {
+                writer.abort().await?;
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Read the statistics of a table from its latest manifest
+    async fn table_info(&self, name: String) -> Result<TableInfo> {
+        let table = self
+            .open_table(OpenTableRequest {
+                name: name.clone(),
+                index_cache_size: None,
+                lance_read_params: None,
+                version: None,
+                skip_existence_check: false,
+                query_cache_size: None,
+            })
+            .await?;
+        let table = table.as_native().ok_or_else(|| Error::Runtime {
+            message: format!("table {} is not a native table", name),
+        })?;
+        let dataset = table.dataset.get().await?.clone();
+        let version = dataset.version();
+        // Row counts are stored in the fragment metadata, so this does not read data
+        let num_rows = dataset.count_rows(None).await?;
+        // Each index may be made up of several segments with the same name
+        let num_indices = dataset
+            .load_indices()
+            .await?
+            .iter()
+            .map(|index| index.name.as_str())
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+
+        let table_dir = self
+            .base_path
+            .child(format!("{}.{}", name, LANCE_EXTENSION));
+        let mut size_bytes = 0;
+        let mut files = self.object_store.read_dir_all(&table_dir, None).await?;
+        while let Some(file) = files.try_next().await? {
+            size_bytes += file.size as u64;
+        }
+
+        Ok(TableInfo {
+            name,
+            stats: Some(TableStats {
+                num_rows,
+                size_bytes: Some(size_bytes),
+                version: version.version,
+                last_modified: version.timestamp,
+                num_indices,
+            }),
+        })
+    }
+
     async fn drop_tables(&self, names: Vec<String>) -> Result<()> {
         let object_store_params = ObjectStoreParams {
             storage_options: Some(self.storage_options.clone()),
@@ -426,6 +994,7 @@ impl ListingDatabase {
             let full_path = self.base_path.child(dir_name.clone());
 
             commit_handler.delete(&full_path).await?;
+            self.invalidate_table(&name).await;
 
             self.object_store
                 .remove_dir_all(full_path.clone())
@@ -495,6 +1064,12 @@ impl Database for ListingDatabase {
         let storage_options = storage_options.clone();
 
         let mut write_params = request.write_options.lance_write_params.unwrap_or_default();
+        self.use_memory_store(
+            write_params
+                .store_params
+                .get_or_insert_with(Default::default),
+            &table_uri,
+        )?;
 
         if let Some(storage_version) = &self.new_table_config.data_storage_version {
             write_params.data_storage_version = Some(*storage_version);
@@ -532,7 +1107,17 @@ impl Database for ListingDatabase {
         )
         .await
         {
-            Ok(table) => Ok(Arc::new(table)),
+            Ok(table) => {
+                let table = Arc::new(
+                    table
+                        .with_conflict_retries(self.conflict_retries)
+                        .with_auto_index(self.auto_index),
+                );
+                if let Some(cache) = &self.table_cache {
+                    cache.insert(request.name, table.clone()).await;
+                }
+                Ok(table)
+            }
             Err(Error::TableAlreadyExists { name }) => match request.mode {
                 CreateTableMode::Create => Err(Error::TableAlreadyExists { name }),
                 CreateTableMode::ExistOk(callback) => {
@@ -540,6 +1125,9 @@ impl Database for ListingDatabase {
                         name: request.name.clone(),
                         index_cache_size: None,
                         lance_read_params: None,
+                        version: None,
+                        skip_existence_check: false,
+                        query_cache_size: None,
                     };
                     let req = (callback)(req);
                     let table = self.open_table(req).await?;
@@ -564,6 +1152,21 @@ impl Database for ListingDatabase {
     async fn open_table(&self, mut request: OpenTableRequest) -> Result<Arc<dyn BaseTable>> {
         let table_uri = self.table_uri(&request.name)?;
 
+        // Tables opened with custom parameters, or at an old version, are not
+        // shared through the cache
+        let use_cache = request.lance_read_params.is_none()
+            && request.index_cache_size.is_none()
+            && request.query_cache_size.is_none()
+            && request.version.is_none();
+        if let (Some(cache), true) = (&self.table_cache, use_cache) {
+            if let Some(table) = cache.get(&request.name).await {
+                // A holder of the handle may have checked out an old version
+                if table.dataset.time_travel_version().await.is_none() {
+                    return Ok(table);
+                }
+            }
+        }
+
         // Inherit storage options from the connection
         let storage_options = request
             .lance_read_params
@@ -584,13 +1187,19 @@ impl Database for ListingDatabase {
         // If we have a user provided ReadParams use that
         // If we don't then start with the default ReadParams and customize it with
         // the options from the OpenTableBuilder
-        let read_params = request.lance_read_params.unwrap_or_else(|| {
+        let mut read_params = request.lance_read_params.unwrap_or_else(|| {
             let mut default_params = ReadParams::default();
             if let Some(index_cache_size) = request.index_cache_size {
                 default_params.index_cache_size = index_cache_size as usize;
             }
             default_params
         });
+        self.use_memory_store(
+            read_params
+                .store_options
+                .get_or_insert_with(Default::default),
+            &table_uri,
+        )?;
 
         let native_table = Arc::new(
             NativeTable::open_with_params(
@@ -600,15 +1209,137 @@ impl Database for ListingDatabase {
                 Some(read_params),
                 self.read_consistency_interval,
             )
-            .await?,
+            .await?
+            .with_conflict_retries(self.conflict_retries)
+            .with_auto_index(self.auto_index)
+            .with_query_cache_size(request.query_cache_size.unwrap_or(0)),
         );
+        match &request.version {
+            Some(TableVersionRef::Version(version)) => native_table.checkout(*version).await?,
+            Some(TableVersionRef::Tag(tag)) => native_table.checkout_tag(tag).await?,
+            None => {}
+        }
+        if use_cache {
+            if let Some(cache) = &self.table_cache {
+                cache.insert(request.name, native_table.clone()).await;
+            }
+        }
         Ok(native_table)
     }
 
-    async fn rename_table(&self, _old_name: &str, _new_name: &str) -> Result<()> {
-        Err(Error::NotSupported {
-            message: "rename_table is not supported in LanceDB OSS".to_string(),
-        })
+    async fn table_exists(&self, name: &str) -> Result<bool> {
+        validate_table_name(name)?;
+        // A table is any non-empty `.lance` directory, which matches
+        // `table_names`.  The store is always checked, because the table may
+        // have been dropped by another process since it was cached.
+        let table_path = self
+            .base_path
+            .child(format!("{}.{}", name, LANCE_EXTENSION));
+        let exists = !self.object_store.read_dir(table_path).await?.is_empty();
+        if !exists {
+            self.invalidate_table(name).await;
+        }
+        Ok(exists)
+    }
+
+    async fn list_tables(&self, request: ListTablesRequest) -> Result<Vec<TableInfo>> {
+        let names = self
+            .table_names(TableNamesRequest {
+                start_after: request.start_after,
+                limit: request.limit,
+            })
+            .await?;
+        if !request.include_stats {
+            return Ok(names
+                .into_iter()
+                .map(|name| TableInfo { name, stats: None })
+                .collect());
+        }
+        futures::stream::iter(names)
+            .map(|name| self.table_info(name))
+            .buffered(LIST_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()> {
+        validate_table_name(old_name)?;
+        validate_table_name(new_name)?;
+        let source_dir = self
+            .base_path
+            .child(format!("{}.{}", old_name, LANCE_EXTENSION));
+        let target_dir = self
+            .base_path
+            .child(format!("{}.{}", new_name, LANCE_EXTENSION));
+
+        if self.object_store.is_local() {
+            // A directory rename is atomic on local storage.  Renaming onto an
+            // existing table fails, but the error for a directory that isn't
+            // empty differs between platforms, so the target is checked first.
+            if self.table_exists(new_name).await? {
+                return Err(Error::TableAlreadyExists {
+                    name: new_name.to_string(),
+                });
+            }
+            let local = LocalFileSystem::new();
+            tokio::fs::rename(
+                local.path_to_filesystem(&source_dir)?,
+                local.path_to_filesystem(&target_dir)?,
+            )
+            .await
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::NotFound => Error::TableNotFound {
+                    name: old_name.to_string(),
+                },
+                std::io::ErrorKind::AlreadyExists => Error::TableAlreadyExists {
+                    name: new_name.to_string(),
+                },
+                _ => Error::Runtime {
+                    message: format!(
+                        "failed to rename table {} to {}: {}",
+                        old_name, new_name, err
+                    ),
+                },
+            })?;
+        } else {
+            // Object stores can't rename atomically, so the tables are checked
+            // up front
+            if !self.table_exists(old_name).await? {
+                return Err(Error::TableNotFound {
+                    name: old_name.to_string(),
+                });
+            }
+            if self.table_exists(new_name).await? {
+                return Err(Error::TableAlreadyExists {
+                    name: new_name.to_string(),
+                });
+            }
+            // Object stores have no directories, so every file is copied.  The
+            // manifests are copied last, so the new table is not visible until
+            // all of its files are in place, and the old table is removed once
+            // the copy is complete.
+            let versions_dir = source_dir.child("_versions");
+            let (manifests, files): (Vec<_>, Vec<_>) = self
+                .object_store
+                .read_dir_all(&source_dir, None)
+                .await?
+                .map_ok(|file| file.location)
+                .try_collect::<Vec<_>>()
+                .await?
+                .into_iter()
+                .partition(|path| path.prefix_match(&versions_dir).is_some());
+            for files in [files, manifests] {
+                futures::stream::iter(files)
+                    .map(|file| self.copy_file(file, &source_dir, self, &target_dir))
+                    .buffer_unordered(COPY_CONCURRENCY)
+                    .try_collect::<Vec<_>>()
+                    .await?;
+            }
+            self.object_store.remove_dir_all(source_dir).await?;
+        }
+        self.invalidate_table(old_name).await;
+        self.invalidate_table(new_name).await;
+        Ok(())
     }
 
     async fn drop_table(&self, name: &str) -> Result<()> {
@@ -617,7 +1348,28 @@ impl Database for ListingDatabase {
 
     async fn drop_all_tables(&self) -> Result<()> {
         let tables = self.table_names(TableNamesRequest::default()).await?;
-        self.drop_tables(tables).await
+        // Keep dropping the other tables if one fails, and don't fail on a
+        // table that was dropped by someone else in the meantime
+        let results = futures::stream::iter(tables)
+            .map(|name| async move { self.drop_tables(vec![name]).await })
+            .buffer_unordered(DROP_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        results
+            .into_iter()
+            .filter(|result| !matches!(result, Err(Error::TableNotFound { .. })))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+
+    async fn backup(&self, target_uri: &str) -> Result<()> {
+        let target = self.connect_sibling(target_uri).await?;
+        Self::copy_tables(self, &target).await
+    }
+
+    async fn restore(&self, source_uri: &str) -> Result<()> {
+        let source = self.connect_sibling(source_uri).await?;
+        Self::copy_tables(&source, self).await
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -0,0 +1,447 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Hooks for observing changes made to a database through a connection
+//!
+//! Register a [`DatabaseObserver`] with
+//! [`crate::connection::ConnectBuilder::observer`] to be told when tables are
+//! created, dropped, or written to. Only changes made through that connection
+//! are reported, changes made by other processes are not.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::RecordBatchReader;
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion_physical_plan::ExecutionPlan;
+
+use crate::arrow::SendableRecordBatchStream;
+use crate::connection::NoData;
+use crate::error::Result;
+use crate::index::{IndexBuilder, IndexConfig, IndexStatistics};
+use crate::query::QueryExecutionOptions;
+use crate::table::merge::MergeInsertBuilder;
+use crate::table::stats::TableStatistics;
+use crate::table::tags::TagContents;
+use crate::table::{
+    AddDataBuilder, AnyQuery, BaseTable, ColumnAlteration, DatasetRecordBatchStream, DeleteResult,
+    Filter, NativeTable, NewColumnTransform, OptimizeAction, OptimizeStats, TableDefinition,
+    UpdateBuilder, Version,
+};
+use crate::transaction::StagedWrite;
+
+use super::{
+    CreateTableRequest, Database, ListTablesRequest, OpenTableRequest, TableInfo, TableNamesRequest,
+};
+
+/// The kind of write that was committed to a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOperation {
+    Add,
+    Delete,
+    Update,
+    MergeInsert,
+    CreateIndex,
+    DropIndex,
+    Optimize,
+    AddColumns,
+    AlterColumns,
+    DropColumns,
+    Restore,
+    /// The metadata of the table, or the settings stored with it such as the
+    /// row TTL, the column constraints and the column defaults
+    UpdateMetadata,
+    UpdateTags,
+    /// The data files were rewritten in a new file format, see
+    /// [`crate::Table::migrate_format`]
+    MigrateFormat,
+    /// A write committed by a [`crate::transaction::Transaction`] was undone
+    /// because another write in the transaction failed
+    Revert,
+}
+
+impl std::fmt::Display for WriteOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Add => "add",
+            Self::Delete => "delete",
+            Self::Update => "update",
+            Self::MergeInsert => "merge_insert",
+            Self::CreateIndex => "create_index",
+            Self::DropIndex => "drop_index",
+            Self::Optimize => "optimize",
+            Self::AddColumns => "add_columns",
+            Self::AlterColumns => "alter_columns",
+            Self::DropColumns => "drop_columns",
+            Self::Restore => "restore",
+            Self::UpdateMetadata => "update_metadata",
+            Self::UpdateTags => "update_tags",
+            Self::MigrateFormat => "migrate_format",
+            Self::Revert => "revert",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Receives events when a database is changed through a connection
+///
+/// The methods are called after the change has succeeded, and are called
+/// synchronously, so they should return quickly. All methods do nothing by default.
+pub trait DatabaseObserver: Send + Sync + std::fmt::Debug {
+    /// A table was created
+    ///
+    /// Renaming a table is reported as the old name being dropped and the new
+    /// name being created.
+    fn on_table_created(&self, _name: &str) {}
+    /// A table was dropped
+    fn on_table_dropped(&self, _name: &str) {}
+    /// A write to a table was committed
+    fn on_write_committed(&self, _table_name: &str, _operation: WriteOperation) {}
+}
+
+/// A database that reports changes to an observer
+#[derive(Debug)]
+pub struct ObservedDatabase {
+    inner: Arc<dyn Database>,
+    observer: Arc<dyn DatabaseObserver>,
+}
+
+impl ObservedDatabase {
+    pub fn new(inner: Arc<dyn Database>, observer: Arc<dyn DatabaseObserver>) -> Self {
+        Self { inner, observer }
+    }
+
+    fn wrap_table(&self, table: Arc<dyn BaseTable>) -> Arc<dyn BaseTable> {
+        Arc::new(ObservedTable {
+            inner: table,
+            observer: self.observer.clone(),
+        })
+    }
+}
+
+impl std::fmt::Display for ObservedDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+#[async_trait]
+impl Database for ObservedDatabase {
+    async fn table_names(&self, request: TableNamesRequest) -> Result<Vec<String>> {
+        self.inner.table_names(request).await
+    }
+
+    async fn list_tables(&self, request: ListTablesRequest) -> Result<Vec<TableInfo>> {
+        self.inner.list_tables(request).await
+    }
+
+    async fn create_table(&self, request: CreateTableRequest) -> Result<Arc<dyn BaseTable>> {
+        let name = request.name.clone();
+        let table = self.inner.create_table(request).await?;
+        self.observer.on_table_created(&name);
+        Ok(self.wrap_table(table))
+    }
+
+    async fn open_table(&self, request: OpenTableRequest) -> Result<Arc<dyn BaseTable>> {
+        let table = self.inner.open_table(request).await?;
+        Ok(self.wrap_table(table))
+    }
+
+    async fn table_exists(&self, name: &str) -> Result<bool> {
+        self.inner.table_exists(name).await
+    }
+
+    async fn rename_table(&self, old_name: &str, new_name: &str) -> Result<()> {
+        self.inner.rename_table(old_name, new_name).await?;
+        self.observer.on_table_dropped(old_name);
+        self.observer.on_table_created(new_name);
+        Ok(())
+    }
+
+    async fn drop_table(&self, name: &str) -> Result<()> {
+        self.inner.drop_table(name).await?;
+        self.observer.on_table_dropped(name);
+        Ok(())
+    }
+
+    async fn drop_all_tables(&self) -> Result<()> {
+        let names = self.inner.table_names(TableNamesRequest::default()).await?;
+        self.inner.drop_all_tables().await?;
+        for name in names {
+            self.observer.on_table_dropped(&name);
+        }
+        Ok(())
+    }
+
+    async fn backup(&self, target_uri: &str) -> Result<()> {
+        self.inner.backup(target_uri).await
+    }
+
+    async fn restore(&self, source_uri: &str) -> Result<()> {
+        let names = self.inner.table_names(TableNamesRequest::default()).await?;
+        self.inner.restore(source_uri).await?;
+        let restored = self.inner.table_names(TableNamesRequest::default()).await?;
+        for name in restored {
+            if names.contains(&name) {
+                self.observer.on_table_dropped(&name);
+            }
+            self.observer.on_table_created(&name);
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A table that reports writes to an observer
+#[derive(Debug)]
+struct ObservedTable {
+    inner: Arc<dyn BaseTable>,
+    observer: Arc<dyn DatabaseObserver>,
+}
+
+impl ObservedTable {
+    fn committed<T>(&self, result: Result<T>, operation: WriteOperation) -> Result<T> {
+        if result.is_ok() {
+            self.observer
+                .on_write_committed(self.inner.name(), operation);
+        }
+        result
+    }
+}
+
+impl std::fmt::Display for ObservedTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+#[async_trait]
+impl BaseTable for ObservedTable {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn schema(&self) -> Result<SchemaRef> {
+        self.inner.schema().await
+    }
+
+    async fn count_rows(&self, filter: Option<Filter>) -> Result<usize> {
+        self.inner.count_rows(filter).await
+    }
+
+    async fn create_plan(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.create_plan(query, options).await
+    }
+
+    async fn query(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<DatasetRecordBatchStream> {
+        self.inner.query(query, options).await
+    }
+
+    async fn explain_plan(&self, query: &AnyQuery, verbose: bool) -> Result<String> {
+        self.inner.explain_plan(query, verbose).await
+    }
+
+    async fn analyze_plan(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<String> {
+        self.inner.analyze_plan(query, options).await
+    }
+
+    async fn add(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<()> {
+        let result = self.inner.add(add, data).await;
+        self.committed(result, WriteOperation::Add)
+    }
+
+    async fn stage_add(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<StagedWrite> {
+        self.inner.stage_add(add, data).await
+    }
+
+    async fn check_staged(&self, write: &StagedWrite) -> Result<()> {
+        self.inner.check_staged(write).await
+    }
+
+    async fn commit_staged(&self, write: StagedWrite) -> Result<u64> {
+        let result = self.inner.commit_staged(write).await;
+        self.committed(result, WriteOperation::Add)
+    }
+
+    async fn revert_staged(&self, version: u64) -> Result<()> {
+        let result = self.inner.revert_staged(version).await;
+        self.committed(result, WriteOperation::Revert)
+    }
+
+    async fn add_stream(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: SendableRecordBatchStream,
+    ) -> Result<()> {
+        let result = self.inner.add_stream(add, data).await;
+        self.committed(result, WriteOperation::Add)
+    }
+
+    async fn delete(&self, predicate: &str, with_row_ids: bool) -> Result<DeleteResult> {
+        let result = self.inner.delete(predicate, with_row_ids).await;
+        self.committed(result, WriteOperation::Delete)
+    }
+
+    async fn update(&self, update: UpdateBuilder) -> Result<u64> {
+        let result = self.inner.update(update).await;
+        self.committed(result, WriteOperation::Update)
+    }
+
+    async fn create_index(&self, index: IndexBuilder) -> Result<()> {
+        let result = self.inner.create_index(index).await;
+        self.committed(result, WriteOperation::CreateIndex)
+    }
+
+    async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
+        self.inner.list_indices().await
+    }
+
+    async fn drop_index(&self, name: &str) -> Result<()> {
+        let result = self.inner.drop_index(name).await;
+        self.committed(result, WriteOperation::DropIndex)
+    }
+
+    async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>> {
+        self.inner.index_stats(index_name).await
+    }
+
+    async fn merge_insert(
+        &self,
+        params: MergeInsertBuilder,
+        new_data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<()> {
+        let result = self.inner.merge_insert(params, new_data).await;
+        self.committed(result, WriteOperation::MergeInsert)
+    }
+
+    async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats> {
+        let result = self.inner.optimize(action).await;
+        self.committed(result, WriteOperation::Optimize)
+    }
+
+    async fn add_columns(
+        &self,
+        transforms: NewColumnTransform,
+        read_columns: Option<Vec<String>>,
+    ) -> Result<()> {
+        let result = self.inner.add_columns(transforms, read_columns).await;
+        self.committed(result, WriteOperation::AddColumns)
+    }
+
+    async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
+        let result = self.inner.alter_columns(alterations).await;
+        self.committed(result, WriteOperation::AlterColumns)
+    }
+
+    async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
+        let result = self.inner.drop_columns(columns).await;
+        self.committed(result, WriteOperation::DropColumns)
+    }
+
+    async fn version(&self) -> Result<u64> {
+        self.inner.version().await
+    }
+
+    async fn checkout(&self, version: u64) -> Result<()> {
+        self.inner.checkout(version).await
+    }
+
+    async fn checkout_latest(&self) -> Result<()> {
+        self.inner.checkout_latest().await
+    }
+
+    async fn restore(&self) -> Result<()> {
+        let result = self.inner.restore().await;
+        self.committed(result, WriteOperation::Restore)
+    }
+
+    async fn list_versions(&self) -> Result<Vec<Version>> {
+        self.inner.list_versions().await
+    }
+
+    async fn metadata(&self) -> Result<HashMap<String, String>> {
+        self.inner.metadata().await
+    }
+
+    async fn update_metadata(&self, values: HashMap<String, String>) -> Result<()> {
+        let result = self.inner.update_metadata(values).await;
+        self.committed(result, WriteOperation::UpdateMetadata)
+    }
+
+    async fn delete_metadata(&self, keys: &[&str]) -> Result<()> {
+        let result = self.inner.delete_metadata(keys).await;
+        self.committed(result, WriteOperation::UpdateMetadata)
+    }
+
+    async fn stats(&self) -> Result<TableStatistics> {
+        self.inner.stats().await
+    }
+
+    async fn list_tags(&self) -> Result<HashMap<String, TagContents>> {
+        self.inner.list_tags().await
+    }
+
+    async fn tag_version(&self, tag: &str) -> Result<u64> {
+        self.inner.tag_version(tag).await
+    }
+
+    async fn create_tag(&self, tag: &str, version: u64) -> Result<()> {
+        let result = self.inner.create_tag(tag, version).await;
+        self.committed(result, WriteOperation::UpdateTags)
+    }
+
+    async fn update_tag(&self, tag: &str, version: u64) -> Result<()> {
+        let result = self.inner.update_tag(tag, version).await;
+        self.committed(result, WriteOperation::UpdateTags)
+    }
+
+    async fn delete_tag(&self, tag: &str) -> Result<()> {
+        let result = self.inner.delete_tag(tag).await;
+        self.committed(result, WriteOperation::UpdateTags)
+    }
+
+    async fn table_definition(&self) -> Result<TableDefinition> {
+        self.inner.table_definition().await
+    }
+
+    fn dataset_uri(&self) -> &str {
+        self.inner.dataset_uri()
+    }
+
+    fn native_table(&self, write: Option<WriteOperation>) -> Result<Option<&NativeTable>> {
+        self.inner.native_table(write)
+    }
+
+    fn native_write_committed(&self, write: WriteOperation) {
+        self.inner.native_write_committed(write);
+        self.observer.on_write_committed(self.inner.name(), write);
+    }
+}
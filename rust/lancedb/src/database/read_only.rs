@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Wrappers that reject any operation that would modify a database or its tables
+//!
+//! These are used by connections opened with
+//! [`crate::connection::ConnectBuilder::read_only`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::RecordBatchReader;
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion_physical_plan::ExecutionPlan;
+
+use crate::arrow::SendableRecordBatchStream;
+use crate::connection::NoData;
+use crate::error::{Error, Result};
+use crate::index::{IndexBuilder, IndexConfig, IndexStatistics};
+use crate::query::QueryExecutionOptions;
+use crate::table::merge::MergeInsertBuilder;
+use crate::table::stats::TableStatistics;
+use crate::table::tags::TagContents;
+use crate::table::{
+    AddDataBuilder, AnyQuery, BaseTable, ColumnAlteration, DatasetRecordBatchStream, DeleteResult,
+    Filter, NativeTable, NewColumnTransform, OptimizeAction, OptimizeStats, TableDefinition,
+    UpdateBuilder, Version,
+};
+use crate::transaction::StagedWrite;
+
+use super::observer::WriteOperation;
+use super::{
+    CreateTableRequest, Database, ListTablesRequest, OpenTableRequest, TableInfo, TableNamesRequest,
+};
+
+fn read_only_error<T>(operation: &str) -> Result<T> {
+    Err(Error::ReadOnly {
+        operation: operation.to_string(),
+    })
+}
+
+/// A database that can be read from but not modified
+#[derive(Debug)]
+pub struct ReadOnlyDatabase {
+    inner: Arc<dyn Database>,
+}
+
+impl ReadOnlyDatabase {
+    pub fn new(inner: Arc<dyn Database>) -> Self {
+        Self { inner }
+    }
+}
+
+impl std::fmt::Display for ReadOnlyDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReadOnly({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl Database for ReadOnlyDatabase {
+    async fn table_names(&self, request: TableNamesRequest) -> Result<Vec<String>> {
+        self.inner.table_names(request).await
+    }
+
+    async fn list_tables(&self, request: ListTablesRequest) -> Result<Vec<TableInfo>> {
+        self.inner.list_tables(request).await
+    }
+
+    async fn create_table(&self, _request: CreateTableRequest) -> Result<Arc<dyn BaseTable>> {
+        read_only_error("create_table")
+    }
+
+    async fn open_table(&self, request: OpenTableRequest) -> Result<Arc<dyn BaseTable>> {
+        let table = self.inner.open_table(request).await?;
+        Ok(Arc::new(ReadOnlyTable { inner: table }))
+    }
+
+    async fn table_exists(&self, name: &str) -> Result<bool> {
+        self.inner.table_exists(name).await
+    }
+
+    async fn rename_table(&self, _old_name: &str, _new_name: &str) -> Result<()> {
+        read_only_error("rename_table")
+    }
+
+    async fn drop_table(&self, _name: &str) -> Result<()> {
+        read_only_error("drop_table")
+    }
+
+    async fn drop_all_tables(&self) -> Result<()> {
+        read_only_error("drop_all_tables")
+    }
+
+    async fn backup(&self, target_uri: &str) -> Result<()> {
+        self.inner.backup(target_uri).await
+    }
+
+    async fn restore(&self, _source_uri: &str) -> Result<()> {
+        read_only_error("restore")
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A table that can be read from but not modified
+#[derive(Debug)]
+pub struct ReadOnlyTable {
+    inner: Arc<dyn BaseTable>,
+}
+
+impl std::fmt::Display for ReadOnlyTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReadOnly({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl BaseTable for ReadOnlyTable {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn schema(&self) -> Result<SchemaRef> {
+        self.inner.schema().await
+    }
+
+    async fn count_rows(&self, filter: Option<Filter>) -> Result<usize> {
+        self.inner.count_rows(filter).await
+    }
+
+    async fn create_plan(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.inner.create_plan(query, options).await
+    }
+
+    async fn query(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<DatasetRecordBatchStream> {
+        self.inner.query(query, options).await
+    }
+
+    async fn explain_plan(&self, query: &AnyQuery, verbose: bool) -> Result<String> {
+        self.inner.explain_plan(query, verbose).await
+    }
+
+    async fn analyze_plan(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<String> {
+        self.inner.analyze_plan(query, options).await
+    }
+
+    async fn add(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<()> {
+        read_only_error("add")
+    }
+
+    async fn stage_add(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<StagedWrite> {
+        read_only_error("add")
+    }
+
+    async fn add_stream(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: SendableRecordBatchStream,
+    ) -> Result<()> {
+        read_only_error("add")
+    }
+
+    async fn delete(&self, _predicate: &str, _with_row_ids: bool) -> Result<DeleteResult> {
+        read_only_error("delete")
+    }
+
+    async fn update(&self, _update: UpdateBuilder) -> Result<u64> {
+        read_only_error("update")
+    }
+
+    async fn create_index(&self, _index: IndexBuilder) -> Result<()> {
+        read_only_error("create_index")
+    }
+
+    async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
+        self.inner.list_indices().await
+    }
+
+    async fn drop_index(&self, _name: &str) -> Result<()> {
+        read_only_error("drop_index")
+    }
+
+    async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>> {
+        self.inner.index_stats(index_name).await
+    }
+
+    async fn merge_insert(
+        &self,
+        _params: MergeInsertBuilder,
+        _new_data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<()> {
+        read_only_error("merge_insert")
+    }
+
+    async fn optimize(&self, _action: OptimizeAction) -> Result<OptimizeStats> {
+        read_only_error("optimize")
+    }
+
+    async fn add_columns(
+        &self,
+        _transforms: NewColumnTransform,
+        _read_columns: Option<Vec<String>>,
+    ) -> Result<()> {
+        read_only_error("add_columns")
+    }
+
+    async fn alter_columns(&self, _alterations: &[ColumnAlteration]) -> Result<()> {
+        read_only_error("alter_columns")
+    }
+
+    async fn drop_columns(&self, _columns: &[&str]) -> Result<()> {
+        read_only_error("drop_columns")
+    }
+
+    async fn version(&self) -> Result<u64> {
+        self.inner.version().await
+    }
+
+    async fn checkout(&self, version: u64) -> Result<()> {
+        self.inner.checkout(version).await
+    }
+
+    async fn checkout_latest(&self) -> Result<()> {
+        self.inner.checkout_latest().await
+    }
+
+    async fn restore(&self) -> Result<()> {
+        read_only_error("restore")
+    }
+
+    async fn list_versions(&self) -> Result<Vec<Version>> {
+        self.inner.list_versions().await
+    }
+
+    async fn metadata(&self) -> Result<HashMap<String, String>> {
+        self.inner.metadata().await
+    }
+
+    async fn update_metadata(&self, _values: HashMap<String, String>) -> Result<()> {
+        read_only_error("update_metadata")
+    }
+
+    async fn delete_metadata(&self, _keys: &[&str]) -> Result<()> {
+        read_only_error("delete_metadata")
+    }
+
+    async fn stats(&self) -> Result<TableStatistics> {
+        self.inner.stats().await
+    }
+
+    async fn list_tags(&self) -> Result<HashMap<String, TagContents>> {
+        self.inner.list_tags().await
+    }
+
+    async fn tag_version(&self, tag: &str) -> Result<u64> {
+        self.inner.tag_version(tag).await
+    }
+
+    async fn create_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        read_only_error("create_tag")
+    }
+
+    async fn update_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        read_only_error("update_tag")
+    }
+
+    async fn delete_tag(&self, _tag: &str) -> Result<()> {
+        read_only_error("delete_tag")
+    }
+
+    async fn table_definition(&self) -> Result<TableDefinition> {
+        self.inner.table_definition().await
+    }
+
+    fn dataset_uri(&self) -> &str {
+        self.inner.dataset_uri()
+    }
+
+    fn native_table(&self, write: Option<WriteOperation>) -> Result<Option<&NativeTable>> {
+        match write {
+            Some(write) => read_only_error(&write.to_string()),
+            None => self.inner.native_table(None),
+        }
+    }
+}
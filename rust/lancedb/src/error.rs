@@ -35,6 +35,55 @@ pub enum Error {
     Schema { message: String },
     #[snafu(display("Runtime error: {message}"))]
     Runtime { message: String },
+    #[snafu(display("Cannot {operation}, the connection is read-only"))]
+    ReadOnly { operation: String },
+    /// A write conflicted with a concurrent write and could not be retried
+    ///
+    /// Compatible concurrent writes, such as two appends, are always resolved
+    /// automatically.  This is only returned when the writes are incompatible,
+    /// for example two deletes of the same rows, and the operation still
+    /// conflicted after the retries configured on the connection.
+    ///
+    /// LanceDB Cloud doesn't report the version of the conflicting write, but
+    /// reports the id of the request instead.
+    #[snafu(display(
+        "Commit conflict{}{}: {message}",
+        version
+            .map(|version| format!(" at version {}", version))
+            .unwrap_or_default(),
+        request_id
+            .as_ref()
+            .map(|request_id| format!(" (request_id={})", request_id))
+            .unwrap_or_default()
+    ))]
+    CommitConflict {
+        version: Option<u64>,
+        message: String,
+        request_id: Option<String>,
+    },
+    /// Values that were written did not pass the table's column constraints
+    ///
+    /// Nothing was committed.  At most 100 violations are reported.
+    #[snafu(display(
+        "{} column constraint violation(s){}",
+        violations.len(),
+        violations
+            .first()
+            .map(|first| format!(", the first is {}", first))
+            .unwrap_or_default()
+    ))]
+    ConstraintViolation {
+        violations: Vec<crate::table::constraints::ConstraintViolation>,
+    },
+    /// An operation did not finish within its timeout, see
+    /// [`crate::query::QueryExecutionOptions::timeout`] and
+    /// [`crate::Table::wait_for_index`]
+    #[snafu(display("Timed out after {timeout:?}"))]
+    Timeout { timeout: std::time::Duration },
+    /// A query was cancelled with its cancellation token, see
+    /// [`crate::query::QueryExecutionOptions::cancellation_token`]
+    #[snafu(display("Query was cancelled"))]
+    Cancelled,
 
     // 3rd party / external errors
     #[snafu(display("object_store error: {source}"))]
@@ -72,6 +121,20 @@ pub enum Error {
         source: Box<dyn std::error::Error + Send + Sync>,
         status_code: Option<reqwest::StatusCode>,
     },
+    #[cfg(feature = "remote")]
+    #[snafu(display("Quota exceeded: (request_id={request_id}) {message}"))]
+    QuotaExceeded {
+        message: String,
+        request_id: String,
+        /// Whether the request may succeed if tried again later. This is true
+        /// for rate limits and false for quotas that won't reset on their own.
+        retryable: bool,
+    },
+    #[snafu(display("Invalid filter: (request_id={request_id}) {message}"))]
+    InvalidFilter { message: String, request_id: String },
+    #[cfg(feature = "remote")]
+    #[snafu(display("Index is still being built: (request_id={request_id}) {message}"))]
+    IndexBuilding { message: String, request_id: String },
     #[snafu(display("Arrow error: {source}"))]
     Arrow { source: ArrowError },
     #[snafu(display("LanceDBError: not supported: {message}"))]
@@ -86,6 +149,32 @@ pub enum Error {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// Whether the operation that failed may succeed if tried again later
+    ///
+    /// For example, a write that lost a race with a concurrent write, or a request
+    /// that was rate limited. Errors returned after the remote client has already
+    /// exhausted its own retries are not considered retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::CommitConflict { .. } => true,
+            #[cfg(feature = "remote")]
+            Self::Http {
+                status_code: Some(status_code),
+                ..
+            } => {
+                status_code.is_server_error()
+                    || *status_code == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            #[cfg(feature = "remote")]
+            Self::QuotaExceeded { retryable, .. } => *retryable,
+            #[cfg(feature = "remote")]
+            Self::IndexBuilding { .. } => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<ArrowError> for Error {
     fn from(source: ArrowError) -> Self {
         Self::Arrow { source }
@@ -96,7 +185,16 @@ impl From<lance::Error> for Error {
     fn from(source: lance::Error) -> Self {
         // TODO: Once Lance is changed to preserve ObjectStore, DataFusion, and Arrow errors, we can
         // pass those variants through here as well.
-        Self::Lance { source }
+        match source {
+            lance::Error::CommitConflict {
+                version, source, ..
+            } => Self::CommitConflict {
+                version: Some(version),
+                message: source.to_string(),
+                request_id: None,
+            },
+            source => Self::Lance { source },
+        }
     }
 }
 
@@ -114,6 +212,15 @@ impl From<object_store::path::Error> for Error {
     }
 }
 
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(source: parquet::errors::ParquetError) -> Self {
+        Self::Other {
+            message: "Parquet error.".to_string(),
+            source: Some(Box::new(source)),
+        }
+    }
+}
+
 impl<T> From<PoisonError<T>> for Error {
     fn from(e: PoisonError<T>) -> Self {
         Self::Runtime {
@@ -141,7 +248,7 @@ impl From<hf_hub::api::sync::ApiError> for Error {
         }
     }
 }
-#[cfg(feature = "sentence-transformers")]
+#[cfg(any(feature = "sentence-transformers", feature = "gpu"))]
 impl From<candle_core::Error> for Error {
     fn from(source: candle_core::Error) -> Self {
         Self::Other {
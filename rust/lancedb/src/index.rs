@@ -15,6 +15,8 @@ use self::{
     vector::{IvfHnswPqIndexBuilder, IvfHnswSqIndexBuilder, IvfPqIndexBuilder},
 };
 
+#[cfg(feature = "gpu")]
+pub(crate) mod gpu;
 pub mod scalar;
 pub mod vector;
 
@@ -36,20 +38,35 @@ pub enum Index {
     ///
     /// This index works best for low-cardinality columns,
     /// where the number of unique values is small (i.e., less than a few hundreds).
+    ///
+    /// Bitmap index is useful to answer queries with equality (`=`), `IN` and
+    /// `IS NULL`, including the prefilters of vector searches, such as
+    /// `status IN ('open', 'pending')` on an enum-like column.
     Bitmap(BitmapIndexBuilder),
 
     /// [LabelListIndexBuilder] is a scalar index that can be used on `List<T>` columns to
-    /// support queries with `array_contains_all` and `array_contains_any`
+    /// support queries with `array_has_all` and `array_has_any`
     /// using an underlying bitmap index.
+    ///
+    /// To find the rows with a single label, write the filter as
+    /// `array_has_any(tags, ['x'])`, or build it with
+    /// [`crate::query::filter::Column::array_contains`].
     LabelList(LabelListIndexBuilder),
 
     /// Full text search index using bm25.
     FTS(FtsIndexBuilder),
 
-    /// IVF index
+    /// IVF index without quantization
+    ///
+    /// The vectors are stored as they are, so the distances are exact and
+    /// the recall only depends on how many partitions are searched, at the
+    /// cost of the most memory and I/O per searched vector.
     IvfFlat(IvfFlatIndexBuilder),
 
     /// IVF index with Product Quantization
+    ///
+    /// The smallest of the vector indices, but the compressed vectors make
+    /// the distances approximate, which a refine step can correct.
     IvfPq(IvfPqIndexBuilder),
 
     /// IVF-HNSW index with Product Quantization
@@ -58,6 +75,11 @@ pub enum Index {
 
     /// IVF-HNSW index with Scalar Quantization
     /// It is a variant of the HNSW algorithm that uses scalar quantization to compress the vectors.
+    ///
+    /// Each dimension is stored in 8 bits, a quarter of the size of float32
+    /// vectors, which keeps the distances much closer to exact than product
+    /// quantization.  Scalar quantization is only available with HNSW, the
+    /// index format has no IVF_SQ index without the graph.
     IvfHnswSq(IvfHnswSqIndexBuilder),
 }
 
@@ -166,6 +188,11 @@ pub struct IndexConfig {
     /// Currently this is always a Vec of size 1.  In the future there may
     /// be more columns to represent composite indices.
     pub columns: Vec<String>,
+    /// The version of the table when the index was last updated
+    ///
+    /// Rows added after this version are not indexed until the index is
+    /// optimized.  This is not always reported by LanceDB Cloud.
+    pub version: Option<u64>,
 }
 
 #[skip_serializing_none]
@@ -208,4 +235,6 @@ pub struct IndexStatistics {
     pub num_indices: Option<u32>,
     /// The loss value used by the index.
     pub loss: Option<f64>,
+    /// The total size of the files of the index, in bytes.
+    pub size_bytes: Option<u64>,
 }
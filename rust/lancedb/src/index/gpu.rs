@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Training the IVF partitions of vector indices on a GPU
+//!
+//! See [`super::vector::IvfPqIndexBuilder::accelerator`]
+
+use std::sync::Arc;
+
+use arrow_array::{cast::AsArray, types::Float32Type, FixedSizeListArray, Float32Array};
+use arrow_schema::{DataType, Field};
+use candle_core::{DType, Device, Tensor};
+use lance::dataset::{Dataset, ProjectionRequest};
+
+use crate::error::{Error, Result};
+use crate::table::sample::sample_rows;
+use crate::DistanceType;
+
+/// The largest number of distances computed at once, so the distances
+/// between the sample and the centroids are never all in memory
+const MAX_DISTANCES: usize = 1 << 26;
+
+/// The device named by `accelerator`, such as `cuda`, `cuda:1` or `metal`
+fn device(accelerator: &str) -> Result<Device> {
+    let invalid = || Error::InvalidInput {
+        message: format!(
+            "unknown accelerator {}, expected cuda, cuda:<n>, metal or metal:<n>",
+            accelerator
+        ),
+    };
+    let (kind, ordinal) = match accelerator.split_once(':') {
+        Some((kind, ordinal)) => (kind, ordinal.parse::<usize>().map_err(|_| invalid())?),
+        None => (accelerator, 0),
+    };
+    match kind {
+        "cuda" => Ok(Device::new_cuda(ordinal)?),
+        "metal" => Ok(Device::new_metal(ordinal)?),
+        _ => Err(invalid()),
+    }
+}
+
+/// Train the centroids of `num_partitions` IVF partitions of `field` with
+/// kmeans on `accelerator`
+///
+/// The training sample has `sample_rate` vectors per partition, as when
+/// Lance trains the partitions itself.  The centroids have the type of the
+/// vectors.
+pub(crate) async fn train_ivf_centroids(
+    dataset: &Dataset,
+    field: &Field,
+    distance_type: DistanceType,
+    num_partitions: usize,
+    sample_rate: usize,
+    max_iterations: usize,
+    accelerator: &str,
+) -> Result<Arc<FixedSizeListArray>> {
+    let not_supported = |reason: String| Error::NotSupported {
+        message: format!(
+            "the partitions of the column `{}` can't be trained on a GPU, {}",
+            field.name(),
+            reason
+        ),
+    };
+    let DataType::FixedSizeList(item, dim) = field.data_type() else {
+        return Err(not_supported(
+            "only fixed size lists of floats are supported".to_string(),
+        ));
+    };
+    if !item.data_type().is_floating() {
+        return Err(not_supported(
+            "only float vectors are supported".to_string(),
+        ));
+    }
+    let normalize = match distance_type {
+        DistanceType::L2 => false,
+        DistanceType::Cosine => true,
+        _ => {
+            return Err(not_supported(format!(
+                "the {} distance is not supported",
+                distance_type
+            )))
+        }
+    };
+    let device = device(accelerator)?;
+    let dim = *dim as usize;
+
+    let projection = ProjectionRequest::from_schema(dataset.schema().project(&[field.name()])?);
+    let sample = sample_rows(dataset, num_partitions * sample_rate, None, projection).await?;
+    let vectors = sample.column(0).as_fixed_size_list();
+    let mut values = Vec::with_capacity(vectors.len() * dim);
+    for vector in vectors.iter().flatten() {
+        let vector = arrow_cast::cast(&vector, &DataType::Float32)?;
+        values.extend_from_slice(vector.as_primitive::<Float32Type>().values());
+    }
+    let num_vectors = values.len() / dim;
+    if num_vectors < num_partitions {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "{} partitions can't be trained from {} vectors",
+                num_partitions, num_vectors
+            ),
+        });
+    }
+
+    let centroids = tokio::task::spawn_blocking(move || -> Result<Vec<f32>> {
+        let mut data = Tensor::from_vec(values, (num_vectors, dim), &device)?;
+        if normalize {
+            data = l2_normalize(&data)?;
+        }
+        let centroids = kmeans(&data, num_partitions, max_iterations, normalize)?;
+        Ok(centroids.flatten_all()?.to_vec1::<f32>()?)
+    })
+    .await
+    .map_err(|err| Error::Runtime {
+        message: format!("training the partitions failed: {}", err),
+    })??;
+
+    let centroids = arrow_cast::cast(&Float32Array::from(centroids), item.data_type())?;
+    Ok(Arc::new(FixedSizeListArray::try_new(
+        item.clone(),
+        dim as i32,
+        centroids,
+        None,
+    )?))
+}
+
+fn l2_normalize(vectors: &Tensor) -> candle_core::Result<Tensor> {
+    let norms = vectors
+        .sqr()?
+        .sum_keepdim(1)?
+        .sqrt()?
+        .maximum(f32::EPSILON)?;
+    vectors.broadcast_div(&norms)
+}
+
+/// The partition of each row of `data`, the one with the closest centroid
+fn assign(data: &Tensor, data_norms: &Tensor, centroids: &Tensor) -> candle_core::Result<Tensor> {
+    let (num_vectors, _) = data.dims2()?;
+    let (num_partitions, _) = centroids.dims2()?;
+    let centroid_norms = centroids.sqr()?.sum_keepdim(1)?.t()?;
+    let centroids = centroids.t()?.contiguous()?;
+    let chunk_size = (MAX_DISTANCES / num_partitions).max(1);
+    let mut partitions = Vec::new();
+    for start in (0..num_vectors).step_by(chunk_size) {
+        let len = chunk_size.min(num_vectors - start);
+        // |x - c|^2 = |x|^2 - 2 x.c + |c|^2
+        let distances = data
+            .narrow(0, start, len)?
+            .matmul(&centroids)?
+            .affine(-2.0, 0.0)?
+            .broadcast_add(&data_norms.narrow(0, start, len)?)?
+            .broadcast_add(&centroid_norms)?;
+        partitions.push(distances.argmin(1)?);
+    }
+    Tensor::cat(&partitions, 0)
+}
+
+/// Lloyd's kmeans, starting from `num_partitions` random rows of `data`
+fn kmeans(
+    data: &Tensor,
+    num_partitions: usize,
+    max_iterations: usize,
+    normalize: bool,
+) -> candle_core::Result<Tensor> {
+    let (num_vectors, _) = data.dims2()?;
+    let device = data.device();
+    let initial = rand::seq::index::sample(&mut rand::thread_rng(), num_vectors, num_partitions)
+        .into_iter()
+        .map(|row| row as u32)
+        .collect::<Vec<_>>();
+    let mut centroids = data.index_select(&Tensor::new(initial, device)?, 0)?;
+    let data_norms = data.sqr()?.sum_keepdim(1)?;
+    let ones = Tensor::ones(num_vectors, DType::F32, device)?;
+
+    let mut previous: Option<Vec<u32>> = None;
+    for _ in 0..max_iterations {
+        let partitions = assign(data, &data_norms, &centroids)?;
+        let current = partitions.to_vec1::<u32>()?;
+        if previous.as_ref() == Some(&current) {
+            break;
+        }
+        let sums = centroids.zeros_like()?.index_add(&partitions, data, 0)?;
+        let counts = Tensor::zeros(num_partitions, DType::F32, device)?
+            .index_add(&partitions, &ones, 0)?
+            .unsqueeze(1)?;
+        let means = sums.broadcast_div(&counts.maximum(1f32)?)?;
+        // An empty partition keeps its centroid
+        let empty = counts.eq(0f32)?.broadcast_as(centroids.shape())?;
+        centroids = empty.where_cond(&centroids, &means)?;
+        if normalize {
+            centroids = l2_normalize(&centroids)?;
+        }
+        previous = Some(current);
+    }
+    Ok(centroids)
+}
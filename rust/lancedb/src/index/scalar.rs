@@ -10,6 +10,8 @@
 //! etc.  Scalar indices can also speed up prefiltering for vector searches.  A single
 //! vector search with prefiltering can use both a scalar index and a vector index.
 
+use crate::error::Result;
+
 /// Builder for a btree index
 ///
 /// A btree index is an index on scalar columns.  The index stores a copy of the column
@@ -45,7 +47,7 @@ pub struct BitmapIndexBuilder {}
 /// Builder for LabelList index.
 ///
 /// [LabeListIndexBuilder] is a scalar index that can be used on `List<T>` columns to
-/// support queries with `array_contains_all` and `array_contains_any`
+/// support queries with `array_has_all` and `array_has_any`
 /// using an underlying bitmap index.
 ///
 #[derive(Debug, Clone, Default)]
@@ -78,6 +80,61 @@ impl FtsIndexBuilder {
         self.with_position = with_position;
         self
     }
+
+    /// Set how the text is split into tokens
+    ///
+    /// - `simple` (the default) splits on whitespace and punctuation
+    /// - `whitespace` splits on whitespace only
+    /// - `raw` keeps the whole text as a single token
+    /// - `lindera/*` and `jieba/*` split Japanese, Korean and Chinese text,
+    ///   using the models in the language model home directory
+    pub fn base_tokenizer(mut self, base_tokenizer: impl Into<String>) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.base_tokenizer(base_tokenizer.into());
+        self
+    }
+
+    /// Set the language used for stemming and stop words, the default is
+    /// `English`
+    ///
+    /// Returns an error if the language is not supported.
+    pub fn language(mut self, language: &str) -> Result<Self> {
+        self.tokenizer_configs = self.tokenizer_configs.language(language)?;
+        Ok(self)
+    }
+
+    /// Leave out tokens longer than `max_token_length` characters, the
+    /// default is 40.  `None` keeps every token.
+    pub fn max_token_length(mut self, max_token_length: Option<usize>) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.max_token_length(max_token_length);
+        self
+    }
+
+    /// Whether tokens are lower cased, the default is true
+    pub fn lower_case(mut self, lower_case: bool) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.lower_case(lower_case);
+        self
+    }
+
+    /// Whether tokens are reduced to their stem in the language, so that
+    /// `running` matches `runs`, the default is false
+    pub fn stem(mut self, stem: bool) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.stem(stem);
+        self
+    }
+
+    /// Whether the stop words of the language, such as `the` in English, are
+    /// left out, the default is false
+    pub fn remove_stop_words(mut self, remove_stop_words: bool) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.remove_stop_words(remove_stop_words);
+        self
+    }
+
+    /// Whether accented characters are folded to ASCII, so that `café`
+    /// matches `cafe`, the default is false
+    pub fn ascii_folding(mut self, ascii_folding: bool) -> Self {
+        self.tokenizer_configs = self.tokenizer_configs.ascii_folding(ascii_folding);
+        self
+    }
 }
 
 pub use lance_index::scalar::inverted::TokenizerConfig;
@@ -55,8 +55,11 @@ macro_rules! impl_distance_type_setter {
         /// grouped in partitions with similar vectors according to this distance type) and to
         /// calculate a subvector's code during quantization.
         ///
-        /// The metric type used to train an index MUST match the metric type used to search the
-        /// index.  Failure to do so will yield inaccurate results.
+        /// A search with a different metric type than the one used to train the index can't use
+        /// the index, and falls back to an exact search.
+        ///
+        /// [DistanceType::Hamming] is only for binary (uint8) vectors, which can only be indexed
+        /// by an IVF Flat index with the hamming distance.
         pub fn distance_type(mut self, distance_type: DistanceType) -> Self {
             self.distance_type = distance_type;
             self
@@ -112,6 +115,20 @@ macro_rules! impl_ivf_params_setter {
             self.max_iterations = max_iterations;
             self
         }
+
+        /// Train the IVF partitions on a GPU, such as `cuda`, `cuda:1` or `metal`
+        ///
+        /// Most of the time spent building an index on a large table goes into
+        /// running kmeans on the training sample to find the partitions.  With
+        /// an accelerator kmeans runs on the GPU, and the vectors are then
+        /// assigned to the partitions and quantized on the CPU as usual.
+        ///
+        /// This requires LanceDB to be built with the `cuda` or `metal` feature,
+        /// and is supported for float vectors with the L2 and cosine distances.
+        pub fn accelerator(mut self, accelerator: impl Into<String>) -> Self {
+            self.accelerator = Some(accelerator.into());
+            self
+        }
     };
 }
 
@@ -133,6 +150,12 @@ macro_rules! impl_pq_params_setter {
             self.num_sub_vectors = Some(num_sub_vectors);
             self
         }
+
+        /// Number of bits used to encode each sub-vector, either 4 or 8.
+        ///
+        /// Each sub-vector is replaced by the closest of `2^num_bits` centroids.
+        /// With 4 bits the index is half the size and faster to search, but less
+        /// accurate.  The default is 8.
         pub fn num_bits(mut self, num_bits: u32) -> Self {
             self.num_bits = Some(num_bits);
             self
@@ -182,6 +205,7 @@ pub struct IvfFlatIndexBuilder {
     pub(crate) num_partitions: Option<u32>,
     pub(crate) sample_rate: u32,
     pub(crate) max_iterations: u32,
+    pub(crate) accelerator: Option<String>,
 }
 
 impl Default for IvfFlatIndexBuilder {
@@ -191,6 +215,7 @@ impl Default for IvfFlatIndexBuilder {
             num_partitions: None,
             sample_rate: 256,
             max_iterations: 50,
+            accelerator: None,
         }
     }
 }
@@ -228,6 +253,7 @@ pub struct IvfPqIndexBuilder {
     pub(crate) num_partitions: Option<u32>,
     pub(crate) sample_rate: u32,
     pub(crate) max_iterations: u32,
+    pub(crate) accelerator: Option<String>,
 
     // PQ
     pub(crate) num_sub_vectors: Option<u32>,
@@ -243,6 +269,7 @@ impl Default for IvfPqIndexBuilder {
             num_bits: None,
             sample_rate: 256,
             max_iterations: 50,
+            accelerator: None,
         }
     }
 }
@@ -293,6 +320,7 @@ pub struct IvfHnswPqIndexBuilder {
     pub(crate) num_partitions: Option<u32>,
     pub(crate) sample_rate: u32,
     pub(crate) max_iterations: u32,
+    pub(crate) accelerator: Option<String>,
 
     // HNSW
     pub(crate) m: u32,
@@ -312,6 +340,7 @@ impl Default for IvfHnswPqIndexBuilder {
             num_bits: None,
             sample_rate: 256,
             max_iterations: 50,
+            accelerator: None,
             m: 20,
             ef_construction: 300,
         }
@@ -341,6 +370,7 @@ pub struct IvfHnswSqIndexBuilder {
     pub(crate) num_partitions: Option<u32>,
     pub(crate) sample_rate: u32,
     pub(crate) max_iterations: u32,
+    pub(crate) accelerator: Option<String>,
 
     // HNSW
     pub(crate) m: u32,
@@ -356,6 +386,7 @@ impl Default for IvfHnswSqIndexBuilder {
             num_partitions: None,
             sample_rate: 256,
             max_iterations: 50,
+            accelerator: None,
             m: 20,
             ef_construction: 300,
         }
@@ -2,3 +2,5 @@
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
 pub mod object_store;
+pub mod policy;
+pub mod storage;
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Retry and timeout policies, shared by the object store and the LanceDB Cloud client
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How to handle timeouts for requests to storage or to LanceDB Cloud.
+///
+/// For LanceDB Cloud this configures the HTTP client. For LanceDB OSS the
+/// timeouts are passed to the object store as the `connect_timeout`, `timeout`
+/// and `pool_idle_timeout` storage options. The defaults and environment
+/// variables below apply to LanceDB Cloud, see the object store
+/// documentation for its defaults.
+#[derive(Clone, Default, Debug)]
+pub struct TimeoutConfig {
+    /// The timeout for creating a connection to the server.
+    ///
+    /// You can also set the `LANCE_CLIENT_CONNECT_TIMEOUT` environment variable
+    /// to set this value. Use an integer value in seconds.
+    ///
+    /// The default is 120 seconds (2 minutes).
+    pub connect_timeout: Option<Duration>,
+    /// The timeout for reading a response from the server.
+    ///
+    /// You can also set the `LANCE_CLIENT_READ_TIMEOUT` environment variable
+    /// to set this value. Use an integer value in seconds.
+    ///
+    /// The default is 300 seconds (5 minutes).
+    pub read_timeout: Option<Duration>,
+    /// The timeout for keeping idle connections alive.
+    ///
+    /// You can also set the `LANCE_CLIENT_CONNECTION_TIMEOUT` environment variable
+    /// to set this value. Use an integer value in seconds.
+    ///
+    /// The default is 300 seconds (5 minutes).
+    pub pool_idle_timeout: Option<Duration>,
+}
+
+impl TimeoutConfig {
+    /// The storage options that apply these timeouts to the object store
+    pub(crate) fn storage_options(&self) -> HashMap<String, String> {
+        [
+            ("connect_timeout", self.connect_timeout),
+            ("timeout", self.read_timeout),
+            ("pool_idle_timeout", self.pool_idle_timeout),
+        ]
+        .into_iter()
+        .filter_map(|(key, timeout)| {
+            timeout.map(|timeout| (key.to_string(), format!("{}ms", timeout.as_millis())))
+        })
+        .collect()
+    }
+}
+
+/// How to handle retries for requests to storage or to LanceDB Cloud.
+///
+/// For LanceDB Cloud this configures the HTTP client. For LanceDB OSS only
+/// `retries` is used, and is passed to the object store as the
+/// `client_max_retries` storage option. The defaults and environment variables
+/// below apply to LanceDB Cloud.
+#[derive(Clone, Default, Debug)]
+pub struct RetryConfig {
+    /// The number of times to retry a request if it fails.
+    ///
+    /// You can also set the `LANCE_CLIENT_MAX_RETRIES` environment variable
+    /// to set this value. Use an integer value.
+    ///
+    /// The default is 3 retries.
+    pub retries: Option<u8>,
+    /// The number of times to retry a request if it fails to connect.
+    ///
+    /// You can also set the `LANCE_CLIENT_CONNECT_RETRIES` environment variable
+    /// to set this value. Use an integer value.
+    ///
+    /// The default is 3 retries.
+    pub connect_retries: Option<u8>,
+    /// The number of times to retry a request if it fails to read.
+    ///
+    /// You can also set the `LANCE_CLIENT_READ_RETRIES` environment variable
+    /// to set this value. Use an integer value.
+    ///
+    /// The default is 3 retries.
+    pub read_retries: Option<u8>,
+    /// The exponential backoff factor to use when retrying requests.
+    ///
+    /// Between each retry, the client will wait for the amount of seconds:
+    ///
+    /// ```text
+    /// {backoff factor} * (2 ** ({number of previous retries}))
+    /// ```
+    ///
+    /// You can also set the `LANCE_CLIENT_RETRY_BACKOFF_FACTOR` environment variable
+    /// to set this value. Use a float value.
+    ///
+    /// The default is 0.25. So the first retry will wait 0.25 seconds, the second
+    /// retry will wait 0.5 seconds, the third retry will wait 1 second, etc.
+    pub backoff_factor: Option<f32>,
+    /// The backoff jitter factor to use when retrying requests.
+    ///
+    /// The backoff jitter is a random value between 0 and the jitter factor in
+    /// seconds.
+    ///
+    /// You can also set the `LANCE_CLIENT_RETRY_BACKOFF_JITTER` environment variable
+    /// to set this value. Use a float value.
+    ///
+    /// The default is 0.25. So between 0 and 0.25 seconds will be added to the
+    /// sleep time between retries.
+    pub backoff_jitter: Option<f32>,
+    /// The set of status codes to retry on.
+    ///
+    /// You can also set the `LANCE_CLIENT_RETRY_STATUSES` environment variable
+    /// to set this value. Use a comma-separated list of integer values.
+    ///
+    /// The default is 429, 500, 502, 503.
+    pub statuses: Option<Vec<u16>>,
+    // TODO: should we allow customizing methods?
+}
+
+impl RetryConfig {
+    /// The storage options that apply this policy to the object store
+    pub(crate) fn storage_options(&self) -> HashMap<String, String> {
+        self.retries
+            .map(|retries| ("client_max_retries".to_string(), retries.to_string()))
+            .into_iter()
+            .collect()
+    }
+}
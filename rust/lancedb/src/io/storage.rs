@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Typed configuration for the object stores backing a database
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Options for the object store (S3, GCS, Azure, ...) that tables are stored in
+///
+/// This is a typed alternative to passing string keys to
+/// [`crate::connection::ConnectBuilder::storage_options`] and
+/// [`crate::connection::OpenTableBuilder::storage_options`]. It can be passed to
+/// either of those methods directly:
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use lancedb::io::storage::StorageConfig;
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let db = lancedb::connect("s3://bucket/path")
+///     .storage_options(
+///         StorageConfig::new()
+///             .aws_region("us-east-1")
+///             .timeout(Duration::from_secs(30)),
+///     )
+///     .execute()
+///     .await
+///     .unwrap();
+/// # });
+/// ```
+///
+/// Options that are not set here fall back to the environment, for example
+/// `AWS_REGION`. See <https://lancedb.github.io/lancedb/guides/storage/> for the
+/// full list of options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageConfig {
+    options: HashMap<String, String>,
+}
+
+impl StorageConfig {
+    /// Create a config with no options set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an option by name, for options that don't have a typed setter.
+    pub fn option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+
+    /// Static credentials to use with S3.
+    pub fn aws_credentials(
+        self,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        let config = self
+            .option("aws_access_key_id", access_key_id)
+            .option("aws_secret_access_key", secret_access_key);
+        match session_token {
+            Some(token) => config.option("aws_session_token", token),
+            None => config,
+        }
+    }
+
+    /// The S3 region the bucket is in.
+    pub fn aws_region(self, region: impl Into<String>) -> Self {
+        self.option("aws_region", region)
+    }
+
+    /// A custom endpoint for S3 compatible stores, such as MinIO or R2.
+    pub fn aws_endpoint(self, endpoint: impl Into<String>) -> Self {
+        self.option("aws_endpoint", endpoint)
+    }
+
+    /// Path to the service account file to use with GCS.
+    pub fn google_service_account(self, path: impl Into<String>) -> Self {
+        self.option("google_service_account", path)
+    }
+
+    /// The storage account and access key to use with Azure.
+    pub fn azure_storage_account(
+        self,
+        account_name: impl Into<String>,
+        account_key: impl Into<String>,
+    ) -> Self {
+        self.option("azure_storage_account_name", account_name)
+            .option("azure_storage_account_key", account_key)
+    }
+
+    /// Send unsigned requests, for reading public buckets without credentials.
+    ///
+    /// This is supported for S3 and Azure.
+    pub fn anonymous(self, anonymous: bool) -> Self {
+        self.option("aws_skip_signature", anonymous.to_string())
+            .option("azure_skip_signature", anonymous.to_string())
+    }
+
+    /// Allow connecting to endpoints over plain HTTP instead of HTTPS.
+    pub fn allow_http(self, allow_http: bool) -> Self {
+        self.option("allow_http", allow_http.to_string())
+    }
+
+    /// The timeout for each request to the object store.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.option("timeout", format!("{}ms", timeout.as_millis()))
+    }
+
+    /// The timeout for establishing a connection to the object store.
+    pub fn connect_timeout(self, timeout: Duration) -> Self {
+        self.option("connect_timeout", format!("{}ms", timeout.as_millis()))
+    }
+}
+
+impl IntoIterator for StorageConfig {
+    type Item = (String, String);
+    type IntoIter = std::collections::hash_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.options.into_iter()
+    }
+}
+
+impl From<StorageConfig> for HashMap<String, String> {
+    fn from(config: StorageConfig) -> Self {
+        config.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_config_keys() {
+        let options: HashMap<String, String> = StorageConfig::new()
+            .aws_credentials("key", "secret", None)
+            .aws_region("us-west-2")
+            .allow_http(true)
+            .timeout(Duration::from_millis(1500))
+            .option("aws_virtual_hosted_style_request", "true")
+            .into();
+        assert_eq!(
+            options,
+            HashMap::from(
+                [
+                    ("aws_access_key_id", "key"),
+                    ("aws_secret_access_key", "secret"),
+                    ("aws_region", "us-west-2"),
+                    ("allow_http", "true"),
+                    ("timeout", "1500ms"),
+                    ("aws_virtual_hosted_style_request", "true"),
+                ]
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+            )
+        );
+    }
+}
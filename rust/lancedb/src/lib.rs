@@ -207,6 +207,7 @@ pub mod query;
 pub mod remote;
 pub mod rerankers;
 pub mod table;
+pub mod transaction;
 pub mod utils;
 
 use std::fmt::Display;
@@ -1,16 +1,24 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::pin::pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use arrow::compute::concat_batches;
-use arrow_array::{make_array, Array, Float16Array, Float32Array, Float64Array};
-use arrow_schema::DataType;
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    make_array, Array, Float16Array, Float32Array, Float64Array, RecordBatch, UInt8Array,
+};
+use arrow_schema::{DataType, Schema};
 use datafusion_expr::Expr;
+use datafusion_physical_plan::display::DisplayableExecutionPlan;
 use datafusion_physical_plan::ExecutionPlan;
-use futures::{stream, try_join, FutureExt, TryStreamExt};
-use half::f16;
+use futures::future::{self, Either};
+use futures::{stream, try_join, FutureExt, StreamExt, TryStreamExt};
+use half::{bf16, f16};
 use lance::{
     arrow::RecordBatchExt,
     dataset::{scanner::DatasetRecordBatchStream, ROW_ID},
@@ -20,18 +28,90 @@ use lance_index::scalar::inverted::SCORE_COL;
 use lance_index::scalar::FullTextSearchQuery;
 use lance_index::vector::DIST_COL;
 use lance_io::stream::RecordBatchStreamAdapter;
+use serde::de::DeserializeOwned;
+use tokio::time::Instant;
+pub use tokio_util::sync::CancellationToken;
 
+use crate::arrow::{SendableRecordBatchStream, SimpleRecordBatchStream};
+use crate::data::export::{export_parquet, ExportParquetOptions, ExportStats};
+use crate::data::rows::batch_into_rows;
 use crate::error::{Error, Result};
 use crate::rerankers::rrf::RRFReranker;
-use crate::rerankers::{check_reranker_result, NormalizeMethod, Reranker};
+use crate::rerankers::{check_reranker_result, NormalizeMethod, Reranker, RELEVANCE_SCORE};
+use crate::table::AnyQuery;
 use crate::table::BaseTable;
+use crate::utils::default_vector_column;
 use crate::DistanceType;
-use crate::{arrow::SendableRecordBatchStream, table::AnyQuery};
 
-mod hybrid;
+pub mod aggregate;
+pub mod filter;
+pub(crate) mod hybrid;
+
+use aggregate::{Aggregate, AggregateQuery};
 
 pub(crate) const DEFAULT_TOP_K: usize = 10;
 
+/// The columns that queries can add to the results
+///
+/// These can be renamed with [`QueryBase::rename_meta_column`] or left out
+/// with [`QueryBase::drop_meta_column`].
+pub const META_COLUMNS: [&str; 5] = [DIST_COL, SCORE_COL, RELEVANCE_SCORE, ROW_ID, "_rowaddr"];
+
+/// When the columns of the results are read, see [`QueryBase::materialization`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Materialization {
+    /// Decide for each column from its width and the kind of storage
+    ///
+    /// Narrow columns, such as numbers, are read early and wide columns, such
+    /// as strings and vectors, are read late.  On cloud storage only columns
+    /// wider than about 1KB per row are read late.
+    #[default]
+    Auto,
+    /// Read every column early, i.e. for all the rows that are scanned
+    AllEarly,
+    /// Read every column late, i.e. only for the rows in the results
+    AllLate,
+    /// Read the given columns late and the other columns early
+    Late(Vec<String>),
+}
+
+/// The direction to sort a column in, see [`Query::order_by`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest values first
+    Asc,
+    /// Largest values first
+    Desc,
+}
+
+/// How a filter treats comparisons with null values, see
+/// [`QueryBase::null_comparison`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullComparison {
+    /// As in SQL, comparing a null value gives an unknown result, which is
+    /// neither true nor false
+    ///
+    /// A row is only kept when the filter is true, so `x <> 5` and
+    /// `NOT (x > 5)` both leave out the rows where `x` is null.
+    #[default]
+    Unknown,
+    /// Comparing a null value is false
+    ///
+    /// `x <> 5` and `NOT (x > 5)` keep the rows where `x` is null.
+    False,
+}
+
+/// What happens when a selected column is not in the table, see
+/// [`QueryBase::missing_columns`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingColumns {
+    /// The query fails
+    #[default]
+    Error,
+    /// The column is returned with a null value in every row
+    Null,
+}
+
 /// Which columns should be retrieved from the database
 #[derive(Debug, Clone)]
 pub enum Select {
@@ -40,6 +120,10 @@ pub enum Select {
     /// Warning: This will always be slower than selecting only the columns you need.
     All,
     /// Select the provided columns
+    ///
+    /// A column can also be a nested field, such as `metadata.author` for a
+    /// field of a struct column, or `chunks[0].text` for a field of the first
+    /// item of a list column.  The output column is named after the path.
     Columns(Vec<String>),
     /// Advanced selection which allows for dynamic column calculations
     ///
@@ -72,6 +156,121 @@ impl Select {
     }
 }
 
+/// A full text search, see [`QueryBase::full_text_search`]
+///
+/// The terms are matched against the columns' full text search indices and
+/// the results are ranked by their BM25 score, which is returned in the
+/// `_score` column.
+#[derive(Debug, Clone)]
+pub struct FtsQuery {
+    query: FullTextSearchQuery,
+}
+
+impl FtsQuery {
+    /// Search for the given terms
+    pub fn new(terms: impl Into<String>) -> Self {
+        Self {
+            query: FullTextSearchQuery::new(terms.into()),
+        }
+    }
+
+    /// Only search these columns
+    ///
+    /// Each column must have a full text search index.  By default every
+    /// column with a full text search index is searched.
+    pub fn columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.query.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Trade recall for speed when ranking the results
+    ///
+    /// The default of 1.0 finds the best results, larger values are faster
+    /// but may miss some of them.
+    pub fn wand_factor(mut self, wand_factor: f32) -> Self {
+        self.query.wand_factor = Some(wand_factor);
+        self
+    }
+}
+
+impl From<FtsQuery> for FullTextSearchQuery {
+    fn from(query: FtsQuery) -> Self {
+        query.query
+    }
+}
+
+/// The projection that reads the given columns, see [`Select::Columns`]
+///
+/// Each column is paired with the SQL expression that reads it.  Struct
+/// fields are read on their own, without the rest of the struct, but a list
+/// column is read in full when one of its items is selected.
+pub(crate) fn column_projection(
+    schema: &Schema,
+    columns: &[String],
+    missing_columns: MissingColumns,
+) -> Result<Vec<(String, String)>> {
+    columns
+        .iter()
+        .map(|column| {
+            let top_level = &column[..column.find(['.', '[']).unwrap_or(column.len())];
+            if missing_columns == MissingColumns::Null
+                && schema.column_with_name(column).is_none()
+                && schema.column_with_name(top_level).is_none()
+            {
+                return Ok((column.clone(), "NULL".to_string()));
+            }
+            Ok((column.clone(), column_expr(schema, column)?))
+        })
+        .collect()
+}
+
+pub(crate) fn quote_identifier(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// The SQL expression that reads a column or a nested field
+fn column_expr(schema: &Schema, column: &str) -> Result<String> {
+    // A top level column may have dots or brackets in its name
+    if schema.column_with_name(column).is_some() || !column.contains(['.', '[']) {
+        return Ok(quote_identifier(column));
+    }
+    let invalid = |reason: &str| Error::InvalidInput {
+        message: format!("invalid column path {}: {}", column, reason),
+    };
+    let mut expr = String::new();
+    // Paths of struct fields are pushed down to the reader, the fields of
+    // list items have to be extracted after reading the list
+    let mut field_path = true;
+    for (position, part) in column.split('.').enumerate() {
+        let (name, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if name.is_empty() {
+            return Err(invalid("a field name is empty"));
+        }
+        expr = if position == 0 {
+            quote_identifier(name)
+        } else if field_path {
+            format!("{}.{}", expr, quote_identifier(name))
+        } else {
+            format!("get_field({}, '{}')", expr, name.replace('\'', "''"))
+        };
+        while let Some(rest) = indices.strip_prefix('[') {
+            let end = rest.find(']').ok_or_else(|| invalid("a [ is not closed"))?;
+            let index = rest[..end]
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| invalid("list indices must be non-negative integers"))?;
+            // SQL list indices start at 1
+            expr = format!("array_element({}, {})", expr, index + 1);
+            field_path = false;
+            indices = &rest[end + 1..];
+        }
+        if !indices.is_empty() {
+            return Err(invalid("unexpected characters after a list index"));
+        }
+    }
+    Ok(expr)
+}
+
 /// A trait for converting a type to a query vector
 ///
 /// This is primarily intended to allow rust users that are unfamiliar with Arrow
@@ -203,6 +402,40 @@ impl IntoQueryVector for &[f16] {
     }
 }
 
+/// bfloat16 values, converted to the requested float type
+///
+/// Arrow has no bfloat16 type, so bfloat16 query vectors are converted to the
+/// element type of the vector column, e.g. float16 for a half precision column.
+impl IntoQueryVector for &[bf16] {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        match data_type {
+            DataType::Float16 => {
+                let arr: Vec<f16> = self.iter().map(|x| f16::from_f32(x.to_f32())).collect();
+                Ok(Arc::new(Float16Array::from(arr)))
+            }
+            DataType::Float32 => {
+                let arr: Vec<f32> = self.iter().map(|x| x.to_f32()).collect();
+                Ok(Arc::new(Float32Array::from(arr)))
+            }
+            DataType::Float64 => {
+                let arr: Vec<f64> = self.iter().map(|x| x.to_f64()).collect();
+                Ok(Arc::new(Float64Array::from(arr)))
+            }
+            _ => Err(Error::InvalidInput {
+                message: format!(
+                    "failed to create query vector, the input data type was &[bf16] but the embedding model \"{}\" expected data type {:?}",
+                    embedding_model_label,
+                    data_type
+                ),
+            }),
+        }
+    }
+}
+
 impl IntoQueryVector for &[f32] {
     fn to_query_vector(
         self,
@@ -263,6 +496,65 @@ impl IntoQueryVector for &[f64] {
     }
 }
 
+/// Binary vectors, with the bits of each vector packed into bytes
+///
+/// Search a column of binary (uint8) vectors with these, see
+/// [`crate::DistanceType::Hamming`].
+impl IntoQueryVector for &[u8] {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        match data_type {
+            DataType::UInt8 => Ok(Arc::new(UInt8Array::from(self.to_vec()))),
+            // Every byte is exactly representable as a float, so the bytes
+            // are recovered when the query vector is cast to the column type
+            DataType::Float16 => {
+                let arr: Vec<f16> = self.iter().map(|x| f16::from(*x)).collect();
+                Ok(Arc::new(Float16Array::from(arr)))
+            }
+            DataType::Float32 => {
+                let arr: Vec<f32> = self.iter().map(|x| *x as f32).collect();
+                Ok(Arc::new(Float32Array::from(arr)))
+            }
+            DataType::Float64 => {
+                let arr: Vec<f64> = self.iter().map(|x| *x as f64).collect();
+                Ok(Arc::new(Float64Array::from(arr)))
+            }
+            _ => Err(Error::InvalidInput {
+                message: format!(
+                    "failed to create query vector, the input data type was &[u8] but the embedding model \"{}\" expected data type {:?}",
+                    embedding_model_label,
+                    data_type
+                ),
+            }),
+        }
+    }
+}
+
+impl<const N: usize> IntoQueryVector for &[u8; N] {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
+impl IntoQueryVector for Vec<u8> {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
 impl<const N: usize> IntoQueryVector for &[f16; N] {
     fn to_query_vector(
         self,
@@ -274,6 +566,17 @@ impl<const N: usize> IntoQueryVector for &[f16; N] {
     }
 }
 
+impl<const N: usize> IntoQueryVector for &[bf16; N] {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
 impl<const N: usize> IntoQueryVector for &[f32; N] {
     fn to_query_vector(
         self,
@@ -307,6 +610,17 @@ impl IntoQueryVector for Vec<f16> {
     }
 }
 
+impl IntoQueryVector for Vec<bf16> {
+    fn to_query_vector(
+        self,
+        data_type: &DataType,
+        embedding_model_label: &str,
+    ) -> Result<Arc<dyn Array>> {
+        self.as_slice()
+            .to_query_vector(data_type, embedding_model_label)
+    }
+}
+
 impl IntoQueryVector for Vec<f32> {
     fn to_query_vector(
         self,
@@ -344,6 +658,13 @@ pub trait QueryBase {
     ///
     /// By default, it fetches starting with the first row.
     /// This method can be used to skip the first `offset` rows.
+    ///
+    /// Together with [`Self::limit`] this can be used to page through the
+    /// results.  The offset is applied by the table (or sent to the server for
+    /// remote tables), so the skipped rows are not returned to the client.
+    /// Queries whose results are reranked, such as hybrid searches, need the
+    /// skipped rows to rank the results, so for those the offset is applied
+    /// after reranking.
     fn offset(self, offset: usize) -> Self;
 
     /// Only return rows which match the filter.
@@ -356,6 +677,26 @@ pub trait QueryBase {
     /// x > 5 OR y = 'test'
     /// ```
     ///
+    /// Text columns can be searched with these predicates:
+    ///
+    /// ```sql
+    /// name LIKE 'abc%'
+    /// name ILIKE '%error%'
+    /// regexp_like(name, '^ab+c')
+    /// contains(name, 'error')
+    /// ```
+    ///
+    /// `LIKE` and `contains` are case sensitive and `ILIKE` ignores case.
+    /// Regular expressions use the syntax of the [`regex`] crate.  `contains`
+    /// finds a substring and can use an NGram index on the column, the other
+    /// predicates do not use scalar indices, they are evaluated on every row
+    /// that the rest of the filter does not rule out.  To search large amounts
+    /// of text for words, create a full text search index and use
+    /// [`Self::full_text_search`] instead.
+    ///
+    /// Filters that include values from users can be built with
+    /// [`filter::col`], which escapes the values.
+    ///
     /// Filtering performance can often be improved by creating a scalar index
     /// on the filter column(s).
     fn only_if(self, filter: impl AsRef<str>) -> Self;
@@ -366,20 +707,22 @@ pub trait QueryBase {
     ///
     /// This method is only valid on tables that have a full text search index.
     ///
+    /// The query can be an [`FtsQuery`] or a [`FullTextSearchQuery`].  The
+    /// BM25 score of each result is returned in the `_score` column.
+    ///
     /// ```
-    /// use lance_index::scalar::FullTextSearchQuery;
-    /// use lancedb::query::{QueryBase, ExecutableQuery};
+    /// use lancedb::query::{ExecutableQuery, FtsQuery, QueryBase};
     ///
     /// # use lancedb::Table;
     /// # async fn query(table: &Table) -> Result<(), Box<dyn std::error::Error>> {
     /// let results = table.query()
-    ///     .full_text_search(FullTextSearchQuery::new("hello world".into()))
+    ///     .full_text_search(FtsQuery::new("hello world").columns(["title", "body"]))
     ///     .execute()
     ///     .await?;
     /// # Ok(())
     /// # }
     /// ```
-    fn full_text_search(self, query: FullTextSearchQuery) -> Self;
+    fn full_text_search(self, query: impl Into<FullTextSearchQuery>) -> Self;
 
     /// Return only the specified columns.
     ///
@@ -403,18 +746,68 @@ pub trait QueryBase {
     ///
     /// Columns will always be returned in the order given, even if that order is different than
     /// the order used when adding the data.
+    ///
+    /// Nested fields can be selected with a path, such as `metadata.author` for a field of a
+    /// struct column or `chunks[0].text` for a field of the first item of a list column.  Only
+    /// the selected fields of a struct are read.
     fn select(self, selection: Select) -> Self;
 
     /// Only execute the query over indexed data.
     ///
     /// This allows weak-consistent fast path for queries that only need to access the indexed data.
     ///
+    /// Normally the rows that were added after the index was last updated are
+    /// searched with a brute-force scan and merged into the results.  On large
+    /// tables that are appended to often this scan can dominate the latency.
+    /// With fast search those rows are skipped, so the query is faster but
+    /// does not see them.  This applies to vector searches and full text
+    /// searches.
+    ///
     /// Users can use [`crate::Table::optimize`] to merge new data into the index, and make the
     /// new data available for fast search.
     ///
     /// By default, it is false.
     fn fast_search(self) -> Self;
 
+    /// Choose which columns are read early and which are read late
+    ///
+    /// Columns that are read early are read for every row that is scanned,
+    /// before the filter is applied.  Columns that are read late are only
+    /// read for the rows that are returned, which reads much less data for
+    /// wide columns, such as documents or images, at the cost of more, smaller
+    /// reads.  Vector searches and full text searches always find the top
+    /// rows using only the row ids and distances or scores, and then read the
+    /// selected columns for just those rows.  This setting controls the
+    /// columns of filtered scans, see [`Materialization`].
+    ///
+    /// Remote tables choose the materialization themselves, so only
+    /// [`Materialization::Auto`] is supported for them.
+    fn materialization(self, materialization: Materialization) -> Self;
+
+    /// Set how the filter treats comparisons with null values
+    ///
+    /// Columns that were added to a table after some of its rows were
+    /// written are null in those rows.  With [`NullComparison::False`] a
+    /// filter such as `status <> 'archived'` keeps those rows, without having
+    /// to write `status IS NULL OR status <> 'archived'`.
+    ///
+    /// Each comparison becomes `(comparison) IS TRUE`, which scalar indices
+    /// cannot answer, so the filter is applied to every scanned row.  This is
+    /// not supported for Substrait filters or by remote tables.
+    fn null_comparison(self, null_comparison: NullComparison) -> Self;
+
+    /// Set what happens when a selected column is not in the table
+    ///
+    /// By default selecting a column that the table does not have is an
+    /// error.  With [`MissingColumns::Null`] the column is returned as a column
+    /// of nulls instead, so an application can select the columns of the
+    /// newest schema from tables that were created with an older one.  This
+    /// only applies to columns selected with [`Select::Columns`], and a
+    /// missing field of a struct column that exists is still an error.
+    ///
+    /// This is not supported by remote tables.
+    fn missing_columns(self, missing_columns: MissingColumns) -> Self;
+
     /// If this is called then filtering will happen after the vector search instead of
     /// before.
     ///
@@ -433,20 +826,68 @@ pub trait QueryBase {
     /// Post filtering happens during the "refine stage" (described in more detail in
     /// [`Self::refine_factor`]).  This means that setting a higher refine factor can often
     /// help restore some of the results lost by post filtering.
+    ///
+    /// This is the same as `prefilter(false)`.
     fn postfilter(self) -> Self;
 
+    /// Choose whether the filter is applied before (`true`) or after (`false`)
+    /// the vector search
+    ///
+    /// Prefiltering is the default.  A selective filter usually works best as a
+    /// prefilter, since postfiltering it could leave few or no results.  A broad
+    /// filter that most rows match can be cheaper as a postfilter.  See
+    /// [`Self::postfilter`] for the trade-offs.
+    fn prefilter(self, prefilter: bool) -> Self;
+
     /// Return the `_rowid` meta column from the Table.
+    ///
+    /// The row ids can be passed to [`crate::Table::delete_by_ids`] and
+    /// [`crate::Table::update_by_ids`] to change the same rows later.  Unless
+    /// the table uses stable row ids, a row id is the row's address, which
+    /// changes when the table is compacted, so row ids should be used soon
+    /// after they are read.
     fn with_row_id(self) -> Self;
 
+    /// Return the `_rowaddr` meta column from the Table.
+    ///
+    /// The address of a row is the id of its fragment in the upper 32 bits
+    /// and its offset in the fragment in the lower 32 bits.  Row addresses are
+    /// not supported by remote tables.
+    fn with_row_address(self) -> Self;
+
     /// Rerank the results using the specified reranker.
     ///
-    /// This is currently only supported for Hybrid Search.
+    /// Reranking is the final stage of the query, it runs after all the
+    /// candidates up to the limit have been collected.  Hybrid searches fuse
+    /// the vector and FTS results with [`Reranker::rerank_hybrid`], vector
+    /// searches and full text searches use [`Reranker::rerank_vector`] and
+    /// [`Reranker::rerank_fts`].  Plain scans cannot be reranked.
     fn rerank(self, reranker: Arc<dyn Reranker>) -> Self;
 
     /// The method to normalize the scores. Can be "rank" or "Score". If "Rank",
     /// the scores are converted to ranks and then normalized. If "Score", the
     /// scores are normalized directly.
     fn norm(self, norm: NormalizeMethod) -> Self;
+
+    /// Rename one of the [`META_COLUMNS`] in the results
+    ///
+    /// This avoids a clash with a column of the same name in the code that
+    /// reads the results.  The column keeps its usual name while the query
+    /// runs, so filters, rerankers and [`ExecutableQuery::create_plan`] still
+    /// use the usual name.  The query fails if the new name is already used
+    /// by another column of the results.
+    ///
+    /// This does not help when the table itself has a column with the name of
+    /// a meta column the query adds, such as `_distance` for a vector search.
+    /// Such queries fail before they run, and the table's column must be
+    /// renamed with [`crate::Table::alter_columns`] instead.
+    fn rename_meta_column(self, column: impl Into<String>, name: impl Into<String>) -> Self;
+
+    /// Leave one of the [`META_COLUMNS`] out of the results
+    ///
+    /// For example, the `_distance` column can be dropped when only the
+    /// order of the results matters.
+    fn drop_meta_column(self, column: impl Into<String>) -> Self;
 }
 
 pub trait HasQuery {
@@ -469,11 +910,11 @@ impl<T: HasQuery> QueryBase for T {
         self
     }
 
-    fn full_text_search(mut self, query: FullTextSearchQuery) -> Self {
+    fn full_text_search(mut self, query: impl Into<FullTextSearchQuery>) -> Self {
         if self.mut_query().limit.is_none() {
             self.mut_query().limit = Some(DEFAULT_TOP_K);
         }
-        self.mut_query().full_text_search = Some(query);
+        self.mut_query().full_text_search = Some(query.into());
         self
     }
 
@@ -487,16 +928,41 @@ impl<T: HasQuery> QueryBase for T {
         self
     }
 
+    fn materialization(mut self, materialization: Materialization) -> Self {
+        self.mut_query().materialization = materialization;
+        self
+    }
+
+    fn null_comparison(mut self, null_comparison: NullComparison) -> Self {
+        self.mut_query().null_comparison = null_comparison;
+        self
+    }
+
+    fn missing_columns(mut self, missing_columns: MissingColumns) -> Self {
+        self.mut_query().missing_columns = missing_columns;
+        self
+    }
+
     fn postfilter(mut self) -> Self {
         self.mut_query().prefilter = false;
         self
     }
 
+    fn prefilter(mut self, prefilter: bool) -> Self {
+        self.mut_query().prefilter = prefilter;
+        self
+    }
+
     fn with_row_id(mut self) -> Self {
         self.mut_query().with_row_id = true;
         self
     }
 
+    fn with_row_address(mut self) -> Self {
+        self.mut_query().with_row_address = true;
+        self
+    }
+
     fn rerank(mut self, reranker: Arc<dyn Reranker>) -> Self {
         self.mut_query().reranker = Some(reranker);
         self
@@ -506,6 +972,18 @@ impl<T: HasQuery> QueryBase for T {
         self.mut_query().norm = Some(norm);
         self
     }
+
+    fn rename_meta_column(mut self, column: impl Into<String>, name: impl Into<String>) -> Self {
+        self.mut_query()
+            .meta_columns
+            .insert(column.into(), Some(name.into()));
+        self
+    }
+
+    fn drop_meta_column(mut self, column: impl Into<String>) -> Self {
+        self.mut_query().meta_columns.insert(column.into(), None);
+        self
+    }
 }
 
 /// Options for controlling the execution of a query
@@ -525,16 +1003,127 @@ pub struct QueryExecutionOptions {
     ///
     /// By default, this is 1024
     pub max_batch_length: u32,
+
+    /// The longest the query may take, including reading all of its results
+    ///
+    /// If the query has not finished by then, it is stopped and the next
+    /// result, or the query itself, fails with [`Error::Timeout`].  By default
+    /// there is no timeout.
+    pub timeout: Option<Duration>,
+
+    /// A token to stop the query early
+    ///
+    /// Once the token is cancelled, the query is stopped and the next result,
+    /// or the query itself, fails with [`Error::Cancelled`].  Dropping the
+    /// query's future or its results also stops it.
+    pub cancellation_token: Option<CancellationToken>,
 }
 
 impl Default for QueryExecutionOptions {
     fn default() -> Self {
         Self {
             max_batch_length: 1024,
+            timeout: None,
+            cancellation_token: None,
+        }
+    }
+}
+
+impl QueryExecutionOptions {
+    /// Set [`Self::timeout`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set [`Self::cancellation_token`]
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+}
+
+/// Why a query was stopped early, see [`QueryExecutionOptions::timeout`] and
+/// [`QueryExecutionOptions::cancellation_token`]
+#[derive(Clone)]
+struct QueryStop {
+    deadline: Option<(Instant, Duration)>,
+    token: Option<CancellationToken>,
+}
+
+impl QueryStop {
+    fn new(options: &QueryExecutionOptions) -> Option<Self> {
+        if options.timeout.is_none() && options.cancellation_token.is_none() {
+            return None;
         }
+        Some(Self {
+            deadline: options
+                .timeout
+                .map(|timeout| (Instant::now() + timeout, timeout)),
+            token: options.cancellation_token.clone(),
+        })
+    }
+
+    /// Wait until the query has to stop
+    async fn wait(&self) -> Error {
+        let timed_out = async {
+            match self.deadline {
+                Some((deadline, timeout)) => {
+                    tokio::time::sleep_until(deadline).await;
+                    Error::Timeout { timeout }
+                }
+                None => future::pending().await,
+            }
+        };
+        let cancelled = async {
+            match &self.token {
+                Some(token) => {
+                    token.cancelled().await;
+                    Error::Cancelled
+                }
+                None => future::pending().await,
+            }
+        };
+        future::select(pin!(timed_out), pin!(cancelled))
+            .await
+            .factor_first()
+            .0
     }
 }
 
+/// Stop a query, and the stream of its results, when the timeout or the
+/// cancellation token of `options` says so
+async fn stop_when_asked(
+    options: &QueryExecutionOptions,
+    results: impl Future<Output = Result<SendableRecordBatchStream>>,
+) -> Result<SendableRecordBatchStream> {
+    let Some(stop) = QueryStop::new(options) else {
+        return results.await;
+    };
+    let results = match future::select(pin!(stop.wait()), pin!(results)).await {
+        Either::Left((err, _)) => return Err(err),
+        Either::Right((results, _)) => results?,
+    };
+
+    let schema = results.schema();
+    let stream = stream::unfold(Some((results, stop)), |state| async move {
+        let (mut results, stop) = state?;
+        let next = match future::select(pin!(stop.wait()), results.next()).await {
+            Either::Left((err, _)) => Some(Err(err)),
+            Either::Right((next, _)) => next,
+        };
+        match next {
+            Some(Ok(batch)) => Some((Ok(batch), Some((results, stop)))),
+            Some(Err(err)) => Some((Err(err), None)),
+            None => None,
+        }
+    });
+    Ok(Box::pin(SimpleRecordBatchStream {
+        schema,
+        stream: Box::pin(stream),
+    }))
+}
+
 /// A trait for a query object that can be executed to get results
 ///
 /// There are various kinds of queries but they all return results
@@ -578,7 +1167,94 @@ pub trait ExecutableQuery {
         options: QueryExecutionOptions,
     ) -> impl Future<Output = Result<SendableRecordBatchStream>> + Send;
 
+    /// Execute the query and deserialize each result row into a `T`
+    ///
+    /// The columns are matched to the fields of `T` by name, see
+    /// [`crate::data::rows`] for how Arrow types map to serde, e.g. vector
+    /// columns can be read into a `Vec<f32>`.  All the results are collected
+    /// in memory.
+    fn execute_into<T: DeserializeOwned + Send>(
+        &self,
+    ) -> impl Future<Output = Result<Vec<T>>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            let mut results = self.execute().await?;
+            let mut rows = Vec::new();
+            while let Some(batch) = results.try_next().await? {
+                rows.extend(batch_into_rows::<T>(&batch)?);
+            }
+            Ok(rows)
+        }
+    }
+
+    /// Execute the query and collect the results into a Polars DataFrame
+    ///
+    /// Each result batch becomes a chunk of the DataFrame, see
+    /// [`crate::arrow::IntoPolars`].
+    #[cfg(feature = "polars")]
+    fn execute_polars(&self) -> impl Future<Output = Result<polars::prelude::DataFrame>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            use crate::arrow::IntoPolars;
+            self.execute().await?.into_polars().await
+        }
+    }
+
+    /// Return the physical plan of the query, without running it
+    ///
+    /// The plan shows how the query will be run, e.g. whether a vector or
+    /// scalar index is used or the search falls back to a flat scan, and
+    /// whether the filter is applied before or after the vector search.  With
+    /// `verbose` the plan also shows details such as the expected IO.
     fn explain_plan(&self, verbose: bool) -> impl Future<Output = Result<String>> + Send;
+
+    /// Run the query and return its physical plan annotated with the metrics
+    /// collected while it ran
+    ///
+    /// The metrics include the number of rows each step produced and the time
+    /// it took, as well as the IO done by the scans and index searches.  The
+    /// results of the query are discarded.
+    fn analyze_plan(&self) -> impl Future<Output = Result<String>> + Send {
+        self.analyze_plan_with_options(QueryExecutionOptions::default())
+    }
+
+    /// Like [`Self::analyze_plan`] but with the given execution options
+    ///
+    /// Queries that can't be analyzed return [`Error::NotSupported`].
+    fn analyze_plan_with_options(
+        &self,
+        _options: QueryExecutionOptions,
+    ) -> impl Future<Output = Result<String>> + Send {
+        async {
+            Err(Error::NotSupported {
+                message: "analyzing the plan is not supported by this query".to_string(),
+            })
+        }
+    }
+
+    /// Execute the query and write the results to Parquet files under the
+    /// directory `uri`
+    ///
+    /// The results are streamed into the files as they are produced.  See
+    /// [`ExportParquetOptions`] for how to partition the files.
+    fn export_parquet(
+        &self,
+        uri: &str,
+        options: ExportParquetOptions,
+    ) -> impl Future<Output = Result<ExportStats>> + Send
+    where
+        Self: Sync,
+    {
+        let uri = uri.to_string();
+        async move {
+            let stream = self.execute().await?;
+            export_parquet(stream, &uri, options).await
+        }
+    }
 }
 
 /// A query filter that can be applied to a query
@@ -618,11 +1294,28 @@ pub struct QueryRequest {
     /// By default, this is false.
     pub fast_search: bool,
 
+    /// Which columns are read early and which late, see
+    /// [`QueryBase::materialization`]
+    pub materialization: Materialization,
+
+    /// How the filter treats comparisons with nulls, see
+    /// [`QueryBase::null_comparison`]
+    pub null_comparison: NullComparison,
+
+    /// What happens when a selected column is not in the table, see
+    /// [`QueryBase::missing_columns`]
+    pub missing_columns: MissingColumns,
+
     /// If set to true, the query will return the `_rowid` meta column.
     ///
     /// By default, this is false.
     pub with_row_id: bool,
 
+    /// If set to true, the query will return the `_rowaddr` meta column.
+    ///
+    /// By default, this is false.
+    pub with_row_address: bool,
+
     /// If set to false, the filter will be applied after the vector search.
     pub prefilter: bool,
 
@@ -632,7 +1325,27 @@ pub struct QueryRequest {
 
     /// Configure how query results are normalized when doing hybrid search
     pub norm: Option<NormalizeMethod>,
-}
+
+    /// Only return a random sample of the rows, see [`Query::sample_fraction`]
+    pub sample: Option<SampleFraction>,
+
+    /// The new names of the meta columns of the results, `None` to drop the
+    /// column, see [`QueryBase::rename_meta_column`]
+    pub meta_columns: HashMap<String, Option<String>>,
+
+    /// The columns to sort the results by, see [`Query::order_by`]
+    pub order_by: Vec<(String, SortOrder)>,
+}
+
+/// A random sample of the rows of a query, see [`Query::sample_fraction`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleFraction {
+    /// The fraction of the matching rows to sample
+    pub fraction: f64,
+    /// The seed of the random number generator, if None the sample is
+    /// different each time
+    pub seed: Option<u64>,
+}
 
 impl Default for QueryRequest {
     fn default() -> Self {
@@ -643,11 +1356,42 @@ impl Default for QueryRequest {
             full_text_search: None,
             select: Select::All,
             fast_search: false,
+            materialization: Materialization::Auto,
+            null_comparison: NullComparison::Unknown,
+            missing_columns: MissingColumns::Error,
             with_row_id: false,
+            with_row_address: false,
             prefilter: true,
             reranker: None,
             norm: None,
+            sample: None,
+            meta_columns: HashMap::new(),
+            order_by: Vec::new(),
+        }
+    }
+}
+
+impl QueryRequest {
+    /// The request for the candidates of a query whose results are reranked
+    ///
+    /// The offset can only be applied once the results are in their final
+    /// order, so the candidates include the rows before the offset.
+    fn without_offset(&self) -> Self {
+        let mut request = self.clone();
+        if let Some(offset) = request.offset.take() {
+            request.limit = request.limit.map(|limit| limit + offset);
         }
+        request
+    }
+
+    /// Apply the offset and limit to results that are in their final order
+    fn page(&self, results: RecordBatch) -> RecordBatch {
+        let offset = self.offset.unwrap_or(0).min(results.num_rows());
+        let limit = self
+            .limit
+            .unwrap_or(usize::MAX)
+            .min(results.num_rows() - offset);
+        results.slice(offset, limit)
     }
 }
 
@@ -695,6 +1439,12 @@ impl Query {
     /// By default, there is no embedding model, and the input should be
     /// vector/slice of floats.
     ///
+    /// The floats can be [`f16`], [`bf16`], `f32` or `f64`.  The query vector
+    /// is converted to the element type of the vector column, so a half
+    /// precision (float16) column, which takes half the memory of a float32
+    /// column, is also searched in half precision.  There is no Arrow type for
+    /// bfloat16, so bfloat16 embeddings should be stored as float16.
+    ///
     /// If there is only one vector column (a column whose data type is a
     /// fixed size list of floats) then the column does not need to be specified.
     /// If there is more than one vector column you must use [`Query::column`]
@@ -730,6 +1480,172 @@ impl Query {
         Ok(vector_query)
     }
 
+    /// Only return a random sample of the rows
+    ///
+    /// `fraction` times the number of rows that match the filter, rounded to
+    /// the nearest row, are sampled without replacement.  The fraction must
+    /// be greater than 0 and at most 1.  The limit and offset apply to the
+    /// sampled rows, which are returned in the order they are stored.  With a
+    /// `seed` the same rows are sampled each time from the same version of the
+    /// table.
+    ///
+    /// Like [`crate::Table::sample`], the rows to sample are chosen up front
+    /// and only those rows are read.  Choosing them reads the row ids of the
+    /// matching rows twice, once to count them and once to find the sampled
+    /// ones.
+    ///
+    /// Sampling is only supported for plain, unsorted queries on local tables.
+    pub fn sample_fraction(mut self, fraction: f64, seed: Option<u64>) -> Self {
+        self.request.sample = Some(SampleFraction { fraction, seed });
+        self
+    }
+
+    /// Sort the results by the values of `columns`
+    ///
+    /// The rows are sorted by the first column, then ties are broken by the
+    /// next column and so on.  Null values come last in both directions.  For
+    /// example, the latest rows matching a filter:
+    ///
+    /// ```
+    /// # use lancedb::query::{ExecutableQuery, QueryBase, SortOrder};
+    /// # async fn example(table: lancedb::Table) -> lancedb::Result<()> {
+    /// let latest = table
+    ///     .query()
+    ///     .only_if("user_id = 42")
+    ///     .order_by([("created_at", SortOrder::Desc)])
+    ///     .limit(10)
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// With a limit only the top `limit` (plus `offset`) rows are kept while
+    /// the matching rows are sorted, so the memory used does not depend on the
+    /// number of matching rows.  Without a limit every matching row is held in
+    /// memory to be sorted.
+    ///
+    /// Sorting is only supported for plain queries on local tables.  Vector
+    /// and full text searches are always sorted by relevance.
+    pub fn order_by(
+        mut self,
+        columns: impl IntoIterator<Item = (impl Into<String>, SortOrder)>,
+    ) -> Self {
+        self.request.order_by = columns
+            .into_iter()
+            .map(|(column, order)| (column.into(), order))
+            .collect();
+        self
+    }
+
+    /// Group the rows of the query by the values of `columns`
+    ///
+    /// Use [`AggregateQuery::aggregate`] to choose the values to compute for
+    /// each group, for example:
+    ///
+    /// ```
+    /// # use lancedb::query::{QueryBase, ExecutableQuery};
+    /// # use lancedb::query::aggregate::{avg, count};
+    /// # async fn example(table: &lancedb::Table) -> lancedb::Result<()> {
+    /// let per_label = table
+    ///     .query()
+    ///     .only_if("score > 0")
+    ///     .group_by(["label"])
+    ///     .aggregate([count(), avg("score")])
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn group_by(self, columns: impl IntoIterator<Item = impl Into<String>>) -> AggregateQuery {
+        AggregateQuery::new(self, columns.into_iter().map(Into::into).collect())
+    }
+
+    /// Aggregate all the rows of the query into a single row
+    ///
+    /// See [`Self::group_by`] to aggregate groups of rows.
+    pub fn aggregate(self, aggregates: impl IntoIterator<Item = Aggregate>) -> AggregateQuery {
+        AggregateQuery::new(self, Vec::new()).aggregate(aggregates)
+    }
+
+    /// Find the nearest vectors to each of several query vectors
+    ///
+    /// This is a shortcut for calling [`Self::nearest_to`] with the first
+    /// vector and [`VectorQuery::add_query_vector`] with the rest.  All the
+    /// searches are sent as one query, which avoids a round trip per vector
+    /// when the table is remote.  Each vector gets its own `limit` results.
+    ///
+    /// When there is more than one vector the output has an extra Int32
+    /// `query_index` column, with the position of the vector in `vectors`
+    /// that each result was found for.  With a single vector the output is
+    /// the same as for [`Self::nearest_to`].
+    ///
+    /// Returns [`Error::InvalidInput`] if `vectors` is empty.
+    pub fn nearest_to_batch(
+        self,
+        vectors: impl IntoIterator<Item = impl IntoQueryVector>,
+    ) -> Result<VectorQuery> {
+        let mut vectors = vectors.into_iter();
+        let first = vectors.next().ok_or_else(|| Error::InvalidInput {
+            message: "nearest_to_batch needs at least one query vector".to_string(),
+        })?;
+        vectors.try_fold(self.nearest_to(first)?, |query, vector| {
+            query.add_query_vector(vector)
+        })
+    }
+
+    /// Find the rows whose multivectors best match several query vectors
+    ///
+    /// This is for late interaction models, such as ColBERT, that embed each
+    /// token of a document and a query as its own vector.  The column searched
+    /// must be a multivector column, a list of fixed size lists with one
+    /// vector per token, see [`VectorQuery::column`].  The query vectors are
+    /// the token embeddings of the query.
+    ///
+    /// The rows are scored with MaxSim: each query vector is matched with its
+    /// most similar vector in the row, and the similarities are summed.  The
+    /// `_distance` column is the number of query vectors minus that sum, so
+    /// the best rows have the smallest distance.  Similarity is measured with
+    /// the cosine distance, which is also the only distance type a vector
+    /// index on a multivector column can be built with.
+    ///
+    /// Returns [`Error::InvalidInput`] if `vectors` is empty.  The query fails
+    /// if the column is not a multivector column.
+    pub fn nearest_to_multivector(
+        self,
+        vectors: impl IntoIterator<Item = impl IntoQueryVector>,
+    ) -> Result<VectorQuery> {
+        let mut vectors = vectors.into_iter();
+        let first = vectors.next().ok_or_else(|| Error::InvalidInput {
+            message: "nearest_to_multivector needs at least one query vector".to_string(),
+        })?;
+        let mut query = vectors.try_fold(self.nearest_to(first)?, |query, vector| {
+            query.add_query_vector(vector)
+        })?;
+        query.request.multivector = true;
+        Ok(query)
+    }
+
+    /// Find the nearest vectors to the vector of an existing row
+    ///
+    /// `filter` is an SQL filter that must match exactly one row, such as
+    /// `"id = 42"`.  The vector of that row is read from the column searched
+    /// by the query, see [`VectorQuery::column`], and used as the query
+    /// vector, so "more like this" searches take a single call.  The row is
+    /// looked up when the query runs, and the query fails with
+    /// [`Error::InvalidInput`] if no row or more than one row matches.
+    ///
+    /// The row itself is usually the first result, with a distance of 0.  Use
+    /// [`QueryBase::only_if`] to leave it out, e.g. `"id != 42"`.
+    pub fn nearest_to_row(self, filter: impl Into<String>) -> VectorQuery {
+        let mut vector_query = self.into_vector();
+        vector_query.request.query_row = Some(filter.into());
+        if vector_query.request.base.limit.is_none() {
+            vector_query.request.base.limit = Some(DEFAULT_TOP_K);
+        }
+        vector_query
+    }
+
     pub fn into_request(self) -> QueryRequest {
         self.request
     }
@@ -737,6 +1653,38 @@ impl Query {
     pub fn current_request(&self) -> &QueryRequest {
         &self.request
     }
+
+    /// Execute the query, with the meta columns under their usual names
+    async fn execute_with_meta_columns(
+        &self,
+        options: QueryExecutionOptions,
+    ) -> Result<SendableRecordBatchStream> {
+        let query = AnyQuery::Query(self.request.clone());
+        if let Some(reranker) = &self.request.reranker {
+            let fts_query =
+                self.request
+                    .full_text_search
+                    .as_ref()
+                    .ok_or_else(|| Error::InvalidInput {
+                        message: "only vector searches and full text searches can be reranked"
+                            .to_string(),
+                    })?;
+            let candidates = AnyQuery::Query(self.request.without_offset());
+            let results = collect_results(
+                self.parent
+                    .clone()
+                    .query(&candidates, options)
+                    .await?
+                    .into(),
+            )
+            .await?;
+            let results = reranker.rerank_fts(&fts_query.query, results).await?;
+            return reranked_stream(results, &self.request);
+        }
+        Ok(SendableRecordBatchStream::from(
+            self.parent.clone().query(&query, options).await?,
+        ))
+    }
 }
 
 impl HasQuery for Query {
@@ -745,6 +1693,120 @@ impl HasQuery for Query {
     }
 }
 
+/// Collect all the results of a query into one batch, so they can be reranked
+async fn collect_results(results: SendableRecordBatchStream) -> Result<RecordBatch> {
+    let schema = results.schema();
+    let batches = results.try_collect::<Vec<_>>().await?;
+    Ok(concat_batches(&schema, batches.iter())?)
+}
+
+/// Run a plan to completion and return it annotated with the execution metrics
+pub(crate) async fn execute_and_analyze(plan: Arc<dyn ExecutionPlan>) -> Result<String> {
+    DatasetRecordBatchStream::new(execute_plan(plan.clone(), Default::default())?)
+        .try_for_each(|_| futures::future::ready(Ok(())))
+        .await?;
+    let display = DisplayableExecutionPlan::with_metrics(plan.as_ref());
+
+    Ok(format!("{}", display.indent(true)))
+}
+
+/// Check the output of a reranker, apply the offset and limit of `request`
+/// and turn it back into a stream
+fn reranked_stream(
+    results: RecordBatch,
+    request: &QueryRequest,
+) -> Result<SendableRecordBatchStream> {
+    check_reranker_result(&results)?;
+    let results = request.page(results);
+    Ok(SendableRecordBatchStream::from(
+        RecordBatchStreamAdapter::new(results.schema(), stream::iter([Ok(results)])),
+    ))
+}
+
+/// Check that the meta columns `query` adds to the results don't have the
+/// same name as a column of the table
+///
+/// Lance can't tell the two apart while the query runs, so the query is
+/// rejected before anything is read.
+pub(crate) fn check_meta_columns(query: &VectorQueryRequest, schema: &Schema) -> Result<()> {
+    let mut added = Vec::new();
+    if !query.query_vector.is_empty() {
+        added.push(DIST_COL);
+    }
+    if query.base.full_text_search.is_some() {
+        added.push(SCORE_COL);
+    }
+    match added
+        .into_iter()
+        .find(|column| schema.column_with_name(column).is_some())
+    {
+        Some(column) => Err(Error::InvalidInput {
+            message: format!(
+                "the table has a column named {}, which clashes with the column of that name \
+                 the query adds, rename the table's column with Table::alter_columns",
+                column
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Rename or drop the meta columns of the results as `request` asks for
+fn rename_meta_columns(
+    results: SendableRecordBatchStream,
+    request: &QueryRequest,
+) -> Result<SendableRecordBatchStream> {
+    if request.meta_columns.is_empty() {
+        return Ok(results);
+    }
+    if let Some(column) = request
+        .meta_columns
+        .keys()
+        .find(|column| !META_COLUMNS.contains(&column.as_str()))
+    {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "{} is not a meta column, only {:?} can be renamed or dropped",
+                column, META_COLUMNS
+            ),
+        });
+    }
+
+    let schema = results.schema();
+    let mut indices = Vec::with_capacity(schema.fields().len());
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut names = HashSet::new();
+    for (i, field) in schema.fields().iter().enumerate() {
+        let field = match request.meta_columns.get(field.name()) {
+            Some(None) => continue,
+            Some(Some(name)) => Arc::new(field.as_ref().clone().with_name(name)),
+            None => field.clone(),
+        };
+        if !names.insert(field.name().clone()) {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "cannot rename a meta column to {}, the results already have a column with that name",
+                    field.name()
+                ),
+            });
+        }
+        indices.push(i);
+        fields.push(field);
+    }
+    let schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+
+    let output_schema = schema.clone();
+    let stream = results.map(move |batch| {
+        let batch = batch?;
+        let columns = indices.iter().map(|i| batch.column(*i).clone()).collect();
+        Ok(RecordBatch::try_new(output_schema.clone(), columns)?)
+    });
+    Ok(Box::pin(SimpleRecordBatchStream {
+        schema,
+        stream: Box::pin(stream),
+    }))
+}
+
 impl ExecutableQuery for Query {
     async fn create_plan(&self, options: QueryExecutionOptions) -> Result<Arc<dyn ExecutionPlan>> {
         let req = AnyQuery::Query(self.request.clone());
@@ -755,16 +1817,20 @@ impl ExecutableQuery for Query {
         &self,
         options: QueryExecutionOptions,
     ) -> Result<SendableRecordBatchStream> {
-        let query = AnyQuery::Query(self.request.clone());
-        Ok(SendableRecordBatchStream::from(
-            self.parent.clone().query(&query, options).await?,
-        ))
+        let results =
+            stop_when_asked(&options, self.execute_with_meta_columns(options.clone())).await?;
+        rename_meta_columns(results, &self.request)
     }
 
     async fn explain_plan(&self, verbose: bool) -> Result<String> {
         let query = AnyQuery::Query(self.request.clone());
         self.parent.explain_plan(&query, verbose).await
     }
+
+    async fn analyze_plan_with_options(&self, options: QueryExecutionOptions) -> Result<String> {
+        let query = AnyQuery::Query(self.request.clone());
+        self.parent.analyze_plan(&query, options).await
+    }
 }
 
 /// A request for a nearest-neighbors search into a table
@@ -793,6 +1859,24 @@ pub struct VectorQueryRequest {
     pub distance_type: Option<DistanceType>,
     /// Default is true. Set to false to enforce a brute force search.
     pub use_index: bool,
+    /// Whether the query was explicitly marked as a hybrid query
+    ///
+    /// A vector query that also has a full text search is always run as a
+    /// hybrid query, this only makes it an error to leave out the full text
+    /// search.
+    pub hybrid: bool,
+    /// A filter that matches the row whose vector is the query vector, see
+    /// [`Query::nearest_to_row`]
+    ///
+    /// The vector is looked up when the query runs, and replaces
+    /// `query_vector`.
+    pub query_row: Option<String>,
+    /// Whether the query vectors are the token embeddings of a single
+    /// multivector query, see [`Query::nearest_to_multivector`]
+    pub multivector: bool,
+    /// Fail unless the filter is answered by scalar indices and pushed into
+    /// the vector index search, see [`VectorQuery::require_index_prefilter`]
+    pub require_index_prefilter: bool,
 }
 
 impl Default for VectorQueryRequest {
@@ -808,6 +1892,10 @@ impl Default for VectorQueryRequest {
             refine_factor: None,
             distance_type: None,
             use_index: true,
+            hybrid: false,
+            query_row: None,
+            multivector: false,
+            require_index_prefilter: false,
         }
     }
 }
@@ -866,6 +1954,10 @@ impl VectorQuery {
     ///
     /// This parameter must be specified if the table has more than one column
     /// whose data type is a fixed-size-list of floats.
+    ///
+    /// The query fails with [`Error::InvalidInput`] if the column does not
+    /// exist, is not a vector column, or holds vectors with a different number
+    /// of dimensions than the query vector.
     pub fn column(mut self, column: &str) -> Self {
         self.request.column = Some(column.to_string());
         self
@@ -881,6 +1973,9 @@ impl VectorQuery {
     /// The output data will contain an additional columns `query_index` which
     /// will contain the index of the query vector that was used to generate the
     /// result.
+    ///
+    /// If the column is a multivector column then the vectors are searched
+    /// together as one multivector query, see [`Query::nearest_to_multivector`].
     pub fn add_query_vector(mut self, vector: impl IntoQueryVector) -> Result<Self> {
         let query_vector = vector.to_query_vector(&DataType::Float32, "default")?;
         self.request.query_vector.push(query_vector);
@@ -907,6 +2002,8 @@ impl VectorQuery {
     /// For best results we recommend tuning this parameter with a benchmark against
     /// your actual data to find the smallest possible value that will still give
     /// you the desired recall.
+    ///
+    /// The query fails if this is 0.
     pub fn nprobes(mut self, nprobes: usize) -> Self {
         self.request.nprobes = nprobes;
         self
@@ -914,6 +2011,11 @@ impl VectorQuery {
 
     /// Set the distance range for vector search,
     /// only rows with distances in the range [lower_bound, upper_bound) will be returned
+    ///
+    /// Either bound can be left open with `None`.  The range is applied by the
+    /// search itself, including the IVF and HNSW index searches, so there is no
+    /// need to ask for a large limit and filter the `_distance` column afterwards.
+    /// The query fails if the range is empty, i.e. `lower_bound >= upper_bound`.
     pub fn distance_range(mut self, lower_bound: Option<f32>, upper_bound: Option<f32>) -> Self {
         self.request.lower_bound = lower_bound;
         self.request.upper_bound = upper_bound;
@@ -923,7 +2025,8 @@ impl VectorQuery {
     /// Set the number of candidates to return during the refine step for HNSW
     ///
     /// This argument is only used when the vector column has an HNSW index.
-    /// If there is no index then this value is ignored.
+    /// If there is no index then this value is ignored.  The query fails if the
+    /// column has another kind of index, or if `ef` is smaller than the limit.
     ///
     /// Increasing this value will increase the recall of your query but will
     /// also increase the latency of your query.  The default value is 1.5*limit.
@@ -934,8 +2037,10 @@ impl VectorQuery {
 
     /// A multiplier to control how many additional rows are taken during the refine step
     ///
-    /// This argument is only used when the vector column has an IVF PQ index.
-    /// If there is no index then this value is ignored.
+    /// This argument is only used when the vector column has a quantized index,
+    /// such as IVF PQ.  If there is no index then this value is ignored.  The
+    /// query fails if this is 0 or if the column has an IVF flat index, which
+    /// already stores the full vectors.
     ///
     /// An IVF PQ index stores compressed (quantized) values.  They query vector is compared
     /// against these values and, since they are compressed, the comparison is inaccurate.
@@ -971,16 +2076,43 @@ impl VectorQuery {
     /// use.  See [`DistanceType`] for more details on the different distance metrics
     /// available.
     ///
-    /// Note: if there is a vector index then the distance type used MUST match the distance
-    /// type used to train the vector index.  If this is not done then the results will be
-    /// invalid.
+    /// If there is a vector index that was trained with a different distance type then
+    /// the index can't be used, and the query fails.  Use [`Self::bypass_vector_index`]
+    /// for an exact search that compares the query vector with every row, which gives
+    /// the right results but can be much slower.  Build an index with the distance type
+    /// that you usually query with.
     ///
-    /// By default [`DistanceType::L2`] is used.
+    /// [`DistanceType::Hamming`] can only be used with binary (uint8) vectors, and is the
+    /// only distance type those vectors support.
+    ///
+    /// By default the distance type of the vector index is used, or [`DistanceType::L2`]
+    /// (hamming for binary vectors) if there is no index.
     pub fn distance_type(mut self, distance_type: DistanceType) -> Self {
         self.request.distance_type = Some(distance_type);
         self
     }
 
+    /// Require the filter to be pushed into the vector index search
+    ///
+    /// When every column in the filter has a scalar index, and the filter only
+    /// uses conditions those indices can answer, the indices are searched
+    /// first and the matching rows are passed to the vector index search as a
+    /// prefilter.  The vector search then only considers matching rows, so
+    /// even a very selective filter returns `limit` results, without scanning
+    /// the filter columns.  Otherwise the filter columns are scanned to find
+    /// the matching rows, which can be slow on large tables.
+    ///
+    /// With this option the query fails with [`Error::InvalidInput`] instead
+    /// of falling back to a scan, e.g. when a filter column has no scalar
+    /// index, the vector column has no index, or the query is postfiltered.
+    /// Queries without a filter are not affected.
+    ///
+    /// This is not yet supported for remote tables.
+    pub fn require_index_prefilter(mut self) -> Self {
+        self.request.require_index_prefilter = true;
+        self
+    }
+
     /// If this is called then any vector index is skipped
     ///
     /// An exhaustive (flat) search will be performed.  The query vector will
@@ -993,15 +2125,47 @@ impl VectorQuery {
         self
     }
 
+    /// Run this query as a hybrid query
+    ///
+    /// A hybrid query runs the vector search and the full text search set with
+    /// [`QueryBase::full_text_search`] as two separate branches and fuses the
+    /// two result sets into one. Both branches use the same filter and limit.
+    ///
+    /// The results are fused with the reranker set with [`QueryBase::rerank`].
+    /// If no reranker is set then Reciprocal Rank Fusion ([`RRFReranker`]) is
+    /// used. The scores of each branch are normalized before fusing, see
+    /// [`QueryBase::norm`]. The fused relevance score is returned in the
+    /// `_relevance_score` column.
+    ///
+    /// A vector query with a full text search is run as a hybrid query even
+    /// without calling this method. Calling it makes the intent explicit and
+    /// causes the query to fail if the full text search is missing, rather
+    /// than silently running a plain vector search.
+    pub fn hybrid(mut self) -> Self {
+        self.request.hybrid = true;
+        self
+    }
+
     pub async fn execute_hybrid(&self) -> Result<SendableRecordBatchStream> {
         // clone query and specify we want to include row IDs, which can be needed for reranking
+        let mut base = self.request.base.clone();
+        base.limit = Some(base.limit.unwrap_or(DEFAULT_TOP_K));
+        let mut base = base.without_offset();
+        // The sub-queries keep the usual names, which the reranker needs
+        base.meta_columns.clear();
+
         let mut fts_query = Query::new(self.parent.clone());
-        fts_query.request = self.request.base.clone();
+        fts_query.request = base.clone();
+        fts_query.request.reranker = None;
         fts_query = fts_query.with_row_id();
 
-        let mut vector_query = self.clone().with_row_id();
+        let mut vector_query = self.clone();
+        vector_query.request.base = base;
+        vector_query = vector_query.with_row_id();
 
         vector_query.request.base.full_text_search = None;
+        vector_query.request.base.reranker = None;
+        vector_query.request.hybrid = false;
         let (fts_results, vec_results) = try_join!(fts_query.execute(), vector_query.execute())?;
 
         let (fts_results, vec_results) = try_join!(
@@ -1047,10 +2211,19 @@ impl VectorQuery {
 
         check_reranker_result(&results)?;
 
-        let limit = self.request.base.limit.unwrap_or(DEFAULT_TOP_K);
-        if results.num_rows() > limit {
-            results = results.slice(0, limit);
-        }
+        let offset = self
+            .request
+            .base
+            .offset
+            .unwrap_or(0)
+            .min(results.num_rows());
+        let limit = self
+            .request
+            .base
+            .limit
+            .unwrap_or(DEFAULT_TOP_K)
+            .min(results.num_rows() - offset);
+        results = results.slice(offset, limit);
 
         if !self.request.base.with_row_id {
             results = results.drop_column(ROW_ID)?;
@@ -1060,35 +2233,130 @@ impl VectorQuery {
             RecordBatchStreamAdapter::new(results.schema(), stream::iter([Ok(results)])),
         ))
     }
-}
 
-impl ExecutableQuery for VectorQuery {
-    async fn create_plan(&self, options: QueryExecutionOptions) -> Result<Arc<dyn ExecutionPlan>> {
-        let query = AnyQuery::VectorQuery(self.request.clone());
-        self.parent.clone().create_plan(&query, options).await
+    /// Look up the vector of the row given to [`Query::nearest_to_row`]
+    async fn with_row_vector(&self) -> Result<Self> {
+        let mut query = self.clone();
+        let Some(filter) = query.request.query_row.take() else {
+            return Ok(query);
+        };
+        let column = match &query.request.column {
+            Some(column) => column.clone(),
+            None => default_vector_column(&self.parent.schema().await?, None)?,
+        };
+        let lookup = QueryRequest {
+            filter: Some(QueryFilter::Sql(filter.clone())),
+            select: Select::Columns(vec![column.clone()]),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let rows = collect_results(
+            self.parent
+                .clone()
+                .query(&AnyQuery::Query(lookup), Default::default())
+                .await?
+                .into(),
+        )
+        .await?;
+        if rows.num_rows() != 1 {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "the filter of nearest_to_row must match exactly one row, {} matched {}",
+                    filter,
+                    if rows.num_rows() == 0 {
+                        "none"
+                    } else {
+                        "more than one"
+                    }
+                ),
+            });
+        }
+        let vectors = rows.column(0);
+        let vector = match vectors.data_type() {
+            DataType::FixedSizeList(_, _) if vectors.is_valid(0) => {
+                vectors.as_fixed_size_list().value(0)
+            }
+            _ => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the row matching {} has no vector in the column {}",
+                        filter, column
+                    ),
+                })
+            }
+        };
+        query.request.column = Some(column);
+        query.request.query_vector = vec![vector];
+        Ok(query)
     }
 
-    async fn execute_with_options(
+    /// Execute the query, with the meta columns under their usual names
+    async fn execute_with_meta_columns(
         &self,
         options: QueryExecutionOptions,
     ) -> Result<SendableRecordBatchStream> {
+        if self.request.hybrid && self.request.base.full_text_search.is_none() {
+            return Err(Error::InvalidInput {
+                message: "a hybrid query needs a full text search, see full_text_search"
+                    .to_string(),
+            });
+        }
         if self.request.base.full_text_search.is_some() {
             let hybrid_result = async move { self.execute_hybrid().await }.boxed().await?;
             return Ok(hybrid_result);
         }
 
+        if let Some(reranker) = &self.request.base.reranker {
+            let mut candidates = self.request.clone();
+            candidates.base = candidates.base.without_offset();
+            let plan = self
+                .parent
+                .clone()
+                .create_plan(&AnyQuery::VectorQuery(candidates), options)
+                .await?;
+            let results = SendableRecordBatchStream::from(DatasetRecordBatchStream::new(
+                execute_plan(plan, Default::default())?,
+            ));
+            let results = reranker
+                .rerank_vector(collect_results(results).await?)
+                .await?;
+            return reranked_stream(results, &self.request.base);
+        }
+
+        let query = AnyQuery::VectorQuery(self.with_row_vector().await?.request);
         Ok(SendableRecordBatchStream::from(
-            DatasetRecordBatchStream::new(execute_plan(
-                self.create_plan(options).await?,
-                Default::default(),
-            )?),
+            self.parent.clone().query(&query, options).await?,
         ))
     }
+}
+
+impl ExecutableQuery for VectorQuery {
+    async fn create_plan(&self, options: QueryExecutionOptions) -> Result<Arc<dyn ExecutionPlan>> {
+        let query = AnyQuery::VectorQuery(self.with_row_vector().await?.request);
+        self.parent.clone().create_plan(&query, options).await
+    }
+
+    async fn execute_with_options(
+        &self,
+        options: QueryExecutionOptions,
+    ) -> Result<SendableRecordBatchStream> {
+        let results = stop_when_asked(&options, async {
+            let query = self.with_row_vector().await?;
+            query.execute_with_meta_columns(options.clone()).await
+        })
+        .await?;
+        rename_meta_columns(results, &self.request.base)
+    }
 
     async fn explain_plan(&self, verbose: bool) -> Result<String> {
-        let query = AnyQuery::VectorQuery(self.request.clone());
+        let query = AnyQuery::VectorQuery(self.with_row_vector().await?.request);
         self.parent.explain_plan(&query, verbose).await
     }
+
+    async fn analyze_plan_with_options(&self, options: QueryExecutionOptions) -> Result<String> {
+        let query = AnyQuery::VectorQuery(self.with_row_vector().await?.request);
+        self.parent.analyze_plan(&query, options).await
+    }
 }
 
 impl HasQuery for VectorQuery {
@@ -1102,12 +2370,13 @@ mod tests {
     use std::{collections::HashSet, sync::Arc};
 
     use super::*;
+    use arrow::buffer::OffsetBuffer;
     use arrow::{array::downcast_array, compute::concat_batches, datatypes::Int32Type};
     use arrow_array::{
-        cast::AsArray, types::Float32Type, FixedSizeListArray, Float32Array, Int32Array,
-        RecordBatch, RecordBatchIterator, RecordBatchReader, StringArray,
+        cast::AsArray, types::Float32Type, ArrayRef, FixedSizeListArray, Float32Array, Int32Array,
+        ListArray, RecordBatch, RecordBatchIterator, RecordBatchReader, StringArray, StructArray,
     };
-    use arrow_schema::{DataType, Field as ArrowField, Schema as ArrowSchema};
+    use arrow_schema::{DataType, Field as ArrowField, Fields, Schema as ArrowSchema};
     use futures::{StreamExt, TryStreamExt};
     use lance_testing::datagen::{BatchGenerator, IncrementingInt32, RandomVector};
     use tempfile::tempdir;
@@ -1170,8 +2439,13 @@ mod tests {
         assert_eq!(query.request.base.offset.unwrap(), 1);
         assert_eq!(query.request.nprobes, 1000);
         assert!(query.request.use_index);
+        assert!(!query.request.base.prefilter);
         assert_eq!(query.request.distance_type, Some(DistanceType::Cosine));
         assert_eq!(query.request.refine_factor, Some(999));
+
+        let query = query.prefilter(true);
+        assert!(query.request.base.prefilter);
+        assert!(!query.prefilter(false).request.base.prefilter);
     }
 
     #[tokio::test]
@@ -1278,6 +2552,97 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_select_nested_fields() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let metadata = StructArray::from(vec![
+            (
+                Arc::new(ArrowField::new("author", DataType::Utf8, false)),
+                Arc::new(StringArray::from(vec!["ann", "bob"])) as ArrayRef,
+            ),
+            (
+                Arc::new(ArrowField::new("year", DataType::Int32, false)),
+                Arc::new(Int32Array::from(vec![2020, 2021])) as ArrayRef,
+            ),
+        ]);
+        let chunk_fields = Fields::from(vec![ArrowField::new("text", DataType::Utf8, false)]);
+        let chunk_items = StructArray::new(
+            chunk_fields.clone(),
+            vec![Arc::new(StringArray::from(vec!["a0", "a1", "b0"])) as ArrayRef],
+            None,
+        );
+        let chunk_field = Arc::new(ArrowField::new(
+            "item",
+            DataType::Struct(chunk_fields),
+            true,
+        ));
+        let chunks = ListArray::new(
+            chunk_field,
+            OffsetBuffer::from_lengths([2, 1]),
+            Arc::new(chunk_items),
+            None,
+        );
+        let batch = RecordBatch::try_from_iter(vec![
+            ("metadata", Arc::new(metadata) as ArrayRef),
+            ("chunks", Arc::new(chunks) as ArrayRef),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table("nested", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let batches = table
+            .query()
+            .select(Select::columns(&[
+                "metadata.author",
+                "chunks[0].text",
+                "chunks[1].text",
+            ]))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = concat_batches(&batches[0].schema(), &batches).unwrap();
+        assert_eq!(batch.num_columns(), 3);
+        let strings = |name: &str| {
+            batch
+                .column_by_name(name)
+                .unwrap()
+                .as_string::<i32>()
+                .iter()
+                .map(|value| value.map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            strings("metadata.author"),
+            vec![Some("ann".to_string()), Some("bob".to_string())]
+        );
+        assert_eq!(
+            strings("chunks[0].text"),
+            vec![Some("a0".to_string()), Some("b0".to_string())]
+        );
+        assert_eq!(
+            strings("chunks[1].text"),
+            vec![Some("a1".to_string()), None]
+        );
+
+        let err = table
+            .query()
+            .select(Select::columns(&["chunks[x].text"]))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
     #[tokio::test]
     async fn test_execute_no_vector() {
         // TODO: Switch back to memory://foo after https://github.com/lancedb/lancedb/issues/1051
@@ -1443,33 +2808,116 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_with_row_id() {
+    async fn test_materialization() {
         let tmp_dir = tempdir().unwrap();
         let table = make_test_table(&tmp_dir).await;
+        let plan = |materialization: Materialization| {
+            table
+                .query()
+                .only_if("id > 5")
+                .materialization(materialization)
+                .explain_plan(true)
+        };
+
+        // The vector column is wide enough to be read late by default
+        assert!(plan(Materialization::Auto).await.unwrap().contains("Take"));
+        let early = plan(Materialization::AllEarly).await.unwrap();
+        assert!(!early.contains("Take"), "{}", early);
+        let late = plan(Materialization::Late(vec!["vector".to_string()]))
+            .await
+            .unwrap();
+        assert!(late.contains("Take"), "{}", late);
+
         let results = table
-            .vector_search(&[0.1, 0.2, 0.3, 0.4])
-            .unwrap()
-            .with_row_id()
-            .limit(10)
+            .query()
+            .only_if("id > 5")
+            .materialization(Materialization::AllLate)
             .execute()
             .await
             .unwrap()
             .try_collect::<Vec<_>>()
             .await
             .unwrap();
-        for batch in results {
-            assert!(batch.column_by_name("_rowid").is_some());
-        }
+        assert_eq!(
+            results.iter().map(|b| b.num_rows()).sum::<usize>(),
+            table.count_rows(Some("id > 5".to_string())).await.unwrap()
+        );
+
+        assert!(plan(Materialization::Late(vec!["missing".to_string()]))
+            .await
+            .is_err());
     }
 
     #[tokio::test]
-    async fn test_distance_range() {
+    async fn test_fast_search_skips_unindexed_rows() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                crate::index::Index::IvfFlat(
+                    crate::index::vector::IvfFlatIndexBuilder::default().num_partitions(1),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table.add(make_non_empty_batches()).execute().await.unwrap();
+
+        let count = |fast_search: bool| {
+            let table = table.clone();
+            async move {
+                let mut query = table
+                    .query()
+                    .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+                    .unwrap()
+                    .limit(2048);
+                if fast_search {
+                    query = query.fast_search();
+                }
+                query
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|batch| batch.num_rows())
+                    .sum::<usize>()
+            }
+        };
+        assert_eq!(count(false).await, 1024);
+        // Only the rows in the index are searched
+        assert_eq!(count(true).await, 512);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_plan() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let query = table
+            .query()
+            .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .limit(5);
+
+        let plan = query.explain_plan(false).await.unwrap();
+        assert!(!plan.contains("metrics="));
+
+        let analyzed = query.analyze_plan().await.unwrap();
+        assert!(analyzed.contains("metrics="), "{}", analyzed);
+        assert!(analyzed.contains("output_rows=5"), "{}", analyzed);
+    }
+
+    #[tokio::test]
+    async fn test_with_row_id() {
         let tmp_dir = tempdir().unwrap();
         let table = make_test_table(&tmp_dir).await;
         let results = table
             .vector_search(&[0.1, 0.2, 0.3, 0.4])
             .unwrap()
-            .distance_range(Some(0.0), Some(1.0))
+            .with_row_id()
             .limit(10)
             .execute()
             .await
@@ -1478,30 +2926,991 @@ mod tests {
             .await
             .unwrap();
         for batch in results {
-            let distances = batch["_distance"].as_primitive::<Float32Type>();
-            assert!(distances.iter().all(|d| {
-                let d = d.unwrap();
-                (0.0..1.0).contains(&d)
-            }));
+            assert!(batch.column_by_name("_rowid").is_some());
         }
     }
 
     #[tokio::test]
-    async fn test_multiple_query_vectors() {
+    async fn test_with_row_address() {
         let tmp_dir = tempdir().unwrap();
         let table = make_test_table(&tmp_dir).await;
-        let query = table
+        let results = table
+            .query()
+            .with_row_id()
+            .with_row_address()
+            .limit(10)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        for batch in results {
+            assert!(batch.column_by_name("_rowid").is_some());
+            assert!(batch.column_by_name("_rowaddr").is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rename_meta_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let results = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .with_row_id()
+            .rename_meta_column(DIST_COL, "dist")
+            .drop_meta_column(ROW_ID)
+            .limit(5)
+            .execute()
+            .await
+            .unwrap();
+        let schema = results.schema();
+        let batches = results.try_collect::<Vec<_>>().await.unwrap();
+        let names = schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["vector", "id", "dist"]);
+        for batch in &batches {
+            assert_eq!(batch.schema(), schema);
+        }
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+
+        // The new name cannot clash with another column
+        let err = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .rename_meta_column(DIST_COL, "id")
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        let err = table
+            .query()
+            .drop_meta_column("id")
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_meta_column_clash() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let vectors = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            (0..4).map(|i| Some(vec![Some(i as f32), Some(0.0)])),
+            2,
+        );
+        let batch = RecordBatch::try_from_iter(vec![
+            ("vector", Arc::new(vectors) as ArrayRef),
+            (
+                DIST_COL,
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(batch)], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Renaming the distance doesn't help, the query fails before it runs
+        let err = table
+            .vector_search(&[0.0, 0.0])
+            .unwrap()
+            .rename_meta_column(DIST_COL, "dist")
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        // Once the table's column is renamed, both can be read
+        table
+            .alter_columns(&[
+                crate::table::ColumnAlteration::new(DIST_COL.into()).rename("priority".into())
+            ])
+            .await
+            .unwrap();
+        let results = table
+            .vector_search(&[0.0, 0.0])
+            .unwrap()
+            .limit(2)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0]["priority"].as_primitive::<Int32Type>().value(0),
+            1
+        );
+        assert_eq!(
+            results[0][DIST_COL].as_primitive::<Float32Type>().value(0),
+            0.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_into() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Row {
+            id: i32,
+            vector: Vec<f32>,
+            _distance: f32,
+        }
+
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let rows = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .only_if("id < 100")
+            .limit(5)
+            .execute_into::<Row>()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 5);
+        for row in &rows {
+            assert!(row.id < 100);
+            assert_eq!(row.vector.len(), 4);
+        }
+        assert!(rows.windows(2).all(|w| w[0]._distance <= w[1]._distance));
+
+        let err = table
+            .query()
+            .select(Select::columns(&["id"]))
+            .execute_into::<Row>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_distance_range() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let results = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .distance_range(Some(0.0), Some(1.0))
+            .limit(10)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        for batch in results {
+            let distances = batch["_distance"].as_primitive::<Float32Type>();
+            assert!(distances.iter().all(|d| {
+                let d = d.unwrap();
+                (0.0..1.0).contains(&d)
+            }));
+        }
+
+        let err = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .distance_range(Some(1.0), Some(1.0))
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_multiple_query_vectors() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let query = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .add_query_vector(&[0.5, 0.6, 0.7, 0.8])
+            .unwrap()
+            .limit(1);
+
+        let plan = query.explain_plan(true).await.unwrap();
+        assert!(plan.contains("UnionExec"));
+
+        let results = query
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(results.num_rows(), 2); // One result for each query vector.
+        let query_index = results["query_index"].as_primitive::<Int32Type>();
+        // We don't guarantee order.
+        assert!(query_index.values().contains(&0));
+        assert!(query_index.values().contains(&1));
+    }
+
+    #[tokio::test]
+    async fn test_nearest_to_row() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let results = table
+            .query()
+            .nearest_to_row("id = 3")
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(results.num_rows(), 3);
+        assert_eq!(results["id"].as_primitive::<Int32Type>().value(0), 3);
+        assert_eq!(
+            results[DIST_COL].as_primitive::<Float32Type>().value(0),
+            0.0
+        );
+
+        // The row can be left out of the results
+        let results = table
+            .query()
+            .nearest_to_row("id = 3")
+            .only_if("id != 3")
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert!(results.iter().all(|b| b["id"]
+            .as_primitive::<Int32Type>()
+            .values()
+            .iter()
+            .all(|id| *id != 3)));
+
+        for filter in ["id = -1", "id > 3"] {
+            let err = table
+                .query()
+                .nearest_to_row(filter)
+                .execute()
+                .await
+                .err()
+                .unwrap();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ann_params_validation() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let search = || {
+            table
+                .query()
+                .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+                .unwrap()
+                .limit(5)
+        };
+        let is_invalid = |query: VectorQuery| async move {
+            let err = query.execute().await.err().unwrap();
+            matches!(err, Error::InvalidInput { .. })
+        };
+
+        assert!(is_invalid(search().nprobes(0)).await);
+        assert!(is_invalid(search().refine_factor(0)).await);
+        // Without an index the parameters are ignored
+        search().ef(4).refine_factor(2).execute().await.unwrap();
+
+        table
+            .create_index(
+                &["vector"],
+                crate::index::Index::IvfFlat(
+                    crate::index::vector::IvfFlatIndexBuilder::default().num_partitions(1),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        search().nprobes(1).execute().await.unwrap();
+        assert!(is_invalid(search().ef(10)).await);
+        assert!(is_invalid(search().refine_factor(2)).await);
+        // Unless the index is bypassed
+        search()
+            .ef(10)
+            .bypass_vector_index()
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .create_index(
+                &["vector"],
+                crate::index::Index::IvfHnswSq(
+                    crate::index::vector::IvfHnswSqIndexBuilder::default().num_partitions(1),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        assert!(is_invalid(search().ef(4)).await);
+        search().ef(10).execute().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_distance_type_mismatch_with_index() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        table
+            .create_index(
+                &["vector"],
+                crate::index::Index::IvfFlat(
+                    crate::index::vector::IvfFlatIndexBuilder::default()
+                        .num_partitions(1)
+                        .distance_type(DistanceType::Cosine),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let search = || table.query().nearest_to(vec![0.1, 0.2, 0.3, 0.4]).unwrap();
+
+        // The index is used for its own distance type, or when none is given
+        for query in [search(), search().distance_type(DistanceType::Cosine)] {
+            assert!(query.explain_plan(false).await.unwrap().contains("ANN"));
+        }
+        // Other distance types can't use the index
+        let err = search()
+            .distance_type(DistanceType::Dot)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        // Unless the search bypasses it
+        let query = search()
+            .distance_type(DistanceType::Dot)
+            .bypass_vector_index();
+        assert!(!query.explain_plan(false).await.unwrap().contains("ANN"));
+        let results = query
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        let err = search()
+            .distance_type(DistanceType::Hamming)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_to_multivector() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        // 128 rows with 4 token vectors of 8 dimensions each
+        let (num_rows, num_tokens, dim) = (128, 4, 8);
+        let item = Arc::new(ArrowField::new("item", DataType::Float32, true));
+        let token = Arc::new(ArrowField::new(
+            "item",
+            DataType::FixedSizeList(item.clone(), dim),
+            true,
+        ));
+        let values = Float32Array::from_iter_values(
+            (0..num_rows * num_tokens * dim).map(|_| rand::random::<f32>()),
+        );
+        let tokens =
+            FixedSizeListArray::try_new(item, dim, Arc::new(values.clone()), None).unwrap();
+        let multivectors = ListArray::try_new(
+            token,
+            OffsetBuffer::from_lengths(
+                std::iter::repeat(num_tokens as usize).take(num_rows as usize),
+            ),
+            Arc::new(tokens),
+            None,
+        )
+        .unwrap();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(0..num_rows)) as ArrayRef,
+            ),
+            ("tokens", Arc::new(multivectors) as ArrayRef),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table(
+                "multivector",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // The query is the token vectors of row 5
+        let row_size = (num_tokens * dim) as usize;
+        let query_tokens = values.values()[5 * row_size..6 * row_size]
+            .chunks(dim as usize)
+            .map(|token| token.to_vec())
+            .collect::<Vec<_>>();
+        let search = || {
+            table
+                .query()
+                .nearest_to_multivector(query_tokens.clone())
+                .unwrap()
+                .limit(3)
+        };
+        let results = search()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(results.num_rows(), 3);
+        assert_eq!(results["id"].as_primitive::<Int32Type>().value(0), 5);
+
+        // Multivector columns are indexed with the cosine distance
+        let err = table
+            .create_index(
+                &["tokens"],
+                crate::index::Index::IvfFlat(crate::index::vector::IvfFlatIndexBuilder::default()),
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        table
+            .create_index(&["tokens"], crate::index::Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+        let index = table.list_indices().await.unwrap().pop().unwrap();
+        assert_eq!(index.index_type, crate::index::IndexType::IvfPq);
+        let results = search()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        // A multivector query needs a multivector column
+        let table = make_test_table(&tmp_dir).await;
+        for num_vectors in [1, 2] {
+            let err = table
+                .query()
+                .nearest_to_multivector(vec![vec![0.1, 0.2, 0.3, 0.4]; num_vectors])
+                .unwrap()
+                .execute()
+                .await
+                .err()
+                .unwrap();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_index_prefilter() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let search = || {
+            table
+                .query()
+                .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+                .unwrap()
+                .only_if("id > 500")
+                .limit(20)
+                .require_index_prefilter()
+        };
+        let is_invalid = |query: VectorQuery| async move {
+            let err = query.execute().await.err().unwrap();
+            matches!(err, Error::InvalidInput { .. })
+        };
+
+        // Neither column is indexed
+        assert!(is_invalid(search()).await);
+        table
+            .create_index(
+                &["vector"],
+                crate::index::Index::IvfFlat(
+                    crate::index::vector::IvfFlatIndexBuilder::default().num_partitions(1),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        // The filter column is not indexed
+        assert!(is_invalid(search()).await);
+        table
+            .create_index(&["id"], crate::index::Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let results = search()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let ids = results
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 11);
+        assert!(ids.iter().all(|id| *id > 500));
+        assert!(is_invalid(search().postfilter()).await);
+        // Queries without a filter are not affected
+        table
+            .query()
+            .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .require_index_prefilter()
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_cache() {
+        let tmp_dir = tempdir().unwrap();
+        make_test_table(&tmp_dir).await;
+        let uri = tmp_dir.path().join("test.lance");
+        let conn = connect(uri.to_str().unwrap()).execute().await.unwrap();
+        let table = conn
+            .open_table("my_table")
+            .query_cache_size(1 << 20)
+            .execute()
+            .await
+            .unwrap();
+        let ids = |query: Query| async move {
+            query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        let scan = table.query().only_if("id < 10").limit(100);
+        assert_eq!(ids(scan.clone()).await.len(), 10);
+        assert_eq!(ids(scan.clone()).await.len(), 10);
+
+        let search = table
+            .query()
+            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .limit(5);
+        let first = search.clone().execute().await.unwrap();
+        let first = first.try_collect::<Vec<_>>().await.unwrap();
+        let second = search.clone().execute().await.unwrap();
+        let second = second.try_collect::<Vec<_>>().await.unwrap();
+        assert_eq!(first, second);
+
+        // A new version of the table is not served from the cache
+        table.add(make_non_empty_batches()).execute().await.unwrap();
+        assert_eq!(ids(scan).await.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_filter_builder() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+
+        let filter = filter::col("id")
+            .is_in([1, 3, 5, 700])
+            .or(filter::col("id").between(10, 12));
+        let results = table
+            .query()
+            .only_if(filter)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut ids = results
+            .iter()
+            .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3, 5, 10, 11, 12]);
+    }
+
+    /// The sorted values of a string column in the rows matching `filter`
+    async fn filtered_strings(table: &Table, filter: filter::Filter, column: &str) -> Vec<String> {
+        let results = table
+            .query()
+            .only_if(filter)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut values = results
+            .iter()
+            .flat_map(|b| {
+                b[column]
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+
+    #[tokio::test]
+    async fn test_text_predicates() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let messages = StringArray::from(vec![
+            "Disk ERROR: retrying",
+            "3 errors found",
+            "all good",
+            "error-code 7",
+        ]);
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "message",
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(messages)]).unwrap();
+        let table = conn
+            .create_table("logs", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        let matches = |filter| filtered_strings(&table, filter, "message");
+
+        assert_eq!(
+            matches(filter::col("message").ilike("%error%")).await,
+            vec!["3 errors found", "Disk ERROR: retrying", "error-code 7"]
+        );
+        assert_eq!(
+            matches(filter::col("message").regexp_match(r"^\d+ ")).await,
+            vec!["3 errors found"]
+        );
+        assert_eq!(
+            matches(filter::col("message").contains_token("error")).await,
+            vec!["Disk ERROR: retrying", "error-code 7"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_geo_filters() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("name", DataType::Utf8, false),
+            ArrowField::new("lat", DataType::Float64, false),
+            ArrowField::new("lon", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![
+                    "berlin", "potsdam", "hamburg", "east", "west",
+                ])),
+                Arc::new(Float64Array::from(vec![52.52, 52.39, 53.55, -17.0, -17.0])),
+                Arc::new(Float64Array::from(vec![
+                    13.405, 13.065, 9.99, 179.9, -179.9,
+                ])),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("places", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        let matches = |filter| filtered_strings(&table, filter, "name");
+        let (lat, lon) = (filter::col("lat"), filter::col("lon"));
+
+        // Potsdam is about 27km from Berlin and Hamburg about 255km
+        let near_berlin =
+            |meters| filter::within_distance(lat.clone(), lon.clone(), 52.52, 13.405, meters);
+        assert_eq!(matches(near_berlin(1_000.0)).await, vec!["berlin"]);
+        assert_eq!(
+            matches(near_berlin(50_000.0)).await,
+            vec!["berlin", "potsdam"]
+        );
+        assert_eq!(
+            matches(near_berlin(300_000.0)).await,
+            vec!["berlin", "hamburg", "potsdam"]
+        );
+
+        // Circles and boxes across the antimeridian
+        assert_eq!(
+            matches(filter::within_distance(
+                lat.clone(),
+                lon.clone(),
+                -17.0,
+                180.0,
+                20_000.0
+            ))
+            .await,
+            vec!["east", "west"]
+        );
+        assert_eq!(
+            matches(filter::within_bounding_box(
+                lat.clone(),
+                lon.clone(),
+                -20.0,
+                179.0,
+                -10.0,
+                -179.95
+            ))
+            .await,
+            vec!["east"]
+        );
+        assert_eq!(
+            matches(filter::within_bounding_box(
+                lat, lon, 50.0, 10.0, 55.0, 14.0
+            ))
+            .await,
+            vec!["berlin", "potsdam"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_null_comparison_and_missing_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("id", DataType::Int32, false),
+            ArrowField::new("status", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec![
+                    Some("archived"),
+                    Some("open"),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+        let table = conn
+            .create_table("items", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        let ids = |query: Query| async move {
+            let mut ids = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+
+        let not_archived = table.query().only_if("status <> 'archived'");
+        assert_eq!(ids(not_archived.clone()).await, vec![2]);
+        assert_eq!(
+            ids(not_archived.null_comparison(NullComparison::False)).await,
+            vec![2, 3]
+        );
+        let not_open = table
             .query()
-            .nearest_to(&[0.1, 0.2, 0.3, 0.4])
+            .only_if("NOT (status IN ('open'))")
+            .null_comparison(NullComparison::False);
+        assert_eq!(ids(not_open).await, vec![1, 3]);
+
+        let select = table.query().select(Select::columns(&["id", "priority"]));
+        assert!(select.clone().execute().await.is_err());
+        let results = select
+            .missing_columns(MissingColumns::Null)
+            .execute()
+            .await
             .unwrap()
-            .add_query_vector(&[0.5, 0.6, 0.7, 0.8])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch["priority"].logical_nulls().unwrap().null_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_order_by() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let ids = |query: Query| async move {
+            query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        let latest = table
+            .query()
+            .only_if("id < 100")
+            .order_by([("id", SortOrder::Desc)])
+            .limit(5);
+        assert_eq!(ids(latest.clone()).await, vec![99, 98, 97, 96, 95]);
+        assert_eq!(
+            ids(latest.clone().offset(2)).await,
+            vec![97, 96, 95, 94, 93]
+        );
+        let first = table.query().order_by([("id", SortOrder::Asc)]).limit(3);
+        assert_eq!(ids(first).await, vec![0, 1, 2]);
+
+        // Only the rows that can be returned are kept while sorting
+        let plan = latest.offset(2).explain_plan(false).await.unwrap();
+        assert!(plan.contains("TopK(fetch=7)"), "{}", plan);
+
+        let err = table
+            .query()
+            .order_by([("id", SortOrder::Asc)])
+            .nearest_to(vec![0.1, 0.2, 0.3, 0.4])
             .unwrap()
-            .limit(1);
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
 
-        let plan = query.explain_plan(true).await.unwrap();
-        assert!(plan.contains("UnionExec"));
+    #[tokio::test]
+    async fn test_half_precision_vectors() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let dim = 4;
+        let values = (0..256 * dim)
+            .map(|i| f16::from_f32((i % 97) as f32 / 97.0))
+            .collect::<Vec<_>>();
+        let item = Arc::new(ArrowField::new("item", DataType::Float16, true));
+        let vectors = FixedSizeListArray::try_new(
+            item,
+            dim as i32,
+            Arc::new(Float16Array::from(values.clone())),
+            None,
+        )
+        .unwrap();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(0..256)) as ArrayRef,
+            ),
+            ("vector", Arc::new(vectors) as ArrayRef),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table("half", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
 
-        let results = query
+        let row = &values[3 * dim..4 * dim];
+        let nearest = |query: VectorQuery| async move {
+            let results = query
+                .limit(1)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            (
+                results[0]["id"].as_primitive::<Int32Type>().value(0),
+                results[0][DIST_COL].as_primitive::<Float32Type>().value(0),
+            )
+        };
+        let (id, distance) = nearest(table.query().nearest_to(row).unwrap()).await;
+        assert_eq!(distance, 0.0);
+        assert_eq!(&values[id as usize * dim..(id as usize + 1) * dim], row);
+        let bf16_row = row
+            .iter()
+            .map(|x| bf16::from_f32(x.to_f32()))
+            .collect::<Vec<_>>();
+        let (_, distance) = nearest(table.query().nearest_to(bf16_row).unwrap()).await;
+        assert!(distance < 1e-3, "{}", distance);
+
+        let err = table
+            .query()
+            .nearest_to(&values[..dim - 1])
+            .unwrap()
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_to_batch() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let results = table
+            .query()
+            .nearest_to_batch(&[[0.1, 0.2, 0.3, 0.4], [0.5, 0.6, 0.7, 0.8], [0.9; 4]])
+            .unwrap()
+            .limit(2)
             .execute()
             .await
             .unwrap()
@@ -1509,11 +3918,93 @@ mod tests {
             .await
             .unwrap();
         let results = concat_batches(&results[0].schema(), &results).unwrap();
-        assert_eq!(results.num_rows(), 2); // One result for each query vector.
+        assert_eq!(results.num_rows(), 6);
         let query_index = results["query_index"].as_primitive::<Int32Type>();
-        // We don't guarantee order.
-        assert!(query_index.values().contains(&0));
-        assert!(query_index.values().contains(&1));
+        for i in 0..3 {
+            assert_eq!(query_index.values().iter().filter(|q| **q == i).count(), 2);
+        }
+
+        let err = table
+            .query()
+            .nearest_to_batch(Vec::<Vec<f32>>::new())
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_full_text_search() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "title",
+                Arc::new(StringArray::from(vec![
+                    "rust guide",
+                    "python guide",
+                    "cooking",
+                ])) as ArrayRef,
+            ),
+            (
+                "body",
+                Arc::new(StringArray::from(vec![
+                    "learn rust",
+                    "learn python",
+                    "rust stains",
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table("docs", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        for column in ["title", "body"] {
+            table
+                .create_index(&[column], crate::index::Index::FTS(Default::default()))
+                .execute()
+                .await
+                .unwrap();
+        }
+
+        let search = |query: FtsQuery| {
+            let table = table.clone();
+            async move {
+                let batches = table
+                    .query()
+                    .full_text_search(query)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap();
+                let batch = concat_batches(&batches[0].schema(), &batches).unwrap();
+                let titles = batch["title"]
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|title| title.unwrap().to_string())
+                    .collect::<Vec<_>>();
+                let scores = batch[SCORE_COL]
+                    .as_primitive::<Float32Type>()
+                    .values()
+                    .to_vec();
+                (titles, scores)
+            }
+        };
+
+        let (titles, scores) = search(FtsQuery::new("rust").columns(["title", "body"])).await;
+        assert_eq!(
+            titles.iter().collect::<HashSet<_>>(),
+            HashSet::from([&"rust guide".to_string(), &"cooking".to_string()])
+        );
+        assert!(scores.iter().all(|score| *score > 0.0));
+
+        let (titles, _) = search(FtsQuery::new("rust").columns(["title"])).await;
+        assert_eq!(titles, vec!["rust guide"]);
     }
 
     #[tokio::test]
@@ -1585,6 +4076,39 @@ mod tests {
         assert!(texts.contains("cat")); // should be close by vector search
         assert!(texts.contains("b")); // should be close by fts search
 
+        // the same query, built from the vector side and marked as hybrid
+        let results = table
+            .query()
+            .nearest_to(&[-10.0, -10.0])
+            .unwrap()
+            .full_text_search(FullTextSearchQuery::new("b".to_string()))
+            .limit(2)
+            .hybrid()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let batch = &results[0];
+        assert!(batch.column_by_name("_relevance_score").is_some());
+        let texts: StringArray = downcast_array(batch.column_by_name("text").unwrap());
+        let texts = texts.iter().map(|e| e.unwrap()).collect::<HashSet<_>>();
+        assert!(texts.contains("cat"));
+        assert!(texts.contains("b"));
+
+        // a hybrid query without a full text search is an error
+        let err = table
+            .query()
+            .nearest_to(&[-10.0, -10.0])
+            .unwrap()
+            .hybrid()
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
         // ensure that this works correctly if there are no matching FTS results
         let fts_query = FullTextSearchQuery::new("z".to_string());
         table
@@ -1667,4 +4191,245 @@ mod tests {
         assert_eq!(0, batch.num_rows());
         assert_eq!(2, batch.num_columns());
     }
+
+    /// Puts the results in reverse order and remembers the FTS queries
+    #[derive(Debug, Default)]
+    struct ReverseReranker {
+        fts_queries: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ReverseReranker {
+        fn reverse(results: RecordBatch) -> Result<RecordBatch> {
+            let num_rows = results.num_rows();
+            let indices = arrow_array::UInt32Array::from_iter_values((0..num_rows as u32).rev());
+            let results = arrow::compute::take_record_batch(&results, &indices)?;
+            let scores = Float32Array::from_iter_values((0..num_rows).map(|i| -(i as f32)));
+            let mut fields = results.schema().fields().to_vec();
+            fields.push(Arc::new(ArrowField::new(
+                "_relevance_score",
+                DataType::Float32,
+                false,
+            )));
+            let mut columns = results.columns().to_vec();
+            columns.push(Arc::new(scores));
+            Ok(RecordBatch::try_new(
+                Arc::new(ArrowSchema::new(fields)),
+                columns,
+            )?)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Reranker for ReverseReranker {
+        async fn rerank_hybrid(
+            &self,
+            _query: &str,
+            vector_results: RecordBatch,
+            _fts_results: RecordBatch,
+        ) -> Result<RecordBatch> {
+            Self::reverse(vector_results)
+        }
+
+        async fn rerank_vector(&self, vector_results: RecordBatch) -> Result<RecordBatch> {
+            Self::reverse(vector_results)
+        }
+
+        async fn rerank_fts(&self, query: &str, fts_results: RecordBatch) -> Result<RecordBatch> {
+            self.fts_queries.lock().unwrap().push(query.to_string());
+            Self::reverse(fts_results)
+        }
+    }
+
+    /// Takes longer than any test should wait
+    #[derive(Debug)]
+    struct SlowReranker;
+
+    #[async_trait::async_trait]
+    impl Reranker for SlowReranker {
+        async fn rerank_hybrid(
+            &self,
+            _query: &str,
+            vector_results: RecordBatch,
+            _fts_results: RecordBatch,
+        ) -> Result<RecordBatch> {
+            Ok(vector_results)
+        }
+
+        async fn rerank_vector(&self, vector_results: RecordBatch) -> Result<RecordBatch> {
+            tokio::time::sleep(Duration::from_secs(600)).await;
+            Ok(vector_results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_cancellation() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_test_table(&tmp_dir).await;
+        let slow_query = table
+            .vector_search(&[0.1, 0.2, 0.3, 0.4])
+            .unwrap()
+            .rerank(Arc::new(SlowReranker));
+
+        let err = slow_query
+            .execute_with_options(
+                QueryExecutionOptions::default().with_timeout(Duration::from_millis(50)),
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Timeout { .. }), "{:?}", err);
+
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let err = slow_query
+            .execute_with_options(QueryExecutionOptions::default().with_cancellation_token(token))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Cancelled), "{:?}", err);
+
+        // Cancelling while the results are read stops the stream
+        let token = CancellationToken::new();
+        let mut results = table
+            .query()
+            .execute_with_options(QueryExecutionOptions {
+                max_batch_length: 10,
+                cancellation_token: Some(token.clone()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(results.try_next().await.unwrap().is_some());
+        token.cancel();
+        let err = results.try_next().await.err().unwrap();
+        assert!(matches!(err, Error::Cancelled), "{:?}", err);
+        assert!(results.next().await.is_none());
+
+        // Queries that finish in time are not affected
+        let results = table
+            .query()
+            .execute_with_options(
+                QueryExecutionOptions::default().with_timeout(Duration::from_secs(60)),
+            )
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 512);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_vector_and_fts() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let text = StringArray::from(vec!["dog", "cat", "dog cat", "bird"]);
+        let vectors = (0..4).map(|i| Some(vec![Some(i as f32), Some(i as f32)]));
+        let vector = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vectors, 2);
+        let batch = RecordBatch::try_from_iter(vec![
+            ("text", Arc::new(text) as ArrayRef),
+            ("vector", Arc::new(vector) as ArrayRef),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["text"], crate::index::Index::FTS(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let texts = |batches: Vec<RecordBatch>| {
+            let batch = concat_batches(&batches[0].schema(), &batches).unwrap();
+            assert!(batch.column_by_name("_relevance_score").is_some());
+            batch["text"]
+                .as_string::<i32>()
+                .iter()
+                .map(|text| text.unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let reranker = Arc::new(ReverseReranker::default());
+        let results = table
+            .query()
+            .nearest_to(&[0.0, 0.0])
+            .unwrap()
+            .limit(3)
+            .rerank(reranker.clone())
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(texts(results), vec!["dog cat", "cat", "dog"]);
+
+        // The offset is applied after reranking
+        let results = table
+            .query()
+            .nearest_to(&[0.0, 0.0])
+            .unwrap()
+            .limit(2)
+            .offset(1)
+            .rerank(reranker.clone())
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(texts(results), vec!["cat", "dog"]);
+
+        let results = table
+            .query()
+            .full_text_search(FullTextSearchQuery::new("cat".to_string()))
+            .rerank(reranker.clone())
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            texts(results).into_iter().collect::<HashSet<_>>(),
+            HashSet::from(["cat".to_string(), "dog cat".to_string()])
+        );
+        assert_eq!(*reranker.fts_queries.lock().unwrap(), vec!["cat"]);
+
+        // The built-in rerankers only fuse hybrid results
+        let err = table
+            .query()
+            .nearest_to(&[0.0, 0.0])
+            .unwrap()
+            .rerank(Arc::new(RRFReranker::default()))
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::NotSupported { .. }), "{:?}", err);
+
+        // Plain scans cannot be reranked
+        let err = table
+            .query()
+            .rerank(reranker)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
 }
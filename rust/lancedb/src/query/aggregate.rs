@@ -0,0 +1,430 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Aggregations that are computed by the query engine
+//!
+//! See [`super::Query::group_by`] and [`super::Query::aggregate`]
+
+use std::sync::Arc;
+
+use arrow_schema::SchemaRef;
+use async_trait::async_trait;
+use datafusion::functions_aggregate::expr_fn;
+use datafusion::prelude::{SessionConfig, SessionContext};
+use datafusion_catalog::{Session, TableProvider};
+use datafusion_common::{DataFusionError, Result as DataFusionResult};
+use datafusion_expr::{ident, lit, Expr, TableType};
+use datafusion_physical_plan::display::DisplayableExecutionPlan;
+use datafusion_physical_plan::expressions::Column;
+use datafusion_physical_plan::projection::ProjectionExec;
+use datafusion_physical_plan::{ExecutionPlan, PhysicalExpr};
+use lance::dataset::scanner::DatasetRecordBatchStream;
+use lance_datafusion::exec::execute_plan;
+
+use super::{
+    execute_and_analyze, stop_when_asked, ExecutableQuery, Query, QueryExecutionOptions, Select,
+};
+use crate::arrow::SendableRecordBatchStream;
+use crate::error::{Error, Result};
+use crate::table::datafusion::MetadataEraserExec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+}
+
+/// A value computed over the rows of each group, see [`AggregateQuery::aggregate`]
+///
+/// By default the output column is named after the function and the column,
+/// e.g. `avg(score)`, or `count` for [`count`].  Use [`Aggregate::alias`] to
+/// choose another name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregate {
+    function: AggregateFunction,
+    column: Option<String>,
+    alias: Option<String>,
+}
+
+impl Aggregate {
+    fn new(function: AggregateFunction, column: Option<String>) -> Self {
+        Self {
+            function,
+            column,
+            alias: None,
+        }
+    }
+
+    /// Name the output column
+    pub fn alias(mut self, name: impl Into<String>) -> Self {
+        self.alias = Some(name.into());
+        self
+    }
+
+    /// The name of the output column
+    pub fn name(&self) -> String {
+        match (&self.alias, &self.column) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(column)) => format!("{}({})", self.function.name(), column),
+            (None, None) => self.function.name().to_string(),
+        }
+    }
+
+    fn to_expr(&self) -> Expr {
+        let input = self.column.as_deref().map(ident).unwrap_or_else(|| lit(1));
+        let expr = match self.function {
+            AggregateFunction::Count => expr_fn::count(input),
+            AggregateFunction::Sum => expr_fn::sum(input),
+            AggregateFunction::Avg => expr_fn::avg(input),
+            AggregateFunction::Min => expr_fn::min(input),
+            AggregateFunction::Max => expr_fn::max(input),
+        };
+        expr.alias(self.name())
+    }
+}
+
+/// The number of rows in each group
+pub fn count() -> Aggregate {
+    Aggregate::new(AggregateFunction::Count, None)
+}
+
+/// The number of non-null values of `column` in each group
+pub fn count_column(column: impl Into<String>) -> Aggregate {
+    Aggregate::new(AggregateFunction::Count, Some(column.into()))
+}
+
+/// The sum of `column` in each group
+pub fn sum(column: impl Into<String>) -> Aggregate {
+    Aggregate::new(AggregateFunction::Sum, Some(column.into()))
+}
+
+/// The average of `column` in each group, as a Float64
+pub fn avg(column: impl Into<String>) -> Aggregate {
+    Aggregate::new(AggregateFunction::Avg, Some(column.into()))
+}
+
+/// The smallest value of `column` in each group
+pub fn min(column: impl Into<String>) -> Aggregate {
+    Aggregate::new(AggregateFunction::Min, Some(column.into()))
+}
+
+/// The largest value of `column` in each group
+pub fn max(column: impl Into<String>) -> Aggregate {
+    Aggregate::new(AggregateFunction::Max, Some(column.into()))
+}
+
+/// A query that groups the rows of another query and aggregates each group
+///
+/// The output has one column for each grouping column, followed by one column
+/// for each aggregate, and one row per group.  The order of the groups is not
+/// defined.
+///
+/// The rows are read with the filter and other options of the query they were
+/// created from, then aggregated by the query engine as they are read, so only
+/// the aggregated rows are returned.  For remote tables the aggregation runs
+/// on the client.
+#[derive(Debug, Clone)]
+pub struct AggregateQuery {
+    query: Query,
+    group_by: Vec<String>,
+    aggregates: Vec<Aggregate>,
+}
+
+impl AggregateQuery {
+    pub(super) fn new(query: Query, group_by: Vec<String>) -> Self {
+        Self {
+            query,
+            group_by,
+            aggregates: Vec::new(),
+        }
+    }
+
+    /// Add values to compute for each group
+    pub fn aggregate(mut self, aggregates: impl IntoIterator<Item = Aggregate>) -> Self {
+        self.aggregates.extend(aggregates);
+        self
+    }
+}
+
+fn plan_error(err: DataFusionError) -> Error {
+    Error::InvalidInput {
+        message: format!("could not plan the aggregation: {}", err),
+    }
+}
+
+impl ExecutableQuery for AggregateQuery {
+    async fn create_plan(&self, options: QueryExecutionOptions) -> Result<Arc<dyn ExecutionPlan>> {
+        if self.group_by.is_empty() && self.aggregates.is_empty() {
+            return Err(Error::InvalidInput {
+                message: "an aggregation needs at least one grouping column or aggregate"
+                    .to_string(),
+            });
+        }
+        let mut query = self.query.clone();
+        if matches!(query.request.select, Select::All) {
+            // Only the columns the aggregation uses are read.  A column that
+            // doesn't exist is reported when the aggregation is planned.
+            let schema = query.parent.schema().await?;
+            let mut columns = Vec::<String>::new();
+            for column in self
+                .group_by
+                .iter()
+                .chain(self.aggregates.iter().filter_map(|a| a.column.as_ref()))
+            {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            if columns.iter().all(|c| schema.column_with_name(c).is_some()) {
+                if columns.is_empty() {
+                    // Counting rows reads no columns, only the row ids
+                    query.request.with_row_id = true;
+                }
+                query.request.select = Select::Columns(columns);
+            }
+        }
+        let input = query.create_plan(options).await?;
+        // The query engine is only used to plan the aggregation, so one
+        // partition is enough, and the plan can be run with `execute_plan`
+        let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(1));
+        let group_by = self.group_by.iter().map(ident).collect();
+        let aggregates = self.aggregates.iter().map(Aggregate::to_expr).collect();
+        ctx.read_table(Arc::new(PlanProvider::new(input)))
+            .and_then(|df| df.aggregate(group_by, aggregates))
+            .map_err(plan_error)?
+            .create_physical_plan()
+            .await
+            .map_err(plan_error)
+    }
+
+    async fn execute_with_options(
+        &self,
+        options: QueryExecutionOptions,
+    ) -> Result<SendableRecordBatchStream> {
+        let results = async {
+            Ok(SendableRecordBatchStream::from(
+                DatasetRecordBatchStream::new(execute_plan(
+                    self.create_plan(options.clone()).await?,
+                    Default::default(),
+                )?),
+            ))
+        };
+        stop_when_asked(&options, results).await
+    }
+
+    async fn explain_plan(&self, verbose: bool) -> Result<String> {
+        let plan = self.create_plan(Default::default()).await?;
+        let display = DisplayableExecutionPlan::new(plan.as_ref());
+
+        Ok(format!("{}", display.indent(verbose)))
+    }
+
+    async fn analyze_plan_with_options(&self, options: QueryExecutionOptions) -> Result<String> {
+        execute_and_analyze(self.create_plan(options).await?).await
+    }
+}
+
+/// A table provider that reads the output of an existing plan
+#[derive(Debug)]
+struct PlanProvider {
+    plan: Arc<dyn ExecutionPlan>,
+}
+
+impl PlanProvider {
+    fn new(plan: Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            plan: Arc::new(MetadataEraserExec::new(plan)),
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for PlanProvider {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.plan.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Temporary
+    }
+
+    async fn scan(
+        &self,
+        _state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let Some(projection) = projection else {
+            return Ok(self.plan.clone());
+        };
+        let schema = self.plan.schema();
+        let columns = projection
+            .iter()
+            .map(|i| {
+                let name = schema.field(*i).name().clone();
+                (
+                    Arc::new(Column::new(&name, *i)) as Arc<dyn PhysicalExpr>,
+                    name,
+                )
+            })
+            .collect();
+        Ok(Arc::new(ProjectionExec::try_new(
+            columns,
+            self.plan.clone(),
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use arrow::array::AsArray;
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{Float64Type, Int64Type};
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, RecordBatchIterator, StringArray};
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::QueryBase;
+
+    #[tokio::test]
+    async fn test_group_by() {
+        let tmp_dir = tempdir().unwrap();
+        let conn = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "label",
+                Arc::new(StringArray::from(vec!["cat", "dog", "cat", "dog", "bird"])) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0, 4.0, 5.0])) as ArrayRef,
+            ),
+            (
+                "comment",
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let results = table
+            .query()
+            .only_if("label != 'bird'")
+            .group_by(["label"])
+            .aggregate([count(), avg("score"), max("score").alias("best")])
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        let names = results
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["label", "count", "avg(score)", "best"]);
+        let groups = (0..results.num_rows())
+            .map(|i| {
+                (
+                    results["label"].as_string::<i32>().value(i).to_string(),
+                    (
+                        results["count"].as_primitive::<Int64Type>().value(i),
+                        results["avg(score)"].as_primitive::<Float64Type>().value(i),
+                        results["best"].as_primitive::<Float64Type>().value(i),
+                    ),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(
+            groups,
+            HashMap::from([
+                ("cat".to_string(), (2, 2.0, 3.0)),
+                ("dog".to_string(), (2, 3.0, 4.0)),
+            ])
+        );
+
+        // Without grouping there is a single row
+        let results = table
+            .query()
+            .aggregate([sum("score"), min("score")])
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+        assert_eq!(
+            results[0]["sum(score)"]
+                .as_primitive::<Float64Type>()
+                .value(0),
+            15.0
+        );
+
+        // Only the columns that are aggregated are read
+        let plan = table
+            .query()
+            .group_by(["label"])
+            .aggregate([avg("score")])
+            .explain_plan(true)
+            .await
+            .unwrap();
+        assert!(!plan.contains("comment"), "{}", plan);
+        let results = table
+            .query()
+            .only_if("label = 'cat'")
+            .aggregate([count()])
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(results[0]["count"].as_primitive::<Int64Type>().value(0), 2);
+
+        let err = table
+            .query()
+            .group_by(["missing"])
+            .aggregate([count()])
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+}
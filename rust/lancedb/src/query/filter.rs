@@ -0,0 +1,519 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! A builder for query filters
+//!
+//! Filters are given to [`super::QueryBase::only_if`] as SQL strings.  Building
+//! them with [`col`] quotes the column names and escapes the values, so values
+//! that come from users cannot change the meaning of the filter.
+//!
+//! ```
+//! use lancedb::query::filter::col;
+//!
+//! let filter = col("id")
+//!     .is_in([1, 2, 3])
+//!     .and(col("name").like("O'Brien%"));
+//! assert_eq!(
+//!     filter.to_string(),
+//!     "(`id` IN (1, 2, 3)) AND (`name` LIKE 'O''Brien%')"
+//! );
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::quote_identifier;
+
+/// A value that a column is compared with in a [`Filter`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Null,
+    Boolean(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    Date(NaiveDate),
+    /// A timestamp without a time zone
+    Timestamp(NaiveDateTime),
+}
+
+impl FilterValue {
+    fn to_sql(&self) -> String {
+        match self {
+            Self::Null => "NULL".to_string(),
+            Self::Boolean(value) => value.to_string(),
+            Self::Int(value) => value.to_string(),
+            Self::UInt(value) => value.to_string(),
+            Self::Float(value) if value.is_nan() => "CAST('NaN' AS DOUBLE)".to_string(),
+            Self::Float(value) if value.is_infinite() => {
+                let sign = if *value < 0.0 { "-" } else { "" };
+                format!("CAST('{}inf' AS DOUBLE)", sign)
+            }
+            // The debug format keeps the decimal point of whole numbers
+            Self::Float(value) => format!("{:?}", value),
+            Self::String(value) => format!("'{}'", value.replace('\'', "''")),
+            Self::Date(value) => format!("DATE '{}'", value.format("%Y-%m-%d")),
+            Self::Timestamp(value) => {
+                format!("TIMESTAMP '{}'", value.format("%Y-%m-%d %H:%M:%S%.f"))
+            }
+        }
+    }
+}
+
+macro_rules! impl_from_value {
+    ($variant:ident, $target:ty, $($source:ty),+) => {
+        $(
+            impl From<$source> for FilterValue {
+                fn from(value: $source) -> Self {
+                    Self::$variant(<$target>::from(value))
+                }
+            }
+        )+
+    };
+}
+
+impl_from_value!(Boolean, bool, bool);
+impl_from_value!(Int, i64, i8, i16, i32, i64);
+impl_from_value!(UInt, u64, u8, u16, u32, u64);
+impl_from_value!(Float, f64, f32, f64);
+impl_from_value!(String, String, &str, String, &String);
+impl_from_value!(Date, NaiveDate, NaiveDate);
+impl_from_value!(Timestamp, NaiveDateTime, NaiveDateTime);
+
+impl From<DateTime<Utc>> for FilterValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::Timestamp(value.naive_utc())
+    }
+}
+
+impl<T: Into<Self>> From<Option<T>> for FilterValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// A column, or a field of a struct column, that is filtered on
+///
+/// See [`col`]
+#[derive(Debug, Clone)]
+pub struct Column {
+    expr: String,
+}
+
+/// Start a filter on the column `name`
+///
+/// The name is used as is, so it may contain dots or other special
+/// characters.  Use [`Column::field`] to filter on a field of a struct column.
+pub fn col(name: impl AsRef<str>) -> Column {
+    Column {
+        expr: quote_identifier(name.as_ref()),
+    }
+}
+
+impl Column {
+    /// The field `name` of this struct column
+    pub fn field(self, name: impl AsRef<str>) -> Self {
+        Self {
+            expr: format!("{}.{}", self.expr, quote_identifier(name.as_ref())),
+        }
+    }
+
+    fn compare(self, op: &str, value: impl Into<FilterValue>) -> Filter {
+        Filter::new(format!("{} {} {}", self.expr, op, value.into().to_sql()))
+    }
+
+    /// Rows where the column equals `value`
+    ///
+    /// As in SQL, comparing with a null value matches no rows, use
+    /// [`Self::is_null`] to find null values.
+    pub fn eq(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare("=", value)
+    }
+
+    /// Rows where the column does not equal `value`
+    pub fn not_eq(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare("<>", value)
+    }
+
+    /// Rows where the column is less than `value`
+    pub fn lt(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare("<", value)
+    }
+
+    /// Rows where the column is less than or equal to `value`
+    pub fn lt_eq(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare("<=", value)
+    }
+
+    /// Rows where the column is greater than `value`
+    pub fn gt(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare(">", value)
+    }
+
+    /// Rows where the column is greater than or equal to `value`
+    pub fn gt_eq(self, value: impl Into<FilterValue>) -> Filter {
+        self.compare(">=", value)
+    }
+
+    fn in_list<V: Into<FilterValue>>(
+        self,
+        values: impl IntoIterator<Item = V>,
+        negated: bool,
+    ) -> Filter {
+        let values = values
+            .into_iter()
+            .map(|value| value.into().to_sql())
+            .collect::<Vec<_>>();
+        // SQL has no empty lists
+        if values.is_empty() {
+            return Filter::new(if negated { "true" } else { "false" });
+        }
+        let op = if negated { "NOT IN" } else { "IN" };
+        Filter::new(format!("{} {} ({})", self.expr, op, values.join(", ")))
+    }
+
+    /// Rows where the column equals one of `values`
+    ///
+    /// An empty list matches no rows.
+    pub fn is_in<V: Into<FilterValue>>(self, values: impl IntoIterator<Item = V>) -> Filter {
+        self.in_list(values, false)
+    }
+
+    /// Rows where the column equals none of `values`
+    pub fn not_in<V: Into<FilterValue>>(self, values: impl IntoIterator<Item = V>) -> Filter {
+        self.in_list(values, true)
+    }
+
+    /// Rows where the column is between `low` and `high`, inclusive
+    pub fn between(self, low: impl Into<FilterValue>, high: impl Into<FilterValue>) -> Filter {
+        Filter::new(format!(
+            "{} BETWEEN {} AND {}",
+            self.expr,
+            low.into().to_sql(),
+            high.into().to_sql()
+        ))
+    }
+
+    /// Rows where the column matches the SQL LIKE `pattern`
+    ///
+    /// In the pattern `%` matches any sequence of characters and `_` matches
+    /// a single character.
+    pub fn like(self, pattern: impl Into<String>) -> Filter {
+        self.compare("LIKE", pattern.into())
+    }
+
+    /// Rows where the column matches the SQL LIKE `pattern`, ignoring case
+    pub fn ilike(self, pattern: impl Into<String>) -> Filter {
+        self.compare("ILIKE", pattern.into())
+    }
+
+    /// Rows where some part of the column matches the regular expression
+    /// `pattern`
+    ///
+    /// The pattern uses the syntax of the [`regex`] crate, so flags such as
+    /// `(?i)` can be set in the pattern.  Use `^` and `$` to match the whole
+    /// value.
+    pub fn regexp_match(self, pattern: impl Into<String>) -> Filter {
+        Filter::new(format!(
+            "regexp_like({}, {})",
+            self.expr,
+            FilterValue::String(pattern.into()).to_sql()
+        ))
+    }
+
+    /// Rows where the column contains `token` as a whole word, ignoring case
+    ///
+    /// Words are separated by whitespace and punctuation, much like the
+    /// default tokenizer of full text search.  For example the token `error`
+    /// matches `"disk error: retrying"` but not `"errors"`.
+    ///
+    /// The token is anchored with the `\b` word boundary of [`regex`], which
+    /// only falls between a word character and a non-word character.  A token
+    /// that starts or ends with punctuation, such as `c++`, only matches where
+    /// a letter, digit or underscore is next to that end, so `c++` doesn't
+    /// match `"c++ code"`.  Use [`Self::regexp_match`] for such tokens.
+    ///
+    /// This filter can't use a scalar index.  To find a substring with an
+    /// NGram index use `contains(column, 'text')` in the filter instead.
+    pub fn contains_token(self, token: impl AsRef<str>) -> Filter {
+        self.regexp_match(format!(r"(?i)\b{}\b", regex::escape(token.as_ref())))
+    }
+
+    fn array_has<V: Into<FilterValue>>(
+        self,
+        function: &str,
+        values: impl IntoIterator<Item = V>,
+        if_empty: &str,
+    ) -> Filter {
+        let values = values
+            .into_iter()
+            .map(|value| value.into().to_sql())
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return Filter::new(if_empty);
+        }
+        Filter::new(format!(
+            "{}({}, [{}])",
+            function,
+            self.expr,
+            values.join(", ")
+        ))
+    }
+
+    /// Rows where this list column contains `value`
+    ///
+    /// The filter is written as `array_has_any`, so a label list index on the
+    /// column is used to find the rows.
+    pub fn array_contains(self, value: impl Into<FilterValue>) -> Filter {
+        self.array_has_any([value])
+    }
+
+    /// Rows where this list column contains at least one of `values`
+    ///
+    /// An empty list matches no rows.
+    pub fn array_has_any<V: Into<FilterValue>>(
+        self,
+        values: impl IntoIterator<Item = V>,
+    ) -> Filter {
+        self.array_has("array_has_any", values, "false")
+    }
+
+    /// Rows where this list column contains all of `values`
+    ///
+    /// An empty list matches every row.
+    pub fn array_has_all<V: Into<FilterValue>>(
+        self,
+        values: impl IntoIterator<Item = V>,
+    ) -> Filter {
+        self.array_has("array_has_all", values, "true")
+    }
+
+    /// Rows where the column is null
+    pub fn is_null(self) -> Filter {
+        Filter::new(format!("{} IS NULL", self.expr))
+    }
+
+    /// Rows where the column is not null
+    pub fn is_not_null(self) -> Filter {
+        Filter::new(format!("{} IS NOT NULL", self.expr))
+    }
+}
+
+/// A filter built with [`col`]
+///
+/// A filter can be passed to [`super::QueryBase::only_if`] or anywhere else
+/// that takes an SQL filter string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    sql: String,
+}
+
+impl Filter {
+    fn new(sql: impl Into<String>) -> Self {
+        Self { sql: sql.into() }
+    }
+
+    /// Rows that match both this filter and `other`
+    pub fn and(self, other: Self) -> Self {
+        Self::new(format!("({}) AND ({})", self.sql, other.sql))
+    }
+
+    /// Rows that match this filter, `other` or both
+    pub fn or(self, other: Self) -> Self {
+        Self::new(format!("({}) OR ({})", self.sql, other.sql))
+    }
+
+    /// Rows that do not match this filter
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self::new(format!("NOT ({})", self.sql))
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.sql)
+    }
+}
+
+impl AsRef<str> for Filter {
+    fn as_ref(&self) -> &str {
+        &self.sql
+    }
+}
+
+impl From<Filter> for String {
+    fn from(filter: Filter) -> Self {
+        filter.sql
+    }
+}
+
+/// The mean radius of the earth in meters
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+fn float(value: f64) -> String {
+    FilterValue::Float(value).to_sql()
+}
+
+/// Rows whose point, given in degrees by the `lat` and `lon` columns, lies in
+/// a bounding box
+///
+/// The box may cross the antimeridian, in which case `min_lon` is greater
+/// than `max_lon`.  The filter only compares the columns with the bounds of
+/// the box, so BTree indices on the columns are used to find the rows.
+pub fn within_bounding_box(
+    lat: Column,
+    lon: Column,
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+) -> Filter {
+    let lat = lat.between(min_lat, max_lat);
+    if min_lon <= max_lon {
+        lat.and(lon.between(min_lon, max_lon))
+    } else {
+        lat.and(lon.clone().gt_eq(min_lon).or(lon.lt_eq(max_lon)))
+    }
+}
+
+/// Rows whose point, given in degrees by the `lat` and `lon` columns, is no
+/// more than `meters` away from the point at (`center_lat`, `center_lon`)
+///
+/// The distance is the great circle distance on a sphere the size of the
+/// earth, which is within 0.5% of the distance on the earth's surface.  The
+/// filter first limits the rows to the bounding box of the circle, like
+/// [`within_bounding_box`], so BTree indices on the columns narrow down the
+/// rows whose distance is computed.
+///
+/// Combined with a vector search this finds the nearest neighbors among the
+/// rows close to a place:
+///
+/// ```
+/// # use lancedb::query::filter::{col, within_distance};
+/// let nearby = within_distance(col("lat"), col("lon"), 52.52, 13.405, 5_000.0);
+/// ```
+pub fn within_distance(
+    lat: Column,
+    lon: Column,
+    center_lat: f64,
+    center_lon: f64,
+    meters: f64,
+) -> Filter {
+    if meters.is_nan() || meters < 0.0 {
+        return Filter::new("false");
+    }
+    let angle = (meters / EARTH_RADIUS_METERS).min(std::f64::consts::PI);
+    let angle_degrees = angle.to_degrees();
+    let min_lat = (center_lat - angle_degrees).max(-90.0);
+    let max_lat = (center_lat + angle_degrees).min(90.0);
+    let bounding_box = if min_lat <= -90.0 || max_lat >= 90.0 {
+        // The circle contains a pole, so it spans every longitude
+        within_bounding_box(lat.clone(), lon.clone(), min_lat, -180.0, max_lat, 180.0)
+    } else {
+        let delta = (angle.sin() / center_lat.to_radians().cos())
+            .asin()
+            .to_degrees();
+        let mut min_lon = center_lon - delta;
+        let mut max_lon = center_lon + delta;
+        if min_lon < -180.0 {
+            min_lon += 360.0;
+        }
+        if max_lon > 180.0 {
+            max_lon -= 360.0;
+        }
+        within_bounding_box(lat.clone(), lon.clone(), min_lat, min_lon, max_lat, max_lon)
+    };
+
+    // The haversine formula, compared with the haversine of the angle so the
+    // distance itself is never computed
+    let half_radians = std::f64::consts::PI / 360.0;
+    let haversine = format!(
+        "power(sin(({} - {}) * {}), 2) + {} * cos({} * {}) * power(sin(({} - {}) * {}), 2)",
+        lat.expr,
+        float(center_lat),
+        float(half_radians),
+        float(center_lat.to_radians().cos()),
+        lat.expr,
+        float(2.0 * half_radians),
+        lon.expr,
+        float(center_lon),
+        float(half_radians),
+    );
+    let threshold = (angle / 2.0).sin().powi(2);
+    bounding_box.and(Filter::new(format!(
+        "{} <= {}",
+        haversine,
+        float(threshold)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(col("a b").eq(1).to_string(), "`a b` = 1");
+        assert_eq!(col("a`b").gt_eq(1.5).to_string(), "`a``b` >= 1.5");
+        assert_eq!(col("x").lt(2.0_f64).to_string(), "`x` < 2.0");
+        assert_eq!(
+            col("s").field("name").eq("it's").to_string(),
+            "`s`.`name` = 'it''s'"
+        );
+        assert_eq!(
+            col("name").eq("x' OR '1'='1").to_string(),
+            "`name` = 'x'' OR ''1''=''1'"
+        );
+        assert_eq!(col("id").is_in(Vec::<i32>::new()).to_string(), "false");
+        assert_eq!(
+            col("id").not_in(["a", "b"]).not().to_string(),
+            "NOT (`id` NOT IN ('a', 'b'))"
+        );
+        let day = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert_eq!(
+            col("ts")
+                .between(day.and_hms_opt(0, 0, 0).unwrap(), day.and_hms_opt(12, 30, 0).unwrap())
+                .or(col("ts").is_null())
+                .to_string(),
+            "(`ts` BETWEEN TIMESTAMP '2024-01-02 00:00:00' AND TIMESTAMP '2024-01-02 12:30:00') OR (`ts` IS NULL)"
+        );
+        assert_eq!(col("d").eq(day).to_string(), "`d` = DATE '2024-01-02'");
+        assert_eq!(col("x").eq(None::<i32>).to_string(), "`x` = NULL");
+        assert_eq!(
+            col("x").lt(f64::NEG_INFINITY).to_string(),
+            "`x` < CAST('-inf' AS DOUBLE)"
+        );
+        assert_eq!(col("m").ilike("%ERR%").to_string(), "`m` ILIKE '%ERR%'");
+        assert_eq!(
+            col("tags").array_contains("it's").to_string(),
+            "array_has_any(`tags`, ['it''s'])"
+        );
+        assert_eq!(
+            col("tags").array_has_all(["a", "b"]).to_string(),
+            "array_has_all(`tags`, ['a', 'b'])"
+        );
+        assert_eq!(
+            col("tags").array_has_any(Vec::<&str>::new()).to_string(),
+            "false"
+        );
+        assert_eq!(
+            within_bounding_box(col("lat"), col("lon"), -10.0, 170.0, 10.0, -170.0).to_string(),
+            "(`lat` BETWEEN -10.0 AND 10.0) AND ((`lon` >= 170.0) OR (`lon` <= -170.0))"
+        );
+        assert_eq!(
+            within_distance(col("lat"), col("lon"), 0.0, 0.0, -1.0).to_string(),
+            "false"
+        );
+        assert_eq!(
+            col("m").regexp_match("^a'b").to_string(),
+            "regexp_like(`m`, '^a''b')"
+        );
+        assert_eq!(
+            col("m").contains_token("a.b").to_string(),
+            r"regexp_like(`m`, '(?i)\ba\.b\b')"
+        );
+    }
+}
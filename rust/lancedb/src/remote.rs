@@ -6,16 +6,18 @@
 //! building client/server applications with LanceDB or as a client for some
 //! other custom LanceDB service.
 
+pub(crate) mod catalog;
 pub(crate) mod client;
 pub(crate) mod db;
 pub(crate) mod table;
 pub(crate) mod util;
 
 const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+const CHECKSUM_HEADER: &str = "x-lancedb-checksum-crc32";
 #[cfg(test)]
 const ARROW_FILE_CONTENT_TYPE: &str = "application/vnd.apache.arrow.file";
 #[cfg(test)]
 const JSON_CONTENT_TYPE: &str = "application/json";
 
-pub use client::{ClientConfig, RetryConfig, TimeoutConfig};
+pub use client::{ClientConfig, ConnectionConfig, RetryConfig, TimeoutConfig};
 pub use db::{RemoteDatabaseOptions, RemoteDatabaseOptionsBuilder};
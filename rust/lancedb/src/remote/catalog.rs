@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Catalog implementation for LanceDB Cloud, where a single account can own many databases
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::catalog::{
+    Catalog, CreateDatabaseMode, CreateDatabaseRequest, DatabaseNamesRequest, OpenDatabaseRequest,
+};
+use crate::database::Database;
+use crate::error::{Error, Result};
+
+use super::client::{ClientConfig, HttpSend, RequestResultExt, RestfulLanceDbClient, Sender};
+use super::db::{RemoteDatabase, RemoteOptions};
+
+type DatabaseFactory = Arc<dyn Fn(&str) -> Result<Arc<dyn Database>> + Send + Sync>;
+
+#[derive(Deserialize)]
+struct ListDatabasesResponse {
+    databases: Vec<String>,
+}
+
+impl From<&CreateDatabaseMode> for &'static str {
+    fn from(val: &CreateDatabaseMode) -> Self {
+        match val {
+            CreateDatabaseMode::Create => "create",
+            CreateDatabaseMode::ExistOk => "exist_ok",
+            CreateDatabaseMode::Overwrite => "overwrite",
+        }
+    }
+}
+
+/// A catalog of the databases available to a LanceDB Cloud / Enterprise account
+///
+/// Each database in the catalog is opened as a [`RemoteDatabase`] that shares the
+/// credentials and client configuration of the connection used to create the catalog.
+pub struct RemoteCatalog<S: HttpSend = Sender> {
+    client: RestfulLanceDbClient<S>,
+    open_database: DatabaseFactory,
+}
+
+impl<S: HttpSend> std::fmt::Debug for RemoteCatalog<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteCatalog")
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+impl RemoteCatalog {
+    pub fn try_new(
+        uri: &str,
+        api_key: &str,
+        region: &str,
+        host_override: Option<String>,
+        client_config: ClientConfig,
+        options: RemoteOptions,
+    ) -> Result<Self> {
+        let client = RestfulLanceDbClient::try_new(
+            uri,
+            api_key,
+            region,
+            host_override.clone(),
+            client_config.clone(),
+            &options,
+        )?;
+
+        let base_url = url::Url::parse(uri).map_err(|err| Error::InvalidInput {
+            message: format!("db_url is not a valid URL. '{uri}'. Error: {err}"),
+        })?;
+        let api_key = api_key.to_string();
+        let region = region.to_string();
+        let open_database: DatabaseFactory = Arc::new(move |name: &str| {
+            // Keep any prefix from the original URI, only the database name changes
+            let mut db_url = base_url.clone();
+            db_url
+                .set_host(Some(name))
+                .map_err(|err| Error::InvalidInput {
+                    message: format!("'{name}' is not a valid database name: {err}"),
+                })?;
+            let database = RemoteDatabase::try_new(
+                db_url.as_str(),
+                &api_key,
+                &region,
+                host_override.clone(),
+                client_config.clone(),
+                options.clone(),
+            )?;
+            Ok(Arc::new(database) as Arc<dyn Database>)
+        });
+
+        Ok(Self {
+            client,
+            open_database,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "remote"))]
+mod test_utils {
+    use super::*;
+    use crate::remote::client::test_utils::{client_with_handler, MockSender};
+
+    impl RemoteCatalog<MockSender> {
+        pub fn new_mock<F, T>(handler: F) -> Self
+        where
+            F: Fn(reqwest::Request) -> http::Response<T> + Clone + Send + Sync + 'static,
+            T: Into<reqwest::Body>,
+        {
+            let client = client_with_handler(handler.clone());
+            let open_database: DatabaseFactory = Arc::new(move |_: &str| {
+                Ok(Arc::new(RemoteDatabase::new_mock(handler.clone())) as Arc<dyn Database>)
+            });
+            Self {
+                client,
+                open_database,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: HttpSend> Catalog for RemoteCatalog<S> {
+    async fn database_names(&self, request: DatabaseNamesRequest) -> Result<Vec<String>> {
+        let mut req = self.client.get("/v1/database/");
+        if let Some(limit) = request.limit {
+            req = req.query(&[("limit", limit)]);
+        }
+        if let Some(start_after) = request.start_after {
+            req = req.query(&[("page_token", start_after)]);
+        }
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        let rsp = self.client.check_response(&request_id, rsp).await?;
+        Ok(rsp
+            .json::<ListDatabasesResponse>()
+            .await
+            .err_to_http(request_id)?
+            .databases)
+    }
+
+    async fn create_database(&self, request: CreateDatabaseRequest) -> Result<Arc<dyn Database>> {
+        let req = self
+            .client
+            .post(&format!("/v1/database/{}/create/", request.name))
+            .query(&[("mode", Into::<&str>::into(&request.mode))])
+            .json(&request.options);
+        let (request_id, rsp) = self.client.send(req, false).await?;
+        if rsp.status() == StatusCode::CONFLICT {
+            return Err(Error::DatabaseAlreadyExists { name: request.name });
+        }
+        self.client.check_response(&request_id, rsp).await?;
+        (self.open_database)(&request.name)
+    }
+
+    async fn open_database(&self, request: OpenDatabaseRequest) -> Result<Arc<dyn Database>> {
+        // Describe the database to confirm it exists before handing out a connection to it
+        let req = self
+            .client
+            .post(&format!("/v1/database/{}/describe/", request.name));
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        if rsp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::DatabaseNotFound { name: request.name });
+        }
+        self.client.check_response(&request_id, rsp).await?;
+        (self.open_database)(&request.name)
+    }
+
+    async fn rename_database(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let req = self
+            .client
+            .post(&format!("/v1/database/{}/rename/", old_name))
+            .json(&serde_json::json!({ "new_database_name": new_name }));
+        let (request_id, rsp) = self.client.send(req, false).await?;
+        if rsp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::DatabaseNotFound {
+                name: old_name.to_string(),
+            });
+        }
+        self.client.check_response(&request_id, rsp).await?;
+        Ok(())
+    }
+
+    async fn drop_database(&self, name: &str) -> Result<()> {
+        let req = self.client.post(&format!("/v1/database/{}/drop/", name));
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        if rsp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::DatabaseNotFound {
+                name: name.to_string(),
+            });
+        }
+        self.client.check_response(&request_id, rsp).await?;
+        Ok(())
+    }
+
+    async fn drop_all_databases(&self) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "Dropping all databases is not supported in the remote API".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::JSON_CONTENT_TYPE;
+    use crate::{Connection, Error};
+
+    #[tokio::test]
+    async fn test_database_names() {
+        let conn = Connection::new_with_handler(|request| {
+            assert_eq!(request.method(), &reqwest::Method::GET);
+            assert_eq!(request.url().path(), "/v1/database/");
+            assert!(request.url().query().unwrap().contains("limit=2"));
+            assert!(request.url().query().unwrap().contains("page_token=db1"));
+
+            http::Response::builder()
+                .status(200)
+                .body(r#"{"databases": ["db2", "db3"]}"#)
+                .unwrap()
+        });
+        let names = conn
+            .database_names()
+            .start_after("db1")
+            .limit(2)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["db2", "db3"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_drop_database() {
+        let conn = Connection::new_with_handler(|request| {
+            assert_eq!(request.method(), &reqwest::Method::POST);
+            match request.url().path() {
+                "/v1/database/tenant1/create/" => {
+                    assert_eq!(request.url().query(), Some("mode=create"));
+                    assert_eq!(
+                        request.headers().get("Content-Type").unwrap(),
+                        JSON_CONTENT_TYPE
+                    );
+                    http::Response::builder().status(200).body("").unwrap()
+                }
+                "/v1/database/tenant1/drop/" => {
+                    http::Response::builder().status(200).body("").unwrap()
+                }
+                "/v1/database/tenant2/create/" => http::Response::builder()
+                    .status(409)
+                    .body("database tenant2 already exists")
+                    .unwrap(),
+                path => panic!("unexpected path: {:?}", path),
+            }
+        });
+        conn.create_database("tenant1").await.unwrap();
+        conn.drop_database("tenant1").await.unwrap();
+        let result = conn.create_database("tenant2").await;
+        assert!(matches!(result, Err(Error::DatabaseAlreadyExists { name }) if name == "tenant2"));
+    }
+
+    #[tokio::test]
+    async fn test_use_database() {
+        let conn = Connection::new_with_handler(|request| match request.url().path() {
+            "/v1/database/tenant1/describe/" => {
+                http::Response::builder().status(200).body("{}").unwrap()
+            }
+            "/v1/database/missing/describe/" => http::Response::builder()
+                .status(404)
+                .body("database not found")
+                .unwrap(),
+            "/v1/table/" => http::Response::builder()
+                .status(200)
+                .body(r#"{"tables": ["table1"]}"#)
+                .unwrap(),
+            path => panic!("unexpected path: {:?}", path),
+        });
+        let tenant = conn.use_database("tenant1").await.unwrap();
+        assert_eq!(tenant.uri(), "db://tenant1");
+        assert_eq!(
+            tenant.table_names().execute().await.unwrap(),
+            vec!["table1"]
+        );
+
+        let result = conn.use_database("missing").await;
+        assert!(matches!(result, Err(Error::DatabaseNotFound { name }) if name == "missing"));
+    }
+}
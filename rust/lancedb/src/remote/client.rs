@@ -1,17 +1,28 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
-use std::{collections::HashMap, future::Future, str::FromStr, time::Duration};
+use std::{
+    collections::HashMap,
+    future::Future,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use http::HeaderName;
+use http::{header::CONTENT_TYPE, HeaderName};
 use log::debug;
 use reqwest::{
     header::{HeaderMap, HeaderValue},
     Request, RequestBuilder, Response,
 };
+use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use crate::error::{Error, Result};
+pub use crate::io::policy::{RetryConfig, TimeoutConfig};
 use crate::remote::db::RemoteOptions;
+use crate::remote::util::ipc_checksum;
+use crate::remote::{ARROW_STREAM_CONTENT_TYPE, CHECKSUM_HEADER};
 
 const REQUEST_ID_HEADER: &str = "x-request-id";
 
@@ -20,11 +31,21 @@ const REQUEST_ID_HEADER: &str = "x-request-id";
 pub struct ClientConfig {
     pub timeout_config: TimeoutConfig,
     pub retry_config: RetryConfig,
+    pub connection_config: ConnectionConfig,
     /// User agent to use for requests. The default provides the library
     /// name and version.
     pub user_agent: String,
     // TODO: how to configure request ids?
     pub extra_headers: HashMap<String, String>,
+    /// Whether to send a CRC32 checksum alongside uploaded Arrow IPC data and
+    /// verify the checksums the server sends back with query results.
+    ///
+    /// Computing the checksum requires the whole payload up front, so when this
+    /// is enabled data added to a table is buffered in memory instead of being
+    /// streamed to the server.
+    ///
+    /// The default is false.
+    pub checksum: bool,
 }
 
 impl Default for ClientConfig {
@@ -32,95 +53,69 @@ impl Default for ClientConfig {
         Self {
             timeout_config: TimeoutConfig::default(),
             retry_config: RetryConfig::default(),
+            connection_config: ConnectionConfig::default(),
             user_agent: concat!("LanceDB-Rust-Client/", env!("CARGO_PKG_VERSION")).into(),
             extra_headers: HashMap::new(),
+            checksum: false,
         }
     }
 }
 
-/// How to handle timeouts for HTTP requests.
+/// How to manage the connections used for HTTP requests.
+///
+/// The defaults work well for most workloads. When sending many small requests,
+/// such as thousands of small `add()` calls, keeping connections alive and
+/// multiplexing requests over HTTP/2 avoids paying for a new connection (and TLS
+/// handshake) on each request.
 #[derive(Clone, Default, Debug)]
-pub struct TimeoutConfig {
-    /// The timeout for creating a connection to the server.
+pub struct ConnectionConfig {
+    /// Only use HTTP/2, without first negotiating the protocol with the server.
     ///
-    /// You can also set the `LANCE_CLIENT_CONNECT_TIMEOUT` environment variable
-    /// to set this value. Use an integer value in seconds.
-    ///
-    /// The default is 120 seconds (2 minutes).
-    pub connect_timeout: Option<Duration>,
-    /// The timeout for reading a response from the server.
+    /// With HTTP/2 all requests to the server share a single connection. The
+    /// number of requests in flight on that connection is limited by the
+    /// maximum concurrent streams advertised by the server.
     ///
-    /// You can also set the `LANCE_CLIENT_READ_TIMEOUT` environment variable
-    /// to set this value. Use an integer value in seconds.
+    /// You can also set the `LANCE_CLIENT_HTTP2_ONLY` environment variable
+    /// to `true` to enable this.
     ///
-    /// The default is 300 seconds (5 minutes).
-    pub read_timeout: Option<Duration>,
-    /// The timeout for keeping idle connections alive.
+    /// The default is false, in which case HTTP/1.1 is used unless the server
+    /// negotiates HTTP/2.
+    pub http2_only: Option<bool>,
+    /// Whether to use an adaptive flow control window for HTTP/2 connections,
+    /// which can improve throughput for large uploads.
     ///
-    /// You can also set the `LANCE_CLIENT_CONNECTION_TIMEOUT` environment variable
-    /// to set this value. Use an integer value in seconds.
-    ///
-    /// The default is 300 seconds (5 minutes).
-    pub pool_idle_timeout: Option<Duration>,
-}
-
-/// How to handle retries for HTTP requests.
-#[derive(Clone, Default, Debug)]
-pub struct RetryConfig {
-    /// The number of times to retry a request if it fails.
+    /// The default is false.
+    pub http2_adaptive_window: Option<bool>,
+    /// The interval at which HTTP/2 PING frames are sent to keep idle
+    /// connections alive.
     ///
-    /// You can also set the `LANCE_CLIENT_MAX_RETRIES` environment variable
-    /// to set this value. Use an integer value.
+    /// The default is to not send keep-alive pings.
+    pub http2_keep_alive_interval: Option<Duration>,
+    /// The interval for TCP keep-alive probes on open connections.
     ///
-    /// The default is 3 retries.
-    pub retries: Option<u8>,
-    /// The number of times to retry a request if it fails to connect.
-    ///
-    /// You can also set the `LANCE_CLIENT_CONNECT_RETRIES` environment variable
-    /// to set this value. Use an integer value.
-    ///
-    /// The default is 3 retries.
-    pub connect_retries: Option<u8>,
-    /// The number of times to retry a request if it fails to read.
-    ///
-    /// You can also set the `LANCE_CLIENT_READ_RETRIES` environment variable
-    /// to set this value. Use an integer value.
-    ///
-    /// The default is 3 retries.
-    pub read_retries: Option<u8>,
-    /// The exponential backoff factor to use when retrying requests.
-    ///
-    /// Between each retry, the client will wait for the amount of seconds:
-    ///
-    /// ```text
-    /// {backoff factor} * (2 ** ({number of previous retries}))
-    /// ```
-    ///
-    /// You can also set the `LANCE_CLIENT_RETRY_BACKOFF_FACTOR` environment variable
-    /// to set this value. Use a float value.
-    ///
-    /// The default is 0.25. So the first retry will wait 0.25 seconds, the second
-    /// retry will wait 0.5 seconds, the third retry will wait 1 second, etc.
-    pub backoff_factor: Option<f32>,
-    /// The backoff jitter factor to use when retrying requests.
+    /// You can also set the `LANCE_CLIENT_TCP_KEEPALIVE` environment variable
+    /// to set this value. Use an integer value in seconds.
     ///
-    /// The backoff jitter is a random value between 0 and the jitter factor in
-    /// seconds.
+    /// The default is to not send keep-alive probes.
+    pub tcp_keepalive: Option<Duration>,
+    /// The maximum number of idle connections to keep open to the server.
     ///
-    /// You can also set the `LANCE_CLIENT_RETRY_BACKOFF_JITTER` environment variable
-    /// to set this value. Use a float value.
+    /// The default is no limit.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// The maximum number of requests that can be in flight at once.
     ///
-    /// The default is 0.25. So between 0 and 0.25 seconds will be added to the
-    /// sleep time between retries.
-    pub backoff_jitter: Option<f32>,
-    /// The set of status codes to retry on.
+    /// Requests beyond this limit wait for an earlier request to finish before
+    /// they are sent. This keeps a burst of queries from overwhelming the server
+    /// or exhausting local sockets.
     ///
-    /// You can also set the `LANCE_CLIENT_RETRY_STATUSES` environment variable
-    /// to set this value. Use a comma-separated list of integer values.
+    /// The default is no limit.  The limit must be greater than 0.
+    pub max_concurrent_requests: Option<usize>,
+    /// How long a request may wait for an in-flight request to finish when
+    /// `max_concurrent_requests` has been reached. If the wait exceeds this
+    /// timeout the request fails with an error reporting how long it waited.
     ///
-    /// The default is 429, 500, 502, 503.
-    pub statuses: Option<Vec<u16>>,
-    // TODO: should we allow customizing methods?
+    /// The default is to wait indefinitely.
+    pub queue_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
@@ -153,6 +148,58 @@ impl TryFrom<RetryConfig> for ResolvedRetryConfig {
     }
 }
 
+/// Limits the number of requests a client has in flight at once.
+#[derive(Clone, Debug)]
+struct RequestLimiter {
+    semaphore: Arc<Semaphore>,
+    max_concurrent_requests: usize,
+    queue_timeout: Option<Duration>,
+}
+
+impl RequestLimiter {
+    fn new(config: &ConnectionConfig) -> Result<Option<Self>> {
+        match config.max_concurrent_requests {
+            // No request could ever be sent
+            Some(0) => Err(Error::InvalidInput {
+                message: "max_concurrent_requests must be greater than 0".to_string(),
+            }),
+            Some(max_concurrent_requests) => Ok(Some(Self {
+                semaphore: Arc::new(Semaphore::new(max_concurrent_requests)),
+                max_concurrent_requests,
+                queue_timeout: config.queue_timeout,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn acquire(&self, request_id: &str) -> Result<OwnedSemaphorePermit> {
+        let start = Instant::now();
+        let acquire = self.semaphore.clone().acquire_owned();
+        let permit = match self.queue_timeout {
+            Some(queue_timeout) => tokio::time::timeout(queue_timeout, acquire)
+                .await
+                .map_err(|_| Error::Http {
+                    source: format!(
+                        "Gave up after waiting {:?} to send request, all {} request slots are in use",
+                        start.elapsed(),
+                        self.max_concurrent_requests
+                    )
+                    .into(),
+                    request_id: request_id.to_string(),
+                    status_code: None,
+                })?,
+            None => acquire.await,
+        };
+        debug!(
+            "Request request_id={} waited {:?} for a request slot",
+            request_id,
+            start.elapsed()
+        );
+        // We never close the semaphore
+        Ok(permit.unwrap())
+    }
+}
+
 // We use the `HttpSend` trait to abstract over the `reqwest::Client` so that
 // we can mock responses in tests. Based on the patterns from this blog post:
 // https://write.as/balrogboogie/testing-reqwest-based-clients
@@ -161,6 +208,8 @@ pub struct RestfulLanceDbClient<S: HttpSend = Sender> {
     client: reqwest::Client,
     host: String,
     retry_config: ResolvedRetryConfig,
+    checksum: bool,
+    request_limiter: Option<RequestLimiter>,
     sender: S,
 }
 
@@ -248,10 +297,40 @@ impl RestfulLanceDbClient<Sender> {
             Duration::from_secs(300),
         )?;
 
-        let client = reqwest::Client::builder()
+        let connection_config = &client_config.connection_config;
+        let http2_only = match connection_config.http2_only {
+            Some(http2_only) => http2_only,
+            None => std::env::var("LANCE_CLIENT_HTTP2_ONLY")
+                .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+                .unwrap_or(false),
+        };
+        let tcp_keepalive = if connection_config.tcp_keepalive.is_some()
+            || std::env::var("LANCE_CLIENT_TCP_KEEPALIVE").is_ok()
+        {
+            Some(Self::get_timeout(
+                connection_config.tcp_keepalive,
+                "LANCE_CLIENT_TCP_KEEPALIVE",
+                Duration::ZERO,
+            )?)
+        } else {
+            None
+        };
+
+        let mut client_builder = reqwest::Client::builder()
             .connect_timeout(connect_timeout)
             .read_timeout(read_timeout)
             .pool_idle_timeout(pool_idle_timeout)
+            .tcp_keepalive(tcp_keepalive)
+            .http2_keep_alive_interval(connection_config.http2_keep_alive_interval)
+            .http2_adaptive_window(connection_config.http2_adaptive_window.unwrap_or(false));
+        if http2_only {
+            client_builder = client_builder.http2_prior_knowledge();
+        }
+        if let Some(max_idle) = connection_config.pool_max_idle_per_host {
+            client_builder = client_builder.pool_max_idle_per_host(max_idle);
+        }
+
+        let client = client_builder
             .default_headers(Self::default_headers(
                 api_key,
                 region,
@@ -278,6 +357,8 @@ impl RestfulLanceDbClient<Sender> {
             client,
             host,
             retry_config,
+            checksum: client_config.checksum,
+            request_limiter: RequestLimiter::new(&client_config.connection_config)?,
             sender: Sender,
         })
     }
@@ -375,6 +456,53 @@ impl<S: HttpSend> RestfulLanceDbClient<S> {
         self.client.post(full_uri)
     }
 
+    /// Whether uploads and downloads of Arrow IPC data are checksummed.
+    pub fn checksum_enabled(&self) -> bool {
+        self.checksum
+    }
+
+    /// Attach an Arrow IPC stream as the body of the request, along with its
+    /// checksum if checksums are enabled.
+    pub fn ipc_body(&self, req: RequestBuilder, data: Vec<u8>) -> RequestBuilder {
+        let mut req = req.header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE);
+        if self.checksum {
+            req = req.header(CHECKSUM_HEADER, ipc_checksum(&data));
+        }
+        req.body(data)
+    }
+
+    /// Verify a downloaded body against the checksum provided by the server.
+    ///
+    /// Responses without a checksum header are accepted as-is, since older
+    /// servers don't send one.
+    pub fn verify_checksum(
+        &self,
+        request_id: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<()> {
+        if !self.checksum {
+            return Ok(());
+        }
+        let Some(expected) = headers.get(CHECKSUM_HEADER) else {
+            return Ok(());
+        };
+        let actual = ipc_checksum(body);
+        if expected.as_bytes().eq_ignore_ascii_case(actual.as_bytes()) {
+            Ok(())
+        } else {
+            Err(Error::Http {
+                source: format!(
+                    "Checksum mismatch in response body: expected {:?}, got {:?}",
+                    expected, actual
+                )
+                .into(),
+                request_id: request_id.to_string(),
+                status_code: None,
+            })
+        }
+    }
+
     pub async fn send(&self, req: RequestBuilder, with_retry: bool) -> Result<(String, Response)> {
         let (client, request) = req.build_split();
         let mut request = request.unwrap();
@@ -407,6 +535,12 @@ impl<S: HttpSend> RestfulLanceDbClient<S> {
             }
         }
 
+        // Held until the response headers arrive, including any retries.
+        let _permit = match &self.request_limiter {
+            Some(limiter) => Some(limiter.acquire(&request_id).await?),
+            None => None,
+        };
+
         if with_retry {
             self.send_with_retry_impl(client, request, request_id).await
         } else {
@@ -487,6 +621,12 @@ impl<S: HttpSend> RestfulLanceDbClient<S> {
             Ok(response)
         } else {
             let response_text = response.text().await.ok();
+            if let Some(err) = response_text
+                .as_deref()
+                .and_then(|text| ErrorResponse::parse(request_id, status, text))
+            {
+                return Err(err);
+            }
             let message = if let Some(response_text) = response_text {
                 format!("{}: {}", status, response_text)
             } else {
@@ -501,6 +641,50 @@ impl<S: HttpSend> RestfulLanceDbClient<S> {
     }
 }
 
+/// The structured body LanceDB Cloud sends with some error responses.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    code: String,
+    message: String,
+    retryable: Option<bool>,
+}
+
+impl ErrorResponse {
+    /// Convert an error response into a typed error, if it is one we recognize.
+    ///
+    /// Returns `None` if the body isn't a structured error or the error code is
+    /// unknown, in which case the caller should fall back to [`Error::Http`].
+    fn parse(request_id: &str, status: reqwest::StatusCode, body: &str) -> Option<Error> {
+        let Self {
+            code,
+            message,
+            retryable,
+        } = serde_json::from_str(body).ok()?;
+        let request_id = request_id.to_string();
+        match code.as_str() {
+            "quota_exceeded" => Some(Error::QuotaExceeded {
+                message,
+                request_id,
+                retryable: retryable.unwrap_or(status == reqwest::StatusCode::TOO_MANY_REQUESTS),
+            }),
+            "invalid_filter" => Some(Error::InvalidFilter {
+                message,
+                request_id,
+            }),
+            "index_building" => Some(Error::IndexBuilding {
+                message,
+                request_id,
+            }),
+            "conflict" => Some(Error::CommitConflict {
+                version: None,
+                message,
+                request_id: Some(request_id),
+            }),
+            _ => None,
+        }
+    }
+}
+
 struct RetryCounter<'a> {
     request_failures: u8,
     connect_failures: u8,
@@ -636,6 +820,16 @@ pub mod test_utils {
     pub fn client_with_handler<T>(
         handler: impl Fn(reqwest::Request) -> http::response::Response<T> + Send + Sync + 'static,
     ) -> RestfulLanceDbClient<MockSender>
+    where
+        T: Into<reqwest::Body>,
+    {
+        client_with_handler_and_config(handler, ClientConfig::default())
+    }
+
+    pub fn client_with_handler_and_config<T>(
+        handler: impl Fn(reqwest::Request) -> http::response::Response<T> + Send + Sync + 'static,
+        config: ClientConfig,
+    ) -> RestfulLanceDbClient<MockSender>
     where
         T: Into<reqwest::Body>,
     {
@@ -647,7 +841,9 @@ pub mod test_utils {
         RestfulLanceDbClient {
             client: reqwest::Client::new(),
             host: "http://localhost".to_string(),
-            retry_config: RetryConfig::default().try_into().unwrap(),
+            retry_config: config.retry_config.try_into().unwrap(),
+            checksum: config.checksum,
+            request_limiter: RequestLimiter::new(&config.connection_config).unwrap(),
             sender: MockSender {
                 f: Arc::new(wrapper),
             },
@@ -6,30 +6,32 @@ use std::sync::Arc;
 
 use arrow_array::RecordBatchIterator;
 use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
 use http::StatusCode;
 use lance_io::object_store::StorageOptions;
 use moka::future::Cache;
-use reqwest::header::CONTENT_TYPE;
 use serde::Deserialize;
 use tokio::task::spawn_blocking;
 
 use crate::database::{
     CreateTableData, CreateTableMode, CreateTableRequest, Database, DatabaseOptions,
-    OpenTableRequest, TableNamesRequest,
+    ListTablesRequest, OpenTableRequest, TableInfo, TableNamesRequest, TableStats, TableVersionRef,
 };
 use crate::error::Result;
-use crate::table::BaseTable;
+use crate::table::auto_id::auto_id_columns;
+use crate::table::{BaseTable, TableDefinition};
 use crate::Error;
 
 use super::client::{ClientConfig, HttpSend, RequestResultExt, RestfulLanceDbClient, Sender};
 use super::table::RemoteTable;
 use super::util::{batches_to_ipc_bytes, parse_server_version};
-use super::ARROW_STREAM_CONTENT_TYPE;
 
 // the versions of the server that we support
 // for any new feature that we need to change the SDK behavior, we should bump the server version,
 // and add a feature flag as method of `ServerVersion` here.
 pub const DEFAULT_SERVER_VERSION: semver::Version = semver::Version::new(0, 1, 0);
+/// The number of tables dropped at a time by drop_all_tables
+const DROP_CONCURRENCY: usize = 8;
 #[derive(Debug, Clone)]
 pub struct ServerVersion(pub semver::Version);
 
@@ -168,6 +170,36 @@ struct ListTablesResponse {
     tables: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct ListTableInfoResponse {
+    tables: Vec<TableInfoResponse>,
+}
+
+#[derive(Deserialize)]
+struct TableInfoResponse {
+    name: String,
+    num_rows: usize,
+    size_bytes: Option<u64>,
+    version: u64,
+    last_modified: chrono::DateTime<chrono::Utc>,
+    num_indices: usize,
+}
+
+impl From<TableInfoResponse> for TableInfo {
+    fn from(info: TableInfoResponse) -> Self {
+        Self {
+            name: info.name,
+            stats: Some(TableStats {
+                num_rows: info.num_rows,
+                size_bytes: info.size_bytes,
+                version: info.version,
+                last_modified: info.last_modified,
+                num_indices: info.num_indices,
+            }),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RemoteDatabase<S: HttpSend = Sender> {
     client: RestfulLanceDbClient<S>,
@@ -270,7 +302,57 @@ impl<S: HttpSend> Database for RemoteDatabase<S> {
         Ok(tables)
     }
 
+    async fn list_tables(&self, request: ListTablesRequest) -> Result<Vec<TableInfo>> {
+        if !request.include_stats {
+            let names = self
+                .table_names(TableNamesRequest {
+                    start_after: request.start_after,
+                    limit: request.limit,
+                })
+                .await?;
+            return Ok(names
+                .into_iter()
+                .map(|name| TableInfo { name, stats: None })
+                .collect());
+        }
+        let mut req = self
+            .client
+            .get("/v1/table/")
+            .query(&[("include_info", "true")]);
+        if let Some(limit) = request.limit {
+            req = req.query(&[("limit", limit)]);
+        }
+        if let Some(start_after) = request.start_after {
+            req = req.query(&[("page_token", start_after)]);
+        }
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        let rsp = self.client.check_response(&request_id, rsp).await?;
+        let version = parse_server_version(&request_id, &rsp)?;
+        let tables = rsp
+            .json::<ListTableInfoResponse>()
+            .await
+            .err_to_http(request_id)?
+            .tables;
+        for table in &tables {
+            let remote_table = Arc::new(RemoteTable::new(
+                self.client.clone(),
+                table.name.clone(),
+                version.clone(),
+            ));
+            self.table_cache
+                .insert(table.name.clone(), remote_table)
+                .await;
+        }
+        Ok(tables.into_iter().map(TableInfo::from).collect())
+    }
+
     async fn create_table(&self, request: CreateTableRequest) -> Result<Arc<dyn BaseTable>> {
+        let definition = TableDefinition::try_from_rich_schema(request.data.schema())?;
+        if !auto_id_columns(&definition).is_empty() {
+            return Err(Error::NotSupported {
+                message: "auto id columns are not supported for remote tables".to_string(),
+            });
+        }
         let data = match request.data {
             CreateTableData::Data(data) => data,
             CreateTableData::StreamingData(_) => {
@@ -294,9 +376,8 @@ impl<S: HttpSend> Database for RemoteDatabase<S> {
         let req = self
             .client
             .post(&format!("/v1/table/{}/create/", request.name))
-            .query(&[("mode", Into::<&str>::into(&request.mode))])
-            .body(data_buffer)
-            .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE);
+            .query(&[("mode", Into::<&str>::into(&request.mode))]);
+        let req = self.client.ipc_body(req, data_buffer);
 
         let (request_id, rsp) = self.client.send(req, false).await?;
 
@@ -312,6 +393,9 @@ impl<S: HttpSend> Database for RemoteDatabase<S> {
                             name: request.name.clone(),
                             index_cache_size: None,
                             lance_read_params: None,
+                            version: None,
+                            skip_existence_check: false,
+                            query_cache_size: None,
                         };
                         let req = (callback)(req);
                         self.open_table(req).await
@@ -351,27 +435,70 @@ impl<S: HttpSend> Database for RemoteDatabase<S> {
     }
 
     async fn open_table(&self, request: OpenTableRequest) -> Result<Arc<dyn BaseTable>> {
-        // We describe the table to confirm it exists before moving on.
-        if let Some(table) = self.table_cache.get(&request.name).await {
-            Ok(table.clone())
-        } else {
-            let req = self
-                .client
-                .post(&format!("/v1/table/{}/describe/", request.name));
-            let (request_id, rsp) = self.client.send(req, true).await?;
-            if rsp.status() == StatusCode::NOT_FOUND {
-                return Err(crate::Error::TableNotFound { name: request.name });
+        if request.query_cache_size.is_some() {
+            return Err(crate::Error::NotSupported {
+                message: "query result caching is not supported on LanceDB cloud.".into(),
+            });
+        }
+        let checkout_version = match &request.version {
+            None => None,
+            Some(TableVersionRef::Version(version)) => Some(*version),
+            Some(TableVersionRef::Tag(_)) => {
+                return Err(crate::Error::NotSupported {
+                    message: "opening a table by tag is not supported on LanceDB cloud.".into(),
+                })
+            }
+        };
+        // Tables pinned to a version are not shared through the cache
+        if checkout_version.is_none() {
+            if let Some(table) = self.table_cache.get(&request.name).await {
+                return Ok(table.clone());
             }
-            let rsp = self.client.check_response(&request_id, rsp).await?;
-            let version = parse_server_version(&request_id, &rsp)?;
+        }
+        if request.skip_existence_check {
+            // Without a describe call the server version is unknown, so the
+            // table assumes the oldest supported version.
             let table = Arc::new(RemoteTable::new(
                 self.client.clone(),
                 request.name.clone(),
-                version,
+                ServerVersion::default(),
             ));
-            self.table_cache.insert(request.name, table.clone()).await;
-            Ok(table)
+            if let Some(version) = checkout_version {
+                table.checkout(version).await?;
+            }
+            return Ok(table);
+        }
+        // We describe the table to confirm it exists before moving on.
+        let req = self
+            .client
+            .post(&format!("/v1/table/{}/describe/", request.name));
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        if rsp.status() == StatusCode::NOT_FOUND {
+            return Err(crate::Error::TableNotFound { name: request.name });
+        }
+        let rsp = self.client.check_response(&request_id, rsp).await?;
+        let version = parse_server_version(&request_id, &rsp)?;
+        let table = Arc::new(RemoteTable::new(
+            self.client.clone(),
+            request.name.clone(),
+            version,
+        ));
+        match checkout_version {
+            Some(version) => table.checkout(version).await?,
+            None => self.table_cache.insert(request.name, table.clone()).await,
+        }
+        Ok(table)
+    }
+
+    async fn table_exists(&self, name: &str) -> Result<bool> {
+        let req = self.client.post(&format!("/v1/table/{}/describe/", name));
+        let (request_id, rsp) = self.client.send(req, true).await?;
+        if rsp.status() == StatusCode::NOT_FOUND {
+            self.table_cache.remove(name).await;
+            return Ok(false);
         }
+        self.client.check_response(&request_id, rsp).await?;
+        Ok(true)
     }
 
     async fn rename_table(&self, current_name: &str, new_name: &str) -> Result<()> {
@@ -397,9 +524,26 @@ impl<S: HttpSend> Database for RemoteDatabase<S> {
     }
 
     async fn drop_all_tables(&self) -> Result<()> {
-        Err(crate::Error::NotSupported {
-            message: "Dropping databases is not supported in the remote API".to_string(),
-        })
+        // The names are listed a page at a time
+        let mut names: Vec<String> = Vec::new();
+        loop {
+            let page = self
+                .table_names(TableNamesRequest {
+                    start_after: names.last().cloned(),
+                    limit: None,
+                })
+                .await?;
+            if page.is_empty() {
+                break;
+            }
+            names.extend(page);
+        }
+        futures::stream::iter(names)
+            .map(|name| async move { self.drop_table(&name).await })
+            .buffer_unordered(DROP_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -524,6 +668,67 @@ mod tests {
         assert_eq!(names, vec!["table3", "table4"]);
     }
 
+    #[tokio::test]
+    async fn test_list_tables() {
+        let conn = Connection::new_with_handler(|request| {
+            assert_eq!(request.method(), &reqwest::Method::GET);
+            assert_eq!(request.url().path(), "/v1/table/");
+            let query = request.url().query().unwrap();
+            assert!(query.contains("include_info=true"));
+            assert!(query.contains("limit=1"));
+
+            http::Response::builder()
+                .status(200)
+                .body(
+                    r#"{"tables": [{
+                        "name": "table1",
+                        "num_rows": 100,
+                        "size_bytes": 2048,
+                        "version": 3,
+                        "last_modified": "2024-01-01T00:00:00Z",
+                        "num_indices": 1
+                    }]}"#,
+                )
+                .unwrap()
+        });
+        let tables = conn
+            .list_tables()
+            .limit(1)
+            .include_stats(true)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "table1");
+        let stats = tables[0].stats.as_ref().unwrap();
+        assert_eq!(stats.num_rows, 100);
+        assert_eq!(stats.size_bytes, Some(2048));
+        assert_eq!(stats.version, 3);
+        assert_eq!(
+            stats.last_modified.to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+        assert_eq!(stats.num_indices, 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_table_skip_existence_check() {
+        let conn = Connection::new_with_handler(|request| {
+            // Only the first operation on the table reaches the server
+            assert_eq!(request.url().path(), "/v1/table/missing/count_rows/");
+            http::Response::builder().status(404).body("").unwrap()
+        });
+        let table = conn
+            .open_table("missing")
+            .skip_existence_check(true)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.name(), "missing");
+        let err = table.count_rows(None).await.unwrap_err();
+        assert!(matches!(err, Error::TableNotFound { name } if name == "missing"));
+    }
+
     #[tokio::test]
     async fn test_open_table() {
         let conn = Connection::new_with_handler(|request| {
@@ -562,6 +767,47 @@ mod tests {
         assert!(matches!(result, Err(crate::Error::TableNotFound { .. })));
     }
 
+    #[tokio::test]
+    async fn test_open_table_at_version() {
+        let conn = Connection::new_with_handler(|request| {
+            assert_eq!(request.url().path(), "/v1/table/table1/describe/");
+            http::Response::builder()
+                .status(200)
+                .body(r#"{"version": 42, "schema": { "fields": [] }}"#)
+                .unwrap()
+        });
+        let table = conn
+            .open_table("table1")
+            .version(7)
+            .execute()
+            .await
+            .unwrap();
+        // Pinned tables are read-only
+        assert!(table.delete("true").await.is_err());
+
+        let result = conn.open_table("table1").tag("v1").execute().await;
+        assert!(matches!(result, Err(crate::Error::NotSupported { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_table_exists() {
+        let conn = Connection::new_with_handler(|request| {
+            assert_eq!(request.method(), &reqwest::Method::POST);
+            match request.url().path() {
+                "/v1/table/table1/describe/" => http::Response::builder()
+                    .status(200)
+                    .body(r#"{"table": "table1"}"#)
+                    .unwrap(),
+                _ => http::Response::builder()
+                    .status(404)
+                    .body("table not found")
+                    .unwrap(),
+            }
+        });
+        assert!(conn.table_exists("table1").await.unwrap());
+        assert!(!conn.table_exists("table2").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_table() {
         let conn = Connection::new_with_handler(|request| {
@@ -711,6 +957,49 @@ mod tests {
         // NOTE: the API will return 200 even if the table does not exist. So we shouldn't expect 404.
     }
 
+    #[tokio::test]
+    async fn test_drop_all_tables() {
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let dropped_ref = dropped.clone();
+        let conn = Connection::new_with_handler(move |request| {
+            if request.method() == reqwest::Method::GET {
+                assert_eq!(request.url().path(), "/v1/table/");
+                let body = match request.url().query() {
+                    None => r#"{"tables": ["table1", "table2"]}"#,
+                    Some(query) => {
+                        assert_eq!(query, "page_token=table2");
+                        r#"{"tables": []}"#
+                    }
+                };
+                return http::Response::builder().status(200).body(body).unwrap();
+            }
+            let name = request
+                .url()
+                .path()
+                .strip_prefix("/v1/table/")
+                .and_then(|path| path.strip_suffix("/drop/"))
+                .unwrap()
+                .to_string();
+            dropped_ref.lock().unwrap().push(name);
+            http::Response::builder().status(200).body("").unwrap()
+        });
+
+        let names = conn
+            .drop_all_tables_builder()
+            .dry_run(true)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["table1", "table2"]);
+        assert!(dropped.lock().unwrap().is_empty());
+
+        let names = conn.drop_all_tables_builder().execute().await.unwrap();
+        assert_eq!(names, vec!["table1", "table2"]);
+        let mut dropped = dropped.lock().unwrap().clone();
+        dropped.sort();
+        assert_eq!(dropped, vec!["table1", "table2"]);
+    }
+
     #[tokio::test]
     async fn test_rename_table() {
         let conn = Connection::new_with_handler(|request| {
@@ -741,4 +1030,23 @@ mod tests {
             .await
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn test_connect_zero_max_concurrent_requests() {
+        let config = crate::remote::ClientConfig {
+            connection_config: crate::remote::ConnectionConfig {
+                max_concurrent_requests: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = ConnectBuilder::new("db://my-container")
+            .region("us-east-1")
+            .api_key("my-api-key")
+            .client_config(config)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
 }
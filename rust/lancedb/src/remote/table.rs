@@ -1,13 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 
 use crate::index::Index;
 use crate::index::IndexStatistics;
-use crate::query::{QueryFilter, QueryRequest, Select, VectorQueryRequest};
+use crate::query::{
+    Materialization, MissingColumns, NullComparison, QueryFilter, QueryRequest, Select,
+    VectorQueryRequest,
+};
 use crate::table::{AddDataMode, AnyQuery, Filter};
 use crate::utils::{supported_btree_data_type, supported_vector_data_type};
 use crate::{DistanceType, Error, Table};
@@ -27,6 +31,7 @@ use lance::dataset::{ColumnAlteration, NewColumnTransform, Version};
 use lance_datafusion::exec::{execute_plan, OneShotExec};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
+use tokio::task::spawn_blocking;
 
 use crate::{
     connection::NoData,
@@ -34,14 +39,18 @@ use crate::{
     index::{IndexBuilder, IndexConfig},
     query::QueryExecutionOptions,
     table::{
-        merge::MergeInsertBuilder, AddDataBuilder, BaseTable, OptimizeAction, OptimizeStats,
-        TableDefinition, UpdateBuilder,
+        merge::{DeduplicatedReader, MergeInsertBuilder},
+        stats::{index_coverage, ColumnStatistics, TableStatistics},
+        tags::TagContents,
+        AddDataBuilder, BaseTable, DeleteResult, OptimizeAction, OptimizeStats, TableDefinition,
+        UpdateBuilder,
     },
 };
 
 use super::client::RequestResultExt;
 use super::client::{HttpSend, RestfulLanceDbClient, Sender};
 use super::db::ServerVersion;
+use super::util::batches_to_ipc_bytes;
 use super::ARROW_STREAM_CONTENT_TYPE;
 
 #[derive(Debug)]
@@ -125,6 +134,28 @@ impl<S: HttpSend> RemoteTable<S> {
         Ok(reqwest::Body::wrap_stream(body_stream))
     }
 
+    /// Attach the data as an Arrow IPC stream body.
+    ///
+    /// The data is streamed to the server unless checksums are enabled, in which
+    /// case it has to be serialized up front so the checksum can go in a header.
+    async fn with_data_body(
+        &self,
+        request: reqwest::RequestBuilder,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<reqwest::RequestBuilder> {
+        if self.client.checksum_enabled() {
+            let data = spawn_blocking(move || batches_to_ipc_bytes(data))
+                .await
+                .unwrap()?;
+            Ok(self.client.ipc_body(request, data))
+        } else {
+            let body = Self::reader_as_body(data)?;
+            Ok(request
+                .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE)
+                .body(body))
+        }
+    }
+
     async fn check_table_response(
         &self,
         request_id: &str,
@@ -148,7 +179,9 @@ impl<S: HttpSend> RemoteTable<S> {
 
         // There isn't a way to actually stream this data yet. I have an upstream issue:
         // https://github.com/apache/arrow-rs/issues/6420
+        let headers = response.headers().clone();
         let body = response.bytes().await.err_to_http(request_id.into())?;
+        self.client.verify_checksum(request_id, &headers, &body)?;
         let reader = FileReader::try_new(Cursor::new(body), None)?;
         let schema = reader.schema();
         let stream = futures::stream::iter(reader).map_err(DataFusionError::from);
@@ -203,6 +236,43 @@ impl<S: HttpSend> RemoteTable<S> {
             body["with_row_id"] = serde_json::Value::Bool(true);
         }
 
+        if params.with_row_address {
+            return Err(Error::NotSupported {
+                message: "row addresses are not supported by remote tables".into(),
+            });
+        }
+
+        if params.materialization != Materialization::Auto {
+            return Err(Error::NotSupported {
+                message: "remote tables choose the materialization of the columns themselves"
+                    .into(),
+            });
+        }
+
+        if params.sample.is_some() {
+            return Err(Error::NotSupported {
+                message: "sampling is not supported by remote tables".into(),
+            });
+        }
+
+        if !params.order_by.is_empty() {
+            return Err(Error::NotSupported {
+                message: "sorting query results is not supported by remote tables".into(),
+            });
+        }
+
+        if params.null_comparison != NullComparison::Unknown {
+            return Err(Error::NotSupported {
+                message: "null comparisons can't be changed for remote tables".into(),
+            });
+        }
+
+        if params.missing_columns != MissingColumns::Error {
+            return Err(Error::NotSupported {
+                message: "selecting missing columns is not supported by remote tables".into(),
+            });
+        }
+
         if let Some(full_text_search) = &params.full_text_search {
             if full_text_search.wand_factor.is_some() {
                 return Err(Error::NotSupported {
@@ -225,6 +295,12 @@ impl<S: HttpSend> RemoteTable<S> {
     ) -> Result<Vec<serde_json::Value>> {
         Self::apply_query_params(&mut body, &query.base)?;
 
+        if query.require_index_prefilter {
+            return Err(Error::NotSupported {
+                message: "require_index_prefilter is not supported by remote tables".into(),
+            });
+        }
+
         // Apply general parameters, before we dispatch based on number of query vectors.
         body["distance_type"] = serde_json::json!(query.distance_type.unwrap_or_default());
         body["nprobes"] = query.nprobes.into();
@@ -283,6 +359,11 @@ impl<S: HttpSend> RemoteTable<S> {
                         .collect::<Result<Vec<_>>>()?;
                     body["vector"] = serde_json::Value::Array(vectors);
                     vec![body]
+                } else if query.multivector {
+                    return Err(Error::NotSupported {
+                        message: "multivector queries are not supported by this server version"
+                            .to_string(),
+                    });
                 } else {
                     // Server does not support multiple vectors in a single query.
                     // We need to send multiple requests.
@@ -368,10 +449,25 @@ impl<S: HttpSend> std::fmt::Display for RemoteTable<S> {
 #[cfg(all(test, feature = "remote"))]
 mod test_utils {
     use super::*;
-    use crate::remote::client::test_utils::client_with_handler;
     use crate::remote::client::test_utils::MockSender;
+    use crate::remote::client::test_utils::{client_with_handler, client_with_handler_and_config};
+    use crate::remote::ClientConfig;
 
     impl RemoteTable<MockSender> {
+        pub fn new_mock_with_config<F, T>(name: String, handler: F, config: ClientConfig) -> Self
+        where
+            F: Fn(reqwest::Request) -> http::Response<T> + Send + Sync + 'static,
+            T: Into<reqwest::Body>,
+        {
+            let client = client_with_handler_and_config(handler, config);
+            Self {
+                client,
+                name,
+                server_version: ServerVersion::default(),
+                version: RwLock::new(None),
+            }
+        }
+
         pub fn new_mock<F, T>(name: String, handler: F, version: Option<semver::Version>) -> Self
         where
             F: Fn(reqwest::Request) -> http::Response<T> + Send + Sync + 'static,
@@ -455,6 +551,121 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         Ok(body.versions)
     }
 
+    async fn metadata(&self) -> Result<HashMap<String, String>> {
+        let request = self
+            .client
+            .post(&format!("/v1/table/{}/metadata/list/", self.name));
+        let version = self.current_version().await;
+        let request = request.json(&serde_json::json!({ "version": version }));
+        let (request_id, response) = self.client.send(request, true).await?;
+        let response = self.check_table_response(&request_id, response).await?;
+
+        #[derive(Deserialize)]
+        struct ListMetadataResponse {
+            metadata: HashMap<String, String>,
+        }
+
+        let body = response.text().await.err_to_http(request_id.clone())?;
+        let body: ListMetadataResponse =
+            serde_json::from_str(&body).map_err(|err| Error::Http {
+                source: format!(
+                    "Failed to parse list_metadata response: {}, body: {}",
+                    err, body
+                )
+                .into(),
+                request_id,
+                status_code: None,
+            })?;
+
+        Ok(body.metadata)
+    }
+
+    async fn update_metadata(&self, values: HashMap<String, String>) -> Result<()> {
+        self.check_mutable().await?;
+        let request = self
+            .client
+            .post(&format!("/v1/table/{}/metadata/update/", self.name))
+            .json(&serde_json::json!({ "metadata": values }));
+        let (request_id, response) = self.client.send(request, false).await?;
+        self.check_table_response(&request_id, response).await?;
+        Ok(())
+    }
+
+    async fn delete_metadata(&self, keys: &[&str]) -> Result<()> {
+        self.check_mutable().await?;
+        let request = self
+            .client
+            .post(&format!("/v1/table/{}/metadata/delete/", self.name))
+            .json(&serde_json::json!({ "keys": keys }));
+        let (request_id, response) = self.client.send(request, false).await?;
+        self.check_table_response(&request_id, response).await?;
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<TableStatistics> {
+        let request = self.client.post(&format!("/v1/table/{}/stats/", self.name));
+        let (request_id, response) = self.client.send(request, true).await?;
+        let response = self.check_table_response(&request_id, response).await?;
+
+        #[derive(Deserialize)]
+        struct FragmentStatsResponse {
+            num_fragments: usize,
+        }
+
+        #[derive(Deserialize)]
+        struct StatsResponse {
+            num_rows: usize,
+            total_bytes: Option<u64>,
+            fragment_stats: Option<FragmentStatsResponse>,
+        }
+
+        let body = response.text().await.err_to_http(request_id.clone())?;
+        let body: StatsResponse = serde_json::from_str(&body).map_err(|err| Error::Http {
+            source: format!("Failed to parse stats response: {}, body: {}", err, body).into(),
+            request_id,
+            status_code: None,
+        })?;
+
+        let columns = self
+            .schema()
+            .await?
+            .fields()
+            .iter()
+            .map(|field| ColumnStatistics {
+                name: field.name().clone(),
+                ..Default::default()
+            })
+            .collect();
+        Ok(TableStatistics {
+            num_rows: body.num_rows,
+            num_deleted_rows: None,
+            num_fragments: body.fragment_stats.map(|stats| stats.num_fragments),
+            total_bytes: body.total_bytes,
+            columns,
+            indices: index_coverage(self).await?,
+        })
+    }
+
+    async fn list_tags(&self) -> Result<HashMap<String, TagContents>> {
+        tags_not_supported()
+    }
+
+    async fn tag_version(&self, _tag: &str) -> Result<u64> {
+        tags_not_supported()
+    }
+
+    async fn create_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        tags_not_supported()
+    }
+
+    async fn update_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        tags_not_supported()
+    }
+
+    async fn delete_tag(&self, _tag: &str) -> Result<()> {
+        tags_not_supported()
+    }
+
     async fn schema(&self) -> Result<SchemaRef> {
         let schema = self.describe().await?.schema;
         Ok(Arc::new(schema.try_into()?))
@@ -496,12 +707,20 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         data: Box<dyn RecordBatchReader + Send>,
     ) -> Result<()> {
         self.check_mutable().await?;
-        let body = Self::reader_as_body(data)?;
-        let mut request = self
+        if let Some(on) = add.insert_if_absent {
+            if matches!(add.mode, AddDataMode::Overwrite) {
+                return Err(Error::InvalidInput {
+                    message: "insert_if_absent cannot be used when overwriting a table".to_string(),
+                });
+            }
+            let mut params = MergeInsertBuilder::new(add.parent, on);
+            params.when_not_matched_insert_all().deduplicate_source();
+            return self.merge_insert(params, data).await;
+        }
+        let request = self
             .client
-            .post(&format!("/v1/table/{}/insert/", self.name))
-            .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE)
-            .body(body);
+            .post(&format!("/v1/table/{}/insert/", self.name));
+        let mut request = self.with_data_body(request, data).await?;
 
         match add.mode {
             AddDataMode::Append => {}
@@ -517,6 +736,16 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         Ok(())
     }
 
+    async fn add_stream(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: crate::arrow::SendableRecordBatchStream,
+    ) -> Result<()> {
+        Err(Error::NotSupported {
+            message: "adding a stream of data to a remote table".to_string(),
+        })
+    }
+
     async fn create_plan(
         &self,
         query: &AnyQuery,
@@ -577,11 +806,33 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
 
         let (request_id, response) = self.client.send(request, false).await?;
 
-        self.check_table_response(&request_id, response).await?;
+        let response = self.check_table_response(&request_id, response).await?;
+
+        #[derive(Deserialize)]
+        struct UpdateResponse {
+            // Older servers don't report the number of modified rows
+            #[serde(default)]
+            num_rows_updated: u64,
+        }
 
-        Ok(0) // TODO: support returning number of modified rows once supported in SaaS.
+        let body = response.text().await.err_to_http(request_id.clone())?;
+        if body.trim().is_empty() {
+            return Ok(0);
+        }
+        let body: UpdateResponse = serde_json::from_str(&body).map_err(|err| Error::Http {
+            source: format!("Failed to parse update response: {}, body: {}", err, body).into(),
+            request_id,
+            status_code: None,
+        })?;
+
+        Ok(body.num_rows_updated)
     }
-    async fn delete(&self, predicate: &str) -> Result<()> {
+    async fn delete(&self, predicate: &str, with_row_ids: bool) -> Result<DeleteResult> {
+        if with_row_ids {
+            return Err(Error::NotSupported {
+                message: "returning the deleted row ids is not supported for remote tables".into(),
+            });
+        }
         self.check_mutable().await?;
         let body = serde_json::json!({ "predicate": predicate });
         let request = self
@@ -589,8 +840,29 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
             .post(&format!("/v1/table/{}/delete/", self.name))
             .json(&body);
         let (request_id, response) = self.client.send(request, false).await?;
-        self.check_table_response(&request_id, response).await?;
-        Ok(())
+        let response = self.check_table_response(&request_id, response).await?;
+
+        #[derive(Deserialize)]
+        struct DeleteResponse {
+            // Older servers don't report the number of deleted rows
+            #[serde(default)]
+            num_deleted_rows: u64,
+        }
+
+        let body = response.text().await.err_to_http(request_id.clone())?;
+        if body.trim().is_empty() {
+            return Ok(DeleteResult::default());
+        }
+        let body: DeleteResponse = serde_json::from_str(&body).map_err(|err| Error::Http {
+            source: format!("Failed to parse delete response: {}, body: {}", err, body).into(),
+            request_id,
+            status_code: None,
+        })?;
+
+        Ok(DeleteResult {
+            num_deleted_rows: body.num_deleted_rows,
+            deleted_row_ids: None,
+        })
     }
 
     async fn create_index(&self, mut index: IndexBuilder) -> Result<()> {
@@ -687,14 +959,17 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         new_data: Box<dyn RecordBatchReader + Send>,
     ) -> Result<()> {
         self.check_mutable().await?;
+        let new_data: Box<dyn RecordBatchReader + Send> = if params.deduplicate_source {
+            Box::new(DeduplicatedReader::try_new(new_data, &params.on)?)
+        } else {
+            new_data
+        };
         let query = MergeInsertRequest::try_from(params)?;
-        let body = Self::reader_as_body(new_data)?;
         let request = self
             .client
             .post(&format!("/v1/table/{}/merge_insert/", self.name))
-            .query(&query)
-            .header(CONTENT_TYPE, ARROW_STREAM_CONTENT_TYPE)
-            .body(body);
+            .query(&query);
+        let request = self.with_data_body(request, new_data).await?;
 
         let (request_id, response) = self.client.send(request, false).await?;
 
@@ -714,6 +989,22 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         _read_columns: Option<Vec<String>>,
     ) -> Result<()> {
         self.check_mutable().await?;
+        let transforms = match transforms {
+            // The server only evaluates SQL, so null columns are sent as casts
+            NewColumnTransform::AllNulls(schema) => NewColumnTransform::SqlExpressions(
+                schema
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        Ok((
+                            field.name().clone(),
+                            format!("cast(NULL as {})", sql_type_name(field.data_type())?),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            transforms => transforms,
+        };
         match transforms {
             NewColumnTransform::SqlExpressions(expressions) => {
                 let body = expressions
@@ -736,7 +1027,9 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
             }
             _ => {
                 return Err(Error::NotSupported {
-                    message: "Only SQL expressions are supported for adding columns".into(),
+                    message:
+                        "Only SQL expressions and null columns are supported for adding columns"
+                            .into(),
                 });
             }
         }
@@ -807,6 +1100,8 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
         struct IndexConfigResponse {
             index_name: String,
             columns: Vec<String>,
+            #[serde(default)]
+            version: Option<u64>,
         }
 
         let body = response.text().await.err_to_http(request_id.clone())?;
@@ -830,6 +1125,7 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
                         name: index.index_name,
                         index_type: stats.index_type,
                         columns: index.columns,
+                        version: index.version,
                     })),
                     Ok(None) => Ok(None), // The index must have been deleted since we listed it.
                     Err(e) => Err(e),
@@ -877,6 +1173,25 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
             self.name, index_name
         ));
         let (request_id, response) = self.client.send(request, true).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            // A missing table is reported the same way.  The error code in
+            // the body says which of the two is missing, older servers only
+            // mention the index in the message.
+            let body = response.text().await.err_to_http(request_id)?;
+            let index_missing = match error_code(&body).as_deref() {
+                Some(code) => code == "index_not_found",
+                None => body.to_lowercase().contains("index"),
+            };
+            return Err(if index_missing {
+                Error::IndexNotFound {
+                    name: index_name.to_string(),
+                }
+            } else {
+                Error::TableNotFound {
+                    name: self.name.clone(),
+                }
+            });
+        }
         self.check_table_response(&request_id, response).await?;
         Ok(())
     }
@@ -891,6 +1206,17 @@ impl<S: HttpSend> BaseTable for RemoteTable<S> {
     }
 }
 
+/// The code of a structured error response, if the body is one
+fn error_code(body: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct ErrorCode {
+        code: String,
+    }
+    serde_json::from_str::<ErrorCode>(body)
+        .ok()
+        .map(|error| error.code)
+}
+
 #[derive(Serialize)]
 struct MergeInsertRequest {
     on: String,
@@ -901,6 +1227,40 @@ struct MergeInsertRequest {
     when_not_matched_by_source_delete_filt: Option<String>,
 }
 
+fn tags_not_supported<T>() -> Result<T> {
+    Err(Error::NotSupported {
+        message: "tags are not supported on LanceDB cloud.".into(),
+    })
+}
+
+/// The name of a data type in the SQL accepted by the server
+fn sql_type_name(data_type: &DataType) -> Result<&'static str> {
+    Ok(match data_type {
+        DataType::Boolean => "boolean",
+        DataType::Int8 => "tinyint",
+        DataType::Int16 => "smallint",
+        DataType::Int32 => "int",
+        DataType::Int64 => "bigint",
+        DataType::UInt8 => "tinyint unsigned",
+        DataType::UInt16 => "smallint unsigned",
+        DataType::UInt32 => "int unsigned",
+        DataType::UInt64 => "bigint unsigned",
+        DataType::Float32 => "float",
+        DataType::Float64 => "double",
+        DataType::Utf8 => "varchar",
+        DataType::Binary => "bytea",
+        DataType::Date32 => "date",
+        _ => {
+            return Err(Error::NotSupported {
+                message: format!(
+                    "adding a null column of type {} is not supported for remote tables",
+                    data_type
+                ),
+            })
+        }
+    })
+}
+
 impl TryFrom<MergeInsertBuilder> for MergeInsertRequest {
     type Error = Error;
 
@@ -944,7 +1304,7 @@ mod tests {
 
     use crate::index::vector::IvfFlatIndexBuilder;
     use crate::remote::db::DEFAULT_SERVER_VERSION;
-    use crate::remote::JSON_CONTENT_TYPE;
+    use crate::remote::{ClientConfig, ConnectionConfig, JSON_CONTENT_TYPE};
     use crate::{
         index::{vector::IvfPqIndexBuilder, Index, IndexStatistics, IndexType},
         query::{ExecutableQuery, QueryBase},
@@ -981,7 +1341,7 @@ mod tests {
             Box::pin(table.update().column("a", "a + 1").execute().map_ok(|_| ())),
             Box::pin(table.add(example_data()).execute().map_ok(|_| ())),
             Box::pin(table.merge_insert(&["test"]).execute(example_data())),
-            Box::pin(table.delete("false")),
+            Box::pin(table.delete("false").map_ok(|_| ())),
             Box::pin(table.add_columns(
                 NewColumnTransform::SqlExpressions(vec![("x".into(), "y".into())]),
                 None,
@@ -1017,6 +1377,79 @@ mod tests {
         assert_eq!(version, 42);
     }
 
+    #[rstest]
+    #[case(400, r#"{"code": "invalid_filter", "message": "no column x"}"#)]
+    #[case(429, r#"{"code": "quota_exceeded", "message": "slow down"}"#)]
+    #[case(402, r#"{"code": "quota_exceeded", "message": "storage full"}"#)]
+    #[case(503, r#"{"code": "index_building", "message": "index idx not ready"}"#)]
+    #[case(409, r#"{"code": "conflict", "message": "concurrent write"}"#)]
+    #[case(500, r#"{"code": "something_new", "message": "oops"}"#)]
+    #[case(500, "Internal Server Error")]
+    #[tokio::test]
+    async fn test_structured_errors(#[case] status: u16, #[case] body: &'static str) {
+        let table = Table::new_with_handler("my_table", move |_| {
+            http::Response::builder().status(status).body(body).unwrap()
+        });
+
+        let err = table.delete("x > 1").await.unwrap_err();
+        match (status, &err) {
+            (400, Error::InvalidFilter { message, .. }) => assert_eq!(message, "no column x"),
+            (429, Error::QuotaExceeded { retryable, .. }) => assert!(retryable),
+            (402, Error::QuotaExceeded { retryable, .. }) => assert!(!retryable),
+            (409, Error::CommitConflict { request_id, .. }) => assert!(request_id.is_some()),
+            (503, Error::IndexBuilding { .. }) => {}
+            (500, Error::Http { status_code, .. }) => {
+                assert_eq!(status_code.unwrap().as_u16(), 500)
+            }
+            _ => panic!("Unexpected error for status {}: {:?}", status, err),
+        }
+        assert_eq!(err.is_retryable(), !matches!(status, 400 | 402));
+    }
+
+    #[tokio::test]
+    async fn test_metadata() {
+        let table = Table::new_with_handler("my_table", |request| {
+            assert_eq!(request.method(), "POST");
+            assert_eq!(
+                request.headers().get("Content-Type").unwrap(),
+                JSON_CONTENT_TYPE
+            );
+            let body: serde_json::Value =
+                serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+
+            match request.url().path() {
+                "/v1/table/my_table/metadata/list/" => {
+                    assert_eq!(body, serde_json::json!({ "version": null }));
+                    http::Response::builder()
+                        .status(200)
+                        .body(r#"{"metadata": {"owner": "alice"}}"#)
+                        .unwrap()
+                }
+                "/v1/table/my_table/metadata/update/" => {
+                    assert_eq!(body, serde_json::json!({ "metadata": { "owner": "bob" } }));
+                    http::Response::builder().status(200).body("{}").unwrap()
+                }
+                "/v1/table/my_table/metadata/delete/" => {
+                    assert_eq!(body, serde_json::json!({ "keys": ["owner"] }));
+                    http::Response::builder().status(200).body("{}").unwrap()
+                }
+                path => panic!("Unexpected path: {}", path),
+            }
+        });
+
+        assert_eq!(
+            table.get_metadata("owner").await.unwrap().as_deref(),
+            Some("alice")
+        );
+        assert_eq!(table.get_metadata("missing").await.unwrap(), None);
+        assert_eq!(
+            table.list_metadata().await.unwrap(),
+            HashMap::from([("owner".to_string(), "alice".to_string())])
+        );
+        table.set_metadata("owner", "bob").await.unwrap();
+        table.delete_metadata(&["owner"]).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_schema() {
         let table = Table::new_with_handler("my_table", |request| {
@@ -1213,6 +1646,152 @@ mod tests {
         assert_eq!(&body, &expected_body);
     }
 
+    #[tokio::test]
+    async fn test_add_with_checksum() {
+        let data = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let expected_body = write_ipc_stream(&data);
+        let expected_checksum = format!("{:08x}", crc32fast::hash(&expected_body));
+
+        let table = RemoteTable::new_mock_with_config(
+            "my_table".to_string(),
+            move |request| {
+                assert_eq!(request.url().path(), "/v1/table/my_table/insert/");
+                assert_eq!(
+                    request.headers().get("Content-Type").unwrap(),
+                    ARROW_STREAM_CONTENT_TYPE
+                );
+                assert_eq!(
+                    request.headers().get("x-lancedb-checksum-crc32").unwrap(),
+                    expected_checksum.as_str()
+                );
+                let body = request.body().unwrap().as_bytes().unwrap();
+                assert_eq!(body, expected_body.as_slice());
+
+                http::Response::builder().status(200).body("").unwrap()
+            },
+            ClientConfig {
+                checksum: true,
+                ..Default::default()
+            },
+        );
+        let table = Table::new(Arc::new(table));
+
+        table
+            .add(RecordBatchIterator::new([Ok(data.clone())], data.schema()))
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_max_concurrent_requests() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{mpsc, Mutex};
+
+        let config = ClientConfig {
+            connection_config: ConnectionConfig {
+                max_concurrent_requests: Some(1),
+                queue_timeout: Some(std::time::Duration::from_millis(50)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // A request signals that it started, then holds on to its slot until
+        // it is released.  Only the first of several concurrent requests
+        // waits, so that a request past the limit fails the test instead of
+        // hanging it.
+        let (started_tx, mut started_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (handler_in_flight, handler_peak) = (in_flight.clone(), peak.clone());
+        let table = Table::new(Arc::new(RemoteTable::new_mock_with_config(
+            "my_table".to_string(),
+            move |_| {
+                let current = handler_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                handler_peak.fetch_max(current, Ordering::SeqCst);
+                started_tx.send(()).unwrap();
+                if current == 1 {
+                    release_rx.lock().unwrap().recv().unwrap();
+                }
+                handler_in_flight.fetch_sub(1, Ordering::SeqCst);
+                http::Response::builder().status(200).body("").unwrap()
+            },
+            config,
+        )));
+
+        let slow_table = table.clone();
+        let slow = tokio::spawn(async move { slow_table.delete("x > 1").await });
+        started_rx.recv().await.unwrap();
+
+        let err = table.delete("x > 2").await.unwrap_err();
+        assert!(
+            err.to_string().contains("all 1 request slots are in use"),
+            "Unexpected error: {}",
+            err
+        );
+
+        release_tx.send(()).unwrap();
+        slow.await.unwrap().unwrap();
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_checksum_mismatch() {
+        let data = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let response_body = write_ipc_file(&data);
+        let checksum = format!("{:08x}", crc32fast::hash(&response_body));
+
+        let make_table = |checksum: String| {
+            let response_body = response_body.clone();
+            let table = RemoteTable::new_mock_with_config(
+                "my_table".to_string(),
+                move |_| {
+                    http::Response::builder()
+                        .status(200)
+                        .header(CONTENT_TYPE, ARROW_FILE_CONTENT_TYPE)
+                        .header("x-lancedb-checksum-crc32", checksum.as_str())
+                        .body(response_body.clone())
+                        .unwrap()
+                },
+                ClientConfig {
+                    checksum: true,
+                    ..Default::default()
+                },
+            );
+            Table::new(Arc::new(table))
+        };
+
+        let table = make_table(checksum);
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(batches, vec![data]);
+
+        let table = make_table("00000000".to_string());
+        let res = table.query().execute().await;
+        assert!(
+            matches!(res, Err(Error::Http { ref source, .. }) if source.to_string().contains("Checksum mismatch")),
+            "Expected checksum mismatch, got {:?}",
+            res.err()
+        );
+    }
+
     #[tokio::test]
     async fn test_update() {
         let table = Table::new_with_handler("my_table", |request| {
@@ -1243,10 +1822,13 @@ mod tests {
                 assert_eq!(only_if, "b > 10");
             }
 
-            http::Response::builder().status(200).body("{}").unwrap()
+            http::Response::builder()
+                .status(200)
+                .body(r#"{"num_rows_updated": 3}"#)
+                .unwrap()
         });
 
-        table
+        let rows_updated = table
             .update()
             .column("a", "a + 1")
             .column("b", "b - 1")
@@ -1254,6 +1836,14 @@ mod tests {
             .execute()
             .await
             .unwrap();
+        assert_eq!(rows_updated, 3);
+
+        // Servers that don't report the count return an empty object
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder().status(200).body("{}").unwrap()
+        });
+        let rows_updated = table.update().column("a", "1").execute().await.unwrap();
+        assert_eq!(rows_updated, 0);
     }
 
     #[tokio::test]
@@ -1330,6 +1920,47 @@ mod tests {
         assert_eq!(&body, &expected_body);
     }
 
+    #[tokio::test]
+    async fn test_add_insert_if_absent() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 1]))],
+        )
+        .unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let table = Table::new_with_handler("my_table", move |mut request| {
+            assert_eq!(request.method(), "POST");
+            assert_eq!(request.url().path(), "/v1/table/my_table/merge_insert/");
+
+            let params = request.url().query_pairs().collect::<HashMap<_, _>>();
+            assert_eq!(params["on"], "a");
+            assert_eq!(params["when_matched_update_all"], "false");
+            assert_eq!(params["when_not_matched_insert_all"], "true");
+            assert_eq!(params["when_not_matched_by_source_delete"], "false");
+
+            let mut body_out = reqwest::Body::from(Vec::new());
+            std::mem::swap(request.body_mut().as_mut().unwrap(), &mut body_out);
+            sender.send(body_out).unwrap();
+
+            http::Response::builder().status(200).body("").unwrap()
+        });
+        table
+            .add(RecordBatchIterator::new([Ok(batch)], schema.clone()))
+            .insert_if_absent(["a"])
+            .execute()
+            .await
+            .unwrap();
+
+        // The repeated key is dropped before the data is sent
+        let body = receiver.recv().unwrap();
+        let body = collect_body(body).await;
+        let expected =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))]).unwrap();
+        assert_eq!(&body, &write_ipc_stream(&expected));
+    }
+
     #[tokio::test]
     async fn test_delete() {
         let table = Table::new_with_handler("my_table", |request| {
@@ -1351,6 +1982,41 @@ mod tests {
         table.delete("id in (1, 2, 3)").await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_delete_with_count() {
+        let table = Table::new_with_handler("my_table", |request| {
+            assert_eq!(request.method(), "POST");
+            assert_eq!(request.url().path(), "/v1/table/my_table/delete/");
+
+            let body = request.body().unwrap().as_bytes().unwrap();
+            let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let predicate = body.get("predicate").unwrap().as_str().unwrap();
+            assert_eq!(predicate, "id in (1, 2, 3)");
+
+            http::Response::builder()
+                .status(200)
+                .body(r#"{"num_deleted_rows": 2}"#)
+                .unwrap()
+        });
+
+        let deleted = table.delete_with_count("id in (1, 2, 3)").await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 2);
+        assert_eq!(deleted.deleted_row_ids, None);
+
+        // Row ids can't be returned by the server
+        assert!(matches!(
+            table.delete_with_row_ids("id in (1, 2, 3)").await,
+            Err(Error::NotSupported { .. })
+        ));
+
+        // Servers that don't report the count return an empty body
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder().status(200).body("").unwrap()
+        });
+        let deleted = table.delete_with_count("id in (1, 2, 3)").await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 0);
+    }
+
     #[tokio::test]
     async fn test_query_plain() {
         let expected_data = RecordBatch::try_new(
@@ -1747,6 +2413,61 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_stats() {
+        let table = Table::new_with_handler("my_table", |request| {
+            assert_eq!(request.method(), "POST");
+
+            let response_body = match request.url().path() {
+                "/v1/table/my_table/stats/" => serde_json::json!({
+                    "total_bytes": 4096,
+                    "num_rows": 100,
+                    "num_indices": 1,
+                    "fragment_stats": {
+                        "num_fragments": 3,
+                        "num_small_fragments": 2,
+                    },
+                }),
+                "/v1/table/my_table/describe/" => serde_json::json!({
+                    "version": 1,
+                    "schema": {"fields": [
+                        {"name": "a", "type": { "type": "int32" }, "nullable": false},
+                    ]},
+                }),
+                "/v1/table/my_table/index/list/" => serde_json::json!({
+                    "indexes": [{
+                        "index_name": "a_idx",
+                        "index_uuid": "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+                        "columns": ["a"],
+                        "index_status": "done",
+                    }]
+                }),
+                "/v1/table/my_table/index/a_idx/stats/" => serde_json::json!({
+                    "num_indexed_rows": 90,
+                    "num_unindexed_rows": 10,
+                    "index_type": "BTREE",
+                }),
+                path => panic!("Unexpected path: {}", path),
+            };
+            http::Response::builder()
+                .status(200)
+                .body(serde_json::to_string(&response_body).unwrap())
+                .unwrap()
+        });
+
+        let stats = table.stats().execute().await.unwrap();
+        assert_eq!(stats.num_rows, 100);
+        assert_eq!(stats.total_bytes, Some(4096));
+        assert_eq!(stats.num_fragments, Some(3));
+        assert_eq!(stats.num_deleted_rows, None);
+        assert_eq!(stats.columns.len(), 1);
+        assert_eq!(stats.columns[0].name, "a");
+        assert_eq!(stats.columns[0].bytes_on_disk, None);
+        assert_eq!(stats.indices.len(), 1);
+        assert_eq!(stats.indices[0].index_type, IndexType::BTree);
+        assert_eq!(stats.indices[0].num_unindexed_rows, 10);
+    }
+
     #[tokio::test]
     async fn test_list_indices() {
         let table = Table::new_with_handler("my_table", |request| {
@@ -1761,6 +2482,7 @@ mod tests {
                                 "index_uuid": "3fa85f64-5717-4562-b3fc-2c963f66afa6",
                                 "columns": ["vector"],
                                 "index_status": "done",
+                                "version": 3,
                             },
                             {
                                 "index_name": "my_idx",
@@ -1800,11 +2522,13 @@ mod tests {
                 name: "vector_idx".into(),
                 index_type: IndexType::IvfPq,
                 columns: vec!["vector".into()],
+                version: Some(3),
             },
             IndexConfig {
                 name: "my_idx".into(),
                 index_type: IndexType::LabelList,
                 columns: vec!["my_column".into()],
+                version: None,
             },
         ];
         assert_eq!(indices, expected);
@@ -1885,6 +2609,7 @@ mod tests {
             distance_type: Some(DistanceType::L2),
             num_indices: None,
             loss: None,
+            size_bytes: None,
         };
         assert_eq!(indices, expected);
 
@@ -1901,6 +2626,58 @@ mod tests {
         assert!(indices.is_none());
     }
 
+    #[tokio::test]
+    async fn test_wait_for_index() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let table = Table::new_with_handler("my_table", {
+            let calls = calls.clone();
+            move |request| {
+                assert_eq!(
+                    request.url().path(),
+                    "/v1/table/my_table/index/my_index/stats/"
+                );
+                // The index doesn't exist, then is partially built, then ready
+                let num_unindexed_rows = match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => {
+                        return http::Response::builder()
+                            .status(404)
+                            .body(String::new())
+                            .unwrap()
+                    }
+                    1 => 100,
+                    _ => 0,
+                };
+                let response_body = serde_json::json!({
+                  "num_indexed_rows": 1000 - num_unindexed_rows,
+                  "num_unindexed_rows": num_unindexed_rows,
+                  "index_type": "IVF_PQ",
+                  "distance_type": "l2"
+                });
+                http::Response::builder()
+                    .status(200)
+                    .body(serde_json::to_string(&response_body).unwrap())
+                    .unwrap()
+            }
+        });
+        table
+            .wait_for_index(&["my_index"], Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder().status(404).body("").unwrap()
+        });
+        let err = table
+            .wait_for_index(&["my_index"], Duration::from_millis(250))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{:?}", err);
+    }
+
     #[tokio::test]
     async fn test_passes_version() {
         let table = Table::new_with_handler("my_table", |request| {
@@ -2131,6 +2908,72 @@ mod tests {
             )
             .await
             .unwrap();
+
+        // Null columns are sent as SQL casts
+        let types = [
+            (DataType::Boolean, "boolean"),
+            (DataType::Int8, "tinyint"),
+            (DataType::Int16, "smallint"),
+            (DataType::Int32, "int"),
+            (DataType::Int64, "bigint"),
+            (DataType::UInt8, "tinyint unsigned"),
+            (DataType::UInt16, "smallint unsigned"),
+            (DataType::UInt32, "int unsigned"),
+            (DataType::UInt64, "bigint unsigned"),
+            (DataType::Float32, "float"),
+            (DataType::Float64, "double"),
+            (DataType::Utf8, "varchar"),
+            (DataType::Binary, "bytea"),
+            (DataType::Date32, "date"),
+        ];
+        let expected = types
+            .iter()
+            .enumerate()
+            .map(|(i, (_, name))| (format!("c{}", i), format!("cast(NULL as {})", name)))
+            .collect::<Vec<_>>();
+        let table = Table::new_with_handler("my_table", move |request| {
+            let body = request.body().unwrap().as_bytes().unwrap();
+            let value: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let new_columns = value
+                .get("new_columns")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|column| {
+                    (
+                        column["name"].as_str().unwrap().to_string(),
+                        column["expression"].as_str().unwrap().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(new_columns, expected);
+
+            http::Response::builder().status(200).body("{}").unwrap()
+        });
+        let schema = Arc::new(Schema::new(
+            types
+                .iter()
+                .enumerate()
+                .map(|(i, (data_type, _))| Field::new(format!("c{}", i), data_type.clone(), true))
+                .collect::<Vec<_>>(),
+        ));
+        table
+            .add_columns(NewColumnTransform::AllNulls(schema), None)
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "list",
+            DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+            true,
+        )]));
+        assert!(matches!(
+            table
+                .add_columns(NewColumnTransform::AllNulls(schema), None)
+                .await,
+            Err(Error::NotSupported { .. })
+        ));
     }
 
     #[tokio::test]
@@ -2213,5 +3056,39 @@ mod tests {
             http::Response::builder().status(200).body("{}").unwrap()
         });
         table.drop_index("my_index").await.unwrap();
+
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder()
+                .status(404)
+                .body(r#"{"code": "index_not_found", "message": "not found"}"#)
+                .unwrap()
+        });
+        let err = table.drop_index("my_index").await.unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { name } if name == "my_index"));
+
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder()
+                .status(404)
+                .body(r#"{"code": "table_not_found", "message": "not found"}"#)
+                .unwrap()
+        });
+        let err = table.drop_index("my_index").await.unwrap_err();
+        assert!(matches!(err, Error::TableNotFound { name } if name == "my_table"));
+
+        // Without an error code, the message says what is missing
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder()
+                .status(404)
+                .body("Index my_index not found")
+                .unwrap()
+        });
+        let err = table.drop_index("my_index").await.unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { name } if name == "my_index"));
+
+        let table = Table::new_with_handler("my_table", |_| {
+            http::Response::builder().status(404).body("").unwrap()
+        });
+        let err = table.drop_index("my_index").await.unwrap_err();
+        assert!(matches!(err, Error::TableNotFound { name } if name == "my_table"));
     }
 }
@@ -2,6 +2,7 @@
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
 use std::io::Cursor;
+use std::time::Duration;
 
 use arrow_array::RecordBatchReader;
 use reqwest::Response;
@@ -26,6 +27,38 @@ pub fn batches_to_ipc_bytes(batches: impl RecordBatchReader) -> Result<Vec<u8>>
     Ok(buf.into_inner())
 }
 
+/// CRC32 checksum of a request or response body, as a lowercase hex string.
+pub fn ipc_checksum(data: &[u8]) -> String {
+    format!("{:08x}", crc32fast::hash(data))
+}
+
+/// Parse a duration such as `30s`, `500ms`, `2m` or `1h`
+///
+/// A bare number is taken to be seconds, to match the environment variables.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let invalid = || crate::Error::InvalidInput {
+        message: format!("invalid duration '{}'", value),
+    };
+    let number = number.parse::<u64>().map_err(|_| invalid())?;
+    let seconds_per_unit = match unit {
+        "ms" => return Ok(Duration::from_millis(number)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let too_long = || crate::Error::InvalidInput {
+        message: format!("duration '{}' is too long", value),
+    };
+    let seconds = number.checked_mul(seconds_per_unit).ok_or_else(too_long)?;
+    Ok(Duration::from_secs(seconds))
+}
+
 pub fn parse_server_version(req_id: &str, rsp: &Response) -> Result<ServerVersion> {
     let version = rsp
         .headers()
@@ -46,3 +79,31 @@ pub fn parse_server_version(req_id: &str, rsp: &Response) -> Result<ServerVersio
         .unwrap_or_default();
     Ok(version)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert!(matches!(
+            parse_duration("soon"),
+            Err(crate::Error::InvalidInput { .. })
+        ));
+
+        // Values that don't fit in a duration are rejected rather than wrapped
+        let max = u64::MAX.to_string();
+        assert_eq!(parse_duration(&max).unwrap(), Duration::from_secs(u64::MAX));
+        for unit in ["m", "h"] {
+            assert!(matches!(
+                parse_duration(&format!("{}{}", max, unit)),
+                Err(crate::Error::InvalidInput { .. })
+            ));
+        }
+    }
+}
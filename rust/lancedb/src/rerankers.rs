@@ -13,10 +13,11 @@ use lance::dataset::ROW_ID;
 
 use crate::error::{Error, Result};
 
+pub mod linear_combination;
 pub mod rrf;
 
 /// column name for reranker relevance score
-const RELEVANCE_SCORE: &str = "_relevance_score";
+pub(crate) const RELEVANCE_SCORE: &str = "_relevance_score";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum NormalizeMethod {
@@ -50,10 +51,19 @@ impl std::fmt::Display for NormalizeMethod {
 /// Interface for a reranker. A reranker is used to rerank the results from a
 /// vector and FTS search. This is useful for combining the results from both
 /// search methods.
+///
+/// The reranker is applied as the final stage of a query, after all the
+/// candidates have been collected, see [`crate::query::QueryBase::rerank`].
+/// Every method must return the results sorted by relevance, with the score
+/// in a Float32 column named `_relevance_score`.
+///
+/// The built-in rerankers, [`rrf::RRFReranker`] and
+/// [`linear_combination::LinearCombinationReranker`], only fuse hybrid search
+/// results. Implement [`Reranker::rerank_vector`] and [`Reranker::rerank_fts`]
+/// to rerank plain searches as well, e.g. by calling out to a cross-encoder
+/// service.
 #[async_trait]
 pub trait Reranker: std::fmt::Debug + Sync + Send {
-    // TODO support vector reranking and FTS reranking. Currently only hybrid reranking is supported.
-
     /// Rerank function receives the individual results from the vector and FTS search
     /// results. You can choose to use any of the results to generate the final results,
     /// allowing maximum flexibility.
@@ -64,6 +74,35 @@ pub trait Reranker: std::fmt::Debug + Sync + Send {
         fts_results: RecordBatch,
     ) -> Result<RecordBatch>;
 
+    /// Rerank the results of a vector search
+    ///
+    /// A vector search has no query text, so rerankers that need it, such as
+    /// cross-encoders, should be given the text when they are created.
+    ///
+    /// The default implementation returns [`Error::NotSupported`].
+    async fn rerank_vector(&self, vector_results: RecordBatch) -> Result<RecordBatch> {
+        let _ = vector_results;
+        Err(Error::NotSupported {
+            message: format!(
+                "{:?} does not support reranking vector search results",
+                self
+            ),
+        })
+    }
+
+    /// Rerank the results of a full text search for `query`
+    ///
+    /// The default implementation returns [`Error::NotSupported`].
+    async fn rerank_fts(&self, query: &str, fts_results: RecordBatch) -> Result<RecordBatch> {
+        let _ = (query, fts_results);
+        Err(Error::NotSupported {
+            message: format!(
+                "{:?} does not support reranking full text search results",
+                self
+            ),
+        })
+    }
+
     fn merge_results(
         &self,
         vector_results: RecordBatch,
@@ -100,7 +139,7 @@ pub fn check_reranker_result(result: &RecordBatch) -> Result<()> {
     if result.schema().column_with_name(RELEVANCE_SCORE).is_none() {
         return Err(Error::Schema {
             message: format!(
-                "rerank_hybrid must return a RecordBatch with a column named {}",
+                "a reranker must return a RecordBatch with a column named {}",
                 RELEVANCE_SCORE
             ),
         });
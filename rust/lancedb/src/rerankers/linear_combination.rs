@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::{
+    array::downcast_array,
+    compute::{sort_to_indices, take},
+};
+use arrow_array::{ArrayRef, Float32Array, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SortOptions};
+use async_trait::async_trait;
+use lance::dataset::ROW_ID;
+use lance_index::{scalar::inverted::SCORE_COL, vector::DIST_COL};
+
+use crate::error::{Error, Result};
+use crate::query::hybrid::normalize_scores;
+use crate::rerankers::{Reranker, RELEVANCE_SCORE};
+
+/// Reranks the results using a weighted sum of the vector and FTS scores.
+///
+/// The relevance score of a row is
+/// `weight * (1 - distance) + (1 - weight) * score`, where `distance` and
+/// `score` are the vector distance and FTS score of the row.  Both are min-max
+/// normalized across the results to lie between 0 and 1 before they are
+/// combined, so the weight balances the two searches whatever the distance
+/// type and however high the FTS scores are.
+#[derive(Debug)]
+pub struct LinearCombinationReranker {
+    weight: f32,
+    fill: f32,
+}
+
+impl LinearCombinationReranker {
+    /// Create a new LinearCombinationReranker
+    ///
+    /// `weight` is the weight given to the vector search results (default is
+    /// 0.7), the FTS results are given `1 - weight`. It must be between 0 and 1.
+    pub fn new(weight: f32) -> Result<Self> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(Error::InvalidInput {
+                message: format!("weight must be between 0 and 1, got {}", weight),
+            });
+        }
+        Ok(Self { weight, fill: 1.0 })
+    }
+
+    /// The distance used for rows that were only found by the FTS search
+    ///
+    /// Distances are normalized to lie between 0 and 1, see
+    /// [`LinearCombinationReranker`], so the default of 1.0 means the row gets
+    /// no credit from the vector search. Rows that were
+    /// only found by the vector search get an FTS score of 0.
+    pub fn fill(mut self, fill: f32) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+impl Default for LinearCombinationReranker {
+    fn default() -> Self {
+        Self {
+            weight: 0.7,
+            fill: 1.0,
+        }
+    }
+}
+
+fn find_column<'a>(results: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef> {
+    results.column_by_name(name).ok_or(Error::InvalidInput {
+        message: format!(
+            "expected column {} not found in results. found columns {:?}",
+            name,
+            results
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name())
+                .collect::<Vec<_>>()
+        ),
+    })
+}
+
+fn scores_by_row_id(results: &RecordBatch, column: &str) -> Result<HashMap<u64, f32>> {
+    let row_ids: UInt64Array = downcast_array(find_column(results, ROW_ID)?);
+    let scores: Float32Array = downcast_array(find_column(results, column)?);
+    Ok(row_ids
+        .values()
+        .iter()
+        .copied()
+        .zip(scores.values().iter().copied())
+        .collect())
+}
+
+#[async_trait]
+impl Reranker for LinearCombinationReranker {
+    async fn rerank_hybrid(
+        &self,
+        _query: &str,
+        vector_results: RecordBatch,
+        fts_results: RecordBatch,
+    ) -> Result<RecordBatch> {
+        let distances = scores_by_row_id(
+            &normalize_scores(vector_results.clone(), DIST_COL, None)?,
+            DIST_COL,
+        )?;
+        let scores = scores_by_row_id(
+            &normalize_scores(fts_results.clone(), SCORE_COL, None)?,
+            SCORE_COL,
+        )?;
+
+        let combined_results = self.merge_results(vector_results, fts_results)?;
+
+        let combined_row_ids: UInt64Array = downcast_array(find_column(&combined_results, ROW_ID)?);
+        let relevance_scores =
+            Float32Array::from_iter_values(combined_row_ids.values().iter().map(|row_id| {
+                let distance = distances.get(row_id).copied().unwrap_or(self.fill);
+                let score = scores.get(row_id).copied().unwrap_or(0.0);
+                self.weight * (1.0 - distance) + (1.0 - self.weight) * score
+            }));
+
+        // keep track of indices sorted by the relevance column
+        let sort_indices = sort_to_indices(
+            &relevance_scores,
+            Some(SortOptions {
+                descending: true,
+                ..Default::default()
+            }),
+            None,
+        )?;
+
+        // add relevance scores to columns
+        let mut columns = combined_results.columns().to_vec();
+        columns.push(Arc::new(relevance_scores));
+
+        // sort by the relevance scores
+        let columns = columns
+            .iter()
+            .map(|c| take(c, &sort_indices, None))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // add relevance score to schema
+        let mut fields = combined_results.schema().fields().to_vec();
+        fields.push(Arc::new(Field::new(
+            RELEVANCE_SCORE,
+            DataType::Float32,
+            false,
+        )));
+        let schema = Schema::new(fields);
+
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_array::StringArray;
+
+    #[tokio::test]
+    async fn test_linear_combination_reranker() {
+        let vec_schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new(DIST_COL, DataType::Float32, false),
+            Field::new(ROW_ID, DataType::UInt64, false),
+        ]));
+        let fts_schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new(SCORE_COL, DataType::Float32, false),
+            Field::new(ROW_ID, DataType::UInt64, false),
+        ]));
+
+        let vec_results = RecordBatch::try_new(
+            vec_schema,
+            vec![
+                Arc::new(StringArray::from(vec!["foo", "bar", "baz"])),
+                Arc::new(Float32Array::from(vec![0.0, 1.0, 2.0])),
+                Arc::new(UInt64Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let fts_results = RecordBatch::try_new(
+            fts_schema,
+            vec![
+                Arc::new(StringArray::from(vec!["baz", "dog"])),
+                Arc::new(Float32Array::from(vec![4.0, 2.0])),
+                Arc::new(UInt64Array::from(vec![3, 4])),
+            ],
+        )
+        .unwrap();
+
+        // the distances normalize to [0.0, 0.5, 1.0] and the FTS scores to
+        // [1.0, 0.0], so scores should be calculated as:
+        // - foo = 0.7 * 1.0             = 0.7
+        // - bar = 0.7 * 0.5             = 0.35
+        // - baz = 0.7 * 0.0 + 0.3 * 1.0 = 0.3
+        // - dog = 0.3 * 0.0             = 0.0
+        let reranker = LinearCombinationReranker::default();
+        let result = reranker
+            .rerank_hybrid("", vec_results.clone(), fts_results.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            RELEVANCE_SCORE,
+            result.schema().fields().last().unwrap().name()
+        );
+        let ids: UInt64Array = downcast_array(result.column_by_name(ROW_ID).unwrap());
+        assert_eq!(
+            ids.iter().map(|e| e.unwrap()).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        let scores: Float32Array = downcast_array(result.column_by_name(RELEVANCE_SCORE).unwrap());
+        for (score, expected) in scores.values().iter().zip([0.7, 0.35, 0.3, 0.0]) {
+            assert!((score - expected).abs() < 1e-6, "{} != {}", score, expected);
+        }
+
+        // The row ids are needed to match the results of the two searches
+        let without_row_ids = vec_results.project(&[0, 1]).unwrap();
+        let err = reranker
+            .rerank_hybrid("", without_row_ids, fts_results)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        assert!(LinearCombinationReranker::new(1.5).is_err());
+    }
+}
@@ -3,51 +3,67 @@
 
 //! LanceDB Table APIs
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use arrow::array::{AsArray, FixedSizeListBuilder, Float32Builder};
-use arrow::datatypes::{Float32Type, UInt8Type};
+use arrow::datatypes::{Float32Type, UInt64Type, UInt8Type};
 use arrow_array::{RecordBatchIterator, RecordBatchReader};
 use arrow_schema::{DataType, Field, Schema, SchemaRef};
 use async_trait::async_trait;
-use datafusion_expr::Expr;
+use datafusion_common::tree_node::{Transformed, TreeNode, TreeNodeRecursion};
+use datafusion_expr::{Expr, Operator};
 use datafusion_physical_plan::display::DisplayableExecutionPlan;
 use datafusion_physical_plan::projection::ProjectionExec;
 use datafusion_physical_plan::repartition::RepartitionExec;
+use datafusion_physical_plan::sorts::sort::SortExec;
 use datafusion_physical_plan::union::UnionExec;
 use datafusion_physical_plan::ExecutionPlan;
 use futures::{StreamExt, TryStreamExt};
 use lance::dataset::builder::DatasetBuilder;
-use lance::dataset::cleanup::RemovalStats;
+pub use lance::dataset::cleanup::RemovalStats;
 use lance::dataset::optimize::{compact_files, CompactionMetrics, IndexRemapperOptions};
-use lance::dataset::scanner::Scanner;
+use lance::dataset::scanner::{ColumnOrdering, MaterializationStyle, Scanner};
+use lance::dataset::statistics::DatasetStatisticsExt;
+use lance::dataset::transaction::{Operation, Transaction as LanceTransaction};
 pub use lance::dataset::ColumnAlteration;
 pub use lance::dataset::NewColumnTransform;
 pub use lance::dataset::ReadParams;
 pub use lance::dataset::Version;
 use lance::dataset::{
-    InsertBuilder, UpdateBuilder as LanceUpdateBuilder, WhenMatched, WriteMode, WriteParams,
+    CommitBuilder, InsertBuilder, UpdateBuilder as LanceUpdateBuilder, WhenMatched,
+    WriteDestination, WriteMode, WriteParams,
+};
+use lance::dataset::{Dataset, ProjectionRequest, ROW_ID};
+use lance::dataset::{
+    MergeInsertBuilder as LanceMergeInsertBuilder, MergeInsertJob, WhenNotMatchedBySource,
 };
-use lance::dataset::{MergeInsertBuilder as LanceMergeInsertBuilder, WhenNotMatchedBySource};
 use lance::index::vector::utils::infer_vector_dim;
+use lance::io::exec::{ANNIvfSubIndexExec, Planner, ScalarIndexExec};
 use lance::io::WrappingObjectStore;
 use lance_datafusion::exec::execute_plan;
 use lance_datafusion::utils::StreamingWriteSource;
+use lance_encoding::version::LanceFileVersion;
 use lance_index::vector::hnsw::builder::HnswBuildParams;
 use lance_index::vector::ivf::IvfBuildParams;
 use lance_index::vector::pq::PQBuildParams;
 use lance_index::vector::sq::builder::SQBuildParams;
 use lance_index::DatasetIndexExt;
 use lance_index::IndexType;
-use lance_table::format::Manifest;
+use lance_table::format::{Fragment, Manifest};
 use lance_table::io::commit::ManifestNamingScheme;
-use log::info;
+use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
-use crate::arrow::IntoArrow;
+use crate::arrow::{
+    IntoArrow, IntoArrowStream, SendableRecordBatchStream, SendableRecordBatchStreamExt,
+};
 use crate::connection::NoData;
+use crate::data::export::{ExportParquetOptions, ExportStats};
+use crate::data::file::FileSource;
+use crate::database::observer::WriteOperation;
 use crate::embeddings::{EmbeddingDefinition, EmbeddingRegistry, MaybeEmbedded, MemoryRegistry};
 use crate::error::{Error, Result};
 use crate::index::scalar::FtsIndexBuilder;
@@ -62,21 +78,57 @@ use crate::index::{
 };
 use crate::index::{IndexConfig, IndexStatisticsImpl};
 use crate::query::{
-    IntoQueryVector, Query, QueryExecutionOptions, QueryFilter, QueryRequest, Select, VectorQuery,
-    VectorQueryRequest, DEFAULT_TOP_K,
+    check_meta_columns, column_projection, execute_and_analyze, ExecutableQuery, IntoQueryVector,
+    Materialization, NullComparison, Query, QueryExecutionOptions, QueryFilter, QueryRequest,
+    SampleFraction, Select, SortOrder, VectorQuery, VectorQueryRequest, DEFAULT_TOP_K,
 };
+use crate::transaction::{self, StagedWrite};
 use crate::utils::{
-    default_vector_column, supported_bitmap_data_type, supported_btree_data_type,
-    supported_fts_data_type, supported_label_list_data_type, supported_vector_data_type,
-    PatchReadParam, PatchWriteParam,
+    default_vector_column, is_binary_vector_data_type, supported_bitmap_data_type,
+    supported_btree_data_type, supported_fts_data_type, supported_label_list_data_type,
+    supported_vector_data_type, validate_vector_column, PatchReadParam, PatchWriteParam,
 };
+use crate::DistanceType;
 
+use self::auto_id::{with_auto_ids, AutoId};
+use self::changes::{read_change, ChangeStream, TableChange};
+use self::constraints::{
+    check_column, check_constraints, constraints_from_schema, constraints_metadata,
+    ColumnConstraint, CONSTRAINTS_METADATA_KEY,
+};
 use self::dataset::DatasetConsistencyWrapper;
-use self::merge::MergeInsertBuilder;
+use self::defaults::{
+    default_columns_transform, default_field_metadata, defaults_from_schema, validate_defaults,
+    with_defaults,
+};
+use self::merge::{DeduplicatedReader, MergeInsertBuilder};
+use self::migrate::MigrateFormatBuilder;
+use self::partition::{
+    partition_columns, prune_fragments, record_partition_values, write_partitioned, PartitionValues,
+};
+use self::sample::{sample_row_ids, SampleBuilder, SampleExec};
+use self::stats::{
+    field_bytes, index_coverage, ColumnStatistics, TableStatistics, TableStatsBuilder,
+};
+use self::tags::{TagContents, Tags};
+use self::ttl::{
+    ttl_from_schema, ttl_metadata, validate_ttl, ExpirationTask, Ttl, TTL_METADATA_KEY,
+};
 
+pub mod auto_id;
+pub mod changes;
+pub mod constraints;
 pub mod datafusion;
 pub(crate) mod dataset;
+pub mod defaults;
 pub mod merge;
+pub mod migrate;
+pub mod partition;
+pub(crate) mod query_cache;
+pub mod sample;
+pub mod stats;
+pub mod tags;
+pub mod ttl;
 
 pub use chrono::Duration;
 pub use lance::dataset::optimize::CompactionOptions;
@@ -90,6 +142,8 @@ pub enum ColumnKind {
     Physical,
     /// Columns populated by applying an embedding function to the input
     Embedding(EmbeddingDefinition),
+    /// Columns of unique ids that the table fills in when data is added
+    AutoId(AutoId),
 }
 
 /// Defines a column in a table
@@ -176,6 +230,14 @@ pub enum OptimizeAction {
     /// new files.  If these operations are run frequently then compaction should run frequently.
     ///
     /// If these operations are never run (search only) then compaction is not necessary.
+    ///
+    /// The most useful [`CompactionOptions`] are `target_rows_per_fragment`, which decides
+    /// which files are small enough to be merged, and `num_threads`, which limits how many
+    /// files are rewritten in parallel.  The returned [`OptimizeStats::compaction`] reports
+    /// how many fragments and files were removed and added.
+    ///
+    /// Indices are remapped to the new files as part of the compaction, unless
+    /// `remap_options` says otherwise.
     Compact {
         options: CompactionOptions,
         remap_options: Option<Arc<dyn IndexRemapperOptions>>,
@@ -200,6 +262,7 @@ pub enum OptimizeAction {
         /// If you are sure that there are no in-progress transactions, then you can set this to True to delete all files older than `older_than`.
         delete_unverified: Option<bool>,
         /// If true, an error will be returned if there are any old versions that are still tagged.
+        /// If false, tagged versions are kept.  Defaults to true.
         error_if_tagged_old_versions: Option<bool>,
     },
     /// Optimize the indices
@@ -217,6 +280,13 @@ pub enum OptimizeAction {
     ///
     /// For example, when using IVF, an index will create clusters.  Optimizing an index assigns unindexed
     /// data to the existing clusters, but it does not move the clusters or create new clusters.
+    ///
+    /// The unindexed data is indexed into a new delta index, which is then merged with the latest
+    /// [`OptimizeOptions::num_indices_to_merge`] parts of the index.  [`OptimizeOptions::append`]
+    /// keeps the new delta separate, which is the cheapest after a small ingest, while merging more
+    /// parts makes later searches faster.  [`IndexStatistics::num_indices`] reports how many parts an
+    /// index has.  [`OptimizeOptions::index_names`] limits the optimization to some indices, and
+    /// [`crate::Error::IndexNotFound`] is returned if one of them doesn't exist.
     Index(OptimizeOptions),
 }
 
@@ -235,6 +305,207 @@ pub struct OptimizeStats {
     pub prune: Option<RemovalStats>,
 }
 
+/// When a native table adds newly written rows to its indices
+///
+/// See [`crate::database::listing::ListingDatabaseOptions::auto_index`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoIndex {
+    /// Before the write returns
+    ///
+    /// Writes take longer, but searches never scan unindexed rows written
+    /// through this table.  Failures are logged rather than returned, since
+    /// the write has already been committed.
+    Inline,
+    /// In a background task started after the write
+    ///
+    /// Failures are logged.  While a task is waiting to start, later writes
+    /// don't start another one.
+    Background,
+}
+
+impl std::str::FromStr for AutoIndex {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "inline" => Ok(Self::Inline),
+            "background" => Ok(Self::Background),
+            _ => Err(Error::InvalidInput {
+                message: format!(
+                    "auto_index must be inline or background, received {}",
+                    value
+                ),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for AutoIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inline => write!(f, "inline"),
+            Self::Background => write!(f, "background"),
+        }
+    }
+}
+
+/// The outcome of a delete operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeleteResult {
+    /// The number of rows that matched the predicate and were deleted
+    pub num_deleted_rows: u64,
+    /// The row ids of the deleted rows
+    ///
+    /// Only set by [`Table::delete_with_row_ids`].
+    pub deleted_row_ids: Option<Vec<u64>>,
+}
+
+/// The number of rows left in each fragment, by fragment id
+///
+/// The count is read from the fragment metadata.  It is `None` for fragments
+/// written by old versions of Lance that don't record how many rows they have.
+fn fragment_num_rows(fragments: &[Fragment]) -> HashMap<u64, Option<usize>> {
+    fragments
+        .iter()
+        .map(|fragment| {
+            let num_deleted = match &fragment.deletion_file {
+                Some(deletion_file) => deletion_file.num_deleted_rows,
+                None => Some(0),
+            };
+            let num_rows = fragment
+                .physical_rows
+                .zip(num_deleted)
+                .map(|(physical, deleted)| physical.saturating_sub(deleted));
+            (fragment.id, num_rows)
+        })
+        .collect()
+}
+
+/// The filter matching the rows with the given row ids
+fn row_id_filter(row_ids: &[u64]) -> String {
+    if row_ids.is_empty() {
+        return "false".to_string();
+    }
+    let row_ids = row_ids
+        .iter()
+        .map(|row_id| row_id.to_string())
+        .collect::<Vec<_>>();
+    format!("{} IN ({})", ROW_ID, row_ids.join(", "))
+}
+
+/// Rewrite a filter so that comparing a null value is false
+///
+/// See [`NullComparison::False`]
+fn compare_nulls_as_false(dataset: &Dataset, filter: &QueryFilter) -> Result<QueryFilter> {
+    let planner = Planner::new(Arc::new(Schema::from(dataset.schema())));
+    let filter = match filter {
+        QueryFilter::Sql(sql) => planner.parse_expr(sql)?,
+        QueryFilter::Datafusion(expr) => expr.clone(),
+        QueryFilter::Substrait(_) => {
+            return Err(Error::NotSupported {
+                message: "null comparisons can't be changed for Substrait filters".into(),
+            })
+        }
+    };
+    let filter = filter
+        .transform_up(|expr| {
+            let is_comparison = match &expr {
+                Expr::BinaryExpr(binary) => matches!(
+                    binary.op,
+                    Operator::Eq
+                        | Operator::NotEq
+                        | Operator::Lt
+                        | Operator::LtEq
+                        | Operator::Gt
+                        | Operator::GtEq
+                ),
+                Expr::Like(_) | Expr::SimilarTo(_) | Expr::InList(_) | Expr::Between(_) => true,
+                _ => false,
+            };
+            Ok(if is_comparison {
+                Transformed::yes(Expr::IsTrue(Box::new(expr)))
+            } else {
+                Transformed::no(expr)
+            })
+        })
+        .map_err(|err| Error::InvalidInput {
+            message: err.to_string(),
+        })?
+        .data;
+    Ok(QueryFilter::Datafusion(planner.optimize_expr(filter)?))
+}
+
+/// Only scan the rows that match `filter`
+fn apply_filter(scanner: &mut Scanner, filter: &QueryFilter) -> Result<()> {
+    match filter {
+        QueryFilter::Sql(sql) => {
+            scanner.filter(sql)?;
+        }
+        QueryFilter::Substrait(substrait) => {
+            scanner.filter_substrait(substrait)?;
+        }
+        QueryFilter::Datafusion(expr) => {
+            scanner.filter_expr(expr.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Make the sort of `plan` only keep the first `fetch` rows, so the memory it
+/// uses doesn't depend on the number of rows it sorts
+fn limit_sort(plan: Arc<dyn ExecutionPlan>, fetch: usize) -> Result<Arc<dyn ExecutionPlan>> {
+    Ok(plan
+        .transform_down(|node| {
+            Ok(match node.as_any().downcast_ref::<SortExec>() {
+                Some(sort) => Transformed::new(
+                    Arc::new(sort.with_fetch(Some(fetch))) as Arc<dyn ExecutionPlan>,
+                    true,
+                    TreeNodeRecursion::Stop,
+                ),
+                None => Transformed::no(node),
+            })
+        })
+        .map_err(|err| Error::Runtime {
+            message: err.to_string(),
+        })?
+        .data)
+}
+
+/// Check that the filter of a vector search plan is answered by scalar indices
+///
+/// Each vector index search ([`ANNIvfSubIndexExec`]) gets its prefilter as a
+/// second child, which is a [`ScalarIndexExec`] only when the scalar indices
+/// answer the whole filter.
+fn check_index_prefilter(plan: &dyn ExecutionPlan) -> Result<()> {
+    fn ann_searches<'a>(plan: &'a dyn ExecutionPlan, found: &mut Vec<&'a dyn ExecutionPlan>) {
+        if plan.as_any().is::<ANNIvfSubIndexExec>() {
+            found.push(plan);
+        }
+        for child in plan.children() {
+            ann_searches(child.as_ref(), found);
+        }
+    }
+    let mut searches = Vec::new();
+    ann_searches(plan, &mut searches);
+    if searches.is_empty() {
+        return Err(Error::InvalidInput {
+            message: "the filter cannot be pushed into the vector index search, the vector column is not indexed".to_string(),
+        });
+    }
+    let indexed = searches.iter().all(|search| {
+        search
+            .children()
+            .get(1)
+            .is_some_and(|prefilter| prefilter.as_any().is::<ScalarIndexExec>())
+    });
+    if !indexed {
+        return Err(Error::InvalidInput {
+            message: "the filter cannot be pushed into the vector index search, it is not fully answered by scalar indices".to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Describes what happens when a vector either contains NaN or
 /// does not have enough values
 #[derive(Clone, Debug, Default)]
@@ -272,30 +543,40 @@ pub enum AddDataMode {
     #[default]
     Append,
     /// The existing table will be overwritten with the new data
+    ///
+    /// The old rows are replaced in a single commit, so readers see either the
+    /// old or the new data and never an empty table.  The old data stays
+    /// available as an earlier version of the table until it is cleaned up.
+    /// The new data may have a different schema.
     Overwrite,
 }
 
 /// A builder for configuring a [`crate::connection::Connection::create_table`] or [`Table::add`]
 /// operation
-pub struct AddDataBuilder<T: IntoArrow> {
-    parent: Arc<dyn BaseTable>,
+pub struct AddDataBuilder<T> {
+    pub(crate) parent: Arc<dyn BaseTable>,
     pub(crate) data: T,
     pub(crate) mode: AddDataMode,
     pub(crate) write_options: WriteOptions,
+    pub(crate) max_rows_per_file: Option<usize>,
+    pub(crate) insert_if_absent: Option<Vec<String>>,
     embedding_registry: Option<Arc<dyn EmbeddingRegistry>>,
 }
 
-impl<T: IntoArrow> std::fmt::Debug for AddDataBuilder<T> {
+impl<T> std::fmt::Debug for AddDataBuilder<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AddDataBuilder")
             .field("parent", &self.parent)
             .field("mode", &self.mode)
             .field("write_options", &self.write_options)
+            .field("max_rows_per_file", &self.max_rows_per_file)
+            .field("insert_if_absent", &self.insert_if_absent)
             .finish()
     }
 }
 
-impl<T: IntoArrow> AddDataBuilder<T> {
+impl<T> AddDataBuilder<T> {
+    /// Whether to append to the table or replace its contents, see [`AddDataMode`]
     pub fn mode(mut self, mode: AddDataMode) -> Self {
         self.mode = mode;
         self
@@ -306,18 +587,76 @@ impl<T: IntoArrow> AddDataBuilder<T> {
         self
     }
 
-    pub async fn execute(self) -> Result<()> {
-        let parent = self.parent.clone();
-        let data = self.data.into_arrow()?;
+    /// The maximum number of rows written to each data file
+    ///
+    /// Once a file has this many rows a new file is started.  The default is
+    /// about one million rows.  This takes precedence over the value in
+    /// [`WriteOptions::lance_write_params`].  It is ignored by remote tables.
+    pub fn max_rows_per_file(mut self, max_rows_per_file: usize) -> Self {
+        self.max_rows_per_file = Some(max_rows_per_file);
+        self
+    }
+
+    /// Only add the rows whose key columns do not match an existing row
+    ///
+    /// Rows that repeat the keys of an earlier row of the new data are dropped
+    /// as well, so adding the same data twice has the same effect as adding it
+    /// once.  This is useful for ingesting events that may be delivered more
+    /// than once.
+    ///
+    /// The add is run as a [`Table::merge_insert`] that only inserts, so if
+    /// there is a single key column with a scalar index the existing keys are
+    /// looked up in the index instead of scanning the table.  This cannot be
+    /// combined with [`AddDataMode::Overwrite`].
+    pub fn insert_if_absent(mut self, on: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.insert_if_absent = Some(on.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Split the builder into the options and the data
+    fn split_data(self) -> (AddDataBuilder<NoData>, T) {
         let without_data = AddDataBuilder::<NoData> {
             data: NoData {},
             mode: self.mode,
             parent: self.parent,
             write_options: self.write_options,
+            max_rows_per_file: self.max_rows_per_file,
+            insert_if_absent: self.insert_if_absent,
             embedding_registry: self.embedding_registry,
         };
+        (without_data, self.data)
+    }
+}
+
+impl<T: IntoArrow> AddDataBuilder<T> {
+    pub async fn execute(self) -> Result<()> {
+        let (parent, without_data, data) = self.into_parts()?;
         parent.add(without_data, data).await
     }
+
+    /// Split the builder into the table, the options and the data
+    pub(crate) fn into_parts(
+        self,
+    ) -> Result<(
+        Arc<dyn BaseTable>,
+        AddDataBuilder<NoData>,
+        Box<dyn RecordBatchReader + Send>,
+    )> {
+        let parent = self.parent.clone();
+        let (without_data, data) = self.split_data();
+        Ok((parent, without_data, data.into_arrow()?))
+    }
+}
+
+/// The data of an [`AddDataBuilder`] created by [`Table::add_streaming`]
+pub struct StreamingData<T: IntoArrowStream>(T);
+
+impl<T: IntoArrowStream> AddDataBuilder<StreamingData<T>> {
+    pub async fn execute(self) -> Result<()> {
+        let parent = self.parent.clone();
+        let (without_data, data) = self.split_data();
+        parent.add_stream(without_data, data.0.into_arrow()?).await
+    }
 }
 
 /// A builder for configuring an [`Table::update`] operation
@@ -433,14 +772,33 @@ pub trait BaseTable: std::fmt::Display + std::fmt::Debug + Send + Sync {
 
         Ok(format!("{}", display.indent(verbose)))
     }
+    /// Run a query and return its plan annotated with the execution metrics.
+    async fn analyze_plan(
+        &self,
+        query: &AnyQuery,
+        options: QueryExecutionOptions,
+    ) -> Result<String> {
+        execute_and_analyze(self.create_plan(query, options).await?).await
+    }
     /// Add new records to the table.
     async fn add(
         &self,
         add: AddDataBuilder<NoData>,
         data: Box<dyn arrow_array::RecordBatchReader + Send>,
     ) -> Result<()>;
+    /// Insert a stream of data into the table.
+    async fn add_stream(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: SendableRecordBatchStream,
+    ) -> Result<()> {
+        Err(not_supported(self.name(), "adding a stream"))
+    }
     /// Delete rows from the table.
-    async fn delete(&self, predicate: &str) -> Result<()>;
+    ///
+    /// If `with_row_ids` is true then the row ids of the deleted rows are
+    /// returned as well as the count.
+    async fn delete(&self, predicate: &str, with_row_ids: bool) -> Result<DeleteResult>;
     /// Update rows in the table.
     async fn update(&self, update: UpdateBuilder) -> Result<u64>;
     /// Create an index on the provided column(s).
@@ -451,6 +809,64 @@ pub trait BaseTable: std::fmt::Display + std::fmt::Debug + Send + Sync {
     async fn drop_index(&self, name: &str) -> Result<()>;
     /// Get statistics about the index.
     async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>>;
+    /// Wait until the indices exist and cover every row of the table.
+    async fn wait_for_index(
+        &self,
+        index_names: &[&str],
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        let start = std::time::Instant::now();
+        let mut interval = std::time::Duration::from_millis(100);
+        let mut pending = index_names.to_vec();
+        loop {
+            let mut still_pending = Vec::new();
+            for name in pending {
+                match self.index_stats(name).await? {
+                    Some(stats) if stats.num_unindexed_rows == 0 => {}
+                    _ => still_pending.push(name),
+                }
+            }
+            pending = still_pending;
+            if pending.is_empty() {
+                return Ok(());
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::Timeout { timeout });
+            }
+            debug!(
+                "LanceDB: waiting for indices {:?} of table {}",
+                pending,
+                self.name()
+            );
+            tokio::time::sleep(interval.min(remaining)).await;
+            interval = (interval * 2).min(MAX_INDEX_WAIT_INTERVAL);
+        }
+    }
+    /// Write the data files of an add without committing them
+    ///
+    /// See [`crate::transaction`].  Only local tables support transactions.
+    async fn stage_add(
+        &self,
+        _add: AddDataBuilder<NoData>,
+        _data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<StagedWrite> {
+        Err(transaction::not_supported(self.name()))
+    }
+    /// Check that a staged write doesn't conflict with the writes committed
+    /// to the table since it was staged
+    async fn check_staged(&self, _write: &StagedWrite) -> Result<()> {
+        Err(transaction::not_supported(self.name()))
+    }
+    /// Commit a staged write, returning the new version of the table
+    async fn commit_staged(&self, _write: StagedWrite) -> Result<u64> {
+        Err(transaction::not_supported(self.name()))
+    }
+    /// Undo a staged write that was committed as `version`, keeping the
+    /// writes committed after it
+    async fn revert_staged(&self, _version: u64) -> Result<()> {
+        Err(transaction::not_supported(self.name()))
+    }
     /// Merge insert new records into the table.
     async fn merge_insert(
         &self,
@@ -479,10 +895,57 @@ pub trait BaseTable: std::fmt::Display + std::fmt::Debug + Send + Sync {
     async fn restore(&self) -> Result<()>;
     /// List the versions of the table.
     async fn list_versions(&self) -> Result<Vec<Version>>;
+    /// Get the key-value metadata stored with the table.
+    async fn metadata(&self) -> Result<HashMap<String, String>> {
+        Err(not_supported(self.name(), "reading metadata"))
+    }
+    /// Insert or overwrite key-value metadata stored with the table.
+    async fn update_metadata(&self, _values: HashMap<String, String>) -> Result<()> {
+        Err(not_supported(self.name(), "updating metadata"))
+    }
+    /// Remove keys from the metadata stored with the table.
+    async fn delete_metadata(&self, _keys: &[&str]) -> Result<()> {
+        Err(not_supported(self.name(), "deleting metadata"))
+    }
     /// Get the table definition.
     async fn table_definition(&self) -> Result<TableDefinition>;
+    /// Get statistics about the table from its metadata.
+    async fn stats(&self) -> Result<TableStatistics> {
+        Err(not_supported(self.name(), "reading statistics"))
+    }
+    /// List the tags of the table and the versions they point to.
+    async fn list_tags(&self) -> Result<HashMap<String, TagContents>> {
+        Err(not_supported(self.name(), "listing tags"))
+    }
+    /// Get the version a tag points to.
+    async fn tag_version(&self, _tag: &str) -> Result<u64> {
+        Err(not_supported(self.name(), "reading tags"))
+    }
+    /// Create a tag pointing to a version.
+    async fn create_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        Err(not_supported(self.name(), "creating tags"))
+    }
+    /// Point an existing tag at a different version.
+    async fn update_tag(&self, _tag: &str, _version: u64) -> Result<()> {
+        Err(not_supported(self.name(), "updating tags"))
+    }
+    /// Delete a tag.
+    async fn delete_tag(&self, _tag: &str) -> Result<()> {
+        Err(not_supported(self.name(), "deleting tags"))
+    }
     /// Get the table URI
     fn dataset_uri(&self) -> &str;
+    /// The local table that runs the operations of [`Table`] only local
+    /// tables support, such as [`Table::changes`]
+    ///
+    /// `write` is the kind of write for the operations that modify the
+    /// table, which a table that wraps a local table can refuse.  By default
+    /// there is no local table.
+    fn native_table(&self, _write: Option<WriteOperation>) -> Result<Option<&NativeTable>> {
+        Ok(None)
+    }
+    /// Called after a write made on [`Self::native_table`] is committed
+    fn native_write_committed(&self, _write: WriteOperation) {}
 }
 
 /// A Table is a collection of strong typed Rows.
@@ -558,6 +1021,33 @@ impl Table {
         &self.inner
     }
 
+    /// Create a DataFusion table provider that reads this table
+    ///
+    /// The provider can be registered in a DataFusion `SessionContext` to
+    /// query the table with SQL or DataFrames, and join it with other sources.
+    /// Filters and projections are pushed down into the table scan.  With the
+    /// `vector-search-udtf` feature, use `Self::vector_search_function` to run
+    /// vector searches from DataFusion.
+    pub async fn as_datafusion_provider(&self) -> Result<Arc<self::datafusion::BaseTableAdapter>> {
+        Ok(Arc::new(
+            self::datafusion::BaseTableAdapter::try_new(self.inner.clone()).await?,
+        ))
+    }
+
+    /// Create a DataFusion table function that runs vector searches on this
+    /// table
+    ///
+    /// See [`self::datafusion::VectorSearchFunction`] for how to call it.
+    /// This requires the `vector-search-udtf` feature.
+    #[cfg(feature = "vector-search-udtf")]
+    pub async fn vector_search_function(
+        &self,
+    ) -> Result<Arc<self::datafusion::VectorSearchFunction>> {
+        Ok(Arc::new(
+            self::datafusion::VectorSearchFunction::try_new(self.inner.clone()).await?,
+        ))
+    }
+
     pub(crate) fn new_with_embedding_registry(
         inner: Arc<dyn BaseTable>,
         embedding_registry: Arc<dyn EmbeddingRegistry>,
@@ -576,6 +1066,20 @@ impl Table {
         self.inner.as_native()
     }
 
+    /// The local table for an operation only local tables support, see
+    /// [`BaseTable::native_table`]
+    fn local(&self, write: Option<WriteOperation>, operation: &str) -> Result<&NativeTable> {
+        local_table(self.inner.as_ref(), write, operation)
+    }
+
+    /// Tell the table a write made on its local table was committed
+    fn committed<T>(&self, result: Result<T>, write: WriteOperation) -> Result<T> {
+        if result.is_ok() {
+            self.inner.native_write_committed(write);
+        }
+        result
+    }
+
     /// Get the name of the table.
     pub fn name(&self) -> &str {
         self.inner.name()
@@ -595,9 +1099,15 @@ impl Table {
 
     /// Count the number of rows in this dataset.
     ///
+    /// Without a filter the count comes from the table's metadata and no data
+    /// is read.  With a filter, the filter is pushed down into the scan, which
+    /// uses any scalar indices on the filtered columns and only reads the
+    /// columns the filter needs.
+    ///
     /// # Arguments
     ///
-    /// * `filter` if present, only count rows matching the filter
+    /// * `filter` if present, only count rows matching the filter, an SQL
+    ///   expression such as `"label = 'spam'"`
     pub async fn count_rows(&self, filter: Option<String>) -> Result<usize> {
         self.inner.count_rows(filter.map(Filter::Sql)).await
     }
@@ -614,10 +1124,77 @@ impl Table {
             data: batches,
             mode: AddDataMode::Append,
             write_options: WriteOptions::default(),
+            max_rows_per_file: None,
+            insert_if_absent: None,
             embedding_registry: Some(self.embedding_registry.clone()),
         }
     }
 
+    /// Insert a stream of records into this Table
+    ///
+    /// The stream is written as it is read, a file at a time, so sources that
+    /// are larger than memory, or that produce data slowly, can be added.  The
+    /// stream is only polled as fast as the data can be written, and at most
+    /// one file of rows is buffered, which can be limited with
+    /// [`AddDataBuilder::max_rows_per_file`].  Nothing is visible to readers
+    /// until the stream ends and the new data is committed.
+    ///
+    /// Embedding functions are not applied to streams, and adding a stream to
+    /// a remote table is not supported yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `batches` the stream of data to be added to the Table
+    pub fn add_streaming<T: IntoArrowStream>(
+        &self,
+        batches: T,
+    ) -> AddDataBuilder<StreamingData<T>> {
+        AddDataBuilder {
+            parent: self.inner.clone(),
+            data: StreamingData(batches),
+            mode: AddDataMode::Append,
+            write_options: WriteOptions::default(),
+            max_rows_per_file: None,
+            insert_if_absent: None,
+            embedding_registry: None,
+        }
+    }
+
+    /// Insert the records of data files into this Table
+    ///
+    /// The files are read from local disk or object storage and may be CSV,
+    /// newline delimited JSON, Arrow IPC or Parquet files.  See [`FileSource`]
+    /// for how to read several files at once and how the format is chosen.
+    ///
+    /// Unless a schema is set on the source, CSV and JSON files are parsed
+    /// with the table's schema, so their columns must be in the same order as
+    /// the table's columns.  Rows that cannot be converted to the schema fail
+    /// the add.
+    ///
+    /// The files are streamed into the table as they are downloaded, like
+    /// [`Table::add_streaming`], and have the same restrictions.
+    ///
+    /// ```no_run
+    /// # async fn example(table: &lancedb::Table) -> lancedb::Result<()> {
+    /// table
+    ///     .add_from_uri("s3://bucket/data/*.csv")
+    ///     .await?
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_from_uri(
+        &self,
+        source: impl Into<FileSource>,
+    ) -> Result<AddDataBuilder<StreamingData<SendableRecordBatchStream>>> {
+        let mut source = source.into();
+        if !source.has_schema() {
+            source = source.schema(self.schema().await?);
+        }
+        Ok(self.add_streaming(source.open().await?))
+    }
+
     /// Update existing records in the Table
     ///
     /// An update operation can be used to adjust existing values.  Use the
@@ -686,7 +1263,52 @@ impl Table {
     /// # });
     /// ```
     pub async fn delete(&self, predicate: &str) -> Result<()> {
-        self.inner.delete(predicate).await
+        self.inner.delete(predicate, false).await?;
+        Ok(())
+    }
+
+    /// Delete the rows from the table that match the predicate, returning the
+    /// number of deleted rows
+    ///
+    /// This is the same as [`Self::delete`].  The count is read from the
+    /// deletion files of the new version, so it costs nothing extra.
+    pub async fn delete_with_count(&self, predicate: &str) -> Result<DeleteResult> {
+        self.inner.delete(predicate, false).await
+    }
+
+    /// Delete the rows from the table that match the predicate, returning the
+    /// row ids of the deleted rows
+    ///
+    /// This is the same as [`Self::delete_with_count`] but the row ids of the deleted
+    /// rows are returned in [`DeleteResult::deleted_row_ids`], which is useful
+    /// for keeping audit records.  Collecting the row ids requires scanning
+    /// the matching rows, so this is slower than a plain delete when many
+    /// rows match.
+    ///
+    /// Row ids are not supported by remote tables.
+    pub async fn delete_with_row_ids(&self, predicate: &str) -> Result<DeleteResult> {
+        self.inner.delete(predicate, true).await
+    }
+
+    /// Delete the rows with the given row ids
+    ///
+    /// Row ids are returned by queries with
+    /// [`crate::query::QueryBase::with_row_id`].  Only
+    /// the row ids are read to find the rows, not the data.  Ids that are not
+    /// in the table are ignored.
+    pub async fn delete_by_ids(&self, row_ids: &[u64]) -> Result<DeleteResult> {
+        if row_ids.is_empty() {
+            return Ok(DeleteResult::default());
+        }
+        self.inner.delete(&row_id_filter(row_ids), false).await
+    }
+
+    /// Update the rows with the given row ids
+    ///
+    /// This is the same as [`Self::update`] with a condition that only
+    /// matches the rows, see [`Self::delete_by_ids`].
+    pub fn update_by_ids(&self, row_ids: &[u64]) -> UpdateBuilder {
+        self.update().only_if(row_id_filter(row_ids))
     }
 
     /// Create an index on the provided column(s).
@@ -829,11 +1451,12 @@ impl Table {
     ///     schema.clone(),
     /// );
     /// // Perform an upsert operation
-    /// let mut merge_insert = tbl.merge_insert(&["id"]);
-    /// merge_insert
+    /// tbl.merge_insert(&["id"])
     ///     .when_matched_update_all(None)
-    ///     .when_not_matched_insert_all();
-    /// merge_insert.execute(Box::new(new_data)).await.unwrap();
+    ///     .when_not_matched_insert_all()
+    ///     .execute(new_data)
+    ///     .await
+    ///     .unwrap();
     /// # });
     /// ```
     pub fn merge_insert(&self, on: &[&str]) -> MergeInsertBuilder {
@@ -934,6 +1557,55 @@ impl Table {
         Query::new(self.inner.clone())
     }
 
+    /// Export the whole table to Parquet files under the directory `uri`
+    ///
+    /// The rows are streamed from the table, so tables larger than memory can
+    /// be exported.  To export only some rows or columns, use
+    /// [`ExecutableQuery::export_parquet`] on a query instead.
+    ///
+    /// ```no_run
+    /// # async fn example(table: &lancedb::Table) -> lancedb::Result<()> {
+    /// use lancedb::data::export::ExportParquetOptions;
+    /// table
+    ///     .export_parquet(
+    ///         "s3://bucket/exports/events",
+    ///         ExportParquetOptions {
+    ///             partition_by: vec!["date".to_string()],
+    ///             ..Default::default()
+    ///         },
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn export_parquet(
+        &self,
+        uri: &str,
+        options: ExportParquetOptions,
+    ) -> Result<ExportStats> {
+        self.query().export_parquet(uri, options).await
+    }
+
+    /// Read the whole table into a Polars DataFrame
+    ///
+    /// All the rows are loaded into memory.  To read only some rows or
+    /// columns, use [`ExecutableQuery::execute_polars`] on a query instead.
+    #[cfg(feature = "polars")]
+    pub async fn to_polars(&self) -> Result<polars::prelude::DataFrame> {
+        self.query().execute_polars().await
+    }
+
+    /// Read a random sample of `num_rows` rows of the table
+    ///
+    /// Only the sampled rows are read, so this is much cheaper than scanning
+    /// the table, e.g. to build an evaluation set or to look at some of the
+    /// data.  The rows are sampled without replacement.  Use
+    /// [`Query::sample_fraction`] to sample the rows that match a filter.
+    /// This is only supported for local tables.
+    pub fn sample(&self, num_rows: usize) -> SampleBuilder {
+        SampleBuilder::new(self.inner.clone(), num_rows)
+    }
+
     /// Search the table with a given query vector.
     ///
     /// This is a convenience method for preparing a vector query and
@@ -973,7 +1645,58 @@ impl Table {
         self.inner.optimize(action).await
     }
 
+    /// Remove versions of the table that are older than `older_than`
+    ///
+    /// This deletes the old manifests and any data and index files that are
+    /// only referenced by those versions, and returns the number of versions
+    /// and bytes removed.  The latest version is always kept, as are tagged
+    /// versions.
+    ///
+    /// Files that are not referenced by any version, such as those left
+    /// behind by a failed write, are only removed once they are 7 days old,
+    /// because they may belong to a write that is still in progress.  Set
+    /// `delete_unverified` to remove them regardless of age, but only if you
+    /// are sure no other process is writing to the table.
+    ///
+    /// This is the same as [`OptimizeAction::Prune`].
+    pub async fn cleanup_old_versions(
+        &self,
+        older_than: Duration,
+        delete_unverified: bool,
+    ) -> Result<RemovalStats> {
+        let stats = self
+            .inner
+            .optimize(OptimizeAction::Prune {
+                older_than: Some(older_than),
+                delete_unverified: Some(delete_unverified),
+                // Tagged versions are kept rather than reported as an error
+                error_if_tagged_old_versions: Some(false),
+            })
+            .await?;
+        stats.prune.ok_or_else(|| Error::Runtime {
+            message: "cleaning up old versions did not report any statistics".to_string(),
+        })
+    }
+
     /// Add new columns to the table, providing values to fill in.
+    ///
+    /// The values of the new columns can be given as:
+    ///
+    /// * [`NewColumnTransform::SqlExpressions`]: pairs of column name and SQL
+    ///   expression over the existing columns, such as `("title_lower",
+    ///   "lower(title)")`.
+    /// * [`NewColumnTransform::AllNulls`]: a schema of new nullable columns
+    ///   that start out as all nulls, to be filled in later with
+    ///   [`Self::update`] or [`Self::merge_insert`].  Nothing is written, so
+    ///   this is cheap even for large tables.
+    /// * [`NewColumnTransform::Reader`] or [`NewColumnTransform::Stream`]:
+    ///   the new values for every row, in the same order as the rows are
+    ///   stored.  The values are streamed into new data files, so the
+    ///   backfill does not need to fit in memory.
+    ///
+    /// `read_columns` limits which existing columns are read to compute the
+    /// new values.  Remote tables only support SQL expressions and null
+    /// columns.
     pub async fn add_columns(
         &self,
         transforms: NewColumnTransform,
@@ -982,12 +1705,77 @@ impl Table {
         self.inner.add_columns(transforms, read_columns).await
     }
 
-    /// Change a column's name or nullability.
+    /// Add new columns to the table, filled with default values
+    ///
+    /// Each column is given as a field and a SQL expression for its default,
+    /// such as `0` or `'unknown'`, which can't refer to other columns.  The
+    /// default is evaluated for every existing row and is stored with the
+    /// column, so later adds that don't provide the column get it filled in
+    /// as well.  This is not supported for remote tables.
+    pub async fn add_columns_with_defaults(&self, columns: Vec<(Field, String)>) -> Result<()> {
+        let read_column = self
+            .schema()
+            .await?
+            .fields()
+            .first()
+            .map(|field| field.name().clone());
+        let transform = default_columns_transform(&columns)?;
+        self.inner
+            .add_columns(
+                NewColumnTransform::BatchUDF(transform),
+                Some(read_column.into_iter().collect()),
+            )
+            .await
+    }
+
+    /// Get the default expressions of the columns of the table, by column name
+    pub async fn column_defaults(&self) -> Result<BTreeMap<String, String>> {
+        Ok(defaults_from_schema(self.schema().await?.as_ref()))
+    }
+
+    /// Set or remove the default expression of a column
+    ///
+    /// When data is added without the column, the default is filled in.
+    /// Existing rows are not changed.  Defaults are only supported for local
+    /// tables.
+    pub async fn set_column_default(&self, column: &str, default: Option<&str>) -> Result<()> {
+        let native = self.local(
+            Some(WriteOperation::UpdateMetadata),
+            "setting column defaults",
+        )?;
+        let result = native.set_column_default(column, default).await;
+        self.committed(result, WriteOperation::UpdateMetadata)
+    }
+
+    /// Change a column's name, nullability, or data type.
+    ///
+    /// Renaming a column or making it nullable only changes the table's
+    /// metadata, and any index on the column keeps working under the new
+    /// name.  Changing the data type rewrites the column's data and drops any
+    /// index on the column, which needs to be created again.
+    ///
+    /// ```no_run
+    /// # use arrow_schema::DataType;
+    /// # use lancedb::table::ColumnAlteration;
+    /// # async fn doctest_helper(tbl: lancedb::Table) {
+    /// tbl.alter_columns(&[
+    ///     ColumnAlteration::new("name".to_string()).rename("title".to_string()),
+    ///     ColumnAlteration::new("price".to_string()).cast_to(DataType::Float64),
+    /// ])
+    /// .await
+    /// .unwrap();
+    /// # }
+    /// ```
     pub async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
         self.inner.alter_columns(alterations).await
     }
 
     /// Remove columns from the table.
+    ///
+    /// This only changes the table's metadata, the data is not rewritten.
+    /// Indices on the dropped columns are removed, and the space used by the
+    /// dropped columns is reclaimed by [`Self::optimize`] once the old
+    /// versions are cleaned up.
     pub async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
         self.inner.drop_columns(columns).await
     }
@@ -995,8 +1783,8 @@ impl Table {
     /// Retrieve the version of the table
     ///
     /// LanceDb supports versioning.  Every operation that modifies the table increases
-    /// version.  As long as a version hasn't been deleted you can `[Self::checkout]` that
-    /// version to view the data at that point.  In addition, you can `[Self::restore]` the
+    /// version.  As long as a version hasn't been deleted you can [`Self::checkout`] that
+    /// version to view the data at that point.  In addition, you can [`Self::restore`] the
     /// version to replace the current table with a previous version.
     pub async fn version(&self) -> Result<u64> {
         self.inner.version().await
@@ -1010,12 +1798,12 @@ impl Table {
     ///
     /// This is a read-only operation that turns the table into a sort of "view"
     /// or "detached head".  Other table instances will not be affected.  To make the change
-    /// permanent you can use the `[Self::restore]` method.
+    /// permanent you can use the [`Self::restore`] method.
     ///
     /// Any operation that modifies the table will fail while the table is in a checked
     /// out state.
     ///
-    /// To return the table to a normal state use `[Self::checkout_latest]`
+    /// To return the table to a normal state use [`Self::checkout_latest`]
     pub async fn checkout(&self, version: u64) -> Result<()> {
         self.inner.checkout(version).await
     }
@@ -1023,7 +1811,7 @@ impl Table {
     /// Ensures the table is pointing at the latest version
     ///
     /// This can be used to manually update a table when the read_consistency_interval is None
-    /// It can also be used to undo a `[Self::checkout]` operation
+    /// It can also be used to undo a [`Self::checkout`] operation
     pub async fn checkout_latest(&self) -> Result<()> {
         self.inner.checkout_latest().await
     }
@@ -1047,30 +1835,280 @@ impl Table {
         self.inner.list_versions().await
     }
 
-    /// List all indices that have been created with [`Self::create_index`]
-    pub async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
-        self.inner.list_indices().await
+    /// Get the commits made to the table after `since_version`, oldest first
+    ///
+    /// Each commit is read from the table's history when the stream reaches
+    /// it, and the rows it added can then be streamed, so downstream systems can sync
+    /// incrementally by remembering the last version they applied.  Commits
+    /// made after this is called are not included, see [`Self::listen`].
+    /// Versions removed by [`Self::cleanup_old_versions`] can't be read.
+    ///
+    /// ```no_run
+    /// # use futures::TryStreamExt;
+    /// # use lancedb::table::changes::ChangeKind;
+    /// # async fn doctest_helper(tbl: lancedb::Table, last_synced: u64) -> lancedb::Result<()> {
+    /// let mut changes = tbl.changes(last_synced).await?;
+    /// while let Some(change) = changes.try_next().await? {
+    ///     if let ChangeKind::Append { rows } = change.kind {
+    ///         let batches = rows.stream().await?.try_collect::<Vec<_>>().await?;
+    ///         println!("version {} added {} batches", change.version, batches.len());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// This is only supported for local tables.
+    pub async fn changes(&self, since_version: u64) -> Result<ChangeStream> {
+        let native = self.local(None, "reading changes")?;
+        changes::changes(native.dataset.get().await?.clone(), since_version).await
     }
 
-    /// Get the underlying dataset URI
+    /// Wait for the commits made to the table after `since_version`
     ///
-    /// Warning: This is an internal API and the return value is subject to change.
-    pub fn dataset_uri(&self) -> &str {
-        self.inner.dataset_uri()
+    /// This is like [`Self::changes`] but the stream doesn't end, it checks
+    /// for new commits every `poll_interval` instead.  The stream ends after
+    /// the first error.
+    pub fn listen(&self, since_version: u64, poll_interval: std::time::Duration) -> ChangeStream {
+        changes::listen(self.clone(), since_version, poll_interval)
+    }
+
+    /// Read the commit that created `version`, or `None` if there is no such
+    /// version yet
+    pub(crate) async fn change(&self, version: u64) -> Result<Option<TableChange>> {
+        let native = self.local(None, "reading changes")?;
+        let dataset = native.dataset.get().await?.clone();
+        if dataset.latest_version_id().await? < version {
+            return Ok(None);
+        }
+        Ok(Some(read_change(&dataset, version).await?))
     }
 
-    /// Get statistics about an index.
-    /// Returns None if the index does not exist.
-    pub async fn index_stats(
-        &self,
-        index_name: impl AsRef<str>,
-    ) -> Result<Option<IndexStatistics>> {
+    /// Manage the named tags of the table's versions
+    ///
+    /// ```no_run
+    /// # async fn doctest_helper(tbl: lancedb::Table) {
+    /// let version = tbl.version().await.unwrap();
+    /// tbl.tags().create("v1.0", version).await.unwrap();
+    /// assert_eq!(tbl.tags().get_version("v1.0").await.unwrap(), version);
+    /// # }
+    /// ```
+    ///
+    /// Note: This is not yet available in LanceDB cloud.
+    pub fn tags(&self) -> Tags {
+        Tags::new(self.inner.clone())
+    }
+
+    /// Get statistics about the table
+    ///
+    /// This returns the number of rows, deleted rows and fragments, the size
+    /// of the data overall and for each column, and how many rows each index
+    /// covers.  These come from the table's metadata and do not read the
+    /// data.  The null count, minimum and maximum of columns can be
+    /// calculated as well with [`TableStatsBuilder::column_values`].
+    ///
+    /// ```no_run
+    /// # async fn doctest_helper(tbl: lancedb::Table) -> lancedb::Result<()> {
+    /// let stats = tbl.stats().column_values(["price"]).execute().await?;
+    /// println!("{} rows in {:?} bytes", stats.num_rows, stats.total_bytes);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> TableStatsBuilder {
+        TableStatsBuilder::new(self.inner.clone())
+    }
+
+    /// Rewrite the table in a newer file format
+    ///
+    /// Every row is read and written again in `target_version`, in a single
+    /// commit, so older versions of the table can still be checked out.
+    /// Nothing is written if the table already uses `target_version`, and
+    /// migrating to an older format is an error.  The commit replaces all of
+    /// the data, so indices are dropped and have to be created again, see
+    /// [`migrate::FormatMigration::dropped_indices`].  Other writes can run
+    /// during a migration, but if one is committed before the migration, the
+    /// migration fails with [`crate::Error::CommitConflict`] instead of
+    /// discarding it.  This is only supported for local tables.
+    ///
+    /// ```no_run
+    /// # use lancedb::connection::LanceFileVersion;
+    /// # async fn doctest_helper(tbl: lancedb::Table) -> lancedb::Result<()> {
+    /// tbl.migrate_format(LanceFileVersion::Stable)
+    ///     .progress(|progress| {
+    ///         println!("{}/{} rows", progress.rows_written, progress.total_rows)
+    ///     })
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn migrate_format(&self, target_version: LanceFileVersion) -> MigrateFormatBuilder {
+        MigrateFormatBuilder::new(self.inner.clone(), target_version)
+    }
+
+    /// Set a metadata value on the table
+    ///
+    /// Metadata is a set of arbitrary string key-value pairs (e.g. an owner or a
+    /// description) that is stored with the table. Setting a key that already
+    /// exists overwrites its value. Like any other change, this creates a new
+    /// version of the table.
+    ///
+    /// Keys starting with [`RESERVED_METADATA_PREFIX`] are used by LanceDB
+    /// itself and can't be set.
+    pub async fn set_metadata(
+        &self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<()> {
+        let key = key.into();
+        check_metadata_key(&key)?;
+        self.inner
+            .update_metadata(HashMap::from([(key, value.into())]))
+            .await
+    }
+
+    /// Get a metadata value from the table
+    ///
+    /// Returns `None` if the key has not been set.
+    pub async fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.list_metadata().await?.remove(key))
+    }
+
+    /// List all of the metadata stored with the table
+    ///
+    /// The keys LanceDB uses itself, which start with
+    /// [`RESERVED_METADATA_PREFIX`], are not listed.
+    pub async fn list_metadata(&self) -> Result<HashMap<String, String>> {
+        let mut metadata = self.inner.metadata().await?;
+        metadata.retain(|key, _| !key.starts_with(RESERVED_METADATA_PREFIX));
+        Ok(metadata)
+    }
+
+    /// Remove metadata keys from the table
+    ///
+    /// Keys that are not present are ignored.  Keys starting with
+    /// [`RESERVED_METADATA_PREFIX`] can't be removed.
+    pub async fn delete_metadata(&self, keys: &[&str]) -> Result<()> {
+        for key in keys {
+            check_metadata_key(key)?;
+        }
+        self.inner.delete_metadata(keys).await
+    }
+
+    /// Get the column constraints of the table, by column name
+    pub async fn constraints(&self) -> Result<BTreeMap<String, ColumnConstraint>> {
+        constraints_from_schema(self.schema().await?.as_ref())
+    }
+
+    /// Replace the column constraints of the table
+    ///
+    /// The constraints are checked when data is written, rows already in the
+    /// table are not checked.  Constraints are only supported for local tables.
+    pub async fn set_constraints(
+        &self,
+        constraints: BTreeMap<String, ColumnConstraint>,
+    ) -> Result<()> {
+        let native = self.local(
+            Some(WriteOperation::UpdateMetadata),
+            "setting column constraints",
+        )?;
+        let result = native.set_constraints(constraints).await;
+        self.committed(result, WriteOperation::UpdateMetadata)
+    }
+
+    /// Get the row TTL of the table, if it has one
+    pub async fn ttl(&self) -> Result<Option<Ttl>> {
+        ttl_from_schema(self.schema().await?.as_ref())
+    }
+
+    /// Set or remove the row TTL of the table
+    ///
+    /// Rows are not deleted when they expire, call [`Self::expire_rows`] or
+    /// [`Self::spawn_expiration`] to delete them.  A TTL can only be set on
+    /// local tables.
+    pub async fn set_ttl(&self, ttl: Option<Ttl>) -> Result<()> {
+        let native = self.local(Some(WriteOperation::UpdateMetadata), "setting a row TTL")?;
+        let result = native.set_ttl(ttl).await;
+        self.committed(result, WriteOperation::UpdateMetadata)
+    }
+
+    /// Delete the rows that have expired according to the table's [`Ttl`]
+    ///
+    /// The rows are deleted with a single [`Self::delete_with_count`], so this
+    /// is cheap to call often.  Returns an error if the table has no TTL.
+    pub async fn expire_rows(&self) -> Result<DeleteResult> {
+        let schema = self.schema().await?;
+        let ttl = ttl_from_schema(&schema)?.ok_or_else(|| Error::InvalidInput {
+            message: format!("the table {} has no TTL", self.name()),
+        })?;
+        let filter = ttl.expired_filter(&schema, chrono::Utc::now())?;
+        self.delete_with_count(&filter).await
+    }
+
+    /// Call [`Self::expire_rows`] every `interval` in a background task
+    ///
+    /// Failed sweeps are logged and retried at the next interval.  The task
+    /// runs until the returned [`ExpirationTask`] is stopped or dropped.  This
+    /// must be called from within a tokio runtime.
+    pub fn spawn_expiration(&self, interval: std::time::Duration) -> ExpirationTask {
+        ExpirationTask::spawn(self.clone(), interval)
+    }
+
+    /// Get the partition columns of the table
+    ///
+    /// Partition columns are declared with
+    /// [`crate::connection::CreateTableBuilder::partition_columns`].
+    pub async fn partition_columns(&self) -> Result<Vec<String>> {
+        partition_columns(self.schema().await?.as_ref())
+    }
+
+    /// List all indices that have been created with [`Self::create_index`]
+    ///
+    /// Each index is listed once, with its name, columns, type and the
+    /// version of the table when it was last updated.
+    pub async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
+        self.inner.list_indices().await
+    }
+
+    /// Get the underlying dataset URI
+    ///
+    /// Warning: This is an internal API and the return value is subject to change.
+    pub fn dataset_uri(&self) -> &str {
+        self.inner.dataset_uri()
+    }
+
+    /// Get statistics about an index.
+    /// Returns None if the index does not exist.
+    pub async fn index_stats(
+        &self,
+        index_name: impl AsRef<str>,
+    ) -> Result<Option<IndexStatistics>> {
         self.inner.index_stats(index_name.as_ref()).await
     }
 
+    /// Wait until the indices named `index_names` are ready
+    ///
+    /// Indices are created asynchronously on LanceDB Cloud, and are only
+    /// ready once they exist and cover every row of the table.  Their
+    /// statistics are checked, less and less often, until they are, or
+    /// [`Error::Timeout`] is returned after `timeout`.
+    ///
+    /// Indices of local tables are built by [`Self::create_index`] before it
+    /// returns.  Rows added afterwards are indexed by
+    /// [`OptimizeAction::Index`], or automatically with
+    /// [`crate::database::listing::ListingDatabaseOptions::auto_index`], so
+    /// waiting on a local table only returns once that has happened.
+    pub async fn wait_for_index(
+        &self,
+        index_names: &[&str],
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        self.inner.wait_for_index(index_names, timeout).await
+    }
+
     /// Drop an index from the table.
     ///
-    /// Note: This is not yet available in LanceDB cloud.
+    /// Returns [`Error::IndexNotFound`] if there is no index named `name`.
     ///
     /// This does not delete the index from disk, it just removes it from the table.
     /// To delete the index, run [`Self::optimize()`] after dropping the index.
@@ -1143,12 +2181,68 @@ pub trait NativeTableExt {
     fn as_native(&self) -> Option<&NativeTable>;
 }
 
+/// The prefix of the metadata keys LanceDB stores with a table for its own
+/// use, such as the next value of an auto id column
+pub const RESERVED_METADATA_PREFIX: &str = "lancedb::";
+
+fn check_metadata_key(key: &str) -> Result<()> {
+    if key.starts_with(RESERVED_METADATA_PREFIX) {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "metadata keys starting with {} are reserved for LanceDB, got {}",
+                RESERVED_METADATA_PREFIX, key
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// The local table of `table` for an operation only local tables support
+///
+/// `operation` describes the operation in the error when there is no local
+/// table, such as "reading changes".
+pub(crate) fn local_table<'a>(
+    table: &'a dyn BaseTable,
+    write: Option<WriteOperation>,
+    operation: &str,
+) -> Result<&'a NativeTable> {
+    table
+        .native_table(write)?
+        .ok_or_else(|| Error::NotSupported {
+            message: format!("{} is only supported for local tables", operation),
+        })
+}
+
+/// The error returned by the default implementation of an optional
+/// [`BaseTable`] method
+///
+/// `operation` describes the operation, such as "adding a stream".
+fn not_supported(table_name: &str, operation: &str) -> Error {
+    Error::NotSupported {
+        message: format!("{} is not supported by table {}", operation, table_name),
+    }
+}
+
 impl NativeTableExt for Arc<dyn BaseTable> {
     fn as_native(&self) -> Option<&NativeTable> {
         self.as_any().downcast_ref::<NativeTable>()
     }
 }
 
+/// The longest time between two checks of [`Table::wait_for_index`]
+const MAX_INDEX_WAIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The vector index on a column, see [`NativeTable::vector_index_on`]
+struct VectorIndexInfo {
+    name: String,
+    index_type: crate::index::IndexType,
+    distance_type: Option<DistanceType>,
+}
+
+/// The number of times a native table retries a write that conflicts with a
+/// concurrent write, unless configured otherwise on the connection
+pub const DEFAULT_CONFLICT_RETRIES: u32 = 3;
+
 /// A table in a LanceDB database.
 #[derive(Debug, Clone)]
 pub struct NativeTable {
@@ -1158,6 +2252,25 @@ pub struct NativeTable {
     // This comes from the connection options. We store here so we can pass down
     // to the dataset when we recreate it (for example, in checkout_latest).
     read_consistency_interval: Option<std::time::Duration>,
+    // How many times deletes, updates and compaction are rerun on the latest
+    // version after conflicting with a concurrent write
+    conflict_retries: u32,
+    // The distinct partition values of each fragment, used to prune scans
+    partition_values: Arc<std::sync::Mutex<PartitionValues>>,
+    // The results of recent queries, if enabled when the table was opened
+    query_cache: Option<query_cache::QueryCache>,
+    // Whether written rows are added to the indices after each write
+    auto_index: Option<AutoIndex>,
+    // Coalesces the background runs of `auto_index`
+    auto_index_state: Arc<AutoIndexState>,
+}
+
+/// Whether a background run of [`AutoIndex::Background`] is waiting to start,
+/// and a lock held by the run in progress
+#[derive(Debug, Default)]
+struct AutoIndexState {
+    pending: AtomicBool,
+    running: tokio::sync::Mutex<()>,
 }
 
 impl std::fmt::Display for NativeTable {
@@ -1180,6 +2293,505 @@ impl std::fmt::Display for NativeTable {
 }
 
 impl NativeTable {
+    /// Set how many times a write is retried after conflicting with a
+    /// concurrent write
+    ///
+    /// See [`crate::database::listing::ListingDatabaseOptions::conflict_retries`]
+    pub fn with_conflict_retries(mut self, conflict_retries: u32) -> Self {
+        self.conflict_retries = conflict_retries;
+        self
+    }
+
+    /// Cache up to `size` bytes of the results of recent queries
+    ///
+    /// See [`crate::connection::OpenTableBuilder::query_cache_size`]
+    pub fn with_query_cache_size(mut self, size: usize) -> Self {
+        self.query_cache = (size > 0).then(|| query_cache::QueryCache::new(size));
+        self
+    }
+
+    /// Add the rows written by `add` and `merge_insert` to the indices
+    ///
+    /// See [`crate::database::listing::ListingDatabaseOptions::auto_index`]
+    pub fn with_auto_index(mut self, auto_index: Option<AutoIndex>) -> Self {
+        self.auto_index = auto_index;
+        self
+    }
+
+    /// Add rows that were just written to the indices, if enabled
+    ///
+    /// The write has already been committed, so failures are logged rather
+    /// than returned.  In the background, at most one run is pending at a
+    /// time, and it indexes the rows of every write made while it waited.
+    async fn index_new_rows(&self) {
+        let Some(auto_index) = self.auto_index else {
+            return;
+        };
+        match auto_index {
+            AutoIndex::Inline => self.optimize_new_rows().await,
+            AutoIndex::Background => {
+                if self.auto_index_state.pending.swap(true, Ordering::AcqRel) {
+                    return;
+                }
+                let table = self.clone();
+                tokio::spawn(async move {
+                    let _running = table.auto_index_state.running.lock().await;
+                    table
+                        .auto_index_state
+                        .pending
+                        .store(false, Ordering::Release);
+                    table.optimize_new_rows().await;
+                });
+            }
+        }
+    }
+
+    async fn optimize_new_rows(&self) {
+        let result = async {
+            if self.dataset.get().await?.load_indices().await?.is_empty() {
+                return Ok(());
+            }
+            self.optimize_indices(&OptimizeOptions::append()).await
+        };
+        if let Err(err) = result.await {
+            log::warn!(
+                "LanceDB: failed to index new rows of table {}: {}",
+                self.name,
+                err
+            );
+        }
+    }
+
+    /// Run a write, rerunning it on the latest version of the table if it
+    /// conflicts with a concurrent write
+    ///
+    /// The write must not hold on to the dataset between attempts, since the
+    /// dataset is reloaded before each retry.
+    async fn retry_on_conflict<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut attempts = 0;
+        loop {
+            match op().await {
+                Err(Error::CommitConflict { version, .. }) if attempts < self.conflict_retries => {
+                    attempts += 1;
+                    debug!(
+                        "LanceDB: write to {} conflicted with version {:?}, retrying ({}/{})",
+                        self.name, version, attempts, self.conflict_retries
+                    );
+                    self.dataset.reload().await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// The vector index on `column` of `dataset`, if there is one
+    ///
+    /// Only the statistics of the indices on the column are read, which are
+    /// cached by the dataset's session after the first query.
+    async fn vector_index_on(dataset: &Dataset, column: &str) -> Result<Option<VectorIndexInfo>> {
+        let Some(field) = dataset.schema().field(column) else {
+            return Ok(None);
+        };
+        let mut seen = Vec::new();
+        for index in dataset.load_indices().await?.iter() {
+            // Each delta of an index is listed separately
+            if index.fields != [field.id] || seen.contains(&index.name) {
+                continue;
+            }
+            seen.push(index.name.clone());
+            let stats = dataset.index_statistics(&index.name).await?;
+            let stats: IndexStatisticsImpl =
+                serde_json::from_str(&stats).map_err(|e| Error::Runtime {
+                    message: format!("error deserializing index statistics: {}", e),
+                })?;
+            let first_index = stats.indices.first();
+            let index_type = stats
+                .index_type
+                .or_else(|| first_index.and_then(|index| index.index_type));
+            if let Some(
+                index_type @ (crate::index::IndexType::IvfFlat
+                | crate::index::IndexType::IvfPq
+                | crate::index::IndexType::IvfHnswPq
+                | crate::index::IndexType::IvfHnswSq),
+            ) = index_type
+            {
+                return Ok(Some(VectorIndexInfo {
+                    name: index.name.clone(),
+                    index_type,
+                    distance_type: first_index.and_then(|index| index.metric_type),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Pick the distance type of a vector search on `column`
+    ///
+    /// Binary (uint8) vectors are always compared with the hamming distance,
+    /// which is meaningless for float vectors.  Multivectors default to the
+    /// cosine distance, which MaxSim scoring is based on.  If the query asks for a
+    /// different distance type than the index on the column was built with,
+    /// the index can't be used, which is an error unless the query bypasses
+    /// the index.
+    fn resolve_distance_type(
+        column: &str,
+        is_binary: bool,
+        is_multivector: bool,
+        index: Option<&VectorIndexInfo>,
+        query: &mut VectorQueryRequest,
+    ) -> Result<()> {
+        match (query.distance_type, is_binary) {
+            (None, true) => query.distance_type = Some(DistanceType::Hamming),
+            (None, false) if is_multivector => query.distance_type = Some(DistanceType::Cosine),
+            (Some(DistanceType::Hamming), false) => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the hamming distance needs binary (uint8) vectors, but column {} is not binary",
+                        column
+                    ),
+                });
+            }
+            (Some(distance_type), true) if distance_type != DistanceType::Hamming => {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "column {} has binary (uint8) vectors, which only support the hamming distance, not {}",
+                        column, distance_type
+                    ),
+                });
+            }
+            _ => {}
+        }
+
+        let (Some(distance_type), Some(index)) = (query.distance_type, index) else {
+            return Ok(());
+        };
+        if !query.use_index {
+            return Ok(());
+        }
+        match index.distance_type {
+            Some(index_distance_type) if index_distance_type != distance_type => {
+                Err(Error::InvalidInput {
+                    message: format!(
+                        "the index {} on column {} was built with the {} distance, so it can't search with the {} distance, bypass the index for an exact search",
+                        index.name, column, index_distance_type, distance_type
+                    ),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the ANN parameters of a vector query against the index on `column`
+    ///
+    /// `nprobes` and `refine_factor` must be positive.  `ef` only applies to
+    /// HNSW indices, where it must be at least the limit, and `refine_factor`
+    /// only to quantized ones, so setting them for another kind of index is an
+    /// error.  When the column has no index, or the index is bypassed, `ef` and
+    /// `refine_factor` are ignored.
+    fn check_ann_params(
+        column: &str,
+        index: Option<&VectorIndexInfo>,
+        query: &VectorQueryRequest,
+    ) -> Result<()> {
+        if query.nprobes == 0 {
+            return Err(Error::InvalidInput {
+                message: "nprobes must be at least 1".to_string(),
+            });
+        }
+        if query.refine_factor == Some(0) {
+            return Err(Error::InvalidInput {
+                message: "refine_factor must be at least 1".to_string(),
+            });
+        }
+        let Some(index_type) = index
+            .filter(|_| query.use_index)
+            .map(|index| index.index_type)
+        else {
+            return Ok(());
+        };
+        let is_hnsw = matches!(
+            index_type,
+            crate::index::IndexType::IvfHnswPq | crate::index::IndexType::IvfHnswSq
+        );
+        if let Some(ef) = query.ef {
+            if !is_hnsw {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "ef only applies to HNSW indices, but column {} has an {} index",
+                        column, index_type
+                    ),
+                });
+            }
+            let limit = query.base.limit.unwrap_or(DEFAULT_TOP_K);
+            if ef < limit {
+                return Err(Error::InvalidInput {
+                    message: format!("ef ({}) must be at least the limit ({})", ef, limit),
+                });
+            }
+        }
+        if query.refine_factor.is_some() && index_type == crate::index::IndexType::IvfFlat {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "refine_factor only applies to quantized indices, but column {} has an {} index",
+                    column, index_type
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the new values of an update against the table's constraints
+    async fn check_update_constraints(dataset: &Dataset, update: &UpdateBuilder) -> Result<()> {
+        let constraints = constraints_from_schema(&Schema::from(dataset.schema()))?;
+        let checked = update
+            .columns
+            .iter()
+            .filter(|(column, _)| constraints.contains_key(column))
+            .map(|(column, value)| (column.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        if checked.is_empty() {
+            return Ok(());
+        }
+        // Calculate the new values without writing them
+        let mut scanner = dataset.scan();
+        scanner.project_with_transform(&checked)?.with_row_id();
+        if let Some(filter) = &update.filter {
+            scanner.filter(filter)?;
+        }
+        let mut batches = scanner.try_into_stream().await?;
+        let mut violations = Vec::new();
+        while let Some(batch) = batches.try_next().await? {
+            let row_ids = batch[ROW_ID].as_primitive::<UInt64Type>().clone();
+            for (column, _) in &checked {
+                check_column(
+                    column,
+                    &constraints[*column],
+                    batch[*column].as_ref(),
+                    |index| row_ids.value(index),
+                    &mut violations,
+                )?;
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ConstraintViolation { violations })
+        }
+    }
+
+    /// Delete rows from the table, without retrying on conflicts
+    async fn delete_once(&self, predicate: &str, with_row_ids: bool) -> Result<DeleteResult> {
+        let mut dataset = self.dataset.get_mut().await?;
+        // The matching rows are found on the same version that the delete is
+        // applied to, so the result describes exactly what was deleted
+        let deleted_row_ids = if with_row_ids {
+            let mut scanner = dataset.scan();
+            scanner.filter(predicate)?;
+            scanner.project::<String>(&[])?.with_row_id();
+            let batches = scanner
+                .try_into_stream()
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+            let mut row_ids = Vec::new();
+            for batch in batches {
+                let column = batch.column_by_name(ROW_ID).ok_or_else(|| Error::Runtime {
+                    message: format!("the scan did not return the {} column", ROW_ID),
+                })?;
+                row_ids.extend(column.as_primitive::<UInt64Type>().values().iter().copied());
+            }
+            Some(row_ids)
+        } else {
+            None
+        };
+        let rows_before = fragment_num_rows(&dataset.manifest().fragments);
+        // Fragments written by old versions of Lance don't record how many
+        // rows they have, so the matching rows are counted up front for them
+        let counted = match &deleted_row_ids {
+            Some(row_ids) => Some(row_ids.len() as u64),
+            None if rows_before.values().any(Option::is_none) => {
+                Some(dataset.count_rows(Some(predicate.to_string())).await? as u64)
+            }
+            None => None,
+        };
+        dataset.delete(predicate).await?;
+        let num_deleted_rows = counted.unwrap_or_else(|| {
+            // Only the fragments that existed before the delete are compared,
+            // the commit can include fragments appended concurrently
+            let rows_after = fragment_num_rows(&dataset.manifest().fragments);
+            rows_before
+                .iter()
+                .map(|(id, rows)| {
+                    let rows = rows.unwrap_or(0);
+                    let rows_after = rows_after.get(id).copied().flatten().unwrap_or(0);
+                    rows.saturating_sub(rows_after) as u64
+                })
+                .sum()
+        });
+        Ok(DeleteResult {
+            num_deleted_rows,
+            deleted_row_ids,
+        })
+    }
+
+    /// Plan a query that reads a random sample of the matching rows
+    ///
+    /// See [`Query::sample_fraction`]
+    async fn create_sample_plan(
+        dataset: Arc<Dataset>,
+        query: &QueryRequest,
+        sample: SampleFraction,
+        filter: Option<&QueryFilter>,
+        fragments: Option<Vec<Fragment>>,
+        options: QueryExecutionOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !query.order_by.is_empty() {
+            return Err(Error::InvalidInput {
+                message: "a sampled query can't be sorted, the rows are returned in the order they are stored"
+                    .to_string(),
+            });
+        }
+        let mut scanner = dataset.scan();
+        if let Some(filter) = filter {
+            apply_filter(&mut scanner, filter)?;
+        }
+        if let Some(fragments) = fragments {
+            scanner.with_fragments(fragments);
+        }
+        let row_ids = sample_row_ids(
+            scanner,
+            sample.fraction,
+            sample.seed,
+            query.offset.unwrap_or(0),
+            query.limit,
+        )
+        .await?;
+        let projection = match &query.select {
+            Select::Columns(columns) => ProjectionRequest::from_sql(column_projection(
+                &Schema::from(dataset.schema()),
+                columns,
+                query.missing_columns,
+            )?),
+            Select::Dynamic(select_with_transform) => {
+                ProjectionRequest::from_sql(select_with_transform.clone())
+            }
+            Select::All => ProjectionRequest::from_schema(dataset.schema().clone()),
+        };
+        Ok(Arc::new(
+            SampleExec::try_new(
+                dataset,
+                row_ids,
+                projection,
+                query.with_row_id,
+                query.with_row_address,
+                options.max_batch_length as usize,
+            )
+            .await?,
+        ))
+    }
+
+    /// Apply embeddings and resolve the write parameters for an add
+    async fn prepare_add(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<(WriteParams, Box<dyn RecordBatchReader + Send>)> {
+        let lance_params = Self::add_write_params(&add);
+        let mut data = Box::new(MaybeEmbedded::try_new(
+            data,
+            self.table_definition().await?,
+            add.embedding_registry,
+        )?) as Box<dyn RecordBatchReader + Send>;
+        if let Some(on) = &add.insert_if_absent {
+            if matches!(add.mode, AddDataMode::Overwrite) {
+                return Err(Error::InvalidInput {
+                    message: "insert_if_absent cannot be used when overwriting a table".to_string(),
+                });
+            }
+            data = Box::new(DeduplicatedReader::try_new(data, on)?);
+        }
+
+        Ok((lance_params, data))
+    }
+
+    /// Write the data of an add on top of `read_version`, without committing it
+    ///
+    /// This fills in the defaults and generated columns, and checks the
+    /// constraints, of the table.  Any auto increment ids that were given out
+    /// are reserved before this returns.  The partition values to record once
+    /// the write is committed are returned with it.
+    async fn write_uncommitted(
+        read_version: Arc<Dataset>,
+        lance_params: &WriteParams,
+        data: Box<dyn RecordBatchReader + Send>,
+        insert_if_absent: Option<Vec<String>>,
+    ) -> Result<(LanceTransaction, HashMap<String, String>)> {
+        let schema = Schema::from(read_version.schema());
+        let data = with_defaults(&schema, data)?;
+        let (data, ids) = with_auto_ids(&read_version, data).await?;
+        let (data, violations) = check_constraints(&schema, data)?;
+        let partitioning = partition_columns(&schema)?;
+        let (transaction, partition_values) = match insert_if_absent {
+            Some(on) => Self::insert_if_absent_job(read_version.clone(), on)?
+                .execute_uncommitted(data)
+                .await
+                .map(|(transaction, _stats)| (transaction, HashMap::new()))
+                .map_err(Error::from),
+            None if !partitioning.is_empty() => {
+                write_partitioned(
+                    WriteDestination::Dataset(read_version.clone()),
+                    lance_params,
+                    data,
+                    &partitioning,
+                )
+                .await
+            }
+            None => InsertBuilder::new(read_version.clone())
+                .with_params(lance_params)
+                .execute_uncommitted_stream(data)
+                .await
+                .map(|transaction| (transaction, HashMap::new()))
+                .map_err(Error::from),
+        }
+        .map_err(|err| violations.or_error(err))?;
+        if let Some(ids) = ids {
+            ids.commit(&read_version).await?;
+        }
+        Ok((transaction, partition_values))
+    }
+
+    /// Create a merge insert that only inserts the rows whose keys are not in
+    /// the table, see [`AddDataBuilder::insert_if_absent`]
+    fn insert_if_absent_job(dataset: Arc<Dataset>, on: Vec<String>) -> Result<MergeInsertJob> {
+        let mut builder = LanceMergeInsertBuilder::try_new(dataset, on)?;
+        builder
+            .when_matched(WhenMatched::DoNothing)
+            .when_not_matched(lance::dataset::WhenNotMatched::InsertAll);
+        Ok(builder.try_build()?)
+    }
+
+    fn add_write_params(add: &AddDataBuilder<NoData>) -> WriteParams {
+        let mut lance_params =
+            add.write_options
+                .lance_write_params
+                .clone()
+                .unwrap_or(WriteParams {
+                    mode: match add.mode {
+                        AddDataMode::Append => WriteMode::Append,
+                        AddDataMode::Overwrite => WriteMode::Overwrite,
+                    },
+                    ..Default::default()
+                });
+        if let Some(max_rows_per_file) = add.max_rows_per_file {
+            lance_params.max_rows_per_file = max_rows_per_file;
+        }
+        lance_params
+    }
+
     /// Opens an existing Table
     ///
     /// # Arguments
@@ -1238,6 +2850,11 @@ impl NativeTable {
             uri: uri.to_string(),
             dataset,
             read_consistency_interval,
+            conflict_retries: DEFAULT_CONFLICT_RETRIES,
+            query_cache: None,
+            partition_values: Default::default(),
+            auto_index: None,
+            auto_index_state: Arc::default(),
         })
     }
 
@@ -1286,25 +2903,68 @@ impl NativeTable {
             None => params,
         };
 
-        let insert_builder = InsertBuilder::new(uri).with_params(&params);
-        let dataset = insert_builder
-            .execute_stream(batches)
-            .await
-            .map_err(|e| match e {
-                lance::Error::DatasetAlreadyExists { .. } => Error::TableAlreadyExists {
-                    name: name.to_string(),
-                },
-                source => Error::Lance { source },
-            })?;
+        let partitioning = partition_columns(&batches.arrow_schema())?;
+        let written = if partitioning.is_empty() {
+            let insert_builder = InsertBuilder::new(uri).with_params(&params);
+            insert_builder
+                .execute_stream(batches)
+                .await
+                .map_err(Error::from)
+        } else {
+            Self::create_partitioned(uri, batches, &params, &partitioning).await
+        };
+        let dataset = written.map_err(|e| match e {
+            Error::Lance {
+                source: lance::Error::DatasetAlreadyExists { .. },
+            } => Error::TableAlreadyExists {
+                name: name.to_string(),
+            },
+            e => e,
+        })?;
 
         Ok(Self {
             name: name.to_string(),
             uri: uri.to_string(),
             dataset: DatasetConsistencyWrapper::new_latest(dataset, read_consistency_interval),
             read_consistency_interval,
+            conflict_retries: DEFAULT_CONFLICT_RETRIES,
+            query_cache: None,
+            partition_values: Default::default(),
+            auto_index: None,
+            auto_index_state: Arc::default(),
         })
     }
 
+    /// Write the initial data of a partitioned table, one partition per
+    /// fragment, in a single commit
+    async fn create_partitioned(
+        uri: &str,
+        batches: impl StreamingWriteSource,
+        params: &WriteParams,
+        partitioning: &[String],
+    ) -> Result<Dataset> {
+        // An overwrite records the partition values in its own commit
+        let (transaction, _) =
+            write_partitioned(WriteDestination::Uri(uri), params, batches, partitioning).await?;
+        let mut commit_builder = CommitBuilder::new(uri)
+            .use_move_stable_row_ids(params.enable_move_stable_row_ids)
+            .with_object_store_registry(params.object_store_registry.clone())
+            .enable_v2_manifest_paths(params.enable_v2_manifest_paths);
+        if let Some(store_params) = &params.store_params {
+            commit_builder = commit_builder.with_store_params(store_params.clone());
+        }
+        if let Some(session) = &params.session {
+            commit_builder = commit_builder.with_session(session.clone());
+        }
+        if let Some(commit_handler) = &params.commit_handler {
+            commit_builder = commit_builder.with_commit_handler(commit_handler.clone());
+        }
+        if let Some(storage_format) = params.data_storage_version {
+            commit_builder = commit_builder.with_storage_format(storage_format);
+        }
+        Ok(commit_builder.execute(transaction).await?)
+    }
+
     pub async fn create_empty(
         uri: &str,
         name: &str,
@@ -1327,11 +2987,17 @@ impl NativeTable {
 
     async fn optimize_indices(&self, options: &OptimizeOptions) -> Result<()> {
         info!("LanceDB: optimizing indices: {:?}", options);
-        self.dataset
-            .get_mut()
-            .await?
-            .optimize_indices(options)
-            .await?;
+        let mut dataset = self.dataset.get_mut().await?;
+        if let Some(names) = &options.index_names {
+            let indices = dataset.load_indices().await?;
+            if let Some(name) = names
+                .iter()
+                .find(|name| !indices.iter().any(|index| &index.name == *name))
+            {
+                return Err(Error::IndexNotFound { name: name.clone() });
+            }
+        }
+        dataset.optimize_indices(options).await?;
         Ok(())
     }
 
@@ -1386,9 +3052,13 @@ impl NativeTable {
         options: CompactionOptions,
         remap_options: Option<Arc<dyn IndexRemapperOptions>>,
     ) -> Result<CompactionMetrics> {
-        let mut dataset_mut = self.dataset.get_mut().await?;
-        let metrics = compact_files(&mut dataset_mut, options, remap_options).await?;
-        Ok(metrics)
+        self.retry_on_conflict(|| async {
+            let mut dataset_mut = self.dataset.get_mut().await?;
+            let metrics =
+                compact_files(&mut dataset_mut, options.clone(), remap_options.clone()).await?;
+            Ok(metrics)
+        })
+        .await
     }
 
     // TODO: why are these individual methods and not some single "get_stats" method?
@@ -1419,6 +3089,157 @@ impl NativeTable {
             .collect())
     }
 
+    /// Check that a vector index of type `index_type` with `distance_type` can
+    /// be built on `field`
+    ///
+    /// Binary vectors can only be indexed with IVF_FLAT and the hamming
+    /// distance, and the hamming distance only applies to binary vectors.
+    /// Multivectors can only be indexed with the cosine distance.
+    fn check_vector_index_distance(
+        field: &Field,
+        index_type: crate::index::IndexType,
+        distance_type: DistanceType,
+    ) -> Result<()> {
+        let is_binary = is_binary_vector_data_type(field.data_type());
+        if is_binary && index_type != crate::index::IndexType::IvfFlat {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "An {} index cannot be created on the column `{}` which has binary (uint8) vectors, use an IVF_FLAT index with the hamming distance",
+                    index_type,
+                    field.name()
+                ),
+            });
+        }
+        if matches!(field.data_type(), DataType::List(_)) && distance_type != DistanceType::Cosine {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "An index with the {} distance cannot be created on the column `{}`, multivector columns can only be indexed with the cosine distance",
+                    distance_type,
+                    field.name()
+                ),
+            });
+        }
+        if is_binary != (distance_type == DistanceType::Hamming) {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "An index with the {} distance cannot be created on the column `{}`, the hamming distance is for binary (uint8) vectors and only binary vectors can use it",
+                    distance_type,
+                    field.name()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the build parameters of the IVF partitions and the product
+    /// quantizer of an index on vectors of `dim` dimensions
+    fn check_ivf_pq_params(
+        dim: u32,
+        num_partitions: u32,
+        num_sub_vectors: u32,
+        num_bits: u32,
+    ) -> Result<()> {
+        let invalid = |message: String| Err(Error::InvalidInput { message });
+        if num_partitions == 0 {
+            return invalid("the number of partitions must be positive".to_string());
+        }
+        if num_sub_vectors == 0 || dim % num_sub_vectors != 0 {
+            return invalid(format!(
+                "the number of sub-vectors ({}) must divide the vector dimension ({})",
+                num_sub_vectors, dim
+            ));
+        }
+        if num_bits != 4 && num_bits != 8 {
+            return invalid(format!(
+                "the number of bits of product quantization must be 4 or 8, not {}",
+                num_bits
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the build parameters of an HNSW graph
+    ///
+    /// Each vector is linked to `m` neighbors, which are picked from the
+    /// `ef_construction` candidates found while it is inserted.
+    fn check_hnsw_params(m: u32, ef_construction: u32) -> Result<()> {
+        if m == 0 {
+            return Err(Error::InvalidInput {
+                message: "the number of edges (m) of an HNSW index must be positive".to_string(),
+            });
+        }
+        if ef_construction < m {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "ef_construction ({}) of an HNSW index can't be less than the number of edges (m = {})",
+                    ef_construction, m
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// The total size of the files of all the parts of an index
+    async fn index_size_bytes(&self, dataset: &Dataset, index_name: &str) -> Result<u64> {
+        let object_store = dataset.object_store();
+        let mut size = 0;
+        for index in dataset.load_indices_by_name(index_name).await? {
+            let index_dir = dataset.indices_dir().child(index.uuid.to_string());
+            let mut files = object_store.read_dir_all(&index_dir, None).await?;
+            while let Some(file) = files.try_next().await? {
+                size += file.size as u64;
+            }
+        }
+        Ok(size)
+    }
+
+    /// The build parameters of the IVF partitions of an index on `field`
+    ///
+    /// With an accelerator the partitions are trained here, on the GPU,
+    /// instead of by Lance.
+    async fn ivf_build_params(
+        &self,
+        field: &Field,
+        distance_type: DistanceType,
+        num_partitions: u32,
+        sample_rate: u32,
+        max_iterations: u32,
+        accelerator: Option<&str>,
+    ) -> Result<IvfBuildParams> {
+        let mut params = IvfBuildParams::new(num_partitions as usize);
+        params.sample_rate = sample_rate as usize;
+        params.max_iters = max_iterations as usize;
+        if let Some(accelerator) = accelerator {
+            #[cfg(feature = "gpu")]
+            {
+                let dataset = self.dataset.get().await?;
+                params.centroids = Some(
+                    crate::index::gpu::train_ivf_centroids(
+                        &dataset,
+                        field,
+                        distance_type,
+                        num_partitions as usize,
+                        sample_rate as usize,
+                        max_iterations as usize,
+                        accelerator,
+                    )
+                    .await?,
+                );
+            }
+            #[cfg(not(feature = "gpu"))]
+            {
+                let _ = (field, distance_type);
+                return Err(Error::NotSupported {
+                    message: format!(
+                        "the accelerator {} requires lancedb to be built with the gpu feature",
+                        accelerator
+                    ),
+                });
+            }
+        }
+        Ok(params)
+    }
+
     async fn create_ivf_flat_index(
         &self,
         index: IvfFlatIndexBuilder,
@@ -1434,17 +3255,33 @@ impl NativeTable {
                 ),
             });
         }
+        Self::check_vector_index_distance(
+            field,
+            crate::index::IndexType::IvfFlat,
+            index.distance_type,
+        )?;
 
         let num_partitions = if let Some(n) = index.num_partitions {
             n
         } else {
             suggested_num_partitions(self.count_rows(None).await?)
         };
+        let ivf_params = self
+            .ivf_build_params(
+                field,
+                index.distance_type,
+                num_partitions,
+                index.sample_rate,
+                index.max_iterations,
+                index.accelerator.as_deref(),
+            )
+            .await?;
         let mut dataset = self.dataset.get_mut().await?;
-        let lance_idx_params = lance::index::vector::VectorIndexParams::ivf_flat(
+        let mut lance_idx_params = lance::index::vector::VectorIndexParams::ivf_flat(
             num_partitions as usize,
             index.distance_type.into(),
         );
+        lance_idx_params.stages = vec![lance::index::vector::StageParams::Ivf(ivf_params)];
         dataset
             .create_index(
                 &[field.name()],
@@ -1472,25 +3309,46 @@ impl NativeTable {
                 ),
             });
         }
+        Self::check_vector_index_distance(
+            field,
+            crate::index::IndexType::IvfPq,
+            index.distance_type,
+        )?;
 
+        let dim = infer_vector_dim(field.data_type())? as u32;
         let num_partitions = if let Some(n) = index.num_partitions {
             n
         } else {
             suggested_num_partitions(self.count_rows(None).await?)
         };
-        let num_sub_vectors: u32 = if let Some(n) = index.num_sub_vectors {
-            n
-        } else {
-            let dim = infer_vector_dim(field.data_type())?;
-            suggested_num_sub_vectors(dim as u32)
-        };
+        let num_sub_vectors = index
+            .num_sub_vectors
+            .unwrap_or_else(|| suggested_num_sub_vectors(dim));
+        let num_bits = index.num_bits.unwrap_or(8);
+        Self::check_ivf_pq_params(dim, num_partitions, num_sub_vectors, num_bits)?;
+
+        let ivf_params = self
+            .ivf_build_params(
+                field,
+                index.distance_type,
+                num_partitions,
+                index.sample_rate,
+                index.max_iterations,
+                index.accelerator.as_deref(),
+            )
+            .await?;
         let mut dataset = self.dataset.get_mut().await?;
-        let lance_idx_params = lance::index::vector::VectorIndexParams::ivf_pq(
-            num_partitions as usize,
-            /*num_bits=*/ 8,
-            num_sub_vectors as usize,
+        let pq_params = PQBuildParams {
+            num_sub_vectors: num_sub_vectors as usize,
+            num_bits: num_bits as usize,
+            max_iters: index.max_iterations as usize,
+            sample_rate: index.sample_rate as usize,
+            ..Default::default()
+        };
+        let lance_idx_params = lance::index::vector::VectorIndexParams::with_ivf_pq_params(
             index.distance_type.into(),
-            index.max_iterations as usize,
+            ivf_params,
+            pq_params,
         );
         dataset
             .create_index(
@@ -1519,42 +3377,46 @@ impl NativeTable {
                 ),
             });
         }
+        Self::check_vector_index_distance(
+            field,
+            crate::index::IndexType::IvfHnswPq,
+            index.distance_type,
+        )?;
+        Self::check_hnsw_params(index.m, index.ef_construction)?;
 
         let num_partitions: u32 = if let Some(n) = index.num_partitions {
             n
         } else {
-            match field.data_type() {
-                arrow_schema::DataType::FixedSizeList(_, n) => Ok::<u32, Error>(
-                    suggested_num_partitions_for_hnsw(self.count_rows(None).await?, *n as u32),
-                ),
-                _ => Err(Error::Schema {
-                    message: format!("Column '{}' is not a FixedSizeList", field.name()),
-                }),
-            }?
-        };
-
-        let num_sub_vectors: u32 = if let Some(n) = index.num_sub_vectors {
-            n
-        } else {
-            match field.data_type() {
-                arrow_schema::DataType::FixedSizeList(_, n) => {
-                    Ok::<u32, Error>(suggested_num_sub_vectors(*n as u32))
-                }
-                _ => Err(Error::Schema {
-                    message: format!("Column '{}' is not a FixedSizeList", field.name()),
-                }),
-            }?
+            let dim = infer_vector_dim(field.data_type())?;
+            suggested_num_partitions_for_hnsw(self.count_rows(None).await?, dim as u32)
         };
 
+        let dim = infer_vector_dim(field.data_type())? as u32;
+        let num_sub_vectors = index
+            .num_sub_vectors
+            .unwrap_or_else(|| suggested_num_sub_vectors(dim));
+        let num_bits = index.num_bits.unwrap_or(8);
+        Self::check_ivf_pq_params(dim, num_partitions, num_sub_vectors, num_bits)?;
+
+        let ivf_params = self
+            .ivf_build_params(
+                field,
+                index.distance_type,
+                num_partitions,
+                index.sample_rate,
+                index.max_iterations,
+                index.accelerator.as_deref(),
+            )
+            .await?;
         let mut dataset = self.dataset.get_mut().await?;
-        let mut ivf_params = IvfBuildParams::new(num_partitions as usize);
-        ivf_params.sample_rate = index.sample_rate as usize;
-        ivf_params.max_iters = index.max_iterations as usize;
         let hnsw_params = HnswBuildParams::default()
             .num_edges(index.m as usize)
             .ef_construction(index.ef_construction as usize);
         let pq_params = PQBuildParams {
             num_sub_vectors: num_sub_vectors as usize,
+            num_bits: num_bits as usize,
+            max_iters: index.max_iterations as usize,
+            sample_rate: index.sample_rate as usize,
             ..Default::default()
         };
         let lance_idx_params = lance::index::vector::VectorIndexParams::with_ivf_hnsw_pq_params(
@@ -1590,24 +3452,31 @@ impl NativeTable {
                 ),
             });
         }
+        Self::check_vector_index_distance(
+            field,
+            crate::index::IndexType::IvfHnswSq,
+            index.distance_type,
+        )?;
+        Self::check_hnsw_params(index.m, index.ef_construction)?;
 
         let num_partitions: u32 = if let Some(n) = index.num_partitions {
             n
         } else {
-            match field.data_type() {
-                arrow_schema::DataType::FixedSizeList(_, n) => Ok::<u32, Error>(
-                    suggested_num_partitions_for_hnsw(self.count_rows(None).await?, *n as u32),
-                ),
-                _ => Err(Error::Schema {
-                    message: format!("Column '{}' is not a FixedSizeList", field.name()),
-                }),
-            }?
+            let dim = infer_vector_dim(field.data_type())?;
+            suggested_num_partitions_for_hnsw(self.count_rows(None).await?, dim as u32)
         };
 
+        let ivf_params = self
+            .ivf_build_params(
+                field,
+                index.distance_type,
+                num_partitions,
+                index.sample_rate,
+                index.max_iterations,
+                index.accelerator.as_deref(),
+            )
+            .await?;
         let mut dataset = self.dataset.get_mut().await?;
-        let mut ivf_params = IvfBuildParams::new(num_partitions as usize);
-        ivf_params.sample_rate = index.sample_rate as usize;
-        ivf_params.max_iters = index.max_iterations as usize;
         let hnsw_params = HnswBuildParams::default()
             .num_edges(index.m as usize)
             .ef_construction(index.ef_construction as usize);
@@ -1634,7 +3503,15 @@ impl NativeTable {
     }
 
     async fn create_auto_index(&self, field: &Field, opts: IndexBuilder) -> Result<()> {
-        if supported_vector_data_type(field.data_type()) {
+        if is_binary_vector_data_type(field.data_type()) {
+            let index = IvfFlatIndexBuilder::default().distance_type(DistanceType::Hamming);
+            self.create_ivf_flat_index(index, field, opts.replace).await
+        } else if matches!(field.data_type(), DataType::List(_))
+            && supported_vector_data_type(field.data_type())
+        {
+            let index = IvfPqIndexBuilder::default().distance_type(DistanceType::Cosine);
+            self.create_ivf_pq_index(index, field, opts.replace).await
+        } else if supported_vector_data_type(field.data_type()) {
             self.create_ivf_pq_index(IvfPqIndexBuilder::default(), field, opts.replace)
                 .await
         } else if supported_btree_data_type(field.data_type()) {
@@ -1747,6 +3624,14 @@ impl NativeTable {
             });
         }
 
+        // Fail before reading the column if the tokenizer can't be built
+        fts_opts
+            .tokenizer_configs
+            .build()
+            .map_err(|err| Error::InvalidInput {
+                message: format!("invalid tokenizer configuration: {}", err),
+            })?;
+
         let mut dataset = self.dataset.get_mut().await?;
         let fts_params = lance_index::scalar::InvertedIndexParams {
             with_position: fts_opts.with_position,
@@ -1805,6 +3690,14 @@ impl NativeTable {
         Ok(())
     }
 
+    /// Checks out the version of the table that a tag points to
+    ///
+    /// See [`Table::checkout`]
+    pub async fn checkout_tag(&self, tag: &str) -> Result<()> {
+        let version = self.tag_version(tag).await?;
+        self.dataset.as_time_travel(version).await
+    }
+
     /// Get the table manifest
     pub async fn manifest(&self) -> Result<Manifest> {
         let dataset = self.dataset.get().await?;
@@ -1838,6 +3731,104 @@ impl NativeTable {
         Ok(())
     }
 
+    /// Replace the column constraints of the table
+    pub async fn set_constraints(
+        &self,
+        constraints: BTreeMap<String, ColumnConstraint>,
+    ) -> Result<()> {
+        let mut dataset = self.dataset.get_mut().await?;
+        let schema = Schema::from(dataset.schema());
+        if let Some(column) = constraints
+            .keys()
+            .find(|column| schema.column_with_name(column).is_none())
+        {
+            return Err(Error::InvalidInput {
+                message: format!("cannot constrain {}, the table has no such column", column),
+            });
+        }
+        let value = (!constraints.is_empty()).then(|| constraints_metadata(&constraints).1);
+        Self::set_schema_metadata_key(&mut dataset, CONSTRAINTS_METADATA_KEY, value).await
+    }
+
+    /// Move the column constraints of the table to the new names of their
+    /// columns
+    ///
+    /// `new_name` returns the new name of a column, or None if it was dropped.
+    /// Lance commits the schema change and the constraints separately.  The
+    /// schema change is already committed, so if the constraints conflict with
+    /// a concurrent write they are moved again on the latest version, rather
+    /// than being left on the old columns.
+    async fn update_constraints(
+        &self,
+        dataset: &mut Dataset,
+        new_name: impl Fn(&str) -> Option<String>,
+    ) -> Result<()> {
+        let mut attempts = 0;
+        loop {
+            let constraints = constraints_from_schema(&Schema::from(dataset.schema()))?;
+            let updated = constraints
+                .iter()
+                .filter_map(|(column, constraint)| Some((new_name(column)?, constraint.clone())))
+                .collect::<BTreeMap<_, _>>();
+            if updated == constraints {
+                return Ok(());
+            }
+            let value = (!updated.is_empty()).then(|| constraints_metadata(&updated).1);
+            match Self::set_schema_metadata_key(dataset, CONSTRAINTS_METADATA_KEY, value).await {
+                Err(Error::CommitConflict { .. }) if attempts < self.conflict_retries => {
+                    attempts += 1;
+                    dataset.checkout_latest().await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Set or remove the row TTL of the table
+    pub async fn set_ttl(&self, ttl: Option<Ttl>) -> Result<()> {
+        let mut dataset = self.dataset.get_mut().await?;
+        if let Some(ttl) = &ttl {
+            validate_ttl(&Schema::from(dataset.schema()), ttl)?;
+        }
+        let value = ttl.as_ref().map(|ttl| ttl_metadata(ttl).1);
+        Self::set_schema_metadata_key(&mut dataset, TTL_METADATA_KEY, value).await
+    }
+
+    /// Set or remove one key of the schema metadata
+    async fn set_schema_metadata_key(
+        dataset: &mut Dataset,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<()> {
+        // Lance replaces all of the schema metadata, so keep the other keys
+        let mut metadata = dataset.schema().metadata.clone();
+        match value {
+            Some(value) => metadata.insert(key.to_string(), value),
+            None => metadata.remove(key),
+        };
+        dataset.replace_schema_metadata(metadata).await?;
+        Ok(())
+    }
+
+    /// Set or remove the default expression of a column
+    pub async fn set_column_default(&self, column: &str, default: Option<&str>) -> Result<()> {
+        let mut dataset = self.dataset.get_mut().await?;
+        let field = dataset
+            .schema()
+            .field(column)
+            .ok_or_else(|| Error::InvalidInput {
+                message: format!("the table has no column {}", column),
+            })?;
+        let id = field.id as u32;
+        let field = Field::from(field);
+        if let Some(default) = default {
+            validate_defaults([(&field, default)])?;
+        }
+        let metadata = default_field_metadata(&field, default);
+        dataset.replace_field_metadata([(id, metadata)]).await?;
+        Ok(())
+    }
+
     /// Update field metadata
     ///
     /// # Arguments:
@@ -1884,6 +3875,73 @@ impl BaseTable for NativeTable {
         Ok(self.dataset.get().await?.versions().await?)
     }
 
+    async fn metadata(&self) -> Result<HashMap<String, String>> {
+        Ok(self.dataset.get().await?.manifest().config.clone())
+    }
+
+    async fn update_metadata(&self, values: HashMap<String, String>) -> Result<()> {
+        self.update_config(values).await
+    }
+
+    async fn delete_metadata(&self, keys: &[&str]) -> Result<()> {
+        self.delete_config_keys(keys).await
+    }
+
+    async fn stats(&self) -> Result<TableStatistics> {
+        let dataset = Arc::new(self.dataset.get().await?.clone());
+        let data_stats = dataset.calculate_data_stats().await?;
+        // Field sizes are only recorded by the v2 file format
+        let has_sizes = !dataset.manifest().should_use_legacy_format();
+        let bytes_by_field = data_stats
+            .fields
+            .iter()
+            .map(|field| (field.id as i32, field.bytes_on_disk))
+            .collect::<HashMap<_, _>>();
+        let columns = dataset
+            .schema()
+            .fields
+            .iter()
+            .map(|field| ColumnStatistics {
+                name: field.name.clone(),
+                bytes_on_disk: has_sizes.then(|| field_bytes(field, &bytes_by_field)),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        Ok(TableStatistics {
+            num_rows: dataset.count_rows(None).await?,
+            num_deleted_rows: Some(dataset.count_deleted_rows().await?),
+            num_fragments: Some(dataset.count_fragments()),
+            total_bytes: has_sizes.then(|| bytes_by_field.values().sum()),
+            columns,
+            indices: index_coverage(self).await?,
+        })
+    }
+
+    async fn list_tags(&self) -> Result<HashMap<String, TagContents>> {
+        Ok(self.dataset.get().await?.tags.list().await?)
+    }
+
+    async fn tag_version(&self, tag: &str) -> Result<u64> {
+        Ok(self.dataset.get().await?.tags.get_version(tag).await?)
+    }
+
+    // Tags don't change the data, so they can be managed while a version is
+    // checked out
+    async fn create_tag(&self, tag: &str, version: u64) -> Result<()> {
+        let mut tags = self.dataset.get().await?.tags.clone();
+        Ok(tags.create(tag, version).await?)
+    }
+
+    async fn update_tag(&self, tag: &str, version: u64) -> Result<()> {
+        let mut tags = self.dataset.get().await?.tags.clone();
+        Ok(tags.update(tag, version).await?)
+    }
+
+    async fn delete_tag(&self, tag: &str) -> Result<()> {
+        let mut tags = self.dataset.get().await?.tags.clone();
+        Ok(tags.delete(tag).await?)
+    }
+
     async fn restore(&self) -> Result<()> {
         let version =
             self.dataset
@@ -1921,9 +3979,15 @@ impl BaseTable for NativeTable {
         match filter {
             None => Ok(dataset.count_rows(None).await?),
             Some(Filter::Sql(sql)) => Ok(dataset.count_rows(Some(sql)).await?),
-            Some(Filter::Datafusion(_)) => Err(Error::NotSupported {
-                message: "Datafusion filters are not yet supported".to_string(),
-            }),
+            Some(Filter::Datafusion(expr)) => {
+                let mut scanner = dataset.scan();
+                scanner.filter_expr(expr);
+                Ok(scanner
+                    .project::<String>(&[])?
+                    .with_row_id()
+                    .count_rows()
+                    .await? as usize)
+            }
         }
     }
 
@@ -1932,30 +3996,96 @@ impl BaseTable for NativeTable {
         add: AddDataBuilder<NoData>,
         data: Box<dyn RecordBatchReader + Send>,
     ) -> Result<()> {
-        let data = Box::new(MaybeEmbedded::try_new(
-            data,
-            self.table_definition().await?,
-            add.embedding_registry,
-        )?) as Box<dyn RecordBatchReader + Send>;
-
-        let lance_params = add.write_options.lance_write_params.unwrap_or(WriteParams {
-            mode: match add.mode {
-                AddDataMode::Append => WriteMode::Append,
-                AddDataMode::Overwrite => WriteMode::Overwrite,
-            },
-            ..Default::default()
-        });
+        let insert_if_absent = add.insert_if_absent.clone();
+        let (lance_params, data) = self.prepare_add(add, data).await?;
 
         let dataset = {
             // Limited scope for the mutable borrow of self.dataset avoids deadlock.
             let ds = self.dataset.get_mut().await?;
-            InsertBuilder::new(Arc::new(ds.clone()))
-                .with_params(&lance_params)
-                .execute_stream(data)
+            // The ids are chosen while holding the lock, so that concurrent
+            // adds through this table can't choose the same ids
+            let read_version = Arc::new(ds.clone());
+            let (transaction, partition_values) = Self::write_uncommitted(
+                read_version.clone(),
+                &lance_params,
+                data,
+                insert_if_absent,
+            )
+            .await?;
+            record_partition_values(&read_version, partition_values).await?;
+            CommitBuilder::new(read_version)
+                .execute(transaction)
+                .await?
+        };
+
+        self.dataset.set_latest(dataset).await;
+        self.index_new_rows().await;
+        Ok(())
+    }
+
+    async fn add_stream(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: SendableRecordBatchStream,
+    ) -> Result<()> {
+        let has_generated_columns = self
+            .table_definition()
+            .await?
+            .column_definitions
+            .iter()
+            .any(|column| !matches!(column.kind, ColumnKind::Physical));
+        if has_generated_columns {
+            return Err(Error::NotSupported {
+                message:
+                    "adding a stream to a table with embedding or auto id columns, use Table::add"
+                        .to_string(),
+            });
+        }
+        if add.insert_if_absent.is_some() {
+            return Err(Error::NotSupported {
+                message: "insert_if_absent when adding a stream, use Table::add".to_string(),
+            });
+        }
+        let schema = self.schema().await?;
+        if !constraints_from_schema(&schema)?.is_empty() {
+            return Err(Error::NotSupported {
+                message: "adding a stream to a table with constraints, use Table::add".to_string(),
+            });
+        }
+        if !partition_columns(&schema)?.is_empty() {
+            return Err(Error::NotSupported {
+                message: "adding a stream to a partitioned table, use Table::add".to_string(),
+            });
+        }
+        let stream_schema = data.schema();
+        if defaults_from_schema(&schema)
+            .keys()
+            .any(|column| stream_schema.column_with_name(column).is_none())
+        {
+            return Err(Error::NotSupported {
+                message: "adding a stream that is missing columns with defaults, use Table::add"
+                    .to_string(),
+            });
+        }
+        let lance_params = Self::add_write_params(&add);
+
+        // The stream may take a long time to consume, so its data files are
+        // written without holding the dataset, which is only locked to commit
+        self.dataset.ensure_mutable().await?;
+        let read_version = Arc::new(self.dataset.get().await?.clone());
+        let transaction = InsertBuilder::new(read_version.clone())
+            .with_params(&lance_params)
+            .execute_uncommitted_stream(data.into_df_stream())
+            .await?;
+        let dataset = {
+            let _ds = self.dataset.get_mut().await?;
+            CommitBuilder::new(read_version)
+                .execute(transaction)
                 .await?
         };
 
         self.dataset.set_latest(dataset).await;
+        self.index_new_rows().await;
         Ok(())
     }
 
@@ -1993,27 +4123,35 @@ impl BaseTable for NativeTable {
 
     async fn drop_index(&self, index_name: &str) -> Result<()> {
         let mut dataset = self.dataset.get_mut().await?;
-        dataset.drop_index(index_name).await?;
-        Ok(())
+        dataset.drop_index(index_name).await.map_err(|e| match e {
+            lance::Error::IndexNotFound { .. } => Error::IndexNotFound {
+                name: index_name.to_string(),
+            },
+            source => Error::Lance { source },
+        })
     }
 
     async fn update(&self, update: UpdateBuilder) -> Result<u64> {
-        let dataset = self.dataset.get().await?.clone();
-        let mut builder = LanceUpdateBuilder::new(Arc::new(dataset));
-        if let Some(predicate) = update.filter {
-            builder = builder.update_where(&predicate)?;
-        }
+        self.retry_on_conflict(|| async {
+            let dataset = self.dataset.get().await?.clone();
+            Self::check_update_constraints(&dataset, &update).await?;
+            let mut builder = LanceUpdateBuilder::new(Arc::new(dataset));
+            if let Some(predicate) = &update.filter {
+                builder = builder.update_where(predicate)?;
+            }
 
-        for (column, value) in update.columns {
-            builder = builder.set(column, &value)?;
-        }
+            for (column, value) in &update.columns {
+                builder = builder.set(column, value)?;
+            }
 
-        let operation = builder.build()?;
-        let res = operation.execute().await?;
-        self.dataset
-            .set_latest(res.new_dataset.as_ref().clone())
-            .await;
-        Ok(res.rows_updated)
+            let operation = builder.build()?;
+            let res = operation.execute().await?;
+            self.dataset
+                .set_latest(res.new_dataset.as_ref().clone())
+                .await;
+            Ok(res.rows_updated)
+        })
+        .await
     }
 
     async fn create_plan(
@@ -2021,14 +4159,18 @@ impl BaseTable for NativeTable {
         query: &AnyQuery,
         options: QueryExecutionOptions,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let query = match query {
+        let mut query = match query {
             AnyQuery::VectorQuery(query) => query.clone(),
             AnyQuery::Query(query) => VectorQueryRequest::from_plain_query(query.clone()),
         };
 
         let ds_ref = self.dataset.get().await?;
         let schema = ds_ref.schema();
+        check_meta_columns(&query, &Schema::from(schema))?;
         let mut column = query.column.clone();
+        if let (Some(column), Some(query_vector)) = (&column, query.query_vector.first()) {
+            validate_vector_column(&Schema::from(schema), column, query_vector.len())?;
+        }
 
         let mut query_vector = query.query_vector.first().cloned();
         if query.query_vector.len() > 1 {
@@ -2064,6 +4206,14 @@ impl BaseTable for NativeTable {
                     fsl_builder.append(true);
                 }
                 query_vector = Some(Arc::new(fsl_builder.finish()));
+            } else if query.multivector {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "a multivector query needs a multivector column, but column {} has type {}",
+                        column.as_ref().unwrap(),
+                        vector_field.data_type()
+                    ),
+                });
             } else {
                 // If there are multiple query vectors, create a plan for each of them and union them.
                 let query_vecs = query.query_vector.clone();
@@ -2085,6 +4235,8 @@ impl BaseTable for NativeTable {
         }
 
         let mut scanner: Scanner = ds_ref.scan();
+        // Only plain scans can be restricted to some fragments
+        let is_plain_scan = query_vector.is_none() && query.base.full_text_search.is_none();
 
         if let Some(query_vector) = query_vector {
             // If there is a vector query, default to limit=10 if unspecified
@@ -2096,8 +4248,40 @@ impl BaseTable for NativeTable {
                 default_vector_column(&arrow_schema, Some(query_vector.len() as i32))?
             };
 
-            let (_, element_type) = lance::index::vector::utils::get_vector_type(schema, &column)?;
+            let (vector_type, element_type) =
+                lance::index::vector::utils::get_vector_type(schema, &column)?;
             let is_binary = matches!(element_type, DataType::UInt8);
+            let is_multivector = matches!(vector_type, DataType::List(_));
+            if query.multivector && !is_multivector {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "a multivector query needs a multivector column, but column {} has type {}",
+                        column, vector_type
+                    ),
+                });
+            }
+            // The index only matters for searches that may use it with a
+            // distance type or ANN parameters other than its own
+            let index = if query.use_index
+                && (query.distance_type.is_some()
+                    || is_binary
+                    || is_multivector
+                    || query.ef.is_some()
+                    || query.refine_factor.is_some())
+            {
+                Self::vector_index_on(&ds_ref, &column).await?
+            } else {
+                None
+            };
+            Self::resolve_distance_type(
+                &column,
+                is_binary,
+                is_multivector,
+                index.as_ref(),
+                &mut query,
+            )?;
+            Self::check_ann_params(&column, index.as_ref(), &query)?;
+
             if is_binary {
                 let query_vector = arrow::compute::cast(&query_vector, &DataType::UInt8)?;
                 let query_vector = query_vector.as_primitive::<UInt8Type>();
@@ -2114,6 +4298,30 @@ impl BaseTable for NativeTable {
                 )?;
             }
         }
+        if !query.base.order_by.is_empty() {
+            if !is_plain_scan {
+                return Err(Error::InvalidInput {
+                    message: "only plain queries can be sorted, vector and full text searches are sorted by relevance"
+                        .to_string(),
+                });
+            }
+            let ordering = query
+                .base
+                .order_by
+                .iter()
+                .map(|(column, order)| match order {
+                    SortOrder::Asc => ColumnOrdering::asc_nulls_last(column.clone()),
+                    SortOrder::Desc => ColumnOrdering::desc_nulls_last(column.clone()),
+                })
+                .collect();
+            scanner.order_by(Some(ordering))?;
+        }
+        if query.base.sample.is_some() && !is_plain_scan {
+            return Err(Error::InvalidInput {
+                message: "only plain queries can be sampled, not vector or full text searches"
+                    .to_string(),
+            });
+        }
         scanner.limit(
             query.base.limit.map(|limit| limit as i64),
             query.base.offset.map(|offset| offset as i64),
@@ -2122,12 +4330,26 @@ impl BaseTable for NativeTable {
         if let Some(ef) = query.ef {
             scanner.ef(ef);
         }
+        if let (Some(lower_bound), Some(upper_bound)) = (query.lower_bound, query.upper_bound) {
+            if lower_bound >= upper_bound {
+                return Err(Error::InvalidInput {
+                    message: format!(
+                        "the distance range [{}, {}) is empty",
+                        lower_bound, upper_bound
+                    ),
+                });
+            }
+        }
         scanner.distance_range(query.lower_bound, query.upper_bound);
         scanner.use_index(query.use_index);
         scanner.prefilter(query.base.prefilter);
         match query.base.select {
             Select::Columns(ref columns) => {
-                scanner.project(columns.as_slice())?;
+                scanner.project_with_transform(&column_projection(
+                    &Schema::from(ds_ref.schema()),
+                    columns,
+                    query.base.missing_columns,
+                )?)?;
             }
             Select::Dynamic(ref select_with_transform) => {
                 scanner.project_with_transform(select_with_transform.as_slice())?;
@@ -2139,15 +4361,38 @@ impl BaseTable for NativeTable {
             scanner.with_row_id();
         }
 
+        if query.base.with_row_address {
+            scanner.with_row_address();
+        }
+
         scanner.batch_size(options.max_batch_length as usize);
 
         if query.base.fast_search {
             scanner.fast_search();
         }
 
+        match &query.base.materialization {
+            Materialization::Auto => {}
+            Materialization::AllEarly => {
+                scanner.materialization_style(MaterializationStyle::AllEarly);
+            }
+            Materialization::AllLate => {
+                scanner.materialization_style(MaterializationStyle::AllLate);
+            }
+            Materialization::Late(columns) => {
+                scanner.materialization_style(MaterializationStyle::all_early_except(
+                    columns, schema,
+                )?);
+            }
+        }
+
         match &query.base.select {
             Select::Columns(select) => {
-                scanner.project(select.as_slice())?;
+                scanner.project_with_transform(&column_projection(
+                    &Schema::from(ds_ref.schema()),
+                    select,
+                    query.base.missing_columns,
+                )?)?;
             }
             Select::Dynamic(select_with_transform) => {
                 scanner.project_with_transform(select_with_transform.as_slice())?;
@@ -2155,18 +4400,32 @@ impl BaseTable for NativeTable {
             Select::All => { /* Do nothing */ }
         }
 
-        if let Some(filter) = &query.base.filter {
-            match filter {
-                QueryFilter::Sql(sql) => {
-                    scanner.filter(sql)?;
-                }
-                QueryFilter::Substrait(substrait) => {
-                    scanner.filter_substrait(substrait)?;
-                }
-                QueryFilter::Datafusion(expr) => {
-                    scanner.filter_expr(expr.clone());
-                }
+        let filter = match (&query.base.filter, query.base.null_comparison) {
+            (Some(filter), NullComparison::False) => Some(compare_nulls_as_false(&ds_ref, filter)?),
+            (filter, _) => filter.clone(),
+        };
+        let fragments = match &filter {
+            Some(filter) if is_plain_scan => {
+                prune_fragments(&self.partition_values, &ds_ref, filter).await?
             }
+            _ => None,
+        };
+        if let Some(sample) = query.base.sample {
+            return Self::create_sample_plan(
+                Arc::new(ds_ref.clone()),
+                &query.base,
+                sample,
+                filter.as_ref(),
+                fragments,
+                options,
+            )
+            .await;
+        }
+        if let Some(filter) = &filter {
+            apply_filter(&mut scanner, filter)?;
+        }
+        if let Some(fragments) = fragments {
+            scanner.with_fragments(fragments);
         }
 
         if let Some(fts) = &query.base.full_text_search {
@@ -2181,7 +4440,26 @@ impl BaseTable for NativeTable {
             scanner.distance_metric(distance_type.into());
         }
 
-        Ok(scanner.create_plan().await?)
+        let plan = scanner.create_plan().await?;
+        // Lance sorts every matching row before the limit is applied, so the
+        // sort is told how many rows can be returned
+        let plan = match query.base.limit {
+            Some(limit) if !query.base.order_by.is_empty() => {
+                limit_sort(plan, limit + query.base.offset.unwrap_or(0))?
+            }
+            _ => plan,
+        };
+        if query.require_index_prefilter && query.base.filter.is_some() {
+            if !query.base.prefilter {
+                return Err(Error::InvalidInput {
+                    message:
+                        "a postfiltered query cannot push its filter into the vector index search"
+                            .to_string(),
+                });
+            }
+            check_index_prefilter(plan.as_ref())?;
+        }
+        Ok(plan)
     }
 
     async fn query(
@@ -2189,7 +4467,157 @@ impl BaseTable for NativeTable {
         query: &AnyQuery,
         options: QueryExecutionOptions,
     ) -> Result<DatasetRecordBatchStream> {
-        self.generic_query(query, options).await
+        let Some(cache) = self
+            .query_cache
+            .as_ref()
+            .filter(|_| query_cache::QueryCache::is_cacheable(query))
+        else {
+            return self.generic_query(query, options).await;
+        };
+        let version = self.dataset.get().await?.version().version;
+        let key = query_cache::QueryCache::key(query, &options, version);
+        if let Some(results) = cache.get(&key).await {
+            return Ok(results.into_stream());
+        }
+        let stream = self.generic_query(query, options).await?;
+        let results =
+            match query_cache::CachedResults::collect(stream, cache.max_entry_size()).await? {
+                query_cache::Collected::Results(results) => results,
+                // Results too large to cache are streamed
+                query_cache::Collected::TooLarge(stream) => return Ok(stream),
+            };
+        // A commit while the query ran may have changed what it read
+        if self.dataset.get().await?.version().version == version {
+            cache.insert(key, results.clone()).await;
+        }
+        Ok(results.into_stream())
+    }
+
+    async fn stage_add(
+        &self,
+        add: AddDataBuilder<NoData>,
+        data: Box<dyn RecordBatchReader + Send>,
+    ) -> Result<StagedWrite> {
+        let insert_if_absent = add.insert_if_absent.clone();
+        let (lance_params, data) = self.prepare_add(add, data).await?;
+        let read_version = Arc::new(self.dataset.get().await?.clone());
+        let (transaction, partition_values) =
+            Self::write_uncommitted(read_version.clone(), &lance_params, data, insert_if_absent)
+                .await?;
+        Ok(StagedWrite {
+            read_version,
+            transaction,
+            partition_values,
+        })
+    }
+
+    async fn check_staged(&self, write: &StagedWrite) -> Result<()> {
+        let read_version = write.read_version.version().version;
+        let latest = write.read_version.latest_version_id().await?;
+        for version in read_version + 1..=latest {
+            let committed = write
+                .read_version
+                .checkout_version(version)
+                .await?
+                .read_transaction()
+                .await?;
+            // Without its transaction file, a commit can't be checked
+            if committed.map_or(true, |committed| {
+                write.transaction.conflicts_with(&committed)
+            }) {
+                return Err(Error::CommitConflict {
+                    version: Some(version),
+                    message: format!(
+                        "the staged write to table {} conflicts with a concurrent write",
+                        self.name
+                    ),
+                    request_id: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn commit_staged(&self, write: StagedWrite) -> Result<u64> {
+        record_partition_values(&write.read_version, write.partition_values).await?;
+        let dataset = CommitBuilder::new(write.read_version)
+            .execute(write.transaction)
+            .await?;
+        let version = dataset.version().version;
+        self.dataset.set_latest(dataset).await;
+        Ok(version)
+    }
+
+    async fn revert_staged(&self, version: u64) -> Result<()> {
+        let committed = self.dataset.get().await?.checkout_version(version).await?;
+        let previous = committed.checkout_version(version - 1).await?;
+        let previous_fragments = previous
+            .get_fragments()
+            .into_iter()
+            .map(|fragment| (fragment.id() as u64, fragment.metadata().clone()))
+            .collect::<HashMap<_, _>>();
+        let mut added = Vec::new();
+        let mut kept = 0;
+        for fragment in committed.get_fragments() {
+            match previous_fragments.get(&(fragment.id() as u64)) {
+                Some(metadata) if metadata == fragment.metadata() => kept += 1,
+                Some(_) => {}
+                None => added.push(fragment.id() as u64),
+            }
+        }
+
+        if kept == previous_fragments.len() {
+            // The write only added fragments, so deleting them undoes it
+            // without undoing the writes committed since
+            self.retry_on_conflict(|| async {
+                let latest = Arc::new(self.dataset.get().await?.clone());
+                let transaction = LanceTransaction::new(
+                    latest.version().version,
+                    Operation::Delete {
+                        updated_fragments: vec![],
+                        deleted_fragment_ids: added.clone(),
+                        predicate: format!("rollback of version {}", version),
+                    },
+                    None,
+                    None,
+                );
+                let dataset = CommitBuilder::new(latest).execute(transaction).await?;
+                self.dataset.set_latest(dataset).await;
+                Ok(())
+            })
+            .await
+        } else {
+            // A restore never conflicts with other writes, so it is only
+            // committed while the write is still the latest version, and the
+            // table is locked from the check to the commit
+            let dataset = {
+                let _ds = self.dataset.get_mut().await?;
+                let latest = committed.latest_version_id().await?;
+                if latest != version {
+                    return Err(Error::CommitConflict {
+                        version: Some(latest),
+                        message: format!(
+                            "version {} of table {} changed existing rows and can't be rolled back without discarding the writes committed after it",
+                            version, self.name
+                        ),
+                        request_id: None,
+                    });
+                }
+                let transaction = LanceTransaction::new(
+                    version,
+                    Operation::Restore {
+                        version: version - 1,
+                    },
+                    None,
+                    None,
+                );
+                CommitBuilder::new(Arc::new(committed))
+                    .execute(transaction)
+                    .await?
+            };
+            self.dataset.set_latest(dataset).await;
+            Ok(())
+        }
     }
 
     async fn merge_insert(
@@ -2197,7 +4625,13 @@ impl BaseTable for NativeTable {
         params: MergeInsertBuilder,
         new_data: Box<dyn RecordBatchReader + Send>,
     ) -> Result<()> {
+        let new_data: Box<dyn RecordBatchReader + Send> = if params.deduplicate_source {
+            Box::new(DeduplicatedReader::try_new(new_data, &params.on)?)
+        } else {
+            new_data
+        };
         let dataset = Arc::new(self.dataset.get().await?.clone());
+        let (new_data, violations) = check_constraints(&Schema::from(dataset.schema()), new_data)?;
         let mut builder = LanceMergeInsertBuilder::try_new(dataset.clone(), params.on)?;
         match (
             params.when_matched_update_all,
@@ -2223,15 +4657,19 @@ impl BaseTable for NativeTable {
             builder.when_not_matched_by_source(WhenNotMatchedBySource::Keep);
         }
         let job = builder.try_build()?;
-        let (new_dataset, _stats) = job.execute_reader(new_data).await?;
+        let (new_dataset, _stats) = job
+            .execute_reader(new_data)
+            .await
+            .map_err(|err| violations.or_error(err.into()))?;
         self.dataset.set_latest(new_dataset.as_ref().clone()).await;
+        self.index_new_rows().await;
         Ok(())
     }
 
     /// Delete rows from the table
-    async fn delete(&self, predicate: &str) -> Result<()> {
-        self.dataset.get_mut().await?.delete(predicate).await?;
-        Ok(())
+    async fn delete(&self, predicate: &str, with_row_ids: bool) -> Result<DeleteResult> {
+        self.retry_on_conflict(|| self.delete_once(predicate, with_row_ids))
+            .await
     }
 
     async fn optimize(&self, action: OptimizeAction) -> Result<OptimizeStats> {
@@ -2252,7 +4690,7 @@ impl BaseTable for NativeTable {
                     .optimize(OptimizeAction::Prune {
                         older_than: None,
                         delete_unverified: None,
-                        error_if_tagged_old_versions: None,
+                        error_if_tagged_old_versions: Some(false),
                     })
                     .await?
                     .prune;
@@ -2300,23 +4738,42 @@ impl BaseTable for NativeTable {
     }
 
     async fn alter_columns(&self, alterations: &[ColumnAlteration]) -> Result<()> {
-        self.dataset
-            .get_mut()
-            .await?
-            .alter_columns(alterations)
-            .await?;
-        Ok(())
+        let mut dataset = self.dataset.get_mut().await?;
+        dataset.alter_columns(alterations).await?;
+        self.update_constraints(&mut dataset, |column| {
+            let renamed = alterations
+                .iter()
+                .find(|alteration| alteration.path == column)
+                .and_then(|alteration| alteration.rename.clone());
+            Some(renamed.unwrap_or_else(|| column.to_string()))
+        })
+        .await
     }
 
     async fn drop_columns(&self, columns: &[&str]) -> Result<()> {
-        self.dataset.get_mut().await?.drop_columns(columns).await?;
-        Ok(())
+        let mut dataset = self.dataset.get_mut().await?;
+        dataset.drop_columns(columns).await?;
+        self.update_constraints(&mut dataset, |column| {
+            (!columns.contains(&column)).then(|| column.to_string())
+        })
+        .await
     }
 
     async fn list_indices(&self) -> Result<Vec<IndexConfig>> {
         let dataset = self.dataset.get().await?;
         let indices = dataset.load_indices().await?;
-        futures::stream::iter(indices.as_slice()).then(|idx| async {
+        // An index that was optimized is made of several deltas, which are
+        // listed separately
+        let mut versions = HashMap::<&str, u64>::new();
+        let mut unique = Vec::new();
+        for idx in indices.iter() {
+            let version = versions.entry(idx.name.as_str()).or_insert_with(|| {
+                unique.push(idx);
+                idx.dataset_version
+            });
+            *version = (*version).max(idx.dataset_version);
+        }
+        futures::stream::iter(unique).then(|idx| async {
             let stats = dataset.index_statistics(idx.name.as_str()).await?;
             let stats: serde_json::Value = serde_json::from_str(&stats).map_err(|e| Error::Runtime {
                 message: format!("error deserializing index statistics: {}", e),
@@ -2336,7 +4793,8 @@ impl BaseTable for NativeTable {
             }
 
             let name = idx.name.clone();
-            Ok(IndexConfig { index_type, columns, name })
+            let version = versions.get(name.as_str()).copied();
+            Ok(IndexConfig { index_type, columns, name, version })
         }).try_collect::<Vec<_>>().await
     }
 
@@ -2344,14 +4802,13 @@ impl BaseTable for NativeTable {
         self.uri.as_str()
     }
 
+    fn native_table(&self, _write: Option<WriteOperation>) -> Result<Option<&NativeTable>> {
+        Ok(Some(self))
+    }
+
     async fn index_stats(&self, index_name: &str) -> Result<Option<IndexStatistics>> {
-        let stats = match self
-            .dataset
-            .get()
-            .await?
-            .index_statistics(index_name.as_ref())
-            .await
-        {
+        let dataset = self.dataset.get().await?;
+        let stats = match dataset.index_statistics(index_name.as_ref()).await {
             Ok(stats) => stats,
             Err(lance::error::Error::IndexNotFound { .. }) => return Ok(None),
             Err(e) => return Err(Error::from(e)),
@@ -2380,6 +4837,15 @@ impl BaseTable for NativeTable {
             .sum::<f64>();
 
         let loss = first_index.loss.map(|first_loss| first_loss + loss);
+        // The size is only informational, so failing to list the files of the
+        // index doesn't fail the whole call
+        let size_bytes = match self.index_size_bytes(&dataset, index_name).await {
+            Ok(size) => Some(size),
+            Err(err) => {
+                log::warn!("Failed to list the files of index {}: {}", index_name, err);
+                None
+            }
+        };
         Ok(Some(IndexStatistics {
             num_indexed_rows: stats.num_indexed_rows,
             num_unindexed_rows: stats.num_unindexed_rows,
@@ -2387,6 +4853,7 @@ impl BaseTable for NativeTable {
             distance_type: first_index.metric_type,
             num_indices: stats.num_indices,
             loss,
+            size_bytes,
         }))
     }
 }
@@ -2401,7 +4868,7 @@ mod tests {
 
     use arrow_array::{
         builder::{ListBuilder, StringBuilder},
-        Array, BooleanArray, Date32Array, FixedSizeListArray, Float32Array, Float64Array,
+        Array, ArrayRef, BooleanArray, Date32Array, FixedSizeListArray, Float32Array, Float64Array,
         Int32Array, Int64Array, LargeStringArray, RecordBatch, RecordBatchIterator,
         RecordBatchReader, StringArray, TimestampMillisecondArray, TimestampNanosecondArray,
         UInt32Array,
@@ -2418,8 +4885,9 @@ mod tests {
     use super::*;
     use crate::connect;
     use crate::connection::ConnectBuilder;
-    use crate::index::scalar::BTreeIndexBuilder;
-    use crate::query::{ExecutableQuery, QueryBase};
+    use crate::index::scalar::{BTreeIndexBuilder, FullTextSearchQuery};
+    use crate::query::{filter, ExecutableQuery, QueryBase};
+    use crate::table::stats::ScalarValue;
 
     #[tokio::test]
     async fn test_open() {
@@ -2503,6 +4971,176 @@ mod tests {
         assert_eq!(table.name(), "test");
     }
 
+    #[tokio::test]
+    async fn test_add_streaming() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+
+        // A stream that produces its batches one at a time
+        let stream = futures::stream::iter((0..4).map(|i| {
+            Ok(RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(i * 5..(i + 1) * 5))],
+            )
+            .unwrap())
+        }));
+        let stream: SendableRecordBatchStream = Box::pin(
+            crate::arrow::SimpleRecordBatchStream::new(stream, schema.clone()),
+        );
+        table
+            .add_streaming(stream)
+            .max_rows_per_file(8)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 30);
+        // One fragment from creating the table, and 20 rows in files of 8 rows
+        let native = table.as_native().unwrap();
+        assert_eq!(native.count_fragments().await.unwrap(), 4);
+
+        // Errors from the stream fail the add and nothing is committed
+        let version = table.version().await.unwrap();
+        let stream = futures::stream::iter(vec![
+            Ok(RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(0..5))],
+            )
+            .unwrap()),
+            Err(Error::Runtime {
+                message: "source failed".to_string(),
+            }),
+        ]);
+        let stream: SendableRecordBatchStream = Box::pin(
+            crate::arrow::SimpleRecordBatchStream::new(stream, schema.clone()),
+        );
+        assert!(table.add_streaming(stream).execute().await.is_err());
+        assert_eq!(table.version().await.unwrap(), version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 30);
+
+        // The table can be read while a slow stream is being written
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|batch| (Ok(batch), receiver))
+        });
+        let stream: SendableRecordBatchStream = Box::pin(
+            crate::arrow::SimpleRecordBatchStream::new(stream, schema.clone()),
+        );
+        let writer = {
+            let table = table.clone();
+            tokio::spawn(async move { table.add_streaming(stream).execute().await })
+        };
+        sender
+            .send(
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(0..5))],
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let count = tokio::time::timeout(Duration::from_secs(10), table.count_rows(None));
+        assert_eq!(count.await.unwrap().unwrap(), 30);
+        drop(sender);
+        writer.await.unwrap().unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 35);
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let make_batch = |ids: std::ops::Range<i32>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(ids.clone())),
+                    Arc::new(StringArray::from_iter(
+                        ids.map(|id| (id % 3 != 0).then(|| format!("name-{}", id))),
+                    )),
+                ],
+            )
+            .unwrap()
+        };
+        let table = conn
+            .create_table(
+                "test",
+                RecordBatchIterator::new(vec![Ok(make_batch(0..10))], schema.clone()),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(RecordBatchIterator::new(
+                vec![Ok(make_batch(10..15))],
+                schema.clone(),
+            ))
+            .execute()
+            .await
+            .unwrap();
+        table.delete("id < 2").await.unwrap();
+
+        let stats = table.stats().execute().await.unwrap();
+        assert_eq!(stats.num_rows, 13);
+        assert_eq!(stats.num_deleted_rows, Some(2));
+        assert_eq!(stats.num_fragments, Some(2));
+        assert_eq!(
+            stats
+                .columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["id", "name"]
+        );
+        assert_eq!(
+            stats.total_bytes,
+            Some(
+                stats
+                    .columns
+                    .iter()
+                    .map(|column| column.bytes_on_disk.unwrap())
+                    .sum()
+            )
+        );
+        assert!(stats.total_bytes.unwrap() > 0);
+        assert!(stats.columns.iter().all(|column| column.min.is_none()));
+        assert_eq!(stats.indices.len(), 1);
+        assert_eq!(stats.indices[0].columns, vec!["id"]);
+        assert_eq!(stats.indices[0].num_unindexed_rows, 5);
+
+        let stats = table
+            .stats()
+            .column_values(["id", "name"])
+            .execute()
+            .await
+            .unwrap();
+        let id = &stats.columns[0];
+        assert_eq!(id.null_count, Some(0));
+        assert_eq!(id.min, Some(ScalarValue::Int32(Some(2))));
+        assert_eq!(id.max, Some(ScalarValue::Int32(Some(14))));
+        let name = &stats.columns[1];
+        // 3, 6, 9 and 12 have no name
+        assert_eq!(name.null_count, Some(4));
+        assert_eq!(name.min, Some(ScalarValue::from("name-10")));
+        assert_eq!(name.max, Some(ScalarValue::from("name-8")));
+    }
+
     #[tokio::test]
     async fn test_merge_insert() {
         let tmp_dir = tempdir().unwrap();
@@ -2550,76 +5188,571 @@ mod tests {
             table.count_rows(Some("age = 3".to_string())).await.unwrap(),
             5
         );
+
+        // Reconcile the table with a source containing i=10..20: update the
+        // matches, insert the new rows and delete everything else
+        table
+            .merge_insert(&["i"])
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all()
+            .when_not_matched_by_source_delete(None)
+            .execute(merge_insert_test_batches(10, 4))
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(
+            table.count_rows(Some("age = 4".to_string())).await.unwrap(),
+            10
+        );
+        assert_eq!(
+            table.count_rows(Some("i < 10".to_string())).await.unwrap(),
+            0
+        );
     }
 
     #[tokio::test]
-    async fn test_add_overwrite() {
+    async fn test_insert_if_absent() {
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
 
-        let batches = make_test_batches();
-        let schema = batches.schema().clone();
-        let table = conn.create_table("test", batches).execute().await.unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
-
-        let batches = vec![RecordBatch::try_new(
-            schema.clone(),
-            vec![Arc::new(Int32Array::from_iter_values(100..110))],
-        )
-        .unwrap()]
-        .into_iter()
-        .map(Ok);
+        let schema = table.schema().await.unwrap();
+        let events = |ids: Vec<i32>| {
+            let ages = Int32Array::from_iter_values(iter::repeat(1).take(ids.len()));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(ids)), Arc::new(ages)],
+            )
+            .unwrap();
+            RecordBatchIterator::new(vec![Ok(batch)], schema.clone())
+        };
 
-        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+        // Existing keys and keys repeated within the new data are dropped
+        for _ in 0..2 {
+            table
+                .add(events(vec![8, 9, 10, 10, 11]))
+                .insert_if_absent(["i"])
+                .execute()
+                .await
+                .unwrap();
+            assert_eq!(table.count_rows(None).await.unwrap(), 12);
+            assert_eq!(
+                table.count_rows(Some("age = 1".to_string())).await.unwrap(),
+                2
+            );
+        }
 
-        // Can overwrite using AddDataOptions::mode
+        // The index on the key column is used to find the existing keys
         table
-            .add(new_batches)
-            .mode(AddDataMode::Overwrite)
+            .create_index(&["i"], Index::BTree(Default::default()))
             .execute()
             .await
             .unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
-        assert_eq!(table.name(), "test");
+        table
+            .add(events(vec![11, 12, 13]))
+            .insert_if_absent(["i"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 14);
 
-        // Can overwrite using underlying WriteParams (which
-        // take precedence over AddDataOptions::mode)
+        table
+            .merge_insert(&["i"])
+            .when_not_matched_insert_all()
+            .deduplicate_source()
+            .execute(events(vec![20, 20, 21]))
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 16);
 
-        let param: WriteParams = WriteParams {
-            mode: WriteMode::Overwrite,
-            ..Default::default()
-        };
+        let err = table
+            .add(events(vec![30]))
+            .mode(AddDataMode::Overwrite)
+            .insert_if_absent(["i"])
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        let err = table
+            .add(events(vec![30]))
+            .insert_if_absent(["missing"])
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
 
-        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
-        table
-            .add(new_batches)
-            .write_options(WriteOptions {
-                lance_write_params: Some(param),
-            })
-            .mode(AddDataMode::Append)
+    #[tokio::test]
+    async fn test_delete() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", make_test_batches())
             .execute()
             .await
             .unwrap();
-        assert_eq!(table.count_rows(None).await.unwrap(), 10);
-        assert_eq!(table.name(), "test");
+
+        let deleted = table.delete_with_count("i >= 7").await.unwrap();
+        assert_eq!(
+            deleted,
+            DeleteResult {
+                num_deleted_rows: 3,
+                deleted_row_ids: None,
+            }
+        );
+        assert_eq!(table.count_rows(None).await.unwrap(), 7);
+
+        // Deleting rows that are already gone matches nothing
+        let deleted = table.delete_with_count("i >= 7").await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 0);
+
+        // The rows are in the first fragment, so their ids are their offsets
+        let deleted = table.delete_with_row_ids("i < 2").await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 2);
+        assert_eq!(deleted.deleted_row_ids, Some(vec![0, 1]));
+        assert_eq!(table.count_rows(None).await.unwrap(), 5);
     }
 
     #[tokio::test]
-    async fn test_update_with_predicate() {
+    async fn test_by_row_ids() {
         let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
-        let conn = connect(uri)
-            .read_consistency_interval(Duration::from_secs(0))
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", make_test_batches())
             .execute()
             .await
             .unwrap();
 
-        let schema = Arc::new(Schema::new(vec![
-            Field::new("id", DataType::Int32, false),
-            Field::new("name", DataType::Utf8, false),
-        ]));
+        let batches = table
+            .query()
+            .only_if("i IN (3, 4, 8)")
+            .with_row_id()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let row_ids = batches
+            .iter()
+            .flat_map(|batch| batch[ROW_ID].as_primitive::<UInt64Type>().values().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(row_ids.len(), 3);
+
+        let updated = table
+            .update_by_ids(&row_ids[..2])
+            .column("i", "i * 100")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(updated, 2);
+        assert_eq!(
+            table
+                .count_rows(Some("i >= 100".to_string()))
+                .await
+                .unwrap(),
+            2
+        );
+
+        // The update moved the rows, so only the last id still matches
+        let deleted = table.delete_by_ids(&row_ids).await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 1);
+        assert_eq!(
+            table.count_rows(Some("i = 8".to_string())).await.unwrap(),
+            0
+        );
+        assert_eq!(table.count_rows(None).await.unwrap(), 9);
+
+        let deleted = table.delete_by_ids(&[]).await.unwrap();
+        assert_eq!(deleted.num_deleted_rows, 0);
+    }
+
+    #[tokio::test]
+    async fn test_add_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", make_test_batches())
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![
+                    ("doubled".into(), "i * 2".into()),
+                    ("label".into(), "lower('ITEM')".into()),
+                ]),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            table
+                .count_rows(Some("doubled = i * 2 AND label = 'item'".to_string()))
+                .await
+                .unwrap(),
+            10
+        );
+
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "score",
+            DataType::Float64,
+            true,
+        )]));
+        table
+            .add_columns(NewColumnTransform::AllNulls(schema), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            table
+                .count_rows(Some("score IS NULL".to_string()))
+                .await
+                .unwrap(),
+            10
+        );
+
+        // Backfill from a reader, one value per existing row
+        let schema = Arc::new(Schema::new(vec![Field::new("neg", DataType::Int32, false)]));
+        let backfill = RecordBatchIterator::new(
+            vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values((0..10).map(|i| -i)))],
+            )],
+            schema,
+        );
+        table
+            .add_columns(NewColumnTransform::Reader(Box::new(backfill)), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            table
+                .count_rows(Some("neg = -i".to_string()))
+                .await
+                .unwrap(),
+            10
+        );
+        assert_eq!(table.schema().await.unwrap().fields().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_alter_and_drop_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 1))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["i"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["age"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        // Renaming keeps the index, under the new column name
+        table
+            .alter_columns(&[ColumnAlteration::new("i".into()).rename("id".into())])
+            .await
+            .unwrap();
+        let mut indexed = table
+            .list_indices()
+            .await
+            .unwrap()
+            .into_iter()
+            .flat_map(|index| index.columns)
+            .collect::<Vec<_>>();
+        indexed.sort();
+        assert_eq!(indexed, vec!["age", "id"]);
+        assert_eq!(
+            table.count_rows(Some("id >= 5".to_string())).await.unwrap(),
+            5
+        );
+
+        // Casting rewrites the column and drops its index
+        table
+            .alter_columns(&[ColumnAlteration::new("id".into()).cast_to(DataType::Int64)])
+            .await
+            .unwrap();
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema.field_with_name("id").unwrap().data_type(),
+            &DataType::Int64
+        );
+        let indices = table.list_indices().await.unwrap();
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices[0].columns, vec!["age"]);
+
+        // Dropping a column drops its index
+        table.drop_columns(&["age"]).await.unwrap();
+        let schema = table.schema().await.unwrap();
+        assert_eq!(schema.fields().len(), 1);
+        assert!(table.list_indices().await.unwrap().is_empty());
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        assert!(table.drop_columns(&["not_a_column"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compaction() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        for offset in 1..4 {
+            table
+                .add(merge_insert_test_batches(offset * 10, 0))
+                .execute()
+                .await
+                .unwrap();
+        }
+        let native = table.as_native().unwrap();
+        assert_eq!(native.count_fragments().await.unwrap(), 4);
+
+        let stats = table
+            .optimize(OptimizeAction::Compact {
+                options: CompactionOptions {
+                    target_rows_per_fragment: 1000,
+                    num_threads: Some(1),
+                    ..Default::default()
+                },
+                remap_options: None,
+            })
+            .await
+            .unwrap();
+        let compaction = stats.compaction.unwrap();
+        assert_eq!(compaction.fragments_removed, 4);
+        assert_eq!(compaction.fragments_added, 1);
+        assert!(stats.prune.is_none());
+        assert_eq!(native.count_fragments().await.unwrap(), 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 40);
+
+        // Nothing left to compact
+        let stats = table
+            .optimize(OptimizeAction::Compact {
+                options: CompactionOptions::default(),
+                remap_options: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(stats.compaction.unwrap().fragments_removed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_old_versions() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 0))
+            .mode(AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        table.delete("i > 15").await.unwrap();
+        assert_eq!(table.list_versions().await.unwrap().len(), 3);
+
+        // Tagged versions are kept
+        table.tags().create("overwritten", 2).await.unwrap();
+        let stats = table
+            .cleanup_old_versions(chrono::Duration::zero(), false)
+            .await
+            .unwrap();
+        assert_eq!(stats.old_versions, 1);
+        assert!(stats.bytes_removed > 0);
+        assert_eq!(table.list_versions().await.unwrap().len(), 2);
+        assert_eq!(table.count_rows(None).await.unwrap(), 6);
+
+        table.tags().delete("overwritten").await.unwrap();
+        let stats = table
+            .cleanup_old_versions(chrono::Duration::zero(), false)
+            .await
+            .unwrap();
+        assert_eq!(stats.old_versions, 1);
+        assert_eq!(table.list_versions().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_count_rows_with_filter() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 1))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 2))
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(table.count_rows(None).await.unwrap(), 20);
+        assert_eq!(
+            table.count_rows(Some("age = 2".to_string())).await.unwrap(),
+            10
+        );
+        assert_eq!(
+            table
+                .count_rows(Some("age = 2 AND i < 15".to_string()))
+                .await
+                .unwrap(),
+            5
+        );
+
+        // The same filters work once the column is indexed
+        table
+            .create_index(&["i"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            table.count_rows(Some("i >= 12".to_string())).await.unwrap(),
+            8
+        );
+
+        let native = table.as_native().unwrap();
+        let expr = datafusion_expr::col("i").lt(datafusion_expr::lit(3));
+        assert_eq!(
+            native
+                .count_rows(Some(Filter::Datafusion(expr)))
+                .await
+                .unwrap(),
+            3
+        );
+
+        assert!(table
+            .count_rows(Some("not_a_column = 1".to_string()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_overwrite() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+        let schema = batches.schema().clone();
+        let table = conn.create_table("test", batches).execute().await.unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+
+        let batches = vec![RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(100..110))],
+        )
+        .unwrap()]
+        .into_iter()
+        .map(Ok);
+
+        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+
+        // Can overwrite using AddDataOptions::mode
+        table
+            .add(new_batches)
+            .mode(AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(table.name(), "test");
+        assert_eq!(
+            table
+                .count_rows(Some("i >= 100".to_string()))
+                .await
+                .unwrap(),
+            10
+        );
+
+        // The old data is still available as the previous version
+        assert_eq!(table.list_versions().await.unwrap().len(), 2);
+        table.checkout(1).await.unwrap();
+        assert_eq!(
+            table.count_rows(Some("i < 10".to_string())).await.unwrap(),
+            10
+        );
+        table.checkout_latest().await.unwrap();
+
+        // Can overwrite using underlying WriteParams (which
+        // take precedence over AddDataOptions::mode)
+
+        let param: WriteParams = WriteParams {
+            mode: WriteMode::Overwrite,
+            ..Default::default()
+        };
+
+        let new_batches = RecordBatchIterator::new(batches.clone(), schema.clone());
+        table
+            .add(new_batches)
+            .write_options(WriteOptions {
+                lance_write_params: Some(param),
+            })
+            .mode(AddDataMode::Append)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert_eq!(table.name(), "test");
+
+        // Overwriting can change the schema
+        let new_schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, false)]));
+        let new_batches = RecordBatchIterator::new(
+            vec![RecordBatch::try_new(
+                new_schema.clone(),
+                vec![Arc::new(StringArray::from(vec!["a", "b"]))],
+            )],
+            new_schema.clone(),
+        );
+        table
+            .add(new_batches)
+            .mode(AddDataMode::Overwrite)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.schema().await.unwrap().fields(), new_schema.fields());
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_with_predicate() {
+        let tmp_dir = tempdir().unwrap();
+        let dataset_path = tmp_dir.path().join("test.lance");
+        let uri = dataset_path.to_str().unwrap();
+        let conn = connect(uri)
+            .read_consistency_interval(Duration::from_secs(0))
+            .execute()
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
 
         let record_batch_iter = RecordBatchIterator::new(
             vec![RecordBatch::try_new(
@@ -2901,8 +6034,19 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(1, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
-        tbl.update().column("i", "i+1").execute().await.unwrap();
+        let rows_updated = tbl.update().column("i", "i+1").execute().await.unwrap();
+        assert_eq!(rows_updated, 10);
         assert_eq!(0, tbl.count_rows(Some("i == 0".to_string())).await.unwrap());
+
+        let rows_updated = tbl
+            .update()
+            .only_if("i > 5")
+            .column("i", "i * 2")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(rows_updated, 5);
+        assert_eq!(5, tbl.count_rows(Some("i > 10".to_string())).await.unwrap());
     }
 
     #[derive(Default, Debug)]
@@ -2916,157 +6060,323 @@ mod tests {
         }
     }
 
-    impl WrappingObjectStore for NoOpCacheWrapper {
-        fn wrap(
-            &self,
-            original: Arc<dyn object_store::ObjectStore>,
-        ) -> Arc<dyn object_store::ObjectStore> {
-            self.called.store(true, Ordering::Relaxed);
-            original
-        }
+    impl WrappingObjectStore for NoOpCacheWrapper {
+        fn wrap(
+            &self,
+            original: Arc<dyn object_store::ObjectStore>,
+        ) -> Arc<dyn object_store::ObjectStore> {
+            self.called.store(true, Ordering::Relaxed);
+            original
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_table_options() {
+        let tmp_dir = tempdir().unwrap();
+        let dataset_path = tmp_dir.path().join("test.lance");
+        let uri = dataset_path.to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let batches = make_test_batches();
+
+        conn.create_table("my_table", batches)
+            .execute()
+            .await
+            .unwrap();
+
+        let wrapper = Arc::new(NoOpCacheWrapper::default());
+
+        let object_store_params = ObjectStoreParams {
+            object_store_wrapper: Some(wrapper.clone()),
+            ..Default::default()
+        };
+        let param = ReadParams {
+            store_options: Some(object_store_params),
+            ..Default::default()
+        };
+        assert!(!wrapper.called());
+        conn.open_table("my_table")
+            .lance_read_params(param)
+            .execute()
+            .await
+            .unwrap();
+        assert!(wrapper.called());
+    }
+
+    fn merge_insert_test_batches(
+        offset: i32,
+        age: i32,
+    ) -> impl RecordBatchReader + Send + Sync + 'static {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("age", DataType::Int32, false),
+        ]));
+        RecordBatchIterator::new(
+            vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from_iter_values(offset..(offset + 10))),
+                    Arc::new(Int32Array::from_iter_values(iter::repeat(age).take(10))),
+                ],
+            )],
+            schema,
+        )
+    }
+
+    fn make_test_batches() -> impl RecordBatchReader + Send + Sync + 'static {
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+        RecordBatchIterator::new(
+            vec![RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from_iter_values(0..10))],
+            )],
+            schema,
+        )
+    }
+
+    /// `num_rows` random 16 dimensional vectors in an `embeddings` column
+    fn vector_batches(num_rows: usize) -> impl RecordBatchReader + Send + Sync + 'static {
+        let dimension = 16;
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "embeddings",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dimension,
+            ),
+            false,
+        )]));
+        let mut rng = rand::thread_rng();
+        let float_arr = Float32Array::from(
+            iter::repeat_with(|| rng.gen::<f32>())
+                .take(num_rows * dimension as usize)
+                .collect::<Vec<f32>>(),
+        );
+        let vectors = Arc::new(create_fixed_size_list(float_arr, dimension).unwrap());
+        RecordBatchIterator::new(
+            vec![RecordBatch::try_new(schema.clone(), vec![vectors])],
+            schema,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_create_index() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(table.index_stats("my_index").await.unwrap(), None);
+
+        table
+            .create_index(&["embeddings"], Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+
+        let index_configs = table.list_indices().await.unwrap();
+        assert_eq!(index_configs.len(), 1);
+        let index = index_configs.into_iter().next().unwrap();
+        assert_eq!(index.index_type, crate::index::IndexType::IvfPq);
+        assert_eq!(index.columns, vec!["embeddings".to_string()]);
+        assert_eq!(table.count_rows(None).await.unwrap(), 512);
+        assert_eq!(table.name(), "test");
+
+        let indices = table.as_native().unwrap().load_indices().await.unwrap();
+        let index_name = &indices[0].index_name;
+        let stats = table.index_stats(index_name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 512);
+        assert_eq!(stats.num_unindexed_rows, 0);
+        assert_eq!(stats.index_type, crate::index::IndexType::IvfPq);
+        assert_eq!(stats.distance_type, Some(crate::DistanceType::L2));
+        assert!(stats.loss.is_some());
+
+        table.drop_index(index_name).await.unwrap();
+        assert_eq!(table.list_indices().await.unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_index() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
+
+        table
+            .create_index(&["embeddings"], Index::Auto)
+            .execute()
+            .await
+            .unwrap();
+        let index_name = table.list_indices().await.unwrap().pop().unwrap().name;
+        table
+            .wait_for_index(&[index_name.as_str()], std::time::Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        // New rows aren't indexed until the index is optimized
+        table.add(vector_batches(512)).execute().await.unwrap();
+        let stats = table.index_stats(&index_name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 512);
+        assert_eq!(stats.num_unindexed_rows, 512);
+        let err = table
+            .wait_for_index(&[index_name.as_str()], std::time::Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{}", err);
     }
 
     #[tokio::test]
-    async fn test_open_table_options() {
+    async fn test_index_version_and_drop() {
         let tmp_dir = tempdir().unwrap();
-        let dataset_path = tmp_dir.path().join("test.lance");
-        let uri = dataset_path.to_str().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
 
-        let batches = make_test_batches();
-
-        conn.create_table("my_table", batches)
+        let table = conn
+            .create_table("test", vector_batches(512))
             .execute()
             .await
             .unwrap();
 
-        let wrapper = Arc::new(NoOpCacheWrapper::default());
-
-        let object_store_params = ObjectStoreParams {
-            object_store_wrapper: Some(wrapper.clone()),
-            ..Default::default()
-        };
-        let param = ReadParams {
-            store_options: Some(object_store_params),
-            ..Default::default()
-        };
-        assert!(!wrapper.called());
-        conn.open_table("my_table")
-            .lance_read_params(param)
+        table
+            .create_index(&["embeddings"], Index::Auto)
             .execute()
             .await
             .unwrap();
-        assert!(wrapper.called());
-    }
+        let index = table.list_indices().await.unwrap().pop().unwrap();
+        // The index was built on the version before the one it created
+        assert_eq!(index.version, Some(table.version().await.unwrap() - 1));
 
-    fn merge_insert_test_batches(
-        offset: i32,
-        age: i32,
-    ) -> impl RecordBatchReader + Send + Sync + 'static {
-        let schema = Arc::new(Schema::new(vec![
-            Field::new("i", DataType::Int32, false),
-            Field::new("age", DataType::Int32, false),
-        ]));
-        RecordBatchIterator::new(
-            vec![RecordBatch::try_new(
-                schema.clone(),
-                vec![
-                    Arc::new(Int32Array::from_iter_values(offset..(offset + 10))),
-                    Arc::new(Int32Array::from_iter_values(iter::repeat(age).take(10))),
-                ],
-            )],
-            schema,
-        )
+        table.drop_index(&index.name).await.unwrap();
+        let err = table.drop_index(&index.name).await.unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { .. }), "{}", err);
     }
 
-    fn make_test_batches() -> impl RecordBatchReader + Send + Sync + 'static {
+    #[tokio::test]
+    async fn test_index_size_bytes() {
+        // The files of the index are listed from the table's own store, even
+        // when it is only held in memory
+        let conn = connect("memory://").execute().await.unwrap();
         let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
-        RecordBatchIterator::new(
-            vec![RecordBatch::try_new(
-                schema.clone(),
-                vec![Arc::new(Int32Array::from_iter_values(0..10))],
-            )],
-            schema,
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..1000))],
         )
+        .unwrap();
+        let table = conn
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["i"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let stats = table.index_stats("i_idx").await.unwrap().unwrap();
+        assert!(stats.size_bytes.unwrap() > 0);
     }
 
     #[tokio::test]
-    async fn test_create_index() {
-        use arrow_array::RecordBatch;
+    async fn test_binary_vectors() {
+        use arrow_array::{RecordBatch, UInt8Array};
         use arrow_schema::{DataType, Field, Schema as ArrowSchema};
-        use rand;
-        use std::iter::repeat_with;
-
-        use arrow_array::Float32Array;
 
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
 
-        let dimension = 16;
+        // 64 bit vectors, packed into 8 bytes
+        let dimension = 8;
         let schema = Arc::new(ArrowSchema::new(vec![Field::new(
             "embeddings",
             DataType::FixedSizeList(
-                Arc::new(Field::new("item", DataType::Float32, true)),
+                Arc::new(Field::new("item", DataType::UInt8, true)),
                 dimension,
             ),
             false,
         )]));
-
         let mut rng = rand::thread_rng();
-        let float_arr = Float32Array::from(
-            repeat_with(|| rng.gen::<f32>())
-                .take(512 * dimension as usize)
-                .collect::<Vec<f32>>(),
+        let bytes = UInt8Array::from(
+            (0..512 * dimension)
+                .map(|_| rng.gen::<u8>())
+                .collect::<Vec<_>>(),
         );
-
-        let vectors = Arc::new(create_fixed_size_list(float_arr, dimension).unwrap());
+        let vectors = create_fixed_size_list(bytes.clone(), dimension).unwrap();
         let batches = RecordBatchIterator::new(
-            vec![RecordBatch::try_new(schema.clone(), vec![vectors.clone()]).unwrap()]
+            vec![RecordBatch::try_new(schema.clone(), vec![Arc::new(vectors)]).unwrap()]
                 .into_iter()
                 .map(Ok),
             schema,
         );
-
         let table = conn.create_table("test", batches).execute().await.unwrap();
 
-        assert_eq!(table.index_stats("my_index").await.unwrap(), None);
-
+        // Only IVF_FLAT with the hamming distance can index binary vectors
+        for index in [
+            Index::IvfPq(IvfPqIndexBuilder::default().distance_type(DistanceType::Hamming)),
+            Index::IvfFlat(IvfFlatIndexBuilder::default()),
+        ] {
+            let err = table
+                .create_index(&["embeddings"], index)
+                .execute()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        }
         table
             .create_index(&["embeddings"], Index::Auto)
             .execute()
             .await
             .unwrap();
-
-        let index_configs = table.list_indices().await.unwrap();
-        assert_eq!(index_configs.len(), 1);
-        let index = index_configs.into_iter().next().unwrap();
-        assert_eq!(index.index_type, crate::index::IndexType::IvfPq);
-        assert_eq!(index.columns, vec!["embeddings".to_string()]);
-        assert_eq!(table.count_rows(None).await.unwrap(), 512);
-        assert_eq!(table.name(), "test");
-
-        let indices = table.as_native().unwrap().load_indices().await.unwrap();
-        let index_name = &indices[0].index_name;
-        let stats = table.index_stats(index_name).await.unwrap().unwrap();
-        assert_eq!(stats.num_indexed_rows, 512);
-        assert_eq!(stats.num_unindexed_rows, 0);
-        assert_eq!(stats.index_type, crate::index::IndexType::IvfPq);
-        assert_eq!(stats.distance_type, Some(crate::DistanceType::L2));
-        assert!(stats.loss.is_some());
-
-        table.drop_index(index_name).await.unwrap();
-        assert_eq!(table.list_indices().await.unwrap().len(), 0);
+        let index = table.list_indices().await.unwrap().pop().unwrap();
+        assert_eq!(index.index_type, crate::index::IndexType::IvfFlat);
+        let stats = table.index_stats(&index.name).await.unwrap().unwrap();
+        assert_eq!(stats.distance_type, Some(DistanceType::Hamming));
+
+        // A row is at distance 0 from itself
+        let query = &bytes.values()[8..16];
+        let results = table
+            .query()
+            .nearest_to(query)
+            .unwrap()
+            .limit(1)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0]["_distance"]
+                .as_primitive::<Float32Type>()
+                .value(0),
+            0.0
+        );
+        let err = table
+            .query()
+            .nearest_to(query)
+            .unwrap()
+            .distance_type(DistanceType::L2)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
     }
 
     #[tokio::test]
-    async fn test_create_index_ivf_hnsw_sq() {
+    async fn test_optimize_indices() {
         use arrow_array::RecordBatch;
         use arrow_schema::{DataType, Field, Schema as ArrowSchema};
-        use rand;
-        use std::iter::repeat_with;
-
-        use arrow_array::Float32Array;
 
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
@@ -3081,23 +6391,81 @@ mod tests {
             ),
             false,
         )]));
+        let batches = || {
+            let mut rng = rand::thread_rng();
+            let values =
+                Float32Array::from_iter_values((0..512 * dimension).map(|_| rng.gen::<f32>()));
+            let vectors = create_fixed_size_list(values, dimension).unwrap();
+            RecordBatchIterator::new(
+                vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(vectors)],
+                )],
+                schema.clone(),
+            )
+        };
+        let table = conn
+            .create_table("test", batches())
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(IvfPqIndexBuilder::default().num_partitions(2)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let name = table.list_indices().await.unwrap()[0].name.clone();
 
-        let mut rng = rand::thread_rng();
-        let float_arr = Float32Array::from(
-            repeat_with(|| rng.gen::<f32>())
-                .take(512 * dimension as usize)
-                .collect::<Vec<f32>>(),
-        );
+        // Appending keeps the new rows in a separate delta index
+        table.add(batches()).execute().await.unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_unindexed_rows, 512);
+        table
+            .optimize(OptimizeAction::Index(OptimizeOptions::append()))
+            .await
+            .unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 1024);
+        assert_eq!(stats.num_unindexed_rows, 0);
+        assert_eq!(stats.num_indices, Some(2));
+        assert_eq!(table.list_indices().await.unwrap().len(), 1);
 
-        let vectors = Arc::new(create_fixed_size_list(float_arr, dimension).unwrap());
-        let batches = RecordBatchIterator::new(
-            vec![RecordBatch::try_new(schema.clone(), vec![vectors.clone()]).unwrap()]
-                .into_iter()
-                .map(Ok),
-            schema,
-        );
+        // Merging both deltas with the new rows leaves a single index
+        table.add(batches()).execute().await.unwrap();
+        table
+            .optimize(OptimizeAction::Index(
+                OptimizeOptions::new().num_indices_to_merge(2),
+            ))
+            .await
+            .unwrap();
+        let stats = table.index_stats(&name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 1536);
+        assert_eq!(stats.num_unindexed_rows, 0);
+        assert_eq!(stats.num_indices, Some(1));
 
-        let table = conn.create_table("test", batches).execute().await.unwrap();
+        let err = table
+            .optimize(OptimizeAction::Index(
+                OptimizeOptions::new().index_names(vec!["missing".to_string()]),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::IndexNotFound { name } if name == "missing"));
+    }
+
+    #[tokio::test]
+    async fn test_create_index_ivf_hnsw_sq() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
 
         let stats = table.index_stats("my_index").await.unwrap();
         assert!(stats.is_none());
@@ -3126,66 +6494,145 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_index_ivf_hnsw_pq() {
-        use arrow_array::RecordBatch;
-        use arrow_schema::{DataType, Field, Schema as ArrowSchema};
-        use rand;
-        use std::iter::repeat_with;
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
+        let stats = table.index_stats("my_index").await.unwrap();
+        assert!(stats.is_none());
+
+        let index = IvfHnswPqIndexBuilder::default();
+        table
+            .create_index(&["embeddings"], Index::IvfHnswPq(index))
+            .execute()
+            .await
+            .unwrap();
+
+        let index_configs = table.list_indices().await.unwrap();
+        assert_eq!(index_configs.len(), 1);
+        let index = index_configs.into_iter().next().unwrap();
+        assert_eq!(index.index_type, crate::index::IndexType::IvfHnswPq);
+        assert_eq!(index.columns, vec!["embeddings".to_string()]);
+        assert_eq!(table.count_rows(None).await.unwrap(), 512);
+        assert_eq!(table.name(), "test");
 
-        use arrow_array::Float32Array;
+        let indices: Vec<VectorIndex> = table.as_native().unwrap().load_indices().await.unwrap();
+        let index_name = &indices[0].index_name;
+        let stats = table.index_stats(index_name).await.unwrap().unwrap();
+        assert_eq!(stats.num_indexed_rows, 512);
+        assert_eq!(stats.num_unindexed_rows, 0);
+    }
 
+    #[tokio::test]
+    async fn test_ivf_pq_params() {
         let tmp_dir = tempdir().unwrap();
         let uri = tmp_dir.path().to_str().unwrap();
         let conn = connect(uri).execute().await.unwrap();
 
-        let dimension = 16;
-        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
-            "embeddings",
-            DataType::FixedSizeList(
-                Arc::new(Field::new("item", DataType::Float32, true)),
-                dimension,
-            ),
-            false,
-        )]));
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
 
-        let mut rng = rand::thread_rng();
-        let float_arr = Float32Array::from(
-            repeat_with(|| rng.gen::<f32>())
-                .take(512 * dimension as usize)
-                .collect::<Vec<f32>>(),
-        );
+        for index in [
+            IvfPqIndexBuilder::default().num_partitions(0),
+            IvfPqIndexBuilder::default().num_sub_vectors(3),
+            IvfPqIndexBuilder::default().num_bits(5),
+        ] {
+            let err = table
+                .create_index(&["embeddings"], Index::IvfPq(index))
+                .execute()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        }
+
+        table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .num_partitions(4)
+                        .num_sub_vectors(4)
+                        .num_bits(4)
+                        .sample_rate(64)
+                        .max_iterations(10),
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let index = table.list_indices().await.unwrap().pop().unwrap();
+        assert_eq!(index.index_type, crate::index::IndexType::IvfPq);
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    #[tokio::test]
+    async fn test_ivf_pq_accelerator_not_supported() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
+
+        let err = table
+            .create_index(
+                &["embeddings"],
+                Index::IvfPq(IvfPqIndexBuilder::default().accelerator("cuda")),
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NotSupported { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_ivf_hnsw_pq_params() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
 
-        let vectors = Arc::new(create_fixed_size_list(float_arr, dimension).unwrap());
-        let batches = RecordBatchIterator::new(
-            vec![RecordBatch::try_new(schema.clone(), vec![vectors.clone()]).unwrap()]
-                .into_iter()
-                .map(Ok),
-            schema,
-        );
+        let table = conn
+            .create_table("test", vector_batches(512))
+            .execute()
+            .await
+            .unwrap();
 
-        let table = conn.create_table("test", batches).execute().await.unwrap();
-        let stats = table.index_stats("my_index").await.unwrap();
-        assert!(stats.is_none());
+        for index in [
+            IvfHnswPqIndexBuilder::default().num_edges(0),
+            IvfHnswPqIndexBuilder::default()
+                .num_edges(32)
+                .ef_construction(16),
+        ] {
+            let err = table
+                .create_index(&["embeddings"], Index::IvfHnswPq(index))
+                .execute()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        }
 
-        let index = IvfHnswPqIndexBuilder::default();
+        let index = IvfHnswPqIndexBuilder::default()
+            .num_edges(16)
+            .ef_construction(100)
+            .num_bits(8);
         table
             .create_index(&["embeddings"], Index::IvfHnswPq(index))
             .execute()
             .await
             .unwrap();
-
-        let index_configs = table.list_indices().await.unwrap();
-        assert_eq!(index_configs.len(), 1);
-        let index = index_configs.into_iter().next().unwrap();
+        let index = table.list_indices().await.unwrap().pop().unwrap();
         assert_eq!(index.index_type, crate::index::IndexType::IvfHnswPq);
-        assert_eq!(index.columns, vec!["embeddings".to_string()]);
-        assert_eq!(table.count_rows(None).await.unwrap(), 512);
-        assert_eq!(table.name(), "test");
-
-        let indices: Vec<VectorIndex> = table.as_native().unwrap().load_indices().await.unwrap();
-        let index_name = &indices[0].index_name;
-        let stats = table.index_stats(index_name).await.unwrap().unwrap();
-        assert_eq!(stats.num_indexed_rows, 512);
-        assert_eq!(stats.num_unindexed_rows, 0);
     }
 
     fn create_fixed_size_list<T: Array>(values: T, list_size: i32) -> Result<FixedSizeListArray> {
@@ -3267,6 +6714,57 @@ mod tests {
         assert_eq!(stats.num_unindexed_rows, 0);
     }
 
+    #[tokio::test]
+    async fn test_btree_index_replace_and_filter() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from_iter_values(0..100))],
+        )
+        .unwrap();
+        let conn = ConnectBuilder::new(uri).execute().await.unwrap();
+        let table = conn
+            .create_table(
+                "my_table",
+                RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema()),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["i"], Index::BTree(BTreeIndexBuilder::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        // Without replace an existing index is an error
+        table
+            .create_index(&["i"], Index::BTree(BTreeIndexBuilder::default()))
+            .replace(false)
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(table.list_indices().await.unwrap().len(), 1);
+
+        // Filters on the column are answered by the index
+        let query = table.query().only_if("i = 1");
+        let plan = query.explain_plan(false).await.unwrap();
+        assert!(plan.contains("MaterializeIndex"), "{}", plan);
+        assert_eq!(
+            query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()[0]
+                .num_rows(),
+            1
+        );
+    }
+
     #[tokio::test]
     async fn test_create_bitmap_index() {
         let tmp_dir = tempdir().unwrap();
@@ -3314,6 +6812,123 @@ mod tests {
         assert_eq!(index.columns, vec!["category".to_string()]);
     }
 
+    #[tokio::test]
+    async fn test_bitmap_index_filters() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+
+        let conn = ConnectBuilder::new(uri).execute().await.unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("category", DataType::Utf8, true),
+            Field::new(
+                "vector",
+                DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), 2),
+                true,
+            ),
+        ]));
+
+        // Every tenth row has no category
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..100)),
+                Arc::new(StringArray::from_iter(
+                    (0..100).map(|i| (i % 10 != 9).then(|| format!("category_{}", i % 5))),
+                )),
+                Arc::new(
+                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                        (0..100).map(|i| Some(vec![Some(i as f32), Some(i as f32)])),
+                        2,
+                    ),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let table = conn
+            .create_table(
+                "test_bitmap",
+                RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema()),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["category"], Index::Bitmap(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        let ids = |batches: Vec<RecordBatch>| {
+            let mut ids = batches
+                .iter()
+                .flat_map(|batch| {
+                    batch["id"]
+                        .as_primitive::<arrow_array::types::Int32Type>()
+                        .values()
+                        .to_vec()
+                })
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        for (filter, expected) in [
+            (
+                "category = 'category_1'",
+                (0..100).filter(|i| i % 5 == 1).collect::<Vec<_>>(),
+            ),
+            (
+                "category IN ('category_1', 'category_3')",
+                (0..100).filter(|i| i % 5 == 1 || i % 5 == 3).collect(),
+            ),
+            (
+                "category = 'category_4'",
+                (0..100).filter(|i| i % 10 == 4).collect(),
+            ),
+            (
+                "category IS NULL",
+                (0..100).filter(|i| i % 10 == 9).collect(),
+            ),
+        ] {
+            let results = table
+                .query()
+                .only_if(filter)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(ids(results), expected, "{}", filter);
+        }
+
+        // A vector search can require its prefilter to be answered by the index
+        table
+            .create_index(
+                &["vector"],
+                Index::IvfFlat(IvfFlatIndexBuilder::default().num_partitions(1)),
+            )
+            .execute()
+            .await
+            .unwrap();
+        let results = table
+            .query()
+            .nearest_to(&[0.0, 0.0])
+            .unwrap()
+            .only_if("category = 'category_1'")
+            .require_index_prefilter()
+            .limit(3)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(ids(results), vec![1, 6, 11]);
+    }
+
     #[tokio::test]
     async fn test_create_label_list_index() {
         let tmp_dir = tempdir().unwrap();
@@ -3382,6 +6997,29 @@ mod tests {
         let index = index_configs.into_iter().next().unwrap();
         assert_eq!(index.index_type, crate::index::IndexType::LabelList);
         assert_eq!(index.columns, vec!["tags".to_string()]);
+
+        // Every row has the tag "cat" and all but the first have "dog"
+        for (filter, expected) in [
+            (filter::col("tags").array_contains("dog"), 39),
+            (filter::col("tags").array_has_all(["cat", "fish"]), 39),
+            (filter::col("tags").array_has_any(["bird", "cat"]), 40),
+            (filter::col("tags").array_contains("bird"), 0),
+        ] {
+            let query = table.query().only_if(filter);
+            let plan = query.explain_plan(false).await.unwrap();
+            assert!(plan.contains("MaterializeIndex"), "{}", plan);
+            let rows = query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+                .iter()
+                .map(|batch| batch.num_rows())
+                .sum::<usize>();
+            assert_eq!(rows, expected);
+        }
     }
 
     #[tokio::test]
@@ -3439,12 +7077,81 @@ mod tests {
         assert_eq!(stats.distance_type, None);
     }
 
+    #[tokio::test]
+    async fn test_fts_tokenizer_configuration() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = ConnectBuilder::new(uri).execute().await.unwrap();
+        let batch = RecordBatch::try_from_iter(vec![(
+            "text",
+            Arc::new(StringArray::from(vec![
+                "les chats dorment",
+                "le chien mange",
+            ])) as ArrayRef,
+        )])
+        .unwrap();
+        let table = conn
+            .create_table(
+                "docs",
+                RecordBatchIterator::new(vec![Ok(batch.clone())], batch.schema()),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        let err = table
+            .create_index(
+                &["text"],
+                Index::FTS(FtsIndexBuilder::default().base_tokenizer("no-such-tokenizer")),
+            )
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{}", err);
+        assert!(FtsIndexBuilder::default().language("Klingon").is_err());
+
+        let fts = FtsIndexBuilder::default()
+            .language("French")
+            .unwrap()
+            .stem(true)
+            .remove_stop_words(true);
+        table
+            .create_index(&["text"], Index::FTS(fts))
+            .execute()
+            .await
+            .unwrap();
+
+        let matches = |query: &str| {
+            let table = table.clone();
+            let query = FullTextSearchQuery::new(query.to_string());
+            async move {
+                table
+                    .query()
+                    .full_text_search(query)
+                    .execute()
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|batch| batch.num_rows())
+                    .sum::<usize>()
+            }
+        };
+        // "chat" and "chats" have the same stem
+        assert_eq!(matches("chat").await, 1);
+        assert_eq!(matches("chats").await, 1);
+        assert_eq!(matches("chien").await, 1);
+    }
+
     #[tokio::test]
     async fn test_read_consistency_interval() {
         let intervals = vec![
             None,
             Some(0),
             Some(100), // 100 ms
+            Some(u64::MAX),
         ];
 
         for interval in intervals {
@@ -3461,8 +7168,12 @@ mod tests {
                 .unwrap();
 
             let mut conn2 = ConnectBuilder::new(uri);
-            if let Some(interval) = interval {
-                conn2 = conn2.read_consistency_interval(std::time::Duration::from_millis(interval));
+            match interval {
+                None => {}
+                Some(u64::MAX) => conn2 = conn2.read_consistency_interval(Duration::MAX),
+                Some(interval) => {
+                    conn2 = conn2.read_consistency_interval(Duration::from_millis(interval))
+                }
             }
             let conn2 = conn2.execute().await.unwrap();
             let table2 = conn2.open_table("my_table").execute().await.unwrap();
@@ -3474,7 +7185,7 @@ mod tests {
             assert_eq!(table1.count_rows(None).await.unwrap(), 1);
 
             match interval {
-                None => {
+                None | Some(u64::MAX) => {
                     assert_eq!(table2.count_rows(None).await.unwrap(), 0);
                     table2.checkout_latest().await.unwrap();
                     assert_eq!(table2.count_rows(None).await.unwrap(), 1);
@@ -3513,6 +7224,97 @@ mod tests {
         assert!(table.add(some_sample_data()).execute().await.is_err())
     }
 
+    #[tokio::test]
+    async fn test_time_travel() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        let first_version = table.version().await.unwrap();
+        table
+            .add(merge_insert_test_batches(10, 0))
+            .execute()
+            .await
+            .unwrap();
+        table.delete("i < 5").await.unwrap();
+        let latest_version = table.version().await.unwrap();
+        assert_eq!(latest_version, first_version + 2);
+        let versions = table.list_versions().await.unwrap();
+        assert_eq!(
+            versions.iter().map(|v| v.version).collect::<Vec<_>>(),
+            vec![first_version, first_version + 1, latest_version]
+        );
+
+        // A checked out version is a pinned, read-only view
+        table.checkout(first_version).await.unwrap();
+        assert_eq!(table.version().await.unwrap(), first_version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        assert!(table.delete("true").await.is_err());
+
+        table.checkout_latest().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), latest_version);
+        assert_eq!(table.count_rows(None).await.unwrap(), 15);
+
+        // Restoring makes the old data the new head, keeping the history
+        table.checkout(first_version).await.unwrap();
+        table.restore().await.unwrap();
+        assert_eq!(table.version().await.unwrap(), latest_version + 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 10);
+        table
+            .add(merge_insert_test_batches(10, 0))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.count_rows(None).await.unwrap(), 20);
+
+        assert!(table.checkout(latest_version + 100).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tags() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", merge_insert_test_batches(0, 0))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add(merge_insert_test_batches(10, 0))
+            .execute()
+            .await
+            .unwrap();
+        assert!(table.tags().list().await.unwrap().is_empty());
+
+        let tags = table.tags();
+        tags.create("v1.0", 1).await.unwrap();
+        tags.create("latest", 2).await.unwrap();
+        assert!(tags.create("v1.0", 2).await.is_err());
+        let listed = tags.list().await.unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed["v1.0"].version, 1);
+        assert_eq!(tags.get_version("latest").await.unwrap(), 2);
+
+        // Tags can be managed while a version is checked out
+        table.checkout(1).await.unwrap();
+        tags.update("latest", 1).await.unwrap();
+        assert_eq!(tags.get_version("latest").await.unwrap(), 1);
+        table.checkout_latest().await.unwrap();
+
+        tags.delete("latest").await.unwrap();
+        assert!(tags.get_version("latest").await.is_err());
+        assert_eq!(
+            tags.list().await.unwrap().keys().collect::<Vec<_>>(),
+            vec!["v1.0"]
+        );
+        assert_eq!(table.version().await.unwrap(), 2);
+    }
+
     #[tokio::test]
     async fn test_update_dataset_config() {
         let tmp_dir = tempdir().unwrap();
@@ -3588,6 +7390,68 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_table_metadata() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let conn = connect(uri).execute().await.unwrap();
+        let table = conn
+            .create_table("my_table", some_sample_data())
+            .execute()
+            .await
+            .unwrap();
+
+        assert!(table.list_metadata().await.unwrap().is_empty());
+        assert_eq!(table.get_metadata("owner").await.unwrap(), None);
+
+        let version = table.version().await.unwrap();
+        table.set_metadata("owner", "alice").await.unwrap();
+        table.set_metadata("description", "test").await.unwrap();
+        table.set_metadata("owner", "bob").await.unwrap();
+        assert_eq!(table.version().await.unwrap(), version + 3);
+
+        assert_eq!(
+            table.get_metadata("owner").await.unwrap().as_deref(),
+            Some("bob")
+        );
+        assert_eq!(
+            table.list_metadata().await.unwrap(),
+            HashMap::from([
+                ("owner".to_string(), "bob".to_string()),
+                ("description".to_string(), "test".to_string()),
+            ])
+        );
+
+        // The keys LanceDB uses itself can't be read or changed
+        table
+            .as_native()
+            .unwrap()
+            .update_config([("lancedb::next_id::id".to_string(), "7".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(table.list_metadata().await.unwrap().len(), 2);
+        assert_eq!(
+            table.get_metadata("lancedb::next_id::id").await.unwrap(),
+            None
+        );
+        assert!(matches!(
+            table.set_metadata("lancedb::next_id::id", "0").await,
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            table.delete_metadata(&["lancedb::next_id::id"]).await,
+            Err(Error::InvalidInput { .. })
+        ));
+
+        // Metadata persists when the table is reopened
+        table.delete_metadata(&["description"]).await.unwrap();
+        let table = conn.open_table("my_table").execute().await.unwrap();
+        assert_eq!(
+            table.list_metadata().await.unwrap(),
+            HashMap::from([("owner".to_string(), "bob".to_string())])
+        );
+    }
+
     #[tokio::test]
     async fn test_schema_metadata_config() {
         let tmp_dir = tempdir().unwrap();
@@ -0,0 +1,418 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, RecordBatchReader, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaBuilder, SchemaRef};
+use futures::TryStreamExt;
+use lance::arrow::RecordBatchExt;
+use lance::dataset::transaction::{Operation, Transaction};
+use lance::dataset::CommitBuilder;
+use lance::Dataset;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::{ColumnDefinition, ColumnKind, TableDefinition};
+
+/// How the values of a column generated by the table are chosen
+///
+/// See [`crate::connection::CreateTableBuilder::auto_id`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoId {
+    /// Consecutive non-null `Int64` ids, starting after the largest id the
+    /// table has given out
+    ///
+    /// The next id is stored in the table's config.  Before the rows of an
+    /// add are committed their ids are reserved by a commit that moves the
+    /// next id past them, so if two writers choose the same ids at the same
+    /// time, the later one fails with [`Error::CommitConflict`].  The ids of
+    /// deleted rows are not reused, and a write that fails after reserving
+    /// its ids leaves a gap.
+    Increment,
+    /// Random (version 4) UUIDs, stored as non-null strings
+    Uuid,
+}
+
+impl AutoId {
+    fn field(&self, name: &str) -> Field {
+        match self {
+            Self::Increment => Field::new(name, DataType::Int64, false),
+            Self::Uuid => Field::new(name, DataType::Utf8, false),
+        }
+    }
+
+    fn generate(&self, first_id: i64, num_rows: usize) -> ArrayRef {
+        match self {
+            Self::Increment => Arc::new(Int64Array::from_iter_values(
+                first_id..first_id + num_rows as i64,
+            )),
+            Self::Uuid => Arc::new(StringArray::from_iter_values(
+                (0..num_rows).map(|_| uuid::Uuid::new_v4().to_string()),
+            )),
+        }
+    }
+}
+
+/// The generated columns of a table
+pub(crate) fn auto_id_columns(definition: &TableDefinition) -> Vec<(String, AutoId)> {
+    definition
+        .schema
+        .fields()
+        .iter()
+        .zip(definition.column_definitions.iter())
+        .filter_map(|(field, column)| match &column.kind {
+            ColumnKind::AutoId(kind) => Some((field.name().clone(), *kind)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Add generated columns to a table definition
+pub(crate) fn with_auto_id_columns(
+    definition: TableDefinition,
+    columns: &[(String, AutoId)],
+) -> Result<TableDefinition> {
+    let mut schema = SchemaBuilder::from(definition.schema.as_ref());
+    let mut column_definitions = definition.column_definitions;
+    for (name, kind) in columns {
+        if definition.schema.column_with_name(name).is_some() {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "the column {} is generated by the table and cannot be provided",
+                    name
+                ),
+            });
+        }
+        schema.push(kind.field(name));
+        column_definitions.push(ColumnDefinition {
+            kind: ColumnKind::AutoId(*kind),
+        });
+    }
+    Ok(TableDefinition::new(
+        Arc::new(schema.finish()),
+        column_definitions,
+    ))
+}
+
+/// The config key the next id of an [`AutoId::Increment`] column is stored
+/// under
+fn next_id_key(column: &str) -> String {
+    format!("lancedb::next_id::{}", column)
+}
+
+/// Fill in the generated columns of the table the data is added to, if any
+///
+/// If the table has an [`AutoId::Increment`] column, the ids that were given
+/// out must be reserved with [`IdReservation::commit`] once the data is
+/// written, before it is committed.
+pub(crate) async fn with_auto_ids(
+    dataset: &Dataset,
+    data: Box<dyn RecordBatchReader + Send>,
+) -> Result<(Box<dyn RecordBatchReader + Send>, Option<IdReservation>)> {
+    let definition =
+        TableDefinition::try_from_rich_schema(Arc::new(Schema::from(dataset.schema())))?;
+    let columns = auto_id_columns(&definition);
+    if columns.is_empty() {
+        return Ok((data, None));
+    }
+    let reservation = match columns.iter().find(|(_, kind)| *kind == AutoId::Increment) {
+        Some((name, _)) => Some(IdReservation {
+            column: name.clone(),
+            first_id: next_increment_id(dataset, name).await?,
+            next_id: Arc::default(),
+        }),
+        None => None,
+    };
+    let data = WithAutoIds::try_new(data, columns, reservation.as_ref())?;
+    Ok((Box::new(data), reservation))
+}
+
+/// The ids given out by one write to an [`AutoId::Increment`] column
+pub(crate) struct IdReservation {
+    column: String,
+    first_id: i64,
+    // Updated by the reader as it gives out ids
+    next_id: Arc<AtomicI64>,
+}
+
+impl IdReservation {
+    /// Move the next id of the table past the ids that were given out
+    ///
+    /// This is committed on top of `read_version`, the version the first id
+    /// was read from, so it conflicts with any other write that reserved ids
+    /// since then.
+    pub(crate) async fn commit(&self, read_version: &Arc<Dataset>) -> Result<()> {
+        let next_id = self.next_id.load(Ordering::Relaxed);
+        if next_id == self.first_id {
+            return Ok(());
+        }
+        let operation = Operation::UpdateConfig {
+            upsert_values: Some(HashMap::from([(
+                next_id_key(&self.column),
+                next_id.to_string(),
+            )])),
+            delete_keys: None,
+            schema_metadata: None,
+            field_metadata: None,
+        };
+        let transaction = Transaction::new(read_version.version().version, operation, None, None);
+        CommitBuilder::new(read_version.clone())
+            .execute(transaction)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The next id of an [`AutoId::Increment`] column
+///
+/// Tables written before the next id was stored find it by reading the
+/// column.
+async fn next_increment_id(dataset: &Dataset, column: &str) -> Result<i64> {
+    if let Some(next_id) = dataset.manifest().config.get(&next_id_key(column)) {
+        return next_id.parse().map_err(|_| Error::Runtime {
+            message: format!("the next id of {} is not a number: {}", column, next_id),
+        });
+    }
+    let mut scanner = dataset.scan();
+    scanner.project(&[column])?;
+    let mut batches = scanner.try_into_stream().await?;
+    let mut max_id = None;
+    while let Some(batch) = batches.try_next().await? {
+        let ids = batch
+            .column(0)
+            .as_primitive_opt::<Int64Type>()
+            .ok_or_else(|| Error::Runtime {
+                message: format!("the generated column {} is not an Int64 column", column),
+            })?;
+        max_id = max_id.max(arrow::compute::max(ids));
+    }
+    Ok(max_id.map(|id| id + 1).unwrap_or_default())
+}
+
+/// A record batch reader that appends generated columns to another reader
+pub(crate) struct WithAutoIds {
+    inner: Box<dyn RecordBatchReader + Send>,
+    columns: Vec<(String, AutoId)>,
+    next_id: i64,
+    // Where the ids given out are recorded, if they must be reserved
+    given_out: Option<Arc<AtomicI64>>,
+    schema: SchemaRef,
+}
+
+impl WithAutoIds {
+    /// Give out ids starting at the first id of `reservation`, or at zero
+    pub(crate) fn try_new(
+        inner: Box<dyn RecordBatchReader + Send>,
+        columns: Vec<(String, AutoId)>,
+        reservation: Option<&IdReservation>,
+    ) -> Result<Self> {
+        let definition = TableDefinition::try_from_rich_schema(inner.schema())?;
+        let schema = with_auto_id_columns(definition, &columns)?.into_rich_schema();
+        let next_id = reservation.map(|r| r.first_id).unwrap_or_default();
+        let given_out = reservation.map(|r| r.next_id.clone());
+        if let Some(given_out) = &given_out {
+            given_out.store(next_id, Ordering::Relaxed);
+        }
+        Ok(Self {
+            inner,
+            columns,
+            next_id,
+            given_out,
+            schema,
+        })
+    }
+}
+
+impl Iterator for WithAutoIds {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut batch = match self.inner.next()? {
+            Ok(batch) => batch,
+            Err(err) => return Some(Err(err)),
+        };
+        let num_rows = batch.num_rows();
+        for (name, kind) in self.columns.iter() {
+            let ids = kind.generate(self.next_id, num_rows);
+            batch = match batch.try_with_column(kind.field(name), ids) {
+                Ok(batch) => batch,
+                Err(err) => return Some(Err(err)),
+            };
+        }
+        self.next_id += num_rows as i64;
+        if let Some(given_out) = &self.given_out {
+            given_out.store(self.next_id, Ordering::Relaxed);
+        }
+        Some(Ok(batch))
+    }
+}
+
+impl RecordBatchReader for WithAutoIds {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use arrow_array::RecordBatchIterator;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::ExecutableQuery;
+    use crate::Table;
+
+    fn names(values: &[&str]) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from_iter_values(values.iter()))],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    async fn generated_ids(table: &Table) -> (Vec<i64>, HashSet<String>) {
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        let mut uuids = HashSet::new();
+        for batch in batches {
+            ids.extend(batch["id"].as_primitive::<Int64Type>().values().iter());
+            if let Some(column) = batch.column_by_name("uuid") {
+                uuids.extend(column.as_string::<i32>().iter().flatten().map(String::from));
+            }
+        }
+        ids.sort();
+        (ids, uuids)
+    }
+
+    #[tokio::test]
+    async fn test_auto_id() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let table = db
+            .create_table("test", names(&["a", "b", "c"]))
+            .auto_id("id", AutoId::Increment)
+            .auto_id("uuid", AutoId::Uuid)
+            .execute()
+            .await
+            .unwrap();
+        let schema = table.schema().await.unwrap();
+        assert_eq!(
+            schema.field_with_name("id").unwrap().data_type(),
+            &DataType::Int64
+        );
+        assert_eq!(
+            schema.field_with_name("uuid").unwrap().data_type(),
+            &DataType::Utf8
+        );
+
+        table.add(names(&["d", "e"])).execute().await.unwrap();
+        let (ids, uuids) = generated_ids(&table).await;
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+        assert_eq!(uuids.len(), 5);
+
+        // The column definitions are stored with the table
+        let reopened = connect(uri)
+            .execute()
+            .await
+            .unwrap()
+            .open_table("test")
+            .execute()
+            .await
+            .unwrap();
+        reopened.add(names(&["f"])).execute().await.unwrap();
+        let (ids, uuids) = generated_ids(&reopened).await;
+        assert_eq!(ids, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(uuids.len(), 6);
+
+        // The generated columns can't be provided
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("id", DataType::Int64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["g"])),
+                Arc::new(Int64Array::from(vec![0])),
+            ],
+        )
+        .unwrap();
+        let err = table
+            .add(RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_auto_id_empty_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let table = db
+            .create_empty_table("test", schema)
+            .auto_id("id", AutoId::Increment)
+            .execute()
+            .await
+            .unwrap();
+        table.add(names(&["a", "b"])).execute().await.unwrap();
+        table.add(names(&["c"])).execute().await.unwrap();
+        assert_eq!(generated_ids(&table).await.0, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_auto_id_reserved() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let table = db
+            .create_table("test", names(&["a", "b", "c"]))
+            .auto_id("id", AutoId::Increment)
+            .execute()
+            .await
+            .unwrap();
+        let other = connect(uri)
+            .execute()
+            .await
+            .unwrap()
+            .open_table("test")
+            .execute()
+            .await
+            .unwrap();
+
+        // The ids of deleted rows are not reused
+        table.add(names(&["d", "e"])).execute().await.unwrap();
+        table.delete("id >= 3").await.unwrap();
+        table.add(names(&["f"])).execute().await.unwrap();
+        assert_eq!(generated_ids(&table).await.0, vec![0, 1, 2, 5]);
+
+        // A writer that hasn't seen the other writes would choose ids that
+        // were already given out, so it conflicts
+        let err = other.add(names(&["g"])).execute().await.unwrap_err();
+        assert!(matches!(err, Error::CommitConflict { .. }), "{:?}", err);
+        other.checkout_latest().await.unwrap();
+        other.add(names(&["g"])).execute().await.unwrap();
+        assert_eq!(generated_ids(&other).await.0, vec![0, 1, 2, 5, 6]);
+    }
+}
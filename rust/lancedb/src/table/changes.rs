@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow_array::RecordBatch;
+use arrow_schema::{Schema, SchemaRef};
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use lance::dataset::transaction::Operation;
+use lance::Dataset;
+use lance_table::format::Fragment;
+
+use crate::arrow::{SendableRecordBatchStream, SimpleRecordBatchStream};
+use crate::error::Result;
+
+use super::Table;
+
+/// A stream of the commits to a table, see [`super::Table::changes`]
+pub type ChangeStream = BoxStream<'static, Result<TableChange>>;
+
+/// A commit to a table
+#[derive(Debug, Clone)]
+pub struct TableChange {
+    /// The version the commit created
+    pub version: u64,
+    /// When the commit was made
+    pub timestamp: DateTime<Utc>,
+    /// What the commit changed
+    pub kind: ChangeKind,
+}
+
+/// The rows added by a commit
+///
+/// The rows are only read when they are streamed, so a change can be
+/// inspected without reading them, and large commits are not held in memory.
+#[derive(Debug, Clone)]
+pub struct NewRows {
+    dataset: Arc<Dataset>,
+    fragments: Vec<Fragment>,
+}
+
+impl NewRows {
+    /// Read the rows
+    pub async fn stream(&self) -> Result<SendableRecordBatchStream> {
+        if self.fragments.is_empty() {
+            // Nothing was added
+            let schema = Arc::new(Schema::from(self.dataset.schema()));
+            return Ok(Box::pin(SimpleRecordBatchStream {
+                schema,
+                stream: futures::stream::empty::<Result<RecordBatch>>(),
+            }));
+        }
+        let mut scanner = self.dataset.scan();
+        scanner.with_fragments(self.fragments.clone());
+        Ok(scanner.try_into_stream().await?.into())
+    }
+}
+
+/// What a commit changed
+#[derive(Debug, Clone)]
+pub enum ChangeKind {
+    /// Rows were added
+    Append {
+        /// The added rows
+        rows: NewRows,
+    },
+    /// The rows matching a filter were deleted
+    Delete {
+        /// The filter, as passed to [`super::Table::delete`]
+        predicate: String,
+    },
+    /// Rows were updated or inserted, e.g. by [`super::Table::update`] or
+    /// [`super::Table::merge_insert`]
+    ///
+    /// Updated rows are deleted and written again, so the new versions of the
+    /// rows are given, but not which rows they replace.  Match them to the
+    /// old rows with a key column.
+    Upsert {
+        /// The new and updated rows
+        rows: NewRows,
+    },
+    /// The schema changed, e.g. columns were added, altered or dropped
+    SchemaChange {
+        /// The new schema
+        schema: SchemaRef,
+    },
+    /// All of the data was replaced, so the table must be read again
+    Overwrite {
+        /// The new schema
+        schema: SchemaRef,
+    },
+    /// The table was restored to an older version, so the table must be read
+    /// again
+    Restore {
+        /// The version that was restored
+        version: u64,
+    },
+    /// The rows did not change, e.g. the table was compacted or indexed
+    Other {
+        /// The name of the operation
+        operation: String,
+    },
+}
+
+/// Read the commit that created `version` of the table
+pub(crate) async fn read_change(dataset: &Dataset, version: u64) -> Result<TableChange> {
+    let current = dataset.checkout_version(version).await?;
+    let timestamp = current.version().timestamp;
+    let operation = current.read_transaction().await?.map(|txn| txn.operation);
+    let kind = match operation {
+        Some(Operation::Append { .. }) => ChangeKind::Append {
+            rows: new_rows(dataset, &current, version).await?,
+        },
+        Some(Operation::Update { .. }) => ChangeKind::Upsert {
+            rows: new_rows(dataset, &current, version).await?,
+        },
+        Some(Operation::Delete { predicate, .. }) => ChangeKind::Delete { predicate },
+        Some(Operation::Overwrite { .. }) => ChangeKind::Overwrite {
+            schema: Arc::new(Schema::from(current.schema())),
+        },
+        Some(Operation::Merge { .. }) | Some(Operation::Project { .. }) => {
+            ChangeKind::SchemaChange {
+                schema: Arc::new(Schema::from(current.schema())),
+            }
+        }
+        Some(Operation::Restore { version }) => ChangeKind::Restore { version },
+        Some(operation) => ChangeKind::Other {
+            operation: operation.name().to_string(),
+        },
+        None => ChangeKind::Other {
+            operation: "Unknown".to_string(),
+        },
+    };
+    Ok(TableChange {
+        version,
+        timestamp,
+        kind,
+    })
+}
+
+/// The rows in the fragments that `version` added
+async fn new_rows(dataset: &Dataset, current: &Dataset, version: u64) -> Result<NewRows> {
+    let previous_ids = if version > 1 {
+        let previous = dataset.checkout_version(version - 1).await?;
+        previous
+            .manifest()
+            .fragments
+            .iter()
+            .map(|fragment| fragment.id)
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let new_fragments = current
+        .manifest()
+        .fragments
+        .iter()
+        .filter(|fragment| !previous_ids.contains(&fragment.id))
+        .cloned()
+        .collect::<Vec<_>>();
+    Ok(NewRows {
+        dataset: Arc::new(current.clone()),
+        fragments: new_fragments,
+    })
+}
+
+/// The commits made after `since_version`, see [`super::Table::changes`]
+pub(crate) async fn changes(dataset: Dataset, since_version: u64) -> Result<ChangeStream> {
+    let latest = dataset.latest_version_id().await?;
+    let dataset = Arc::new(dataset);
+    Ok(futures::stream::iter(since_version + 1..=latest)
+        .then(move |version| {
+            let dataset = dataset.clone();
+            async move { read_change(&dataset, version).await }
+        })
+        .boxed())
+}
+
+/// Poll for commits made after `since_version`, see [`super::Table::listen`]
+pub(crate) fn listen(table: Table, since_version: u64, poll_interval: Duration) -> ChangeStream {
+    futures::stream::unfold(Some((table, since_version)), move |state| async move {
+        let (table, version) = state?;
+        loop {
+            match table.change(version + 1).await {
+                Ok(Some(change)) => return Some((Ok(change), Some((table, version + 1)))),
+                Ok(None) => tokio::time::sleep(poll_interval).await,
+                // The stream ends after an error
+                Err(err) => return Some((Err(err), None)),
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int32Array, RecordBatchIterator, RecordBatchReader};
+    use arrow_schema::{DataType, Field};
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::table::NewColumnTransform;
+
+    fn ids(values: Vec<i32>) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))]).unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    async fn num_rows(kind: &ChangeKind) -> usize {
+        let (ChangeKind::Append { rows } | ChangeKind::Upsert { rows }) = kind else {
+            panic!("{:?} adds no rows", kind);
+        };
+        rows.stream()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .map(|batch| batch.num_rows())
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn test_changes() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let table = db
+            .create_table("test", ids(vec![1, 2]))
+            .execute()
+            .await
+            .unwrap();
+        table.add(ids(vec![3, 4, 5])).execute().await.unwrap();
+        table.delete("id > 4").await.unwrap();
+        table
+            .update()
+            .only_if("id = 1")
+            .column("id", "10")
+            .execute()
+            .await
+            .unwrap();
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![("double".into(), "id * 2".into())]),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let changes = table
+            .changes(1)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            changes.iter().map(|c| c.version).collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+        assert!(matches!(&changes[0].kind, ChangeKind::Append { .. }));
+        assert_eq!(num_rows(&changes[0].kind).await, 3);
+        assert!(
+            matches!(&changes[1].kind, ChangeKind::Delete { predicate } if predicate == "id > 4")
+        );
+        assert!(matches!(&changes[2].kind, ChangeKind::Upsert { .. }));
+        assert_eq!(num_rows(&changes[2].kind).await, 1);
+        assert!(matches!(
+            &changes[3].kind,
+            ChangeKind::SchemaChange { schema } if schema.field_with_name("double").is_ok()
+        ));
+
+        // The first version is the creation of the table
+        let first = table.changes(0).await.unwrap().next().await.unwrap();
+        assert!(
+            matches!(first.unwrap().kind, ChangeKind::Overwrite { schema } if schema.fields().len() == 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_listen() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let table = db
+            .create_table("test", ids(vec![1]))
+            .execute()
+            .await
+            .unwrap();
+        let mut changes = table.listen(table.version().await.unwrap(), Duration::from_millis(10));
+
+        let writer = table.clone();
+        tokio::spawn(async move {
+            writer.add(ids(vec![2, 3])).execute().await.unwrap();
+        });
+        let change = changes.next().await.unwrap().unwrap();
+        assert_eq!(change.version, 2);
+        assert!(matches!(change.kind, ChangeKind::Append { .. }));
+        assert_eq!(num_rows(&change.kind).await, 2);
+    }
+}
@@ -0,0 +1,659 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use arrow::compute::kernels::cmp::{gt, lt};
+use arrow::util::display::array_value_to_string;
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    make_array, Array, ArrayRef, BooleanArray, Float64Array, RecordBatch, RecordBatchReader, Scalar,
+};
+use arrow_cast::{cast, cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, DataType, Schema, SchemaRef};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// The schema metadata key the constraints of a table are stored under
+pub const CONSTRAINTS_METADATA_KEY: &str = "lancedb::constraints";
+
+/// The most violations reported by one [`Error::ConstraintViolation`]
+const MAX_REPORTED_VIOLATIONS: usize = 100;
+
+/// Checks that the values of a column must pass before they are written
+///
+/// Constraints are checked by [`super::Table::add`], [`super::Table::update`]
+/// and [`super::Table::merge_insert`] before anything is committed.  They are
+/// only enforced for local tables.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnConstraint {
+    /// The column may not contain nulls
+    #[serde(default)]
+    pub not_null: bool,
+    /// The smallest allowed value, for numeric columns
+    pub min: Option<f64>,
+    /// The largest allowed value, for numeric columns
+    pub max: Option<f64>,
+    /// The allowed values, compared as strings
+    pub allowed_values: Option<Vec<String>>,
+}
+
+impl ColumnConstraint {
+    /// Don't allow nulls
+    pub fn not_null(mut self) -> Self {
+        self.not_null = true;
+        self
+    }
+
+    /// Don't allow values smaller than `min`
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Don't allow values larger than `max`
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Only allow the given values
+    pub fn allowed_values(mut self, values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_values = Some(values.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+/// A value that did not pass a [`ColumnConstraint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintViolation {
+    /// The column of the value
+    pub column: String,
+    /// The row of the value
+    ///
+    /// For adds and merge inserts this is the position of the row in the new
+    /// data.  For updates it is the row id of the updated row.
+    pub row: u64,
+    /// Why the value is not allowed
+    pub reason: String,
+}
+
+impl std::fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "column {} row {}: {}",
+            self.column, self.row, self.reason
+        )
+    }
+}
+
+/// The constraints stored in a table's schema
+pub(crate) fn constraints_from_schema(
+    schema: &Schema,
+) -> Result<BTreeMap<String, ColumnConstraint>> {
+    match schema.metadata.get(CONSTRAINTS_METADATA_KEY) {
+        Some(constraints) => serde_json::from_str(constraints).map_err(|e| Error::Runtime {
+            message: format!("Failed to deserialize column constraints: {}", e),
+        }),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+/// The schema metadata that stores the given constraints
+pub(crate) fn constraints_metadata(
+    constraints: &BTreeMap<String, ColumnConstraint>,
+) -> (String, String) {
+    // We have full control over the structure of the constraints, so this
+    // should not fail except for a bug
+    (
+        CONSTRAINTS_METADATA_KEY.to_string(),
+        serde_json::to_string(constraints).unwrap(),
+    )
+}
+
+/// Check the values of one column, adding any violations to `violations`
+///
+/// `row` gives the row number to report for each index of the array.
+pub(crate) fn check_column(
+    name: &str,
+    constraint: &ColumnConstraint,
+    array: &dyn Array,
+    row: impl Fn(usize) -> u64,
+    violations: &mut Vec<ConstraintViolation>,
+) -> Result<()> {
+    let mut violation = |index: usize, reason: String| {
+        if violations.len() < MAX_REPORTED_VIOLATIONS {
+            violations.push(ConstraintViolation {
+                column: name.to_string(),
+                row: row(index),
+                reason,
+            });
+        }
+    };
+    if constraint.not_null && array.null_count() > 0 {
+        for index in (0..array.len()).filter(|index| array.is_null(*index)) {
+            violation(index, "the value is null".to_string());
+        }
+    }
+    if constraint.min.is_some() || constraint.max.is_some() {
+        if !array.data_type().is_numeric() {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "min and max constraints are only supported for numeric columns, {} is {}",
+                    name,
+                    array.data_type()
+                ),
+            });
+        }
+        // Widening a float is exact, so floats are compared as f64 instead of
+        // rounding the bound to a narrower float
+        let values = if array.data_type().is_floating() {
+            cast(array, &DataType::Float64)?
+        } else {
+            make_array(array.to_data())
+        };
+        for (bound, is_min) in [(constraint.min, true), (constraint.max, false)] {
+            // Nothing is less or more than NaN
+            let Some(bound) = bound.filter(|bound| !bound.is_nan()) else {
+                continue;
+            };
+            let outside = match native_bound(bound, is_min, values.data_type())? {
+                NativeBound::Value(native) => {
+                    let native = Scalar::new(native);
+                    if is_min {
+                        lt(&values, &native)?
+                    } else {
+                        gt(&values, &native)?
+                    }
+                }
+                // Every value is inside a bound past the end of the type's
+                // range, and outside one before its start
+                NativeBound::BelowAll => BooleanArray::from(vec![!is_min; values.len()]),
+                NativeBound::AboveAll => BooleanArray::from(vec![is_min; values.len()]),
+            };
+            for index in 0..values.len() {
+                if values.is_valid(index) && outside.value(index) {
+                    let value = array_value_to_string(&values, index)?;
+                    let reason = if is_min {
+                        format!("{} is less than the minimum {}", value, bound)
+                    } else {
+                        format!("{} is more than the maximum {}", value, bound)
+                    };
+                    violation(index, reason);
+                }
+            }
+        }
+    }
+    if let Some(allowed_values) = &constraint.allowed_values {
+        let allowed_values = allowed_values
+            .iter()
+            .map(String::as_str)
+            .collect::<HashSet<_>>();
+        let values = cast(array, &DataType::Utf8)?;
+        for (index, value) in values.as_string::<i32>().iter().enumerate() {
+            if let Some(value) = value.filter(|value| !allowed_values.contains(value)) {
+                violation(index, format!("{} is not one of the allowed values", value));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A min or max constraint as a value of a column's type
+enum NativeBound {
+    /// The bound, rounded to a value of the type without changing which
+    /// values are inside it
+    Value(ArrayRef),
+    /// The bound is below every value of the type
+    BelowAll,
+    /// The bound is above every value of the type
+    AboveAll,
+}
+
+/// Convert a min (`is_min`) or max constraint to a value of `data_type`
+fn native_bound(bound: f64, is_min: bool, data_type: &DataType) -> Result<NativeBound> {
+    // An integer is below a minimum exactly when it is below the minimum
+    // rounded up, and above a maximum when it is above the maximum rounded
+    // down.  Decimals are rounded the same way, at their scale.
+    let round = |value: f64| {
+        if is_min {
+            value.ceil()
+        } else {
+            value.floor()
+        }
+    };
+    let rounded = match data_type {
+        DataType::Decimal128(_, scale) | DataType::Decimal256(_, scale) => {
+            let factor = 10f64.powi(*scale as i32);
+            round(bound * factor) / factor
+        }
+        data_type if data_type.is_integer() => round(bound),
+        _ => bound,
+    };
+    let options = CastOptions {
+        safe: true,
+        ..Default::default()
+    };
+    let native = cast_with_options(&Float64Array::from(vec![rounded]), data_type, &options)?;
+    // A bound outside of the range of the type can't be cast
+    Ok(if native.is_valid(0) {
+        NativeBound::Value(native)
+    } else if rounded > 0.0 {
+        NativeBound::AboveAll
+    } else {
+        NativeBound::BelowAll
+    })
+}
+
+/// Check a batch of new data, numbering its rows from `first_row`
+fn check_batch(
+    constraints: &BTreeMap<String, ColumnConstraint>,
+    batch: &RecordBatch,
+    first_row: u64,
+) -> Result<Vec<ConstraintViolation>> {
+    let mut violations = Vec::new();
+    for (name, constraint) in constraints {
+        match batch.column_by_name(name) {
+            Some(array) => check_column(
+                name,
+                constraint,
+                array.as_ref(),
+                |index| first_row + index as u64,
+                &mut violations,
+            )?,
+            // Missing columns are filled with nulls
+            None if constraint.not_null => {
+                for index in 0..batch.num_rows() {
+                    if violations.len() < MAX_REPORTED_VIOLATIONS {
+                        violations.push(ConstraintViolation {
+                            column: name.clone(),
+                            row: first_row + index as u64,
+                            reason: "the column is missing".to_string(),
+                        });
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+    Ok(violations)
+}
+
+/// The violations found by a [`CheckedReader`]
+///
+/// The reader can only fail with an [`ArrowError`], which the write turns into
+/// an untyped error, so the violations are kept here to be returned instead.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Violations(Arc<Mutex<Vec<ConstraintViolation>>>);
+
+impl Violations {
+    /// Replace the error of a failed write with the violations that caused it
+    pub(crate) fn or_error(&self, err: Error) -> Error {
+        let violations = std::mem::take(&mut *self.0.lock().unwrap());
+        if violations.is_empty() {
+            err
+        } else {
+            Error::ConstraintViolation { violations }
+        }
+    }
+}
+
+/// A record batch reader that checks each batch against column constraints
+pub(crate) struct CheckedReader {
+    inner: Box<dyn RecordBatchReader + Send>,
+    constraints: BTreeMap<String, ColumnConstraint>,
+    schema: SchemaRef,
+    next_row: u64,
+    violations: Violations,
+}
+
+impl CheckedReader {
+    /// Check the data against `constraints`, which are also added to the
+    /// metadata of the reader's schema
+    pub(crate) fn new(
+        inner: Box<dyn RecordBatchReader + Send>,
+        constraints: BTreeMap<String, ColumnConstraint>,
+    ) -> (Self, Violations) {
+        let mut schema = inner.schema().as_ref().clone();
+        let (key, value) = constraints_metadata(&constraints);
+        schema.metadata.insert(key, value);
+        let violations = Violations::default();
+        let reader = Self {
+            inner,
+            constraints,
+            schema: Arc::new(schema),
+            next_row: 0,
+            violations: violations.clone(),
+        };
+        (reader, violations)
+    }
+
+    fn check(&mut self, batch: &RecordBatch) -> std::result::Result<(), ArrowError> {
+        let violations = check_batch(&self.constraints, batch, self.next_row)
+            .map_err(|err| ArrowError::InvalidArgumentError(err.to_string()))?;
+        self.next_row += batch.num_rows() as u64;
+        if let Some(first) = violations.first() {
+            let message = format!("constraint violated, {}", first);
+            *self.violations.0.lock().unwrap() = violations;
+            return Err(ArrowError::InvalidArgumentError(message));
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for CheckedReader {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = match self.inner.next()? {
+            Ok(batch) => batch,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(self.check(&batch).map(|_| batch))
+    }
+}
+
+impl RecordBatchReader for CheckedReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Check the data written to a table against the table's constraints, if any
+pub(crate) fn check_constraints(
+    table_schema: &Schema,
+    data: Box<dyn RecordBatchReader + Send>,
+) -> Result<(Box<dyn RecordBatchReader + Send>, Violations)> {
+    let constraints = constraints_from_schema(table_schema)?;
+    if constraints.is_empty() {
+        return Ok((data, Violations::default()));
+    }
+    let (data, violations) = CheckedReader::new(data, constraints);
+    Ok((Box::new(data), violations))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{
+        Float32Array, Int32Array, Int64Array, RecordBatchIterator, StringArray, UInt8Array,
+    };
+    use arrow_schema::Field;
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::ExecutableQuery;
+    use crate::table::ColumnAlteration;
+    use crate::Table;
+
+    fn people(
+        ages: Vec<Option<i32>>,
+        colors: Vec<&str>,
+    ) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("age", DataType::Int32, true),
+            Field::new("color", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(ages)),
+                Arc::new(StringArray::from(colors)),
+            ],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    fn violated_rows(err: Error) -> Vec<(String, u64)> {
+        match err {
+            Error::ConstraintViolation { violations } => violations
+                .into_iter()
+                .map(|violation| (violation.column, violation.row))
+                .collect(),
+            err => panic!("expected a constraint violation, got {:?}", err),
+        }
+    }
+
+    async fn count(table: &Table) -> usize {
+        table
+            .query()
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .iter()
+            .map(|batch| batch.num_rows())
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn test_constraints() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let age = ColumnConstraint::default().not_null().min(0.0).max(150.0);
+        let color = ColumnConstraint::default().allowed_values(["red", "green"]);
+
+        // The initial data is checked
+        let err = db
+            .create_table("test", people(vec![Some(1), None], vec!["red", "red"]))
+            .constraint("age", age.clone())
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(violated_rows(err), vec![("age".to_string(), 1)]);
+
+        let table = db
+            .create_table("test", people(vec![Some(1), Some(2)], vec!["red", "green"]))
+            .constraint("age", age.clone())
+            .constraint("color", color.clone())
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            table.constraints().await.unwrap(),
+            BTreeMap::from([("age".to_string(), age), ("color".to_string(), color)])
+        );
+
+        // Nothing is added if any row is invalid
+        let err = table
+            .add(people(
+                vec![Some(3), Some(-1), Some(200)],
+                vec!["red", "blue", "green"],
+            ))
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            violated_rows(err),
+            vec![
+                ("age".to_string(), 1),
+                ("age".to_string(), 2),
+                ("color".to_string(), 1)
+            ]
+        );
+        assert_eq!(count(&table).await, 2);
+
+        let mut merge = table.merge_insert(&["color"]);
+        merge.when_not_matched_insert_all();
+        let err = merge
+            .execute(people(vec![None], vec!["green"]))
+            .await
+            .unwrap_err();
+        assert_eq!(violated_rows(err), vec![("age".to_string(), 0)]);
+
+        // Updates report the row id of the updated row
+        let err = table
+            .update()
+            .only_if("color = 'green'")
+            .column("age", "age - 10")
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(violated_rows(err), vec![("age".to_string(), 1)]);
+        table
+            .update()
+            .column("age", "age + 10")
+            .execute()
+            .await
+            .unwrap();
+
+        // Constraints can be replaced, and are stored with the table
+        table.set_constraints(BTreeMap::new()).await.unwrap();
+        let reopened = connect(uri)
+            .execute()
+            .await
+            .unwrap()
+            .open_table("test")
+            .execute()
+            .await
+            .unwrap();
+        assert!(reopened.constraints().await.unwrap().is_empty());
+        reopened
+            .add(people(vec![None], vec!["blue"]))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(count(&reopened).await, 3);
+
+        let err = table
+            .set_constraints(BTreeMap::from([(
+                "missing".to_string(),
+                ColumnConstraint::default().not_null(),
+            )]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_min_max_native_types() {
+        let check = |constraint: &ColumnConstraint, array: &dyn Array| {
+            let mut violations = Vec::new();
+            check_column(
+                "x",
+                constraint,
+                array,
+                |index| index as u64,
+                &mut violations,
+            )
+            .unwrap();
+            violations
+                .into_iter()
+                .map(|violation| violation.row)
+                .collect::<Vec<_>>()
+        };
+
+        // Integers past 2^53 are not rounded to the nearest f64
+        let big = 1i64 << 53;
+        let values = Int64Array::from(vec![big - 1, big, big + 1]);
+        let max = ColumnConstraint::default().max(big as f64);
+        assert_eq!(check(&max, &values), vec![2]);
+
+        // Fractional bounds of integers
+        let values = Int32Array::from(vec![Some(0), Some(1), None, Some(2)]);
+        let between = ColumnConstraint::default().min(0.5).max(1.5);
+        assert_eq!(check(&between, &values), vec![0, 3]);
+
+        // Bounds outside of the range of the type
+        let values = UInt8Array::from(vec![0, 255]);
+        assert!(check(&ColumnConstraint::default().min(-1.0), &values).is_empty());
+        assert!(check(&ColumnConstraint::default().max(1000.0), &values).is_empty());
+        assert_eq!(
+            check(&ColumnConstraint::default().min(1000.0), &values),
+            vec![0, 1]
+        );
+        assert_eq!(
+            check(&ColumnConstraint::default().max(-1.0), &values),
+            vec![0, 1]
+        );
+
+        // Floats are compared exactly, 0.7 as an f32 is a little less than 0.7
+        let values = Float32Array::from(vec![0.7, 0.8]);
+        let min = ColumnConstraint::default().min(0.7);
+        assert_eq!(check(&min, &values), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_constraints_empty_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = people(vec![], vec![]).schema();
+        let table = db
+            .create_empty_table("test", schema)
+            .constraint("color", ColumnConstraint::default().allowed_values(["red"]))
+            .execute()
+            .await
+            .unwrap();
+        let err = table
+            .add(people(vec![Some(1), Some(2)], vec!["red", "blue"]))
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(violated_rows(err), vec![("color".to_string(), 1)]);
+        table
+            .add(people(vec![Some(1)], vec!["red"]))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(count(&table).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_constraints_follow_columns() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let age = ColumnConstraint::default().min(0.0);
+        let color = ColumnConstraint::default().allowed_values(["red"]);
+        let table = db
+            .create_table("test", people(vec![Some(1)], vec!["red"]))
+            .constraint("age", age.clone())
+            .constraint("color", color.clone())
+            .execute()
+            .await
+            .unwrap();
+
+        // Renamed columns keep their constraints
+        table
+            .alter_columns(&[ColumnAlteration::new("age".into()).rename("years".into())])
+            .await
+            .unwrap();
+        assert_eq!(
+            table.constraints().await.unwrap(),
+            BTreeMap::from([
+                ("color".to_string(), color),
+                ("years".to_string(), age.clone())
+            ])
+        );
+        let schema = table.schema().await.unwrap();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![-1])),
+                Arc::new(StringArray::from(vec!["red"])),
+            ],
+        );
+        let err = table
+            .add(RecordBatchIterator::new(vec![batch], schema))
+            .execute()
+            .await
+            .unwrap_err();
+        assert_eq!(violated_rows(err), vec![("years".to_string(), 0)]);
+
+        // Dropped columns lose them
+        table.drop_columns(&["color"]).await.unwrap();
+        assert_eq!(
+            table.constraints().await.unwrap(),
+            BTreeMap::from([("years".to_string(), age)])
+        );
+    }
+}
@@ -22,11 +22,16 @@ use crate::{
     Result,
 };
 
+#[cfg(feature = "vector-search-udtf")]
+mod udtf;
+#[cfg(feature = "vector-search-udtf")]
+pub use udtf::VectorSearchFunction;
+
 /// Datafusion attempts to maintain batch metadata
 ///
 /// This is needless and it triggers bugs in DF.  This operator erases metadata from the batches.
 #[derive(Debug)]
-struct MetadataEraserExec {
+pub(crate) struct MetadataEraserExec {
     input: Arc<dyn ExecutionPlan>,
     schema: Arc<ArrowSchema>,
     properties: PlanProperties,
@@ -46,7 +51,7 @@ impl MetadataEraserExec {
         input_properties.clone().with_eq_properties(eq_properties)
     }
 
-    fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+    pub(crate) fn new(input: Arc<dyn ExecutionPlan>) -> Self {
         let schema = Arc::new(
             input
                 .schema()
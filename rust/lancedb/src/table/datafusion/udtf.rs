@@ -0,0 +1,391 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! A DataFusion table function that runs vector searches
+//!
+//! This is only built with the `vector-search-udtf` feature.
+
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::datatypes::Float32Type;
+use arrow_array::{Array, Float32Array};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema};
+use async_trait::async_trait;
+use datafusion_catalog::{Session, TableFunctionImpl, TableProvider};
+use datafusion_common::{DataFusionError, Result as DataFusionResult, ScalarValue};
+use datafusion_expr::{Expr, TableProviderFilterPushDown, TableType};
+use datafusion_physical_plan::{
+    expressions::Column, projection::ProjectionExec, ExecutionPlan, PhysicalExpr,
+};
+use futures::TryFutureExt;
+use lance::io::exec::Planner;
+use lance_index::vector::DIST_COL;
+
+use super::MetadataEraserExec;
+use crate::query::{QueryExecutionOptions, QueryFilter, Select, VectorQueryRequest, DEFAULT_TOP_K};
+use crate::table::{AnyQuery, BaseTable};
+use crate::Result;
+
+/// A DataFusion table function that runs a vector search on a table
+///
+/// Register it with [`datafusion::prelude::SessionContext::register_udtf`] to
+/// search the table from SQL.  The function takes the query vector, and
+/// optionally the number of results (10 by default) and the name of the vector
+/// column.  The vector can be an array or a string holding a JSON array:
+///
+/// ```sql
+/// SELECT id, _distance FROM search_items('[0.1, 0.2]', 5) WHERE label = 'cat'
+/// ```
+///
+/// The output has the columns of the table and a `_distance` column.  Filters
+/// on the output are applied before the search, and the selected columns are
+/// the only ones read.
+#[derive(Debug)]
+pub struct VectorSearchFunction {
+    table: Arc<dyn BaseTable>,
+    schema: Arc<ArrowSchema>,
+}
+
+impl VectorSearchFunction {
+    /// Create a search function for `table`
+    ///
+    /// The output schema is read from the table once, here, so the function
+    /// doesn't see columns added to the table later.  Returns an error if the
+    /// schema of the table can't be read, for example because the table was
+    /// dropped.
+    pub async fn try_new(table: Arc<dyn BaseTable>) -> Result<Self> {
+        let mut fields = table.schema().await?.fields().to_vec();
+        fields.push(Arc::new(Field::new(DIST_COL, DataType::Float32, true)));
+        Ok(Self {
+            table,
+            schema: Arc::new(ArrowSchema::new(fields)),
+        })
+    }
+}
+
+fn vector_argument(arg: &Expr) -> DataFusionResult<Vec<f32>> {
+    let invalid = || {
+        DataFusionError::Plan(format!(
+            "the query vector must be an array of numbers, got {}",
+            arg
+        ))
+    };
+    let number = |value: &ScalarValue| {
+        value
+            .cast_to(&DataType::Float32)
+            .ok()
+            .and_then(|value| match value {
+                ScalarValue::Float32(Some(value)) => Some(value),
+                _ => None,
+            })
+            .ok_or_else(invalid)
+    };
+    match arg {
+        Expr::Literal(ScalarValue::Utf8(Some(json))) => {
+            serde_json::from_str::<Vec<f32>>(json).map_err(|_| invalid())
+        }
+        Expr::Literal(ScalarValue::List(list)) if list.len() == 1 => {
+            let values = arrow::compute::cast(&list.value(0), &DataType::Float32)?;
+            Ok(values.as_primitive::<Float32Type>().values().to_vec())
+        }
+        Expr::Literal(ScalarValue::FixedSizeList(list)) if list.len() == 1 => {
+            let values = arrow::compute::cast(&list.value(0), &DataType::Float32)?;
+            Ok(values.as_primitive::<Float32Type>().values().to_vec())
+        }
+        Expr::ScalarFunction(function) if function.name() == "make_array" => function
+            .args
+            .iter()
+            .map(|arg| match arg {
+                Expr::Literal(value) => number(value),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+impl TableFunctionImpl for VectorSearchFunction {
+    fn call(&self, args: &[Expr]) -> DataFusionResult<Arc<dyn TableProvider>> {
+        let (vector, limit, column) = match args {
+            [vector] => (vector, None, None),
+            [vector, limit] => (vector, Some(limit), None),
+            [vector, limit, column] => (vector, Some(limit), Some(column)),
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "expected the query vector, and optionally the number of results and the \
+                     vector column"
+                        .to_string(),
+                ))
+            }
+        };
+        let vector = vector_argument(vector)?;
+        let limit = match limit {
+            None => DEFAULT_TOP_K,
+            Some(Expr::Literal(value)) if value.data_type().is_integer() => {
+                match value.cast_to(&DataType::UInt64)? {
+                    ScalarValue::UInt64(Some(limit)) => limit as usize,
+                    _ => DEFAULT_TOP_K,
+                }
+            }
+            Some(limit) => {
+                return Err(DataFusionError::Plan(format!(
+                    "the number of results must be an integer, got {}",
+                    limit
+                )))
+            }
+        };
+        let column = match column {
+            None => None,
+            Some(Expr::Literal(ScalarValue::Utf8(Some(column)))) => Some(column.clone()),
+            Some(column) => {
+                return Err(DataFusionError::Plan(format!(
+                    "the vector column must be a string, got {}",
+                    column
+                )))
+            }
+        };
+        Ok(Arc::new(VectorSearchProvider {
+            table: self.table.clone(),
+            schema: self.schema.clone(),
+            vector: Arc::new(Float32Array::from(vector)),
+            limit,
+            column,
+        }))
+    }
+}
+
+/// The results of a vector search, see [`VectorSearchFunction`]
+#[derive(Debug)]
+struct VectorSearchProvider {
+    table: Arc<dyn BaseTable>,
+    schema: Arc<ArrowSchema>,
+    vector: Arc<dyn Array>,
+    limit: usize,
+    column: Option<String>,
+}
+
+#[async_trait]
+impl TableProvider for VectorSearchProvider {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> Arc<ArrowSchema> {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Temporary
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let output = projection
+            .cloned()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect())
+            .iter()
+            .map(|i| self.schema.field(*i).name().clone())
+            .collect::<Vec<_>>();
+        let mut query = VectorQueryRequest {
+            column: self.column.clone(),
+            query_vector: vec![self.vector.clone()],
+            ..Default::default()
+        };
+        let columns = output
+            .iter()
+            .filter(|name| *name != DIST_COL)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !columns.is_empty() {
+            query.base.select = Select::Columns(columns);
+        }
+        if let Some(filter) = filters.iter().cloned().reduce(|acc, expr| acc.and(expr)) {
+            query.base.filter = Some(QueryFilter::Datafusion(filter));
+        }
+        query.base.limit = Some(limit.map_or(self.limit, |limit| limit.min(self.limit)));
+
+        let options = QueryExecutionOptions {
+            max_batch_length: state.config().batch_size() as u32,
+            ..Default::default()
+        };
+        let plan = self
+            .table
+            .create_plan(&AnyQuery::VectorQuery(query), options)
+            .map_err(|err| DataFusionError::External(err.into()))
+            .await?;
+        // The search adds `_distance` after the selected columns, so the
+        // columns are put in the requested order
+        let schema = plan.schema();
+        let exprs = output
+            .into_iter()
+            .map(|name| {
+                let index = schema.index_of(&name)?;
+                Ok((
+                    Arc::new(Column::new(&name, index)) as Arc<dyn PhysicalExpr>,
+                    name,
+                ))
+            })
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        let plan = Arc::new(ProjectionExec::try_new(exprs, plan)?);
+        Ok(Arc::new(MetadataEraserExec::new(plan)))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        // Pushed down filters select the rows that are searched, so filters on
+        // `_distance`, and filters Lance can't run, are applied to the results
+        let table_schema = Arc::new(ArrowSchema::new(
+            self.schema
+                .fields()
+                .iter()
+                .filter(|field| field.name() != DIST_COL)
+                .cloned()
+                .collect::<Vec<_>>(),
+        ));
+        let planner = Planner::new(table_schema);
+        Ok(filters
+            .iter()
+            .map(|filter| {
+                let on_distance = filter
+                    .column_refs()
+                    .iter()
+                    .any(|column| column.name == DIST_COL);
+                let supported = !on_distance
+                    && planner
+                        .optimize_expr((*filter).clone())
+                        .and_then(|expr| planner.create_physical_expr(&expr))
+                        .is_ok();
+                if supported {
+                    TableProviderFilterPushDown::Exact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::AsArray;
+    use arrow::compute::concat_batches;
+    use arrow::datatypes::{Float32Type, Int32Type, Int64Type};
+    use arrow_array::{ArrayRef, FixedSizeListArray, Int32Array, RecordBatch, RecordBatchIterator};
+    use datafusion::prelude::SessionContext;
+    use tempfile::tempdir;
+
+    use crate::{connect, Table};
+
+    async fn make_items(uri: &str) -> Table {
+        let db = connect(uri).execute().await.unwrap();
+        let vectors = (0..10).map(|i| Some(vec![Some(i as f32), Some(i as f32)]));
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from_iter_values(0..10)) as ArrayRef,
+            ),
+            (
+                "vector",
+                Arc::new(FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vectors, 2))
+                    as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+        db.create_table("items", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .execute()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_function() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_items(tmp_dir.path().to_str().unwrap()).await;
+
+        let ctx = SessionContext::new();
+        ctx.register_table("items", table.as_datafusion_provider().await.unwrap())
+            .unwrap();
+        ctx.register_udtf(
+            "search_items",
+            table.vector_search_function().await.unwrap(),
+        );
+
+        let results = ctx
+            .sql(
+                "SELECT id, _distance FROM search_items('[0, 0]', 3) \
+                 WHERE id % 2 = 0 ORDER BY _distance",
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(
+            results["id"].as_primitive::<Int32Type>().values().to_vec(),
+            vec![0, 2, 4]
+        );
+        assert_eq!(
+            results["_distance"]
+                .as_primitive::<Float32Type>()
+                .values()
+                .to_vec(),
+            vec![0.0, 8.0, 32.0]
+        );
+
+        // The results can be joined with the table
+        let results = ctx
+            .sql(
+                "SELECT count(*) AS n FROM search_items('[9, 9]', 2) AS s \
+                 JOIN items ON s.id = items.id",
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(results[0]["n"].as_primitive::<Int64Type>().value(0), 2);
+
+        let err = ctx.sql("SELECT * FROM search_items('cat')").await;
+        assert!(err.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vector_search_function_distance_filter() {
+        let tmp_dir = tempdir().unwrap();
+        let table = make_items(tmp_dir.path().to_str().unwrap()).await;
+        let ctx = SessionContext::new();
+        ctx.register_udtf(
+            "search_items",
+            table.vector_search_function().await.unwrap(),
+        );
+
+        // A filter on the distance is applied to the results of the search
+        let results = ctx
+            .sql(
+                "SELECT id FROM search_items('[0, 0]', 5) \
+                 WHERE _distance > 1 AND id < 4 ORDER BY id",
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let results = concat_batches(&results[0].schema(), &results).unwrap();
+        assert_eq!(
+            results["id"].as_primitive::<Int32Type>().values().to_vec(),
+            vec![1, 2, 3]
+        );
+    }
+}
@@ -0,0 +1,402 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions, RecordBatchReader};
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, Field, Schema, SchemaRef};
+use datafusion_physical_plan::PhysicalExpr;
+use lance::dataset::BatchUDF;
+use lance::io::exec::Planner;
+
+use crate::error::{Error, Result};
+
+/// The field metadata key the default expression of a column is stored under
+pub const DEFAULT_METADATA_KEY: &str = "lancedb::default";
+
+/// The default expressions of the columns of a schema, by column name
+pub(crate) fn defaults_from_schema(schema: &Schema) -> BTreeMap<String, String> {
+    schema
+        .fields()
+        .iter()
+        .filter_map(|field| {
+            let default = field.metadata().get(DEFAULT_METADATA_KEY)?;
+            Some((field.name().clone(), default.clone()))
+        })
+        .collect()
+}
+
+/// A field with the given default expression stored in its metadata
+pub(crate) fn with_default(field: &Field, default: &str) -> Field {
+    let mut metadata = field.metadata().clone();
+    metadata.insert(DEFAULT_METADATA_KEY.to_string(), default.to_string());
+    field.clone().with_metadata(metadata)
+}
+
+/// A compiled default expression of a column
+struct ColumnDefault {
+    field: Field,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl ColumnDefault {
+    /// Compile the default expression of `field`
+    ///
+    /// Defaults can't refer to other columns, so they are planned against an
+    /// empty schema.  The value is evaluated once to check that it can be
+    /// cast to the type of the column.
+    fn try_new(field: &Field, default: &str) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidInput {
+            message: format!(
+                "invalid default {} for column {}: {}",
+                default,
+                field.name(),
+                message
+            ),
+        };
+        let planner = Planner::new(Arc::new(Schema::empty()));
+        let expr = planner
+            .parse_expr(default)
+            .and_then(|expr| planner.optimize_expr(expr))
+            .and_then(|expr| planner.create_physical_expr(&expr))
+            .map_err(|err| invalid(err.to_string()))?;
+        let column_default = Self {
+            field: field.clone(),
+            expr,
+        };
+        column_default
+            .evaluate(1)
+            .map_err(|err| invalid(err.to_string()))?;
+        Ok(column_default)
+    }
+
+    fn evaluate(&self, num_rows: usize) -> std::result::Result<ArrayRef, ArrowError> {
+        let batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::empty()),
+            vec![],
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?;
+        let values = self
+            .expr
+            .evaluate(&batch)
+            .and_then(|values| values.into_array(num_rows))
+            .map_err(|err| ArrowError::ComputeError(err.to_string()))?;
+        // Fail instead of writing nulls if the value doesn't fit the column
+        let options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+        cast_with_options(&values, self.field.data_type(), &options)
+    }
+}
+
+/// Check that the default expressions can be used for their columns
+pub(crate) fn validate_defaults<'a>(
+    defaults: impl IntoIterator<Item = (&'a Field, &'a str)>,
+) -> Result<()> {
+    for (field, default) in defaults {
+        ColumnDefault::try_new(field, default)?;
+    }
+    Ok(())
+}
+
+/// Fill in the columns of a table that are missing from the data written to
+/// it and have a default, if any
+pub(crate) fn with_defaults(
+    table_schema: &Schema,
+    data: Box<dyn RecordBatchReader + Send>,
+) -> Result<Box<dyn RecordBatchReader + Send>> {
+    let data_schema = data.schema();
+    let has_missing_defaults = table_schema.fields().iter().any(|field| {
+        field.metadata().contains_key(DEFAULT_METADATA_KEY)
+            && data_schema.column_with_name(field.name()).is_none()
+    });
+    if !has_missing_defaults {
+        return Ok(data);
+    }
+    Ok(Box::new(WithDefaults::try_new(data, table_schema)?))
+}
+
+/// Where a column of a [`WithDefaults`] reader comes from
+enum OutputColumn {
+    Input(usize),
+    Default(ColumnDefault),
+}
+
+/// A record batch reader that fills in the columns missing from another
+/// reader with their default values
+///
+/// The columns are ordered as in the table, followed by any columns of the
+/// data that the table doesn't have.  The output schema has the field metadata
+/// of the table, so this can also be used to store defaults with new data.
+pub(crate) struct WithDefaults {
+    inner: Box<dyn RecordBatchReader + Send>,
+    columns: Vec<OutputColumn>,
+    schema: SchemaRef,
+}
+
+impl WithDefaults {
+    pub(crate) fn try_new(
+        inner: Box<dyn RecordBatchReader + Send>,
+        table_schema: &Schema,
+    ) -> Result<Self> {
+        let input_schema = inner.schema();
+        let mut fields = Vec::new();
+        let mut columns = Vec::new();
+        for field in table_schema.fields() {
+            match input_schema.column_with_name(field.name()) {
+                Some((index, input_field)) => {
+                    fields.push(
+                        input_field
+                            .as_ref()
+                            .clone()
+                            .with_metadata(field.metadata().clone()),
+                    );
+                    columns.push(OutputColumn::Input(index));
+                }
+                None => {
+                    let Some(default) = field.metadata().get(DEFAULT_METADATA_KEY) else {
+                        continue;
+                    };
+                    fields.push(field.as_ref().clone());
+                    columns.push(OutputColumn::Default(ColumnDefault::try_new(
+                        field, default,
+                    )?));
+                }
+            }
+        }
+        for (index, field) in input_schema.fields().iter().enumerate() {
+            if table_schema.column_with_name(field.name()).is_none() {
+                fields.push(field.as_ref().clone());
+                columns.push(OutputColumn::Input(index));
+            }
+        }
+        let schema = Arc::new(Schema::new_with_metadata(
+            fields,
+            input_schema.metadata().clone(),
+        ));
+        Ok(Self {
+            inner,
+            columns,
+            schema,
+        })
+    }
+
+    fn fill(&self, batch: RecordBatch) -> std::result::Result<RecordBatch, ArrowError> {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                OutputColumn::Input(index) => Ok(batch.column(*index).clone()),
+                OutputColumn::Default(default) => default.evaluate(batch.num_rows()),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        RecordBatch::try_new_with_options(
+            self.schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )
+    }
+}
+
+impl Iterator for WithDefaults {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = match self.inner.next()? {
+            Ok(batch) => batch,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(self.fill(batch))
+    }
+}
+
+impl RecordBatchReader for WithDefaults {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// A transform for [`super::Table::add_columns`] that fills new columns with
+/// their default values
+///
+/// The defaults are stored in the metadata of the new fields.
+pub(crate) fn default_columns_transform(columns: &[(Field, String)]) -> Result<BatchUDF> {
+    let defaults = columns
+        .iter()
+        .map(|(field, default)| ColumnDefault::try_new(&with_default(field, default), default))
+        .collect::<Result<Vec<_>>>()?;
+    let output_schema = Arc::new(Schema::new(
+        defaults
+            .iter()
+            .map(|default| default.field.clone())
+            .collect::<Vec<_>>(),
+    ));
+    let schema = output_schema.clone();
+    let mapper = move |batch: &RecordBatch| -> lance::Result<RecordBatch> {
+        let columns = defaults
+            .iter()
+            .map(|default| default.evaluate(batch.num_rows()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            schema.clone(),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
+    };
+    Ok(BatchUDF {
+        mapper: Box::new(mapper),
+        output_schema,
+        result_checkpoint: None,
+    })
+}
+
+/// The metadata of a field after setting or removing its default
+pub(crate) fn default_field_metadata(
+    field: &Field,
+    default: Option<&str>,
+) -> HashMap<String, String> {
+    let mut metadata = field.metadata().clone();
+    match default {
+        Some(default) => {
+            metadata.insert(DEFAULT_METADATA_KEY.to_string(), default.to_string());
+        }
+        None => {
+            metadata.remove(DEFAULT_METADATA_KEY);
+        }
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+    use arrow_array::{Int32Array, RecordBatchIterator, StringArray};
+    use arrow_schema::DataType;
+    use futures::TryStreamExt;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::{ExecutableQuery, QueryBase, Select};
+    use crate::Table;
+
+    fn ids(values: Vec<i32>) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))]).unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    async fn column(table: &Table, name: &str) -> Vec<String> {
+        let batches = table
+            .query()
+            .select(Select::columns(&["id", name]))
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut rows = Vec::new();
+        for batch in batches {
+            let values = arrow_cast::cast(&batch[name], &DataType::Utf8).unwrap();
+            let ids = batch["id"].as_primitive::<Int32Type>();
+            for (id, value) in ids.values().iter().zip(values.as_string::<i32>().iter()) {
+                rows.push((*id, value.unwrap_or("null").to_string()));
+            }
+        }
+        rows.sort();
+        rows.into_iter().map(|(_, value)| value).collect()
+    }
+
+    #[tokio::test]
+    async fn test_column_defaults() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("status", DataType::Utf8, true),
+        ]));
+        let table = db
+            .create_empty_table("test", schema)
+            .column_default("status", "'new'")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(
+            table.column_defaults().await.unwrap(),
+            BTreeMap::from([("status".to_string(), "'new'".to_string())])
+        );
+
+        // Batches without the column get the default
+        table.add(ids(vec![1, 2])).execute().await.unwrap();
+        assert_eq!(column(&table, "status").await, vec!["new", "new"]);
+
+        // New columns are backfilled with their default, which is kept
+        table
+            .add_columns_with_defaults(vec![(
+                Field::new("score", DataType::Int32, false),
+                "40 + 2".to_string(),
+            )])
+            .await
+            .unwrap();
+        assert_eq!(column(&table, "score").await, vec!["42", "42"]);
+        table.add(ids(vec![3])).execute().await.unwrap();
+        assert_eq!(column(&table, "score").await, vec!["42", "42", "42"]);
+
+        // Defaults can be changed and removed
+        table.set_column_default("score", Some("7")).await.unwrap();
+        table.add(ids(vec![4])).execute().await.unwrap();
+        assert_eq!(column(&table, "score").await, vec!["42", "42", "42", "7"]);
+        assert_eq!(column(&table, "status").await, vec!["new"; 4]);
+        table.set_column_default("status", None).await.unwrap();
+        assert_eq!(
+            table.column_defaults().await.unwrap(),
+            BTreeMap::from([("score".to_string(), "7".to_string())])
+        );
+
+        // Defaults can't refer to columns and must fit the column type
+        let err = table
+            .set_column_default("score", Some("id + 1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+        let err = table
+            .set_column_default("score", Some("'abc'"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_column_defaults_initial_data() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1])),
+                Arc::new(StringArray::from(vec!["a"])),
+            ],
+        )
+        .unwrap();
+        let table = db
+            .create_table("test", RecordBatchIterator::new(vec![Ok(batch)], schema))
+            .column_default("name", "'unnamed'")
+            .execute()
+            .await
+            .unwrap();
+        table.add(ids(vec![2])).execute().await.unwrap();
+        assert_eq!(column(&table, "name").await, vec!["a", "unnamed"]);
+    }
+}
@@ -1,11 +1,17 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-FileCopyrightText: Copyright The LanceDB Authors
 
+use std::collections::HashSet;
+use std::future::Future;
 use std::sync::Arc;
 
-use arrow_array::RecordBatchReader;
+use arrow::compute::filter_record_batch;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use arrow_array::{BooleanArray, RecordBatch, RecordBatchReader};
+use arrow_schema::{ArrowError, SchemaRef};
 
-use crate::Result;
+use crate::arrow::IntoArrow;
+use crate::error::{Error, Result};
 
 use super::BaseTable;
 
@@ -21,10 +27,11 @@ pub struct MergeInsertBuilder {
     pub(crate) when_not_matched_insert_all: bool,
     pub(crate) when_not_matched_by_source_delete: bool,
     pub(crate) when_not_matched_by_source_delete_filt: Option<String>,
+    pub(crate) deduplicate_source: bool,
 }
 
 impl MergeInsertBuilder {
-    pub(super) fn new(table: Arc<dyn BaseTable>, on: Vec<String>) -> Self {
+    pub(crate) fn new(table: Arc<dyn BaseTable>, on: Vec<String>) -> Self {
         Self {
             table,
             on,
@@ -33,6 +40,7 @@ impl MergeInsertBuilder {
             when_not_matched_insert_all: false,
             when_not_matched_by_source_delete: false,
             when_not_matched_by_source_delete_filt: None,
+            deduplicate_source: false,
         }
     }
 
@@ -84,10 +92,96 @@ impl MergeInsertBuilder {
         self
     }
 
+    /// Rows of the source table (new data) whose keys repeat those of an
+    /// earlier source row will be dropped.
+    ///
+    /// Only the first row with each key is merged.  Combined with
+    /// [`Self::when_not_matched_insert_all`] and no other clause this is an
+    /// idempotent insert-if-absent, which is useful when the same events may
+    /// be delivered more than once.  The keys of every source row are kept
+    /// in memory while the operation runs.
+    pub fn deduplicate_source(&mut self) -> &mut Self {
+        self.deduplicate_source = true;
+        self
+    }
+
     /// Executes the merge insert operation
     ///
+    /// The builder is not consumed and the returned future does not borrow
+    /// it, so the clauses can be chained and executed in a single expression.
+    /// See [`super::Table::merge_insert`] for an example.
+    ///
     /// Nothing is returned but the [`super::Table`] is updated
-    pub async fn execute(self, new_data: Box<dyn RecordBatchReader + Send>) -> Result<()> {
-        self.table.clone().merge_insert(self, new_data).await
+    pub fn execute(&self, new_data: impl IntoArrow) -> impl Future<Output = Result<()>> + Send {
+        let params = self.clone();
+        let new_data = new_data.into_arrow();
+        async move {
+            let table = params.table.clone();
+            table.merge_insert(params, new_data?).await
+        }
+    }
+}
+
+/// Drops rows whose key columns repeat those of an earlier row
+pub(crate) struct DeduplicatedReader {
+    inner: Box<dyn RecordBatchReader + Send>,
+    key_indices: Vec<usize>,
+    converter: RowConverter,
+    seen: HashSet<OwnedRow>,
+}
+
+impl DeduplicatedReader {
+    pub(crate) fn try_new(inner: Box<dyn RecordBatchReader + Send>, on: &[String]) -> Result<Self> {
+        let schema = inner.schema();
+        let key_indices = on
+            .iter()
+            .map(|name| {
+                schema.index_of(name).map_err(|_| Error::InvalidInput {
+                    message: format!("the key column {} is not in the new data", name),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let converter = RowConverter::new(
+            key_indices
+                .iter()
+                .map(|index| SortField::new(schema.field(*index).data_type().clone()))
+                .collect(),
+        )?;
+        Ok(Self {
+            inner,
+            key_indices,
+            converter,
+            seen: HashSet::new(),
+        })
+    }
+
+    fn deduplicate(&mut self, batch: RecordBatch) -> std::result::Result<RecordBatch, ArrowError> {
+        let keys = self
+            .key_indices
+            .iter()
+            .map(|index| batch.column(*index).clone())
+            .collect::<Vec<_>>();
+        let rows = self.converter.convert_columns(&keys)?;
+        let first_seen = rows
+            .iter()
+            .map(|row| self.seen.insert(row.owned()))
+            .collect::<Vec<_>>();
+        filter_record_batch(&batch, &BooleanArray::from(first_seen))
+    }
+}
+
+impl Iterator for DeduplicatedReader {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|batch| batch.and_then(|batch| self.deduplicate(batch)))
+    }
+}
+
+impl RecordBatchReader for DeduplicatedReader {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
     }
 }
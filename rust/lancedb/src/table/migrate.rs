@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow_schema::Schema;
+use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion_physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use lance::dataset::{CommitBuilder, InsertBuilder, WriteMode, WriteParams};
+use lance_encoding::version::LanceFileVersion;
+use lance_index::DatasetIndexExt;
+
+use crate::database::observer::WriteOperation;
+use crate::error::{Error, Result};
+
+use super::{local_table, BaseTable};
+
+/// How far a format migration has got, see [`MigrateFormatBuilder::progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationProgress {
+    /// The number of rows rewritten so far
+    pub rows_written: usize,
+    /// The number of rows in the table
+    pub total_rows: usize,
+}
+
+/// The result of a format migration, see [`super::Table::migrate_format`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatMigration {
+    /// The file format the table used before
+    pub from: LanceFileVersion,
+    /// The file format the table uses now
+    ///
+    /// This is the same as `from` if the table already used the format.
+    pub to: LanceFileVersion,
+    /// The number of rows that were rewritten
+    pub num_rows: usize,
+    /// The indices that were dropped and have to be created again
+    pub dropped_indices: Vec<String>,
+}
+
+type ProgressCallback = Arc<dyn Fn(MigrationProgress) + Send + Sync>;
+
+/// A builder for rewriting a table in a newer file format, see
+/// [`super::Table::migrate_format`]
+pub struct MigrateFormatBuilder {
+    table: Arc<dyn BaseTable>,
+    target_version: LanceFileVersion,
+    progress: Option<ProgressCallback>,
+}
+
+impl MigrateFormatBuilder {
+    pub(super) fn new(table: Arc<dyn BaseTable>, target_version: LanceFileVersion) -> Self {
+        Self {
+            table,
+            target_version,
+            progress: None,
+        }
+    }
+
+    /// Call `callback` after each batch of rows is read for rewriting
+    ///
+    /// The callback runs on the task doing the migration, so it should be
+    /// quick.
+    pub fn progress(
+        mut self,
+        callback: impl Fn(MigrationProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Rewrite all of the data of the table in the target file format
+    ///
+    /// The rewrite is committed as an overwrite, so the indices of the table
+    /// are dropped and are listed in the result.  If the table already uses
+    /// the target format nothing is written.
+    ///
+    /// Returns [`Error::NotSupported`] for remote tables, [`Error::ReadOnly`]
+    /// for tables of a read-only database, [`Error::InvalidInput`] if the
+    /// target format is older than the table's current format, and
+    /// [`Error::CommitConflict`] if the table was changed while it was being
+    /// rewritten, in which case the table is unchanged.
+    pub async fn execute(self) -> Result<FormatMigration> {
+        let native = local_table(
+            self.table.as_ref(),
+            Some(WriteOperation::MigrateFormat),
+            "migrating the file format",
+        )?;
+        let target = self.target_version.resolve();
+
+        // The rewrite can take a long time, so it reads a snapshot of the
+        // table without holding it, which is only locked to commit
+        native.dataset.ensure_mutable().await?;
+        let read_version = Arc::new(native.dataset.get().await?.clone());
+        let from = read_version
+            .manifest()
+            .data_storage_format
+            .lance_file_version()?
+            .resolve();
+        if target < from {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "cannot migrate the table from file format {} to the older format {}",
+                    from, target
+                ),
+            });
+        }
+        if target == from {
+            return Ok(FormatMigration {
+                from,
+                to: from,
+                num_rows: 0,
+                dropped_indices: Vec::new(),
+            });
+        }
+
+        let dropped_indices = read_version
+            .load_indices()
+            .await?
+            .iter()
+            .map(|index| index.name.clone())
+            .collect();
+        let total_rows = read_version.count_rows(None).await?;
+        let schema = Arc::new(Schema::from(read_version.schema()));
+        let rows_written = Arc::new(AtomicUsize::new(0));
+        let progress = self.progress.clone();
+        let scanned: SendableRecordBatchStream =
+            read_version.scan().try_into_stream().await?.into();
+        let counter = rows_written.clone();
+        // The scan may drop the schema metadata, so the table's schema is
+        // given to the writer, to keep the metadata
+        let data = Box::pin(RecordBatchStreamAdapter::new(
+            schema,
+            scanned.inspect_ok(move |batch| {
+                let rows_written =
+                    counter.fetch_add(batch.num_rows(), Ordering::Relaxed) + batch.num_rows();
+                if let Some(progress) = &progress {
+                    progress(MigrationProgress {
+                        rows_written,
+                        total_rows,
+                    });
+                }
+            }),
+        ));
+
+        let params = WriteParams {
+            mode: WriteMode::Overwrite,
+            data_storage_version: Some(target),
+            enable_move_stable_row_ids: read_version.manifest().uses_move_stable_row_ids(),
+            ..Default::default()
+        };
+        let transaction = InsertBuilder::new(read_version.clone())
+            .with_params(&params)
+            .execute_uncommitted_stream(data as SendableRecordBatchStream)
+            .await?;
+        let migration = FormatMigration {
+            from,
+            to: target,
+            num_rows: rows_written.load(Ordering::Relaxed),
+            dropped_indices,
+        };
+
+        let dataset = {
+            let _ds = native.dataset.get_mut().await?;
+            // An overwrite never conflicts with other writes, so a write
+            // committed during the rewrite would be lost
+            let latest = read_version.latest_version_id().await?;
+            if latest != read_version.version().version {
+                return Err(Error::CommitConflict {
+                    version: Some(latest),
+                    message: "the table was changed while its file format was migrated".to_string(),
+                    request_id: None,
+                });
+            }
+            CommitBuilder::new(read_version)
+                .with_storage_format(target)
+                .execute(transaction)
+                .await?
+        };
+
+        native.dataset.set_latest(dataset).await;
+        self.table
+            .native_write_committed(WriteOperation::MigrateFormat);
+        Ok(migration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
+    use arrow_schema::{DataType, Field};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::database::listing::{ListingDatabaseOptions, NewTableConfig};
+    use crate::index::Index;
+    use crate::table::constraints::ColumnConstraint;
+
+    #[tokio::test]
+    async fn test_migrate_format() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri)
+            .database_options(&ListingDatabaseOptions {
+                new_table_config: NewTableConfig {
+                    data_storage_version: Some(LanceFileVersion::Legacy),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batches = (0..4)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(
+                        i * 100..(i + 1) * 100,
+                    ))],
+                )
+            })
+            .collect::<Vec<_>>();
+        let table = db
+            .create_table("test", RecordBatchIterator::new(batches, schema))
+            .constraint(
+                "id",
+                ColumnConstraint {
+                    min: Some(0.0),
+                    ..Default::default()
+                },
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .create_index(&["id"], Index::BTree(Default::default()))
+            .execute()
+            .await
+            .unwrap();
+
+        // Migrating to the current format does nothing
+        let unchanged = table
+            .migrate_format(LanceFileVersion::Legacy)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(unchanged.from, unchanged.to);
+        assert_eq!(unchanged.num_rows, 0);
+
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let seen = updates.clone();
+        let migration = table
+            .migrate_format(LanceFileVersion::Stable)
+            .progress(move |progress| seen.lock().unwrap().push(progress))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(migration.from, LanceFileVersion::Legacy);
+        assert_eq!(migration.to, LanceFileVersion::Stable.resolve());
+        assert_eq!(migration.num_rows, 400);
+        assert_eq!(migration.dropped_indices, vec!["id_idx".to_string()]);
+        let updates = updates.lock().unwrap();
+        assert_eq!(
+            updates.last(),
+            Some(&MigrationProgress {
+                rows_written: 400,
+                total_rows: 400,
+            })
+        );
+
+        let native = table.as_native().unwrap();
+        let format = native
+            .manifest()
+            .await
+            .unwrap()
+            .data_storage_format
+            .lance_file_version()
+            .unwrap();
+        assert_eq!(format.resolve(), LanceFileVersion::Stable.resolve());
+        assert_eq!(table.count_rows(None).await.unwrap(), 400);
+        assert!(table.list_indices().await.unwrap().is_empty());
+        // The schema metadata is kept
+        assert_eq!(table.constraints().await.unwrap().len(), 1);
+
+        let err = table
+            .migrate_format(LanceFileVersion::Legacy)
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+}
@@ -0,0 +1,695 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use arrow::compute::{cast_with_options, concat_batches, take_record_batch, CastOptions};
+use arrow::row::{RowConverter, SortField};
+use arrow::util::display::array_value_to_string;
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, RecordBatch, StringArray, UInt32Array};
+use arrow_schema::{ArrowError, DataType, Schema, SchemaRef};
+use datafusion_common::DataFusionError;
+use datafusion_execution::SendableRecordBatchStream;
+use datafusion_expr::utils::{conjunction, split_conjunction};
+use datafusion_expr::Expr;
+use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt, TryStreamExt};
+use lance::dataset::transaction::{Operation, Transaction};
+use lance::dataset::{CommitBuilder, InsertBuilder, WriteDestination, WriteParams};
+use lance::io::exec::Planner;
+use lance::Dataset;
+use lance_datafusion::utils::StreamingWriteSource;
+use lance_table::format::Fragment;
+
+use crate::error::{Error, Result};
+use crate::query::QueryFilter;
+
+/// The schema metadata key the partition columns of a table are stored under
+pub const PARTITION_METADATA_KEY: &str = "lancedb::partition_columns";
+
+/// The prefix of the config keys that record the partition values of the
+/// fragments written to a partitioned table, by the path of their first data
+/// file
+const PARTITION_VALUES_PREFIX: &str = "lancedb::partition_values::";
+
+/// The partition columns stored in a table's schema
+pub(crate) fn partition_columns(schema: &Schema) -> Result<Vec<String>> {
+    match schema.metadata.get(PARTITION_METADATA_KEY) {
+        Some(columns) => serde_json::from_str(columns).map_err(|e| Error::Runtime {
+            message: format!("Failed to deserialize partition columns: {}", e),
+        }),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The schema metadata that stores the given partition columns
+pub(crate) fn partition_metadata(columns: &[String]) -> (String, String) {
+    // We have full control over the structure of the columns, so this should
+    // not fail except for a bug
+    (
+        PARTITION_METADATA_KEY.to_string(),
+        serde_json::to_string(columns).unwrap(),
+    )
+}
+
+/// Check that the columns exist and can be used to partition a table
+pub(crate) fn validate_partition_columns(schema: &Schema, columns: &[String]) -> Result<()> {
+    for column in columns {
+        let (_, field) = schema
+            .column_with_name(column)
+            .ok_or_else(|| Error::InvalidInput {
+                message: format!("the partition column {} is not in the table", column),
+            })?;
+        if field.data_type().is_nested()
+            || matches!(
+                field.data_type(),
+                DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_)
+            )
+        {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "the partition column {} must have a primitive or string type, not {}",
+                    column,
+                    field.data_type()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The config entry that records the partition values of the first row of
+/// `values` for the fragment whose first data file is `path`
+fn partition_values_entry(path: &str, values: &RecordBatch) -> Result<(String, String)> {
+    let values = values
+        .columns()
+        .iter()
+        .map(|column| {
+            if column.is_null(0) {
+                Ok(None)
+            } else {
+                array_value_to_string(column, 0).map(Some)
+            }
+        })
+        .collect::<std::result::Result<Vec<_>, ArrowError>>()?;
+    // A list of strings can always be serialized
+    Ok((
+        format!("{}{}", PARTITION_VALUES_PREFIX, path),
+        serde_json::to_string(&values).unwrap(),
+    ))
+}
+
+/// The partition values recorded for a fragment when it was written, if any
+fn recorded_values(
+    dataset: &Dataset,
+    fragment: &Fragment,
+    partition_schema: &SchemaRef,
+) -> Option<RecordBatch> {
+    let path = &fragment.files.first()?.path;
+    let recorded = dataset
+        .manifest()
+        .config
+        .get(&format!("{}{}", PARTITION_VALUES_PREFIX, path))?;
+    let values = serde_json::from_str::<Vec<Option<String>>>(recorded).ok()?;
+    if values.len() != partition_schema.fields().len() {
+        return None;
+    }
+    // A value that doesn't cast back exactly must not prune the fragment
+    let options = CastOptions {
+        safe: false,
+        ..Default::default()
+    };
+    let columns = partition_schema
+        .fields()
+        .iter()
+        .zip(values)
+        .map(|(field, value)| {
+            cast_with_options(&StringArray::from(vec![value]), field.data_type(), &options)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .ok()?;
+    RecordBatch::try_new(partition_schema.clone(), columns).ok()
+}
+
+/// The number of batches of each partition that are buffered while they wait
+/// to be written
+const PARTITION_BUFFER: usize = 2;
+
+/// The destination of a partitioned write, owned so that each partition can
+/// be written by its own task
+#[derive(Clone)]
+enum Destination {
+    Dataset(Arc<Dataset>),
+    Uri(String),
+}
+
+impl Destination {
+    fn as_write_destination(&self) -> WriteDestination<'_> {
+        match self {
+            Self::Dataset(dataset) => WriteDestination::Dataset(dataset.clone()),
+            Self::Uri(uri) => WriteDestination::Uri(uri),
+        }
+    }
+}
+
+/// Write a stream of batches to new fragments, without committing them
+async fn write_partition(
+    dest: Destination,
+    params: WriteParams,
+    schema: SchemaRef,
+    batches: impl Stream<Item = RecordBatch> + Send + 'static,
+) -> Result<Transaction> {
+    let stream = Box::pin(RecordBatchStreamAdapter::new(
+        schema,
+        batches.map(Ok::<_, DataFusionError>),
+    )) as SendableRecordBatchStream;
+    Ok(InsertBuilder::new(dest.as_write_destination())
+        .with_params(&params)
+        .execute_uncommitted_stream(stream)
+        .await?)
+}
+
+/// Write the data to new fragments that each hold one partition, without
+/// committing them
+///
+/// Returns a single append or overwrite of all of the fragments, depending on
+/// the mode in `params`, and the partition values of the new fragments.  An
+/// overwrite records the values in the table config itself, for an append
+/// they must be recorded with [`record_partition_values`] before it is
+/// committed.  The data is streamed: each batch is split by partition and the
+/// parts are sent to a writer for their partition, so only a few batches of
+/// each partition are held in memory.
+pub(crate) async fn write_partitioned(
+    dest: WriteDestination<'_>,
+    params: &WriteParams,
+    data: impl StreamingWriteSource,
+    columns: &[String],
+) -> Result<(Transaction, HashMap<String, String>)> {
+    let schema = data.arrow_schema();
+    let mut data = data.into_stream();
+    let dest = match dest {
+        WriteDestination::Dataset(dataset) => Destination::Dataset(dataset),
+        WriteDestination::Uri(uri) => Destination::Uri(uri.to_string()),
+    };
+    let indices = columns
+        .iter()
+        .map(|column| schema.index_of(column))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let converter = RowConverter::new(
+        indices
+            .iter()
+            .map(|index| SortField::new(schema.field(*index).data_type().clone()))
+            .collect(),
+    )?;
+
+    // The partitions are numbered in the order they first appear, and each
+    // has the values of its partition columns, a sender and a writer
+    let mut partition_of_row = HashMap::new();
+    let mut partition_values = Vec::new();
+    let mut senders: Vec<mpsc::Sender<RecordBatch>> = Vec::new();
+    let mut writers = Vec::new();
+    let mut routed = Ok(());
+    while let Some(batch) = data.next().await {
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(err) => {
+                routed = Err(Error::Runtime {
+                    message: format!("failed to read the data to write: {}", err),
+                });
+                break;
+            }
+        };
+        let keys = indices
+            .iter()
+            .map(|index| batch.column(*index).clone())
+            .collect::<Vec<_>>();
+        let rows = converter.convert_columns(&keys)?;
+        let mut rows_by_partition = HashMap::<usize, Vec<u32>>::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            let partition = match partition_of_row.get(&row) {
+                Some(partition) => *partition,
+                None => {
+                    let partition = senders.len();
+                    partition_of_row.insert(row.owned(), partition);
+                    partition_values.push(batch.slice(row_index, 1).project(&indices)?);
+                    let (sender, receiver) = mpsc::channel(PARTITION_BUFFER);
+                    senders.push(sender);
+                    writers.push(tokio::spawn(write_partition(
+                        dest.clone(),
+                        params.clone(),
+                        schema.clone(),
+                        receiver,
+                    )));
+                    partition
+                }
+            };
+            rows_by_partition
+                .entry(partition)
+                .or_default()
+                .push(row_index as u32);
+        }
+        for (partition, row_indices) in rows_by_partition {
+            let rows = take_record_batch(&batch, &UInt32Array::from(row_indices))?;
+            // The writer only stops early when it fails, its error is
+            // returned below
+            if senders[partition].send(rows).await.is_err() {
+                break;
+            }
+        }
+    }
+    // Closing the channels lets the writers finish
+    drop(senders);
+
+    if writers.is_empty() {
+        // Let lance decide what writing nothing means
+        writers.push(tokio::spawn(write_partition(
+            dest.clone(),
+            params.clone(),
+            schema.clone(),
+            futures::stream::empty(),
+        )));
+    }
+    let mut transactions = Vec::with_capacity(writers.len());
+    for writer in writers {
+        transactions.push(writer.await.map_err(|err| Error::Runtime {
+            message: format!("the writer of a partition failed: {}", err),
+        })?);
+    }
+    routed?;
+
+    let mut values = HashMap::new();
+    let mut written: Option<Transaction> = None;
+    for (index, transaction) in transactions.into_iter().enumerate() {
+        let transaction = transaction?;
+        if transaction.blobs_op.is_some() {
+            return Err(Error::NotSupported {
+                message: "partitioned writes of blob columns".to_string(),
+            });
+        }
+        if let (
+            Some(partition),
+            Operation::Append { fragments } | Operation::Overwrite { fragments, .. },
+        ) = (partition_values.get(index), &transaction.operation)
+        {
+            for fragment in fragments {
+                if let Some(file) = fragment.files.first() {
+                    let (key, value) = partition_values_entry(&file.path, partition)?;
+                    values.insert(key, value);
+                }
+            }
+        }
+        written = Some(match written {
+            None => transaction,
+            Some(mut combined) => {
+                match (&mut combined.operation, transaction.operation) {
+                    (Operation::Append { fragments }, Operation::Append { fragments: new })
+                    | (
+                        Operation::Overwrite { fragments, .. },
+                        Operation::Overwrite { fragments: new, .. },
+                    ) => fragments.extend(new),
+                    (_, operation) => {
+                        return Err(Error::NotSupported {
+                            message: format!("partitioned writes of {}", operation.name()),
+                        })
+                    }
+                }
+                combined
+            }
+        });
+    }
+    // There is always at least one partition
+    let mut written = written.unwrap();
+    if let Operation::Overwrite {
+        config_upsert_values,
+        ..
+    } = &mut written.operation
+    {
+        config_upsert_values
+            .get_or_insert_with(HashMap::new)
+            .extend(values.drain());
+    }
+    Ok((written, values))
+}
+
+/// Record the partition values of an append before it is committed
+///
+/// An append can't change the table config, so this is a commit of its own,
+/// on top of `read_version`.  It is made before the append, so the fragments
+/// of the append have their values recorded as soon as they are visible, and
+/// if it fails the append is not committed.  Values of data files that are
+/// not in the table are removed, which may include the values of an append
+/// that another writer is about to commit, whose fragments are then scanned
+/// for their values instead.
+pub(crate) async fn record_partition_values(
+    read_version: &Arc<Dataset>,
+    values: HashMap<String, String>,
+) -> Result<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let files = read_version
+        .manifest()
+        .fragments
+        .iter()
+        .flat_map(|fragment| fragment.files.iter().map(|file| file.path.as_str()))
+        .collect::<HashSet<_>>();
+    let stale = read_version
+        .manifest()
+        .config
+        .keys()
+        .filter(|key| {
+            key.strip_prefix(PARTITION_VALUES_PREFIX)
+                .is_some_and(|path| !files.contains(path))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let transaction = Transaction::new(
+        read_version.version().version,
+        Operation::UpdateConfig {
+            upsert_values: Some(values),
+            delete_keys: (!stale.is_empty()).then_some(stale),
+            schema_metadata: None,
+            field_metadata: None,
+        },
+        None,
+        None,
+    );
+    CommitBuilder::new(read_version.clone())
+        .execute(transaction)
+        .await?;
+    Ok(())
+}
+
+/// The distinct partition values of the fragments of a table
+///
+/// The values recorded when a fragment was written are read from the table
+/// config.  Fragments without recorded values, such as those written by a
+/// compaction, are scanned.  Data files are never changed, so the values of a
+/// fragment are cached by its id and data files.  Deletions can only remove
+/// values, so the cached values may include values a fragment no longer has,
+/// which only means it is not pruned.
+#[derive(Debug, Default)]
+pub(crate) struct PartitionValues {
+    columns: Vec<String>,
+    fragments: HashMap<(u64, Vec<String>), RecordBatch>,
+}
+
+impl PartitionValues {
+    async fn fragment_values(
+        cache: &Mutex<Self>,
+        dataset: &Dataset,
+        fragment: &Fragment,
+        partition_schema: &SchemaRef,
+        columns: &[String],
+    ) -> Result<RecordBatch> {
+        let key = (
+            fragment.id,
+            fragment
+                .files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>(),
+        );
+        {
+            let mut cache = cache.lock().unwrap();
+            if cache.columns != columns {
+                cache.columns = columns.to_vec();
+                cache.fragments.clear();
+            }
+            if let Some(values) = cache.fragments.get(&key) {
+                return Ok(values.clone());
+            }
+        }
+        if let Some(values) = recorded_values(dataset, fragment, partition_schema) {
+            cache.lock().unwrap().fragments.insert(key, values.clone());
+            return Ok(values);
+        }
+        let mut scanner = dataset.scan();
+        scanner
+            .with_fragments(vec![fragment.clone()])
+            .project(columns)?;
+        let schema = scanner.schema().await?;
+        let batches = scanner
+            .try_into_stream()
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        let values = concat_batches(&schema, &batches)?;
+        // Lance may return the columns in table order
+        let values = values.project(
+            &columns
+                .iter()
+                .map(|column| schema.index_of(column))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )?;
+        let values = distinct_rows(&values)?;
+        cache.lock().unwrap().fragments.insert(key, values.clone());
+        Ok(values)
+    }
+}
+
+/// The distinct rows of a batch
+fn distinct_rows(batch: &RecordBatch) -> Result<RecordBatch> {
+    let converter = RowConverter::new(
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = converter.convert_columns(batch.columns())?;
+    let mut seen = HashSet::new();
+    let indices = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| seen.insert(row.owned()))
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+    Ok(take_record_batch(batch, &UInt32Array::from(indices))?)
+}
+
+/// The part of a filter that only refers to the partition columns, if any
+fn partition_filter(filter: Expr, columns: &[String]) -> Option<Expr> {
+    let conjuncts = split_conjunction(&filter)
+        .into_iter()
+        .filter(|expr| {
+            let referenced = Planner::column_names_in_expr(expr);
+            !referenced.is_empty() && referenced.iter().all(|name| columns.contains(name))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    conjunction(conjuncts)
+}
+
+/// The fragments of the table that may have rows matching the filter, or
+/// `None` if the filter can't prune any fragments
+pub(crate) async fn prune_fragments(
+    cache: &Mutex<PartitionValues>,
+    dataset: &Dataset,
+    filter: &QueryFilter,
+) -> Result<Option<Vec<Fragment>>> {
+    let schema = Schema::from(dataset.schema());
+    let columns = partition_columns(&schema)?;
+    if columns.is_empty() {
+        return Ok(None);
+    }
+    let planner = Planner::new(Arc::new(schema.clone()));
+    let filter = match filter {
+        QueryFilter::Sql(sql) => planner.parse_expr(sql)?,
+        QueryFilter::Datafusion(expr) => expr.clone(),
+        QueryFilter::Substrait(_) => return Ok(None),
+    };
+    let Some(filter) = partition_filter(filter, &columns) else {
+        return Ok(None);
+    };
+    let filter = planner.optimize_expr(filter)?;
+    let partition_schema = Arc::new(
+        schema.project(
+            &columns
+                .iter()
+                .map(|column| schema.index_of(column))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )?,
+    );
+    let filter = Planner::new(partition_schema.clone()).create_physical_expr(&filter)?;
+
+    let mut fragments = Vec::new();
+    for fragment in dataset.manifest().fragments.iter() {
+        let values =
+            PartitionValues::fragment_values(cache, dataset, fragment, &partition_schema, &columns)
+                .await?;
+        let matches = filter
+            .evaluate(&values)
+            .and_then(|matches| matches.into_array(values.num_rows()))
+            .map_err(|e| Error::Runtime {
+                message: format!("Failed to evaluate the partition filter: {}", e),
+            })?;
+        if matches.as_boolean().true_count() > 0 {
+            fragments.push(fragment.clone());
+        }
+    }
+    Ok(Some(fragments))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Int32Array, RecordBatchIterator, RecordBatchReader, StringArray};
+    use arrow_schema::Field;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::{ExecutableQuery, QueryBase};
+    use crate::Table;
+
+    fn events(ids: Vec<i32>, days: Vec<&str>) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("day", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(ids)),
+                Arc::new(StringArray::from(days)),
+            ],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    async fn pruned(table: &Table, filter: &str) -> Option<usize> {
+        let native = table.as_native().unwrap();
+        let dataset = native.dataset.get().await.unwrap();
+        prune_fragments(
+            &native.partition_values,
+            &dataset,
+            &QueryFilter::Sql(filter.to_string()),
+        )
+        .await
+        .unwrap()
+        .map(|fragments| fragments.len())
+    }
+
+    async fn num_recorded(table: &Table) -> usize {
+        let native = table.as_native().unwrap();
+        let dataset = native.dataset.get().await.unwrap();
+        dataset
+            .manifest()
+            .config
+            .keys()
+            .filter(|key| key.starts_with(PARTITION_VALUES_PREFIX))
+            .count()
+    }
+
+    async fn num_fragments(table: &Table) -> usize {
+        let native = table.as_native().unwrap();
+        native.dataset.get().await.unwrap().get_fragments().len()
+    }
+
+    async fn ids(table: &Table, filter: &str) -> Vec<i32> {
+        let batches = table
+            .query()
+            .only_if(filter)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let mut ids = batches
+            .iter()
+            .flat_map(|batch| {
+                batch["id"]
+                    .as_primitive::<arrow_array::types::Int32Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+
+        let err = db
+            .create_table("bad", events(vec![1], vec!["mon"]))
+            .partition_columns(["month"])
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        let table = db
+            .create_table(
+                "test",
+                events(vec![1, 2, 3, 4], vec!["mon", "tue", "mon", "wed"]),
+            )
+            .partition_columns(["day"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(table.partition_columns().await.unwrap(), vec!["day"]);
+        assert_eq!(table.version().await.unwrap(), 1);
+        assert_eq!(num_fragments(&table).await, 3);
+        assert_eq!(num_recorded(&table).await, 3);
+
+        table
+            .add(events(vec![5, 6], vec!["tue", "thu"]))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(num_fragments(&table).await, 5);
+        assert_eq!(num_recorded(&table).await, 5);
+        // The recorded values aren't table metadata
+        assert!(table.list_metadata().await.unwrap().is_empty());
+
+        assert_eq!(pruned(&table, "day = 'tue'").await, Some(2));
+        assert_eq!(pruned(&table, "day = 'tue' AND id > 5").await, Some(2));
+        assert_eq!(pruned(&table, "day IN ('mon', 'thu')").await, Some(2));
+        assert_eq!(pruned(&table, "day = 'fri'").await, Some(0));
+        // Filters that don't only use the partition columns can't prune
+        assert_eq!(pruned(&table, "id > 5").await, None);
+        assert_eq!(pruned(&table, "day = 'tue' OR id = 1").await, None);
+
+        assert_eq!(ids(&table, "day = 'tue'").await, vec![2, 5]);
+        assert_eq!(ids(&table, "day = 'mon' AND id > 1").await, vec![3]);
+        assert_eq!(ids(&table, "day = 'fri'").await, Vec::<i32>::new());
+
+        // Deleted rows don't make pruning wrong
+        table.delete("id = 2").await.unwrap();
+        assert_eq!(ids(&table, "day = 'tue'").await, vec![5]);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_empty_table() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let schema = events(vec![], vec![]).schema();
+        let table = db
+            .create_empty_table("test", schema)
+            .partition_columns(["day"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(num_fragments(&table).await, 0);
+
+        table
+            .add(events(vec![1, 2, 3], vec!["mon", "tue", "mon"]))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(num_fragments(&table).await, 2);
+        assert_eq!(pruned(&table, "day = 'mon'").await, Some(1));
+        assert_eq!(ids(&table, "day = 'mon'").await, vec![1, 3]);
+    }
+}
@@ -0,0 +1,400 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! An in-memory cache of query results
+//!
+//! See [`crate::connection::OpenTableBuilder::query_cache_size`]
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use arrow_array::{Array, Float32Array, RecordBatch};
+use arrow_schema::{DataType, SchemaRef};
+use datafusion_common::DataFusionError;
+use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
+use futures::{stream, StreamExt, TryStreamExt};
+use lance::dataset::scanner::DatasetRecordBatchStream;
+use lance_io::stream::RecordBatchStream;
+use moka::future::Cache;
+use moka::policy::EvictionPolicy;
+
+use crate::error::Result;
+use crate::query::{QueryExecutionOptions, QueryFilter, QueryRequest, VectorQueryRequest};
+
+use super::AnyQuery;
+
+/// The results of a query are only cached if they take at most this fraction
+/// of the cache, so one large result can't evict everything else
+const MAX_ENTRY_FRACTION: usize = 4;
+
+/// The query, with its vectors written out in full, and the table version
+pub(crate) type CacheKey = (String, u64);
+
+/// The results of a query, read fully into memory
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResults {
+    pub(crate) schema: SchemaRef,
+    pub(crate) batches: Arc<Vec<RecordBatch>>,
+    /// The memory used by the batches, in bytes
+    pub(crate) size: usize,
+}
+
+/// The results of a query, if they were small enough to read into memory
+pub(crate) enum Collected {
+    Results(CachedResults),
+    /// The results are too large to cache, the batches that were read are
+    /// streamed again in front of the rest
+    TooLarge(DatasetRecordBatchStream),
+}
+
+impl CachedResults {
+    /// Read the results of a query into memory, until they take more than
+    /// `max_size` bytes
+    pub(crate) async fn collect(
+        mut stream: DatasetRecordBatchStream,
+        max_size: usize,
+    ) -> Result<Collected> {
+        let schema = stream.schema();
+        let mut batches = Vec::new();
+        let mut size = 0;
+        while let Some(batch) = stream.try_next().await? {
+            size += batch.get_array_memory_size();
+            batches.push(batch);
+            if size > max_size {
+                let rest = stream.map_err(|err| DataFusionError::External(err.into()));
+                return Ok(Collected::TooLarge(DatasetRecordBatchStream::new(
+                    Box::pin(RecordBatchStreamAdapter::new(
+                        schema,
+                        stream::iter(batches.into_iter().map(Ok)).chain(rest),
+                    )),
+                )));
+            }
+        }
+        Ok(Collected::Results(Self {
+            schema,
+            batches: Arc::new(batches),
+            size,
+        }))
+    }
+
+    pub(crate) fn into_stream(self) -> DatasetRecordBatchStream {
+        let batches = (*self.batches).clone();
+        DatasetRecordBatchStream::new(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema,
+            stream::iter(batches.into_iter().map(Ok)),
+        )))
+    }
+}
+
+/// The cached results of recent queries, keyed on the query and the table
+/// version
+#[derive(Debug, Clone)]
+pub(crate) struct QueryCache {
+    cache: Cache<CacheKey, CachedResults>,
+    max_bytes: usize,
+}
+
+impl QueryCache {
+    /// A cache holding up to `max_bytes` of results
+    pub(crate) fn new(max_bytes: usize) -> Self {
+        Self {
+            cache: Cache::builder()
+                .max_capacity(max_bytes as u64)
+                .weigher(|key: &CacheKey, results: &CachedResults| {
+                    (key.0.len() + results.size).try_into().unwrap_or(u32::MAX)
+                })
+                .eviction_policy(EvictionPolicy::lru())
+                .build(),
+            max_bytes,
+        }
+    }
+
+    /// Whether the results of `query` can be cached
+    ///
+    /// Queries without a limit may return the whole table, and random samples
+    /// without a seed are expected to differ each time they are run.
+    pub(crate) fn is_cacheable(query: &AnyQuery) -> bool {
+        let (base, has_limit) = match query {
+            AnyQuery::Query(query) => (query, query.limit.is_some()),
+            // Vector searches default to the top 10 rows
+            AnyQuery::VectorQuery(query) => (&query.base, true),
+        };
+        let is_random = base.sample.is_some_and(|sample| sample.seed.is_none());
+        has_limit && !is_random
+    }
+
+    /// The key of `query` on `version` of the table
+    pub(crate) fn key(query: &AnyQuery, options: &QueryExecutionOptions, version: u64) -> CacheKey {
+        let mut key = String::new();
+        match query {
+            AnyQuery::Query(query) => write_query(query, &mut key),
+            AnyQuery::VectorQuery(query) => write_vector_query(query, &mut key),
+        }
+        write!(key, " max_batch_length={}", options.max_batch_length).unwrap();
+        (key, version)
+    }
+
+    /// The size of the largest results that are cached
+    pub(crate) fn max_entry_size(&self) -> usize {
+        self.max_bytes / MAX_ENTRY_FRACTION
+    }
+
+    pub(crate) async fn get(&self, key: &CacheKey) -> Option<CachedResults> {
+        self.cache.get(key).await
+    }
+
+    /// Cache `results`, unless they would take too much of the cache
+    pub(crate) async fn insert(&self, key: CacheKey, results: CachedResults) {
+        if results.size <= self.max_entry_size() {
+            self.cache.insert(key, results).await;
+        }
+    }
+}
+
+/// Write every parameter of `query` that changes its results to `key`
+///
+/// The fields are listed explicitly, so that a new field can't be left out of
+/// the key by accident.
+fn write_query(query: &QueryRequest, key: &mut String) {
+    let QueryRequest {
+        limit,
+        offset,
+        filter,
+        full_text_search,
+        select,
+        fast_search,
+        materialization,
+        null_comparison,
+        missing_columns,
+        with_row_id,
+        with_row_address,
+        prefilter,
+        reranker,
+        norm,
+        sample,
+        meta_columns,
+        order_by,
+    } = query;
+    write!(key, "limit={:?} offset={:?}", limit, offset).unwrap();
+    match filter {
+        None => {}
+        Some(QueryFilter::Sql(sql)) => write!(key, " sql_filter={:?}", sql).unwrap(),
+        Some(QueryFilter::Substrait(bytes)) => {
+            key.push_str(" substrait_filter=");
+            for byte in bytes.iter() {
+                write!(key, "{:02x}", byte).unwrap();
+            }
+        }
+        Some(QueryFilter::Datafusion(expr)) => write!(key, " expr_filter={:?}", expr).unwrap(),
+    }
+    if let Some(full_text_search) = full_text_search {
+        write!(key, " full_text_search={:?}", full_text_search).unwrap();
+    }
+    write!(
+        key,
+        " select={:?} fast_search={} materialization={:?} null_comparison={:?} \
+         missing_columns={:?} with_row_id={} with_row_address={} prefilter={}",
+        select,
+        fast_search,
+        materialization,
+        null_comparison,
+        missing_columns,
+        with_row_id,
+        with_row_address,
+        prefilter
+    )
+    .unwrap();
+    // The built in rerankers have all of their parameters in their debug
+    // output
+    if let Some(reranker) = reranker {
+        write!(key, " reranker={:?}", reranker).unwrap();
+    }
+    if let Some(norm) = norm {
+        write!(key, " norm={:?}", norm).unwrap();
+    }
+    if let Some(sample) = sample {
+        write!(
+            key,
+            " sample={:016x} seed={:?}",
+            sample.fraction.to_bits(),
+            sample.seed
+        )
+        .unwrap();
+    }
+    // Sorted, as the order of a hash map changes from one map to the next
+    let mut meta_columns = meta_columns.iter().collect::<Vec<_>>();
+    meta_columns.sort();
+    for (column, alias) in meta_columns {
+        write!(key, " meta_column={:?} alias={:?}", column, alias).unwrap();
+    }
+    for (column, order) in order_by {
+        write!(key, " order_by={:?} {:?}", column, order).unwrap();
+    }
+}
+
+/// Write every parameter of a vector search that changes its results to `key`
+fn write_vector_query(query: &VectorQueryRequest, key: &mut String) {
+    let VectorQueryRequest {
+        base,
+        column,
+        query_vector,
+        nprobes,
+        lower_bound,
+        upper_bound,
+        ef,
+        refine_factor,
+        distance_type,
+        use_index,
+        hybrid,
+        query_row,
+        multivector,
+        require_index_prefilter,
+    } = query;
+    write_query(base, key);
+    write!(
+        key,
+        " column={:?} nprobes={} lower_bound={:?} upper_bound={:?} ef={:?} \
+         refine_factor={:?} distance_type={:?} use_index={} hybrid={} query_row={:?} \
+         multivector={} require_index_prefilter={}",
+        column,
+        nprobes,
+        lower_bound.map(f32::to_bits),
+        upper_bound.map(f32::to_bits),
+        ef,
+        refine_factor,
+        distance_type,
+        use_index,
+        hybrid,
+        query_row,
+        multivector,
+        require_index_prefilter
+    )
+    .unwrap();
+    // The debug format of an array leaves out the middle of long arrays, so
+    // the query vectors are written out in full
+    for vector in query_vector {
+        write_vector(vector.as_ref(), key);
+    }
+}
+
+fn write_vector(vector: &dyn Array, key: &mut String) {
+    write!(key, " vector: {}", vector.data_type()).unwrap();
+    match arrow_cast::cast(vector, &DataType::Float32) {
+        Ok(values) => {
+            let values = values.as_any().downcast_ref::<Float32Array>().unwrap();
+            for value in values.iter() {
+                match value {
+                    Some(value) => write!(key, " {:08x}", value.to_bits()).unwrap(),
+                    None => key.push_str(" null"),
+                }
+            }
+        }
+        // Not a vector of numbers, e.g. the text of an embedding query
+        Err(_) => write!(key, " {:?}", vector.to_data()).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::query::SampleFraction;
+
+    #[test]
+    fn test_is_cacheable() {
+        let scan = QueryRequest::default();
+        assert!(!QueryCache::is_cacheable(&AnyQuery::Query(scan.clone())));
+        let limited = QueryRequest {
+            limit: Some(10),
+            ..Default::default()
+        };
+        assert!(QueryCache::is_cacheable(&AnyQuery::Query(limited.clone())));
+        assert!(QueryCache::is_cacheable(&AnyQuery::VectorQuery(
+            VectorQueryRequest::default()
+        )));
+
+        let sample = |seed| QueryRequest {
+            sample: Some(SampleFraction {
+                fraction: 0.5,
+                seed,
+            }),
+            ..limited.clone()
+        };
+        assert!(QueryCache::is_cacheable(&AnyQuery::Query(sample(Some(1)))));
+        assert!(!QueryCache::is_cacheable(&AnyQuery::Query(sample(None))));
+    }
+
+    #[test]
+    fn test_key_has_whole_vectors() {
+        let options = QueryExecutionOptions::default();
+        let search = |last: f32| {
+            let mut vector = vec![0.0; 1024];
+            vector[512] = last;
+            AnyQuery::VectorQuery(VectorQueryRequest {
+                query_vector: vec![Arc::new(Float32Array::from(vector))],
+                ..Default::default()
+            })
+        };
+        let key = QueryCache::key(&search(1.0), &options, 1);
+        assert_eq!(key, QueryCache::key(&search(1.0), &options, 1));
+        assert_ne!(key, QueryCache::key(&search(2.0), &options, 1));
+        assert_ne!(key, QueryCache::key(&search(1.0), &options, 2));
+    }
+
+    #[test]
+    fn test_key_ignores_meta_column_order() {
+        let options = QueryExecutionOptions::default();
+        let query = |columns: &[&str]| {
+            let mut meta_columns = HashMap::new();
+            for column in columns {
+                meta_columns.insert(column.to_string(), None);
+            }
+            AnyQuery::Query(QueryRequest {
+                limit: Some(10),
+                meta_columns,
+                ..Default::default()
+            })
+        };
+        let columns = (0..20).map(|i| format!("c{}", i)).collect::<Vec<_>>();
+        let columns = columns.iter().map(String::as_str).collect::<Vec<_>>();
+        let reversed = columns.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(
+            QueryCache::key(&query(&columns), &options, 1),
+            QueryCache::key(&query(&reversed), &options, 1)
+        );
+        assert_ne!(
+            QueryCache::key(&query(&columns), &options, 1),
+            QueryCache::key(&query(&columns[1..]), &options, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_streams_large_results() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "x",
+            Arc::new(Float32Array::from(vec![1.0; 1024])) as Arc<dyn Array>,
+        )])
+        .unwrap();
+        let results = |count| {
+            let batches = vec![batch.clone(); count];
+            DatasetRecordBatchStream::new(Box::pin(RecordBatchStreamAdapter::new(
+                batch.schema(),
+                stream::iter(batches.into_iter().map(Ok)),
+            )))
+        };
+        let size = batch.get_array_memory_size();
+
+        match CachedResults::collect(results(3), 3 * size).await.unwrap() {
+            Collected::Results(results) => assert_eq!(results.batches.len(), 3),
+            Collected::TooLarge(_) => panic!("the results fit in the limit"),
+        }
+        match CachedResults::collect(results(10), 3 * size).await.unwrap() {
+            Collected::Results(_) => panic!("the results are over the limit"),
+            Collected::TooLarge(stream) => {
+                let batches = stream.try_collect::<Vec<_>>().await.unwrap();
+                assert_eq!(batches.len(), 10);
+            }
+        }
+    }
+}
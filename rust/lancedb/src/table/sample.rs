@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Random samples of the rows of a table
+//!
+//! See [`super::Table::sample`] and [`crate::query::Query::sample_fraction`]
+
+use std::sync::Arc;
+
+use arrow::array::AsArray;
+use arrow::datatypes::UInt64Type;
+use arrow_array::{RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion_common::{DataFusionError, Result as DataFusionResult};
+use datafusion_execution::{SendableRecordBatchStream, TaskContext};
+use datafusion_physical_plan::execution_plan::{Boundedness, EmissionType};
+use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion_physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
+};
+use futures::{StreamExt, TryStreamExt};
+use lance::dataset::scanner::Scanner;
+use lance::dataset::{Dataset, ProjectionRequest, ROW_ID};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::error::{Error, Result};
+
+use super::{local_table, BaseTable};
+
+fn new_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// A builder for reading a random sample of a table, see
+/// [`super::Table::sample`]
+pub struct SampleBuilder {
+    table: Arc<dyn BaseTable>,
+    num_rows: usize,
+    seed: Option<u64>,
+    columns: Option<Vec<String>>,
+}
+
+impl SampleBuilder {
+    pub(super) fn new(table: Arc<dyn BaseTable>, num_rows: usize) -> Self {
+        Self {
+            table,
+            num_rows,
+            seed: None,
+            columns: None,
+        }
+    }
+
+    /// Seed the random number generator, so the same rows are sampled each
+    /// time from the same version of the table
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Only read the given columns
+    ///
+    /// By default all columns are read.
+    pub fn select(mut self, columns: &[impl AsRef<str>]) -> Self {
+        self.columns = Some(columns.iter().map(|c| c.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Read the sample
+    ///
+    /// The rows are returned in the order they are stored in the table.  If
+    /// the table has fewer rows than requested, all of them are returned.
+    pub async fn execute(self) -> Result<RecordBatch> {
+        let native = local_table(self.table.as_ref(), None, "sampling a table")?;
+        let dataset = native.dataset.get().await?;
+        let projection = match &self.columns {
+            Some(columns) => ProjectionRequest::from_schema(dataset.schema().project(columns)?),
+            None => ProjectionRequest::from_schema(dataset.schema().clone()),
+        };
+        sample_rows(&dataset, self.num_rows, self.seed, projection).await
+    }
+}
+
+/// Read a random sample of `num_rows` rows of `dataset`, see
+/// [`SampleBuilder::execute`]
+pub(crate) async fn sample_rows(
+    dataset: &Dataset,
+    num_rows: usize,
+    seed: Option<u64>,
+    projection: ProjectionRequest,
+) -> Result<RecordBatch> {
+    let total_rows = dataset.count_rows(None).await?;
+    let mut rng = new_rng(seed);
+    let mut offsets = rand::seq::index::sample(&mut rng, total_rows, num_rows.min(total_rows))
+        .into_iter()
+        .map(|offset| offset as u64)
+        .collect::<Vec<_>>();
+    // Reading the rows in order keeps the reads of each fragment together
+    offsets.sort_unstable();
+    Ok(dataset.take(&offsets, projection).await?)
+}
+
+/// Choose the row ids of a [`crate::query::Query::sample_fraction`] sample
+///
+/// The rows `scanner` matches are counted, the positions of the sampled rows
+/// among them are chosen up front and then the row ids are read again to
+/// find the ones at those positions, so only the sampled ids are kept in
+/// memory.  The offset and limit are applied to the sampled rows.
+pub(crate) async fn sample_row_ids(
+    mut scanner: Scanner,
+    fraction: f64,
+    seed: Option<u64>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<Vec<u64>> {
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "the sample fraction must be greater than 0 and at most 1, got {}",
+                fraction
+            ),
+        });
+    }
+    scanner.project::<&str>(&[])?.with_row_id();
+    let total_rows = scanner.count_rows().await? as usize;
+    let num_rows = ((fraction * total_rows as f64).round() as usize).min(total_rows);
+    let mut positions =
+        rand::seq::index::sample(&mut new_rng(seed), total_rows, num_rows).into_vec();
+    positions.sort_unstable();
+    let positions = positions
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>();
+    if positions.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut row_ids = Vec::with_capacity(positions.len());
+    let mut positions = positions.into_iter().peekable();
+    let mut batch_start = 0;
+    let mut batches = scanner.try_into_stream().await?;
+    while let Some(batch) = batches.try_next().await? {
+        let ids = batch[ROW_ID].as_primitive::<UInt64Type>();
+        while let Some(position) = positions.next_if(|p| *p < batch_start + ids.len()) {
+            row_ids.push(ids.value(position - batch_start));
+        }
+        if positions.peek().is_none() {
+            break;
+        }
+        batch_start += ids.len();
+    }
+    Ok(row_ids)
+}
+
+/// Reads the rows of a sample with `take`, see [`sample_row_ids`]
+#[derive(Debug, Clone)]
+pub(crate) struct SampleExec {
+    dataset: Arc<Dataset>,
+    row_ids: Arc<Vec<u64>>,
+    projection: ProjectionRequest,
+    with_row_id: bool,
+    with_row_address: bool,
+    batch_size: usize,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl SampleExec {
+    pub(crate) async fn try_new(
+        dataset: Arc<Dataset>,
+        row_ids: Vec<u64>,
+        projection: ProjectionRequest,
+        with_row_id: bool,
+        with_row_address: bool,
+        batch_size: usize,
+    ) -> Result<Self> {
+        // Taking no rows is cheap and gives the schema of the projection
+        let empty = dataset
+            .take_builder(&[], projection.clone())?
+            .execute()
+            .await?;
+        let mut fields = empty.schema().fields().to_vec();
+        // In the same order as the meta columns of a scan
+        if with_row_id {
+            fields.push(Arc::new(Field::new(ROW_ID, DataType::UInt64, false)));
+        }
+        if with_row_address {
+            fields.push(Arc::new(Field::new("_rowaddr", DataType::UInt64, false)));
+        }
+        let schema = Arc::new(ArrowSchema::new(fields));
+        Ok(Self {
+            properties: PlanProperties::new(
+                EquivalenceProperties::new(schema.clone()),
+                Partitioning::UnknownPartitioning(1),
+                EmissionType::Incremental,
+                Boundedness::Bounded,
+            ),
+            dataset,
+            row_ids: Arc::new(row_ids),
+            projection,
+            with_row_id,
+            with_row_address,
+            batch_size: batch_size.max(1),
+            schema,
+        })
+    }
+
+    async fn take(&self, row_ids: &[u64]) -> Result<RecordBatch> {
+        let batch = self
+            .dataset
+            .take_builder(row_ids, self.projection.clone())?
+            .with_row_address(self.with_row_address)
+            .execute()
+            .await?;
+        let mut columns = batch.columns().to_vec();
+        if self.with_row_id {
+            let position = columns.len() - self.with_row_address as usize;
+            columns.insert(position, Arc::new(UInt64Array::from(row_ids.to_vec())));
+        }
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+impl DisplayAs for SampleExec {
+    fn fmt_as(&self, _: DisplayFormatType, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SampleExec: rows={}", self.row_ids.len())
+    }
+}
+
+impl ExecutionPlan for SampleExec {
+    fn name(&self) -> &str {
+        "SampleExec"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert!(children.is_empty());
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        assert_eq!(partition, 0);
+        let num_batches = self.row_ids.len().div_ceil(self.batch_size);
+        let exec = Arc::new(self.clone());
+        let stream = futures::stream::iter(0..num_batches).then(move |i| {
+            let exec = exec.clone();
+            async move {
+                let start = i * exec.batch_size;
+                let end = (start + exec.batch_size).min(exec.row_ids.len());
+                exec.take(&exec.row_ids[start..end])
+                    .await
+                    .map_err(|err| DataFusionError::External(err.into()))
+            }
+        });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema.clone(),
+            stream,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
+    use arrow_array::{Int32Array, RecordBatchIterator};
+    use arrow_schema::{DataType, Field, Schema};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+    use crate::query::{ExecutableQuery, QueryBase};
+
+    #[tokio::test]
+    async fn test_sample() {
+        let tmp_dir = tempdir().unwrap();
+        let db = connect(tmp_dir.path().to_str().unwrap())
+            .execute()
+            .await
+            .unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("other", DataType::Int32, false),
+        ]));
+        let batches = (0..4)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(i * 250..(i + 1) * 250)),
+                        Arc::new(Int32Array::from_iter_values(0..250)),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        let table = db
+            .create_table("test", RecordBatchIterator::new(batches, schema))
+            .execute()
+            .await
+            .unwrap();
+
+        let sample = table
+            .sample(10)
+            .seed(42)
+            .select(&["id"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(sample.num_rows(), 10);
+        assert_eq!(sample.num_columns(), 1);
+        let ids = sample["id"].as_primitive::<Int32Type>().values().to_vec();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        // The same seed gives the same sample
+        let again = table
+            .sample(10)
+            .seed(42)
+            .select(&["id"])
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(sample, again);
+        // All the rows are returned if there are not enough
+        let all = table.sample(2000).execute().await.unwrap();
+        assert_eq!(all.num_rows(), 1000);
+
+        let sampled_ids = |batches: Vec<RecordBatch>| {
+            batches
+                .iter()
+                .flat_map(|b| b["id"].as_primitive::<Int32Type>().values().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let query = table
+            .query()
+            .only_if("id >= 500")
+            .sample_fraction(0.1, Some(7));
+        let first = sampled_ids(
+            query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap(),
+        );
+        assert!(first.iter().all(|id| *id >= 500));
+        assert_eq!(first.len(), 50);
+        assert!(first.windows(2).all(|w| w[0] < w[1]));
+        let second = sampled_ids(
+            query
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap(),
+        );
+        assert_eq!(first, second);
+
+        // The limit applies to the sampled rows
+        let limited = table
+            .query()
+            .sample_fraction(0.5, None)
+            .limit(5)
+            .execute()
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(limited.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+        let offset = sampled_ids(
+            query
+                .clone()
+                .limit(10)
+                .offset(5)
+                .execute()
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap(),
+        );
+        assert_eq!(offset, first[5..15]);
+
+        // Only the sampled rows are taken
+        let plan = query.explain_plan(false).await.unwrap();
+        assert!(plan.contains("SampleExec: rows=50"), "{}", plan);
+
+        let err = table
+            .query()
+            .sample_fraction(1.5, None)
+            .execute()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+    }
+}
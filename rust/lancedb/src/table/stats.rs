@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::compute::sort_to_indices;
+use arrow_array::Array;
+use arrow_schema::SortOptions;
+pub use datafusion_common::ScalarValue;
+use futures::TryStreamExt;
+
+use crate::error::{Error, Result};
+use crate::index::IndexType;
+use crate::query::{ExecutableQuery, Query, QueryBase, Select};
+
+use super::BaseTable;
+
+/// Statistics about a table, see [`super::Table::stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableStatistics {
+    /// The number of rows in the table, not counting deleted rows
+    pub num_rows: usize,
+    /// The number of rows that have been deleted but are still stored in the
+    /// data files, until the table is compacted
+    ///
+    /// This is `None` if it is not available, for example for remote tables.
+    pub num_deleted_rows: Option<usize>,
+    /// The number of fragments, the units of data that compaction merges
+    pub num_fragments: Option<usize>,
+    /// The size of the data in the current version of the table, after
+    /// compression
+    ///
+    /// This does not include old versions or indices.  It is `None` for
+    /// tables that use the legacy file format.
+    pub total_bytes: Option<u64>,
+    /// Statistics about each top-level column
+    pub columns: Vec<ColumnStatistics>,
+    /// How much of the table each index covers
+    pub indices: Vec<IndexCoverage>,
+}
+
+/// Statistics about a column, see [`TableStatistics::columns`]
+///
+/// The value statistics (`null_count`, `min` and `max`) are only calculated
+/// for the columns requested with [`TableStatsBuilder::column_values`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnStatistics {
+    /// The name of the column
+    pub name: String,
+    /// The size of the column's data, after compression
+    ///
+    /// This is `None` for tables that use the legacy file format and for
+    /// remote tables.
+    pub bytes_on_disk: Option<u64>,
+    /// The number of null values
+    pub null_count: Option<usize>,
+    /// The smallest non-null value, if the column's type can be sorted
+    pub min: Option<ScalarValue>,
+    /// The largest non-null value, if the column's type can be sorted
+    pub max: Option<ScalarValue>,
+}
+
+/// How much of a table an index covers, see [`TableStatistics::indices`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexCoverage {
+    /// The name of the index
+    pub name: String,
+    /// The type of the index
+    pub index_type: IndexType,
+    /// The columns in the index
+    pub columns: Vec<String>,
+    /// The number of rows covered by the index
+    pub num_indexed_rows: usize,
+    /// The number of rows added since the index was last updated, which
+    /// searches have to scan without the index
+    pub num_unindexed_rows: usize,
+}
+
+/// Collect the coverage of every index on a table
+pub(crate) async fn index_coverage(table: &dyn BaseTable) -> Result<Vec<IndexCoverage>> {
+    let mut coverage = Vec::new();
+    for index in table.list_indices().await? {
+        // The index may have been dropped since it was listed
+        if let Some(stats) = table.index_stats(&index.name).await? {
+            coverage.push(IndexCoverage {
+                name: index.name,
+                index_type: index.index_type,
+                columns: index.columns,
+                num_indexed_rows: stats.num_indexed_rows,
+                num_unindexed_rows: stats.num_unindexed_rows,
+            });
+        }
+    }
+    Ok(coverage)
+}
+
+/// The size of a field's data, including its children
+pub(super) fn field_bytes(
+    field: &lance::datatypes::Field,
+    bytes_by_field: &HashMap<i32, u64>,
+) -> u64 {
+    bytes_by_field.get(&field.id).copied().unwrap_or_default()
+        + field
+            .children
+            .iter()
+            .map(|child| field_bytes(child, bytes_by_field))
+            .sum::<u64>()
+}
+
+/// A builder for [`super::Table::stats`]
+#[derive(Debug, Clone)]
+pub struct TableStatsBuilder {
+    table: Arc<dyn BaseTable>,
+    value_columns: Vec<String>,
+}
+
+impl TableStatsBuilder {
+    pub(super) fn new(table: Arc<dyn BaseTable>) -> Self {
+        Self {
+            table,
+            value_columns: Vec::new(),
+        }
+    }
+
+    /// Also calculate the null count, minimum and maximum of these columns
+    ///
+    /// Unlike the other statistics, which come from the table's metadata,
+    /// these are calculated by reading every value of the columns, which can
+    /// take a long time for large tables.
+    pub fn column_values(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.value_columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Calculate the statistics of the table
+    ///
+    /// With [`Self::column_values`], the columns are scanned as well, and
+    /// their null count, minimum and maximum are filled in.  The minimum and
+    /// maximum are left out for columns whose type can't be sorted.
+    ///
+    /// Returns an error if the statistics can't be read, or if the scan fails,
+    /// for example because one of the columns doesn't exist.
+    pub async fn execute(self) -> Result<TableStatistics> {
+        let mut stats = self.table.stats().await?;
+        if self.value_columns.is_empty() {
+            return Ok(stats);
+        }
+        let mut accumulators = self
+            .value_columns
+            .iter()
+            .map(|_| ValueAccumulator::default())
+            .collect::<Vec<_>>();
+        let mut batches = Query::new(self.table.clone())
+            .select(Select::columns(&self.value_columns))
+            .execute()
+            .await?;
+        while let Some(batch) = batches.try_next().await? {
+            for (column, accumulator) in self.value_columns.iter().zip(accumulators.iter_mut()) {
+                let array = batch.column_by_name(column).ok_or_else(|| Error::Runtime {
+                    message: format!("column {} is missing from the scan", column),
+                })?;
+                accumulator.update(array.as_ref())?;
+            }
+        }
+        let mut values = self
+            .value_columns
+            .into_iter()
+            .zip(accumulators)
+            .collect::<HashMap<_, _>>();
+        for column in stats.columns.iter_mut() {
+            if let Some(accumulator) = values.remove(&column.name) {
+                column.null_count = Some(accumulator.null_count);
+                column.min = accumulator.min;
+                column.max = accumulator.max;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[derive(Debug)]
+struct ValueAccumulator {
+    null_count: usize,
+    min: Option<ScalarValue>,
+    max: Option<ScalarValue>,
+    /// Whether the column's type can be sorted to find the min and max
+    sortable: bool,
+}
+
+impl Default for ValueAccumulator {
+    fn default() -> Self {
+        Self {
+            null_count: 0,
+            min: None,
+            max: None,
+            sortable: true,
+        }
+    }
+}
+
+impl ValueAccumulator {
+    fn update(&mut self, array: &dyn Array) -> Result<()> {
+        self.null_count += array.null_count();
+        if !self.sortable || array.null_count() == array.len() {
+            return Ok(());
+        }
+        for descending in [false, true] {
+            let options = SortOptions {
+                descending,
+                nulls_first: false,
+            };
+            // A partial sort for the first value finds the min or max in
+            // linear time
+            let Ok(indices) = sort_to_indices(array, Some(options), Some(1)) else {
+                self.sortable = false;
+                self.min = None;
+                self.max = None;
+                return Ok(());
+            };
+            let value =
+                ScalarValue::try_from_array(array, indices.value(0) as usize).map_err(|err| {
+                    Error::Runtime {
+                        message: err.to_string(),
+                    }
+                })?;
+            let current = if descending {
+                &mut self.max
+            } else {
+                &mut self.min
+            };
+            let replace = match current {
+                None => true,
+                Some(current) if descending => value > *current,
+                Some(current) => value < *current,
+            };
+            if replace {
+                *current = Some(value);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub use lance::dataset::refs::TagContents;
+
+use crate::Result;
+
+use super::BaseTable;
+
+/// Named references to versions of a table
+///
+/// A tag gives a version a meaningful name, such as `v1.0`, that can be used
+/// instead of the version number, for example with
+/// [`crate::connection::OpenTableBuilder::tag`].  A tagged version is not
+/// removed when old versions are cleaned up.
+///
+/// See [`super::Table::tags`]
+#[derive(Debug, Clone)]
+pub struct Tags {
+    table: Arc<dyn BaseTable>,
+}
+
+impl Tags {
+    pub(super) fn new(table: Arc<dyn BaseTable>) -> Self {
+        Self { table }
+    }
+
+    /// List the tags and the versions they point to
+    pub async fn list(&self) -> Result<HashMap<String, TagContents>> {
+        self.table.list_tags().await
+    }
+
+    /// Get the version a tag points to
+    pub async fn get_version(&self, tag: &str) -> Result<u64> {
+        self.table.tag_version(tag).await
+    }
+
+    /// Create a new tag pointing to `version`
+    ///
+    /// It is an error to create a tag that already exists, use [`Self::update`]
+    /// to move an existing tag.
+    pub async fn create(&self, tag: &str, version: u64) -> Result<()> {
+        self.table.create_tag(tag, version).await
+    }
+
+    /// Point an existing tag at a different version
+    pub async fn update(&self, tag: &str, version: u64) -> Result<()> {
+        self.table.update_tag(tag, version).await
+    }
+
+    /// Delete a tag
+    ///
+    /// The version the tag pointed to is not affected.
+    pub async fn delete(&self, tag: &str) -> Result<()> {
+        self.table.delete_tag(tag).await
+    }
+}
@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+use std::time::Duration;
+
+use arrow_array::timezone::Tz;
+use arrow_schema::{DataType, Schema};
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::query::quote_identifier;
+
+use super::Table;
+
+/// The schema metadata key the row TTL of a table is stored under
+pub const TTL_METADATA_KEY: &str = "lancedb::ttl";
+
+/// How long the rows of a table are kept
+///
+/// A row expires `duration` after the time in `column`, which must be a
+/// timestamp or date column.  With a zero duration the column holds the time
+/// each row expires.  Expired rows are deleted by [`Table::expire_rows`], they
+/// are not hidden from queries before that.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ttl {
+    /// The timestamp column the TTL is counted from
+    pub column: String,
+    /// How long after the time in `column` rows expire
+    pub duration: Duration,
+}
+
+impl Ttl {
+    pub fn new(column: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            column: column.into(),
+            duration,
+        }
+    }
+
+    /// The filter matching the rows that expired before `now`
+    pub(crate) fn expired_filter(&self, schema: &Schema, now: DateTime<Utc>) -> Result<String> {
+        let duration =
+            chrono::Duration::from_std(self.duration).map_err(|e| Error::InvalidInput {
+                message: format!("the TTL {:?} is too long: {}", self.duration, e),
+            })?;
+        let cutoff = now - duration;
+        let column = quote_identifier(&self.column);
+        let cutoff = match column_type(schema, &self.column)? {
+            DataType::Date32 | DataType::Date64 => {
+                return Ok(format!("{} < DATE '{}'", column, cutoff.format("%Y-%m-%d")))
+            }
+            // The literal has no time zone, so it is compared as a local time
+            // in the time zone of the column
+            DataType::Timestamp(_, Some(tz)) => {
+                cutoff.with_timezone(&tz.parse::<Tz>()?).naive_local()
+            }
+            _ => cutoff.naive_utc(),
+        };
+        Ok(format!(
+            "{} < TIMESTAMP '{}'",
+            column,
+            cutoff.format("%Y-%m-%d %H:%M:%S%.6f")
+        ))
+    }
+}
+
+/// The type of the TTL column, which must be a timestamp or date column
+fn column_type<'a>(schema: &'a Schema, column: &str) -> Result<&'a DataType> {
+    let (_, field) = schema
+        .column_with_name(column)
+        .ok_or_else(|| Error::InvalidInput {
+            message: format!("the TTL column {} is not in the table", column),
+        })?;
+    match field.data_type() {
+        DataType::Timestamp(..) | DataType::Date32 | DataType::Date64 => Ok(field.data_type()),
+        data_type => Err(Error::InvalidInput {
+            message: format!(
+                "the TTL column {} must be a timestamp or date column, not {}",
+                column, data_type
+            ),
+        }),
+    }
+}
+
+/// Check that a TTL can be used for a table
+pub(crate) fn validate_ttl(schema: &Schema, ttl: &Ttl) -> Result<()> {
+    column_type(schema, &ttl.column)?;
+    Ok(())
+}
+
+/// The TTL stored in a table's schema, if any
+pub(crate) fn ttl_from_schema(schema: &Schema) -> Result<Option<Ttl>> {
+    schema
+        .metadata
+        .get(TTL_METADATA_KEY)
+        .map(|ttl| {
+            serde_json::from_str(ttl).map_err(|e| Error::Runtime {
+                message: format!("Failed to deserialize the table TTL: {}", e),
+            })
+        })
+        .transpose()
+}
+
+/// The schema metadata that stores the given TTL
+pub(crate) fn ttl_metadata(ttl: &Ttl) -> (String, String) {
+    // We have full control over the structure of the TTL, so this should not
+    // fail except for a bug
+    (
+        TTL_METADATA_KEY.to_string(),
+        serde_json::to_string(ttl).unwrap(),
+    )
+}
+
+/// A background task that periodically deletes the expired rows of a table
+///
+/// Created by [`Table::spawn_expiration`].  The task stops when this is
+/// dropped.
+#[derive(Debug)]
+pub struct ExpirationTask {
+    handle: JoinHandle<()>,
+}
+
+impl ExpirationTask {
+    pub(crate) fn spawn(table: Table, interval: Duration) -> Self {
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                // Failures are retried at the next tick
+                if let Err(err) = table.expire_rows().await {
+                    warn!("Failed to expire rows of table {}: {}", table.name(), err);
+                }
+            }
+        });
+        Self { handle }
+    }
+
+    /// Stop the task
+    ///
+    /// A sweep that is running is cancelled, which doesn't commit anything.
+    pub fn stop(self) {
+        self.handle.abort();
+    }
+}
+
+impl Drop for ExpirationTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::types::TimestampMicrosecondType;
+    use arrow_array::{
+        Int32Array, PrimitiveArray, RecordBatch, RecordBatchIterator, RecordBatchReader,
+    };
+    use arrow_schema::{Field, TimeUnit};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::connect;
+
+    fn events(ids: Vec<i32>, ages: Vec<Duration>) -> impl RecordBatchReader + Send + 'static {
+        events_at("created_at", None, ids, ages)
+    }
+
+    fn events_at(
+        column: &str,
+        tz: Option<&str>,
+        ids: Vec<i32>,
+        ages: Vec<Duration>,
+    ) -> impl RecordBatchReader + Send + 'static {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new(
+                column,
+                DataType::Timestamp(TimeUnit::Microsecond, tz.map(Into::into)),
+                false,
+            ),
+        ]));
+        let now = Utc::now();
+        let times = PrimitiveArray::<TimestampMicrosecondType>::from_iter_values(
+            ages.iter()
+                .map(|age| (now - chrono::Duration::from_std(*age).unwrap()).timestamp_micros()),
+        )
+        .with_timezone_opt(tz);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(ids)), Arc::new(times)],
+        )
+        .unwrap();
+        RecordBatchIterator::new(vec![Ok(batch)], schema)
+    }
+
+    #[tokio::test]
+    async fn test_expire_rows() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let hour = Duration::from_secs(3600);
+        let table = db
+            .create_table(
+                "test",
+                events(vec![1, 2, 3], vec![hour * 3, hour, Duration::ZERO]),
+            )
+            .execute()
+            .await
+            .unwrap();
+
+        // Nothing expires without a TTL
+        let err = table.expire_rows().await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        let err = table.set_ttl(Some(Ttl::new("id", hour))).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+
+        let ttl = Ttl::new("created_at", hour * 2);
+        table.set_ttl(Some(ttl.clone())).await.unwrap();
+        assert_eq!(table.ttl().await.unwrap(), Some(ttl));
+        assert_eq!(table.expire_rows().await.unwrap().num_deleted_rows, 1);
+        assert_eq!(table.count_rows(None).await.unwrap(), 2);
+
+        table
+            .set_ttl(Some(Ttl::new("created_at", hour / 2)))
+            .await
+            .unwrap();
+        assert_eq!(table.expire_rows().await.unwrap().num_deleted_rows, 1);
+        assert_eq!(
+            table.count_rows(Some("id = 3".to_string())).await.unwrap(),
+            1
+        );
+
+        table.set_ttl(None).await.unwrap();
+        assert_eq!(table.ttl().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_expire_rows_time_zone() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let hour = Duration::from_secs(3600);
+        // The column needs quoting, and its times are shown five hours ahead
+        let table = db
+            .create_table(
+                "test",
+                events_at(
+                    "created at",
+                    Some("+05:00"),
+                    vec![1, 2],
+                    vec![hour * 3, hour],
+                ),
+            )
+            .execute()
+            .await
+            .unwrap();
+        table
+            .set_ttl(Some(Ttl::new("created at", hour * 2)))
+            .await
+            .unwrap();
+        assert_eq!(table.expire_rows().await.unwrap().num_deleted_rows, 1);
+        assert_eq!(
+            table.count_rows(Some("id = 2".to_string())).await.unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expiration_task() {
+        let tmp_dir = tempdir().unwrap();
+        let uri = tmp_dir.path().to_str().unwrap();
+        let db = connect(uri).execute().await.unwrap();
+        let hour = Duration::from_secs(3600);
+        let table = db
+            .create_table("test", events(vec![1, 2], vec![hour * 2, Duration::ZERO]))
+            .execute()
+            .await
+            .unwrap();
+        table
+            .set_ttl(Some(Ttl::new("created_at", hour)))
+            .await
+            .unwrap();
+
+        let task = table.spawn_expiration(Duration::from_millis(10));
+        for _ in 0..100 {
+            if table.count_rows(None).await.unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        task.stop();
+        assert_eq!(table.count_rows(None).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-FileCopyrightText: Copyright The LanceDB Authors
+
+//! Writes to several tables that are committed together
+//!
+//! A [`Transaction`] is created with [`crate::connection::Connection::transaction`].
+//! Writes added to the transaction write their data files straight away, but
+//! are not visible to readers until [`Transaction::commit`] is called.
+//!
+//! ```no_run
+//! # use arrow_array::RecordBatchIterator;
+//! # use arrow_schema::{Schema, Field, DataType};
+//! # use std::sync::Arc;
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! # let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+//! # let data = || RecordBatchIterator::new(vec![], schema.clone());
+//! let db = lancedb::connect("data/sample-lancedb").execute().await.unwrap();
+//! let documents = db.open_table("documents").execute().await.unwrap();
+//! let chunks = db.open_table("chunks").execute().await.unwrap();
+//!
+//! let mut transaction = db.transaction();
+//! transaction.add(documents.add(data())).await.unwrap();
+//! transaction.add(chunks.add(data())).await.unwrap();
+//! transaction.commit().await.unwrap();
+//! # });
+//! ```
+//!
+//! Transactions are only supported for local (native) tables.  They are all
+//! or nothing, but they are not isolated, because Lance commits each table
+//! separately:
+//!
+//! * Before anything is committed, every staged write is checked against the
+//!   writes committed to its table since it was staged.  If one of them
+//!   conflicts, no table is changed and the staged data files are removed.
+//! * The tables are then committed one after another.  A reader that looks at
+//!   the tables meanwhile may see some of the tables updated but not others.
+//! * If a commit still fails, for example because of a write that raced with
+//!   the commit, the writes that were already committed are undone by new
+//!   commits that delete their rows, so none of the transaction's rows remain.
+//!   Writes committed to those tables by others in the meantime are kept.  A
+//!   write that replaced existing rows, such as an overwrite, can only be
+//!   undone while it is the latest version of its table, otherwise the error
+//!   says which table is left committed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use lance::dataset::transaction::{Operation, Transaction as LanceTransaction};
+use lance::io::ObjectStore;
+use lance::Dataset;
+
+use crate::arrow::IntoArrow;
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::table::{AddDataBuilder, BaseTable};
+
+/// A write to a table whose data files are written but not committed
+pub struct StagedWrite {
+    pub(crate) read_version: Arc<Dataset>,
+    pub(crate) transaction: LanceTransaction,
+    /// The partition values to record once the write is committed
+    pub(crate) partition_values: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for StagedWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StagedWrite")
+            .field("read_version", &self.read_version.version().version)
+            .field("operation", &self.transaction.operation.name())
+            .finish()
+    }
+}
+
+impl StagedWrite {
+    /// Remove the data files of a write that was never committed
+    ///
+    /// Only appends and overwrites are cleaned up here, the files of other
+    /// writes are removed the next time the table is cleaned up.
+    async fn discard(self) -> Result<()> {
+        let fragments = match &self.transaction.operation {
+            Operation::Append { fragments } | Operation::Overwrite { fragments, .. } => fragments,
+            _ => return Ok(()),
+        };
+        let (_, base_path) = ObjectStore::from_uri(self.read_version.uri()).await?;
+        let data_dir = base_path.child("data");
+        let object_store = self.read_version.object_store();
+        for file in fragments.iter().flat_map(|fragment| fragment.files.iter()) {
+            object_store
+                .delete(&data_dir.child(file.path.as_str()))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned by tables that don't support transactions
+pub(crate) fn not_supported(table_name: &str) -> Error {
+    Error::NotSupported {
+        message: format!(
+            "transactions are only supported for local tables, {} is not a local table",
+            table_name
+        ),
+    }
+}
+
+/// A set of writes to one or more tables that are committed together
+///
+/// See [`crate::transaction`] for the guarantees this gives.  Dropping a
+/// transaction without committing or rolling it back discards the staged
+/// writes, but leaves their data files until the tables are cleaned up.
+pub struct Transaction {
+    database: Arc<dyn Database>,
+    staged: Vec<(Arc<dyn BaseTable>, StagedWrite)>,
+}
+
+impl std::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transaction")
+            .field(
+                "tables",
+                &self
+                    .staged
+                    .iter()
+                    .map(|(table, _)| table.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Transaction {
+    pub(crate) fn new(database: Arc<dyn Database>) -> Self {
+        Self {
+            database,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Stage an add to a table
+    ///
+    /// The data files are written immediately, but the new rows are not visible
+    /// until the transaction is committed.  The table must belong to the
+    /// connection the transaction was started from.
+    pub async fn add<T: IntoArrow>(&mut self, add: AddDataBuilder<T>) -> Result<()> {
+        let (table, add, data) = add.into_parts()?;
+        if !self.database.table_exists(table.name()).await? {
+            return Err(Error::InvalidInput {
+                message: format!(
+                    "table {} is not in the database the transaction was started from",
+                    table.name()
+                ),
+            });
+        }
+        let write = table.stage_add(add, data).await?;
+        self.staged.push((table, write));
+        Ok(())
+    }
+
+    /// Commit all of the staged writes
+    ///
+    /// If a write conflicts with a write committed since it was staged, nothing
+    /// is committed and the staged writes are discarded.  If a write fails to
+    /// commit after others were committed, those are undone and the error is
+    /// returned.
+    pub async fn commit(self) -> Result<()> {
+        let mut conflict = None;
+        for (table, write) in &self.staged {
+            if let Err(err) = table.check_staged(write).await {
+                conflict = Some(err);
+                break;
+            }
+        }
+        if let Some(err) = conflict {
+            if let Err(discard_err) = Self::discard(self.staged).await {
+                log::warn!("Failed to discard staged writes: {}", discard_err);
+            }
+            return Err(err);
+        }
+
+        // The table and the version of each successful commit
+        let mut committed: Vec<(Arc<dyn BaseTable>, u64)> = Vec::new();
+        let mut staged = self.staged.into_iter();
+        while let Some((table, write)) = staged.next() {
+            match table.commit_staged(write).await {
+                Ok(version) => committed.push((table, version)),
+                Err(err) => {
+                    // The failed write is left alone, as a commit that failed
+                    // part way through may still reference its files
+                    if let Err(discard_err) = Self::discard(staged.collect()).await {
+                        log::warn!("Failed to discard staged writes: {}", discard_err);
+                    }
+                    // Undo in reverse order, so a table written more than once
+                    // is undone from its latest write back
+                    for (table, version) in committed.into_iter().rev() {
+                        if let Err(revert_err) = table.revert_staged(version).await {
+                            return Err(Error::Runtime {
+                                message: format!(
+                                    "transaction failed: {}, and undoing version {} of table {} failed: {}",
+                                    err,
+                                    version,
+                                    table.name(),
+                                    revert_err
+                                ),
+                            });
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Discard all of the staged writes and remove their data files
+    pub async fn rollback(self) -> Result<()> {
+        Self::discard(self.staged).await
+    }
+
+    async fn discard(staged: Vec<(Arc<dyn BaseTable>, StagedWrite)>) -> Result<()> {
+        for (_, write) in staged {
+            write.discard().await?;
+        }
+        Ok(())
+    }
+}
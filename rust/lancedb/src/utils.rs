@@ -123,6 +123,31 @@ pub(crate) fn default_vector_column(schema: &Schema, dim: Option<i32>) -> Result
     }
 }
 
+/// Check that `column` is a vector column whose vectors have `dim` dimensions
+pub(crate) fn validate_vector_column(schema: &Schema, column: &str, dim: usize) -> Result<()> {
+    let field = schema
+        .field_with_name(column)
+        .map_err(|_| Error::InvalidInput {
+            message: format!("vector column {} not found in the table", column),
+        })?;
+    let column_dim = infer_vector_dim(field.data_type()).map_err(|_| Error::InvalidInput {
+        message: format!(
+            "column {} is not a vector column, its type is {}",
+            column,
+            field.data_type()
+        ),
+    })?;
+    if column_dim != dim {
+        return Err(Error::InvalidInput {
+            message: format!(
+                "the query vector has {} dimensions but the vectors in column {} have {}",
+                dim, column, column_dim
+            ),
+        });
+    }
+    Ok(())
+}
+
 pub fn supported_btree_data_type(dtype: &DataType) -> bool {
     dtype.is_integer()
         || dtype.is_floating()
@@ -164,6 +189,15 @@ pub fn supported_vector_data_type(dtype: &DataType) -> bool {
     }
 }
 
+/// Whether `dtype` holds binary vectors, i.e. vectors of packed bits stored as uint8
+pub(crate) fn is_binary_vector_data_type(dtype: &DataType) -> bool {
+    match dtype {
+        DataType::FixedSizeList(field, _) => field.data_type() == &DataType::UInt8,
+        DataType::List(field) => is_binary_vector_data_type(field.data_type()),
+        _ => false,
+    }
+}
+
 /// Note: this is temporary until we get a proper datatype conversion in Lance.
 pub fn string_to_datatype(s: &str) -> Option<DataType> {
     let data_type: serde_json::Value = {
@@ -226,6 +260,28 @@ mod tests {
             .contains("More than one"));
     }
 
+    #[test]
+    fn test_validate_vector_column() {
+        let vector_type = |dim| {
+            DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), dim)
+        };
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int16, true),
+            Field::new("text_vector", vector_type(4), false),
+            Field::new("image_vector", vector_type(8), false),
+        ]);
+        assert!(validate_vector_column(&schema, "image_vector", 8).is_ok());
+        for (column, dim, message) in [
+            ("image_vector", 4, "has 4 dimensions"),
+            ("id", 4, "not a vector column"),
+            ("audio_vector", 4, "not found"),
+        ] {
+            let err = validate_vector_column(&schema, column, dim).unwrap_err();
+            assert!(matches!(err, Error::InvalidInput { .. }), "{:?}", err);
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+    }
+
     #[test]
     fn test_validate_table_name() {
         assert!(validate_table_name("my_table").is_ok());
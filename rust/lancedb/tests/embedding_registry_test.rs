@@ -61,6 +61,51 @@ async fn test_custom_func() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_default_embedding() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let tempdir = tempdir.path().to_str().unwrap();
+    let db = connect(tempdir)
+        .default_embedding(EmbeddingDefinition::new(
+            "text",
+            "embed_fun",
+            Some("embeddings"),
+        ))
+        .execute()
+        .await?;
+    let embed_fun = MockEmbed::new("embed_fun".to_string(), 1);
+    db.embedding_registry()
+        .register("embed_fun", Arc::new(embed_fun.clone()))?;
+
+    let tbl = db
+        .create_table("test", create_some_records()?)
+        .execute()
+        .await?;
+    tbl.add(create_some_records()?).execute().await?;
+    let schema = tbl.schema().await?;
+    assert_eq!(
+        schema.field_with_name("embeddings").unwrap().data_type(),
+        embed_fun.dest_type()?.as_ref()
+    );
+    assert_eq!(tbl.count_rows(None).await?, 4);
+
+    // Tables without the source column are left alone
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let data = RecordBatchIterator::new(
+        vec![RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from_iter_values(0..2))],
+        )
+        .unwrap()]
+        .into_iter()
+        .map(Ok),
+        schema.clone(),
+    );
+    let tbl = db.create_table("no_text", data).execute().await?;
+    assert_eq!(tbl.schema().await?, schema);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_custom_registry() -> Result<()> {
     let tempdir = tempfile::tempdir().unwrap();